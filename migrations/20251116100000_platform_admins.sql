@@ -0,0 +1,3 @@
+-- Administradores da plataforma (podem ver/restaurar organizações removidas)
+ALTER TABLE users
+    ADD COLUMN is_admin BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,6 @@
+-- Auditoria: quem criou a organização / o time
+ALTER TABLE organizations
+    ADD COLUMN created_by BIGINT REFERENCES users(id) ON DELETE SET NULL;
+
+ALTER TABLE teams
+    ADD COLUMN created_by BIGINT REFERENCES users(id) ON DELETE SET NULL;
@@ -0,0 +1,3 @@
+-- Contagem de deploys por app em uma janela de tempo (quotas/billing)
+CREATE INDEX idx_deploys_app_created_at
+    ON deploys (app_id, created_at);
@@ -0,0 +1,3 @@
+-- Limite de apps por organização (NULL = usa PAASTEL_MAX_APPS_PER_ORG)
+ALTER TABLE organizations
+    ADD COLUMN max_apps INTEGER CHECK (max_apps >= 0);
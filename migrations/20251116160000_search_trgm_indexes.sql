@@ -0,0 +1,17 @@
+-- Índices trigram para a busca (ILIKE '%termo%') em nome/slug
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+CREATE INDEX idx_organizations_name_trgm
+    ON organizations USING gin (name gin_trgm_ops);
+CREATE INDEX idx_organizations_slug_trgm
+    ON organizations USING gin (slug gin_trgm_ops);
+
+CREATE INDEX idx_teams_name_trgm
+    ON teams USING gin (name gin_trgm_ops);
+CREATE INDEX idx_teams_slug_trgm
+    ON teams USING gin (slug gin_trgm_ops);
+
+CREATE INDEX idx_apps_name_trgm
+    ON apps USING gin (name gin_trgm_ops);
+CREATE INDEX idx_apps_slug_trgm
+    ON apps USING gin (slug gin_trgm_ops);
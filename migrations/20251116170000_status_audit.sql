@@ -0,0 +1,53 @@
+--------------------------------------------------------------
+-- Auditoria de mudanças de status (releases e deploys)
+--------------------------------------------------------------
+
+ALTER TABLE releases
+    ADD COLUMN status_changed_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    ADD COLUMN status_changed_by BIGINT REFERENCES users(id) ON DELETE SET NULL;
+
+ALTER TABLE deploys
+    ADD COLUMN status_changed_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    ADD COLUMN status_changed_by BIGINT REFERENCES users(id) ON DELETE SET NULL;
+
+-- Registros existentes: melhor estimativa é o último evento conhecido
+UPDATE releases SET status_changed_at = updated_at, status_changed_by = created_by;
+UPDATE deploys
+SET status_changed_at = COALESCE(finished_at, started_at, created_at),
+    status_changed_by = triggered_by;
+
+-- Histórico completo de transições de deploy
+CREATE TABLE deploy_status_events (
+    id           BIGSERIAL PRIMARY KEY,
+    deploy_id    BIGINT        NOT NULL REFERENCES deploys(id) ON DELETE CASCADE,
+    from_status  deploy_status,
+    to_status    deploy_status NOT NULL,
+    changed_by   BIGINT        REFERENCES users(id) ON DELETE SET NULL,
+    created_at   TIMESTAMPTZ   NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_deploy_status_events_deploy_id
+    ON deploy_status_events (deploy_id, id);
+
+-- Deploys existentes começam com um evento do status atual
+INSERT INTO deploy_status_events (deploy_id, from_status, to_status, changed_by, created_at)
+SELECT id, NULL, status, status_changed_by, status_changed_at FROM deploys;
+
+-- Cada INSERT ou mudança de status gera um evento, qualquer que seja a origem
+CREATE OR REPLACE FUNCTION record_deploy_status_event()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP = 'INSERT' THEN
+        INSERT INTO deploy_status_events (deploy_id, from_status, to_status, changed_by, created_at)
+        VALUES (NEW.id, NULL, NEW.status, NEW.status_changed_by, NEW.status_changed_at);
+    ELSIF NEW.status IS DISTINCT FROM OLD.status THEN
+        INSERT INTO deploy_status_events (deploy_id, from_status, to_status, changed_by, created_at)
+        VALUES (NEW.id, OLD.status, NEW.status, NEW.status_changed_by, NEW.status_changed_at);
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER trg_deploys_status_event
+    AFTER INSERT OR UPDATE OF status ON deploys
+    FOR EACH ROW EXECUTE FUNCTION record_deploy_status_event();
@@ -0,0 +1,3 @@
+-- Busca do build que gerou uma imagem (reconciliação de deploys)
+CREATE INDEX idx_build_jobs_app_image_ref
+    ON build_jobs (app_id, image_ref, created_at DESC);
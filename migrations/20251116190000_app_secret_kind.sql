@@ -0,0 +1,6 @@
+-- Tipo do secret: valor simples ou conteúdo de arquivo (certificados, JSON).
+-- Secrets do tipo 'file' podem ser gravados em disco no deploy.
+CREATE TYPE secret_kind AS ENUM ('string', 'file');
+
+ALTER TABLE app_secrets
+    ADD COLUMN kind secret_kind NOT NULL DEFAULT 'string';
@@ -0,0 +1,22 @@
+-- Histórico de secrets: o valor anterior é guardado a cada sobrescrita,
+-- permitindo rollback. Mesmo armazenamento dos secrets vivos.
+CREATE TABLE app_secret_versions (
+    id               BIGSERIAL PRIMARY KEY,
+    app_id           BIGINT      NOT NULL REFERENCES apps(id) ON DELETE CASCADE,
+    environment      TEXT        NOT NULL,
+    key              TEXT        NOT NULL,
+
+    -- Sequencial por (app, ambiente, chave), começando em 1
+    version          INTEGER     NOT NULL,
+
+    value            TEXT        NOT NULL,
+    kind             secret_kind NOT NULL,
+
+    -- Quem tinha gravado esse valor
+    created_by       BIGINT      REFERENCES users(id) ON DELETE SET NULL,
+
+    -- Quando o valor foi substituído
+    replaced_at      TIMESTAMPTZ NOT NULL DEFAULT now(),
+
+    UNIQUE (app_id, environment, key, version)
+);
@@ -0,0 +1,3 @@
+-- Expiração de tokens de acesso. NULL = não expira (tokens antigos).
+ALTER TABLE auth_tokens
+    ADD COLUMN expires_at TIMESTAMPTZ;
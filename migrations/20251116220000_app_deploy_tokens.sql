@@ -0,0 +1,16 @@
+-- Tokens de deploy por app (CI): só disparam builds/deploys daquele app.
+-- Guardamos só o hash SHA-256; o token é mostrado uma única vez.
+CREATE TABLE app_deploy_tokens (
+    id            BIGSERIAL PRIMARY KEY,
+    app_id        BIGINT      NOT NULL REFERENCES apps(id) ON DELETE CASCADE,
+    token_hash    TEXT        NOT NULL UNIQUE,
+    created_by    BIGINT      NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    created_at    TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_used_at  TIMESTAMPTZ,
+    revoked_at    TIMESTAMPTZ
+);
+
+-- No máximo um token ativo por app.
+CREATE UNIQUE INDEX idx_app_deploy_tokens_active
+    ON app_deploy_tokens (app_id)
+    WHERE revoked_at IS NULL;
@@ -0,0 +1,3 @@
+-- Estado intermediário: o release está sendo compilado
+-- (pending -> building -> built | failed).
+ALTER TYPE release_status ADD VALUE IF NOT EXISTS 'building' AFTER 'pending';
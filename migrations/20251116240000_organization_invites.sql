@@ -0,0 +1,20 @@
+-- Convites por e-mail para quem ainda não tem conta. Aplicados (viram
+-- membership) quando o usuário se registra com o mesmo e-mail.
+CREATE TABLE organization_invites (
+    id               BIGSERIAL PRIMARY KEY,
+    organization_id  BIGINT      NOT NULL REFERENCES organizations(id) ON DELETE CASCADE,
+    email            TEXT        NOT NULL,
+    role             org_role    NOT NULL,
+    invited_by       BIGINT      REFERENCES users(id) ON DELETE SET NULL,
+    created_at       TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    accepted_at      TIMESTAMPTZ
+);
+
+-- Um convite pendente por e-mail em cada organização.
+CREATE UNIQUE INDEX idx_organization_invites_pending
+    ON organization_invites (organization_id, email)
+    WHERE accepted_at IS NULL;
+
+CREATE INDEX idx_organization_invites_email
+    ON organization_invites (email)
+    WHERE accepted_at IS NULL;
@@ -0,0 +1,16 @@
+-- Convites passam a ser aceitos/recusados explicitamente e expiram.
+-- Convites aceitos eram apenas marcados; agora são apagados.
+DELETE FROM organization_invites WHERE accepted_at IS NOT NULL;
+
+DROP INDEX idx_organization_invites_pending;
+DROP INDEX idx_organization_invites_email;
+ALTER TABLE organization_invites DROP COLUMN accepted_at;
+
+ALTER TABLE organization_invites
+    ADD COLUMN expires_at TIMESTAMPTZ NOT NULL DEFAULT NOW() + INTERVAL '7 days';
+
+CREATE UNIQUE INDEX idx_organization_invites_org_email
+    ON organization_invites (organization_id, email);
+
+CREATE INDEX idx_organization_invites_email
+    ON organization_invites (email);
@@ -0,0 +1,5 @@
+-- Steps com o mesmo grupo rodam em paralelo (ex: lint e testes); steps
+-- sem grupo rodam sozinhos, na ordem de position.
+ALTER TABLE build_steps
+    ADD COLUMN step_group INTEGER,
+    ADD CONSTRAINT build_steps_group_positive CHECK (step_group > 0);
@@ -4,18 +4,20 @@
 use std::fs;
 use std::io::Write;
 use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 use anyhow::{Context, Result, anyhow};
 use bollard::Docker;
 use bollard::auth::DockerCredentials;
-use bollard::image::PushImageOptions;
-use bollard::models::PushImageInfo;
-use bollard::query_parameters::BuildImageOptions;
+use bollard::models::{BuildInfo, PushImageInfo};
+use bollard::query_parameters::{BuildImageOptions, PushImageOptions};
 use bytes::Bytes;
 use clap::Parser;
-use futures_util::stream::StreamExt;
+use futures_util::stream::{Stream, StreamExt};
 use globset::{Glob, GlobMatcher};
 use http_body_util::{Either, Full};
+use serde::Deserialize;
+use tokio::sync::mpsc;
 use walkdir::WalkDir;
 
 /// CLI para buildar uma imagem Docker usando bollard,
@@ -27,6 +29,15 @@ use walkdir::WalkDir;
 ///     --dockerfile Dockerfile \
 ///     --image localhost:5000/teste/nginx:dev \
 ///     --pull
+///
+/// Valores padrão podem vir de um `paastel-build.toml` no diretório de
+/// contexto. Precedência: flag na linha de comando > `paastel-build.toml` >
+/// padrão embutido. Exemplo de arquivo:
+///
+///   image = "localhost:5000/teste/nginx:dev"
+///   dockerfile = "docker/Dockerfile"
+///   pull = true
+///   platform = "linux/arm64"
 #[derive(Parser, Debug)]
 #[command(name = "paastel-build")]
 #[command(about = "Build Docker image using bollard (with .dockerignore)", long_about = None)]
@@ -36,18 +47,139 @@ struct Cli {
     context: String,
 
     /// Nome do Dockerfile dentro do contexto (ex: Dockerfile, docker/Dockerfile).
-    #[arg(long, default_value = "Dockerfile")]
-    dockerfile: String,
+    /// Padrão: "Dockerfile".
+    #[arg(long)]
+    dockerfile: Option<String>,
 
     /// Nome completo da imagem (ex: localhost:5000/org/team/app:tag).
+    /// Obrigatório aqui ou no paastel-build.toml.
     #[arg(long)]
-    image: String,
+    image: Option<String>,
 
     /// Sempre tentar dar pull da base (equivalente a --pull no docker build).
+    /// Também ativado por `pull = true` no paastel-build.toml.
     #[arg(long)]
     pull: bool,
+
+    /// Plataforma alvo no formato os/arch[/variant] (ex: linux/arm64).
+    #[arg(long, value_parser = parse_platform)]
+    platform: Option<String>,
+
+    /// Desliga o contexto reprodutível: mantém a ordem do filesystem e os
+    /// mtimes/uids reais no tar (invalida o cache de camadas a cada build).
+    #[arg(long)]
+    no_reproducible: bool,
+
+    /// Modo silencioso (CI): só imprime `imagem@digest` no sucesso.
+    /// Erros continuam indo para o stderr.
+    #[arg(long, short = 'q')]
+    quiet: bool,
+}
+
+/// Ligado por `--quiet`; silencia o [`say!`].
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+/// `println!` que não imprime nada em modo `--quiet`.
+macro_rules! say {
+    ($($arg:tt)*) => {
+        if !QUIET.load(Ordering::Relaxed) {
+            println!($($arg)*);
+        }
+    };
+}
+
+/// Nome do arquivo de configuração opcional dentro do contexto.
+const CONFIG_FILE: &str = "paastel-build.toml";
+
+/// Padrões lidos do `paastel-build.toml` (todas as chaves são opcionais).
+#[derive(Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+struct FileConfig {
+    dockerfile: Option<String>,
+    image: Option<String>,
+    pull: Option<bool>,
+    platform: Option<String>,
+}
+
+/// Configuração efetiva depois de combinar flags e arquivo.
+#[derive(Debug)]
+struct BuildSettings {
+    dockerfile: String,
+    image: String,
+    pull: bool,
+    platform: Option<String>,
+}
+
+/// Carrega `paastel-build.toml` do contexto, se existir.
+fn load_file_config(context_dir: &Path) -> Result<FileConfig> {
+    let path = context_dir.join(CONFIG_FILE);
+    if !path.exists() {
+        return Ok(FileConfig::default());
+    }
+
+    let contents = fs::read_to_string(&path)
+        .with_context(|| format!("Falha ao ler {}", path.display()))?;
+    toml::from_str(&contents).with_context(|| {
+        format!("Configuração inválida em {}", path.display())
+    })
+}
+
+/// Combina as flags com o arquivo: flag > arquivo > padrão.
+fn resolve_settings(args: &Cli, file: FileConfig) -> Result<BuildSettings> {
+    let image = args.image.clone().or(file.image).ok_or_else(|| {
+        anyhow!("Informe a imagem com --image ou `image` no {CONFIG_FILE}")
+    })?;
+
+    let platform = match (&args.platform, file.platform) {
+        (Some(flag), _) => Some(flag.clone()),
+        (None, Some(from_file)) => {
+            Some(parse_platform(&from_file).with_context(|| {
+                format!("Valor de `platform` no {CONFIG_FILE}")
+            })?)
+        }
+        (None, None) => None,
+    };
+
+    Ok(BuildSettings {
+        dockerfile: args
+            .dockerfile
+            .clone()
+            .or(file.dockerfile)
+            .unwrap_or_else(|| "Dockerfile".to_string()),
+        image,
+        pull: args.pull || file.pull.unwrap_or(false),
+        platform,
+    })
+}
+
+/// Valida uma plataforma no formato `os/arch[/variant]`.
+///
+/// Exemplos válidos: "linux/amd64", "linux/arm64", "linux/arm/v7".
+fn parse_platform(raw: &str) -> Result<String> {
+    let parts: Vec<&str> = raw.split('/').collect();
+
+    let valid_part = |p: &str| {
+        !p.is_empty()
+            && p.chars().all(|c| {
+                c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_'
+            })
+    };
+
+    if !(2..=3).contains(&parts.len()) || !parts.iter().all(|p| valid_part(p))
+    {
+        return Err(anyhow!(
+            "Plataforma inválida '{raw}': use o formato os/arch[/variant] (ex: linux/arm64)"
+        ));
+    }
+
+    Ok(raw.to_string())
 }
 
+/// Dica exibida quando o daemon rejeita um build com --platform.
+const PLATFORM_HINT: &str = "Dica: builds para outra plataforma exigem BuildKit \
+     no daemon (DOCKER_BUILDKIT=1 ou \"features\": {\"buildkit\": true} \
+     no daemon.json).";
+
 #[tokio::main]
 async fn main() {
     if let Err(err) = run().await {
@@ -58,6 +190,7 @@ async fn main() {
 
 async fn run() -> Result<()> {
     let args = Cli::parse();
+    QUIET.store(args.quiet, Ordering::Relaxed);
 
     let context_dir = Path::new(&args.context);
     if !context_dir.exists() {
@@ -67,77 +200,148 @@ async fn run() -> Result<()> {
         ));
     }
 
-    let dockerfile_path = context_dir.join(&args.dockerfile);
+    let file_config = load_file_config(context_dir)?;
+    let settings = resolve_settings(&args, file_config)?;
+
+    let dockerfile_path = context_dir.join(&settings.dockerfile);
     if !dockerfile_path.exists() {
         return Err(anyhow!(
             "Dockerfile '{}' não encontrado em '{}'",
-            args.dockerfile,
+            settings.dockerfile,
             context_dir.display()
         ));
     }
 
-    println!("==> Conectando ao Docker daemon (socket defaults)...");
+    say!("==> Conectando ao Docker daemon (socket defaults)...");
     let docker = Docker::connect_with_socket_defaults()
         .context("Falha ao conectar ao Docker daemon (socket)")?;
 
-    println!("==> Preparando build context (tar+gzip em memória)...");
-    let compressed = build_context_tar_gz(context_dir)?;
+    say!("==> Preparando build context (tar+gzip em memória)...");
+    let compressed = build_context_tar_gz(context_dir, !args.no_reproducible)?;
 
-    println!("==> Iniciando build da imagem: {}", args.image);
-    println!("    Context   : {}", context_dir.display());
-    println!("    Dockerfile: {}", args.dockerfile);
-    println!("    pull base : {}", args.pull);
-    println!();
+    say!("==> Iniciando build da imagem: {}", settings.image);
+    say!("    Context   : {}", context_dir.display());
+    say!("    Dockerfile: {}", settings.dockerfile);
+    say!("    pull base : {}", settings.pull);
+    if let Some(platform) = &settings.platform {
+        say!("    platform  : {}", platform);
+    }
+    say!();
 
     // Usa a API nova: BuildImageOptionsBuilder em vez da struct deprecated.
     // let builder = BuildImageOptionsBuilder::default();
     let options = BuildImageOptions {
-        dockerfile: args.dockerfile.clone(),
-        t: Some(args.image.clone()), // <-- AQUI é onde o tag é setado
+        dockerfile: settings.dockerfile.clone(),
+        t: Some(settings.image.clone()), // <-- AQUI é onde o tag é setado
         rm: true,
-        pull: if args.pull { Some("true".to_string()) } else { None },
+        pull: if settings.pull { Some("true".to_string()) } else { None },
+        platform: settings.platform.clone().unwrap_or_default(),
         ..Default::default()
     };
 
     // builder.clone()
-    //     .t(args.image.as_str())
-    //     .dockerfile(args.dockerfile.as_str())
+    //     .t(settings.image.as_str())
+    //     .dockerfile(settings.dockerfile.as_str())
     //     .rm(true)
-    //     .pull(if args.pull { "true" } else { "false" });
+    //     .pull(if settings.pull { "true" } else { "false" });
 
     // let options: BuildImageOptions = builder.build();
 
     // Corpo do tar.gz em memória.
     let body = Either::Left(Full::new(Bytes::from(compressed)));
 
-    let mut stream = docker.build_image(options, None, Some(body));
+    let stream = docker.build_image(options, None, Some(body));
 
-    while let Some(chunk) = stream.next().await {
-        match chunk {
-            Ok(output) => {
-                if let Some(stream) = output.stream {
-                    print!("{stream}");
+    let (tx, mut rx) = mpsc::channel(BUILD_EVENT_BUFFER);
+    let printer = async {
+        while let Some(event) = rx.recv().await {
+            match event {
+                BuildEvent::Output(text) => {
+                    if !args.quiet {
+                        print!("{text}");
+                    }
                 }
-                if let Some(error) = output.error {
+                BuildEvent::Error(error) => {
                     eprintln!("Docker build error: {error}");
+                    if settings.platform.is_some() {
+                        eprintln!("{PLATFORM_HINT}");
+                    }
                 }
             }
-            Err(e) => {
-                return Err(anyhow!("Erro durante o stream do build: {e}"));
-            }
         }
+    };
+    let (forwarded, ()) =
+        tokio::join!(forward_build_events(stream, tx), printer);
+
+    if let Err(e) = forwarded {
+        let hint = if settings.platform.is_some() {
+            format!("\n{PLATFORM_HINT}")
+        } else {
+            String::new()
+        };
+        return Err(anyhow!("{e:#}{hint}"));
     }
 
-    println!();
-    println!("✅ Build finalizado para imagem: {}", args.image);
+    say!();
+    say!("✅ Build finalizado para imagem: {}", settings.image);
 
     // Push para o registry
-    push_image_to_registry(&docker, &args.image).await?;
+    let digest = push_image_to_registry(&docker, &settings.image).await?;
+
+    if args.quiet {
+        match digest {
+            Some(digest) => println!("{}@{digest}", settings.image),
+            None => println!("{}", settings.image),
+        }
+    }
 
     Ok(())
 }
 
-/// Faz o push da imagem para o registry.
+/// Capacidade do canal de eventos do build.
+const BUILD_EVENT_BUFFER: usize = 64;
+
+/// Evento do build já interpretado, entregue aos consumidores pelo canal.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum BuildEvent {
+    /// Trecho da saída do build (`stream` do daemon).
+    Output(String),
+    /// Erro reportado pelo daemon (o build falhou).
+    Error(String),
+}
+
+/// Consome o stream de build do bollard e envia cada evento em `tx`.
+///
+/// Para cedo, sem erro, se todos os receptores forem descartados. Também é
+/// seguro cancelar: descartar o future apenas descarta o stream e o canal.
+/// Erros de transporte do stream são devolvidos ao chamador.
+async fn forward_build_events<S>(
+    stream: S,
+    tx: mpsc::Sender<BuildEvent>,
+) -> Result<()>
+where
+    S: Stream<Item = Result<BuildInfo, bollard::errors::Error>>,
+{
+    let mut stream = std::pin::pin!(stream);
+
+    while let Some(chunk) = stream.next().await {
+        let info = chunk.context("Erro durante o stream do build")?;
+        let events = info
+            .stream
+            .map(BuildEvent::Output)
+            .into_iter()
+            .chain(info.error.map(BuildEvent::Error));
+        for event in events {
+            if tx.send(event).await.is_err() {
+                return Ok(());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Faz o push da imagem para o registry e devolve o digest publicado,
+/// quando o daemon o informa.
 ///
 /// `image_full` é algo como:
 /// - "sample-nginx:dev"
@@ -145,16 +349,17 @@ async fn run() -> Result<()> {
 async fn push_image_to_registry(
     docker: &Docker,
     image_full: &str,
-) -> Result<()> {
+) -> Result<Option<String>> {
     let (repo, tag) = split_image(image_full);
 
-    println!();
-    println!("==> Realizando push da imagem: {}", image_full);
-    println!("    Repo: {}", repo);
-    println!("    Tag : {}", tag);
+    say!();
+    say!("==> Realizando push da imagem: {}", image_full);
+    say!("    Repo: {}", repo);
+    say!("    Tag : {}", tag);
 
-    let options = Some(PushImageOptions::<String> {
-        tag: tag.clone(),
+    // Mesma API nova do build: a struct de query_parameters
+    let options = Some(PushImageOptions {
+        tag: Some(tag.clone()),
         ..Default::default()
     });
 
@@ -162,6 +367,7 @@ async fn push_image_to_registry(
     let creds: Option<DockerCredentials> = None;
 
     let mut stream = docker.push_image(&repo, options, creds);
+    let mut digest = None;
 
     while let Some(chunk) = stream.next().await {
         match chunk {
@@ -174,12 +380,15 @@ async fn push_image_to_registry(
                     progress: Some(prog),
                     ..
                 } => {
-                    println!("→ {} | {}", msg, prog);
+                    say!("→ {} | {}", msg, prog);
                 }
                 PushImageInfo {
                     status: Some(msg), progress: None, ..
                 } => {
-                    println!("→ {}", msg);
+                    if let Some(found) = parse_push_digest(&msg) {
+                        digest = Some(found.to_string());
+                    }
+                    say!("→ {}", msg);
                 }
                 _ => {}
             },
@@ -189,8 +398,15 @@ async fn push_image_to_registry(
         }
     }
 
-    println!("✅ Push finalizado para {}", image_full);
-    Ok(())
+    say!("✅ Push finalizado para {}", image_full);
+    Ok(digest)
+}
+
+/// Extrai o digest da linha final do push
+/// (ex: "dev: digest: sha256:abc... size: 1234").
+fn parse_push_digest(status: &str) -> Option<&str> {
+    let (_, rest) = status.split_once("digest: ")?;
+    rest.split_whitespace().next().filter(|d| d.starts_with("sha256:"))
 }
 
 /// Divide "repo:tag" em (repo, tag).
@@ -282,12 +498,27 @@ fn load_dockerignore(context_dir: &Path) -> Result<Option<Dockerignore>> {
 
 /// Cria um tar.gz em memória contendo TODO o contexto,
 /// respeitando .dockerignore.
-fn build_context_tar_gz(context_dir: &Path) -> Result<Vec<u8>> {
+///
+/// Com `reproducible`, as entradas saem ordenadas por path e os headers
+/// usam mtime/uid/gid fixos, então o mesmo código gera o mesmo tarball
+/// (e o daemon reaproveita o cache de camadas).
+fn build_context_tar_gz(
+    context_dir: &Path,
+    reproducible: bool,
+) -> Result<Vec<u8>> {
     let dockerignore = load_dockerignore(context_dir)?;
 
     let mut tar_builder = tar::Builder::new(Vec::new());
+    if reproducible {
+        tar_builder.mode(tar::HeaderMode::Deterministic);
+    }
 
-    for entry in WalkDir::new(context_dir).follow_links(false).into_iter() {
+    let mut walker = WalkDir::new(context_dir).follow_links(false);
+    if reproducible {
+        walker = walker.sort_by_file_name();
+    }
+
+    for entry in walker.into_iter() {
         let entry = entry.with_context(|| "Erro ao caminhar build context")?;
         let path = entry.path();
 
@@ -301,10 +532,10 @@ fn build_context_tar_gz(context_dir: &Path) -> Result<Vec<u8>> {
 
         let rel_str = rel.to_string_lossy().replace('\\', "/");
 
-        if let Some(di) = &dockerignore {
-            if di.is_ignored(&rel_str) {
-                continue;
-            }
+        if let Some(di) = &dockerignore
+            && di.is_ignored(&rel_str)
+        {
+            continue;
         }
 
         if entry.file_type().is_file() {
@@ -338,3 +569,270 @@ fn build_context_tar_gz(context_dir: &Path) -> Result<Vec<u8>> {
 
     Ok(compressed)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_platform_accepts_os_arch_and_variant() {
+        for raw in
+            ["linux/amd64", "linux/arm64", "linux/arm/v7", "windows/386"]
+        {
+            assert_eq!(parse_platform(raw).unwrap(), raw);
+        }
+    }
+
+    #[test]
+    fn parse_platform_rejects_other_shapes() {
+        for raw in [
+            "",
+            "linux",
+            "linux/",
+            "/amd64",
+            "linux//amd64",
+            "linux/arm/v7/extra",
+            "Linux/AMD64",
+            "linux/amd 64",
+            "linux:amd64",
+        ] {
+            assert!(parse_platform(raw).is_err(), "{raw:?} aceito");
+        }
+    }
+
+    /// Diretório de contexto temporário contendo `paastel-build.toml`.
+    fn context_with_config(name: &str, contents: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir()
+            .join(format!("paastel-build-{}-{name}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join(CONFIG_FILE), contents).unwrap();
+        dir
+    }
+
+    fn cli(args: &[&str]) -> Cli {
+        Cli::parse_from(std::iter::once("paastel-build").chain(args.to_vec()))
+    }
+
+    #[test]
+    fn file_fills_in_what_flags_omit() {
+        let dir = context_with_config(
+            "file",
+            r#"
+            image = "registry/web:dev"
+            dockerfile = "docker/Dockerfile"
+            pull = true
+            platform = "linux/arm64"
+            "#,
+        );
+        let file = load_file_config(&dir).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        let settings = resolve_settings(&cli(&[]), file).unwrap();
+
+        assert_eq!(settings.image, "registry/web:dev");
+        assert_eq!(settings.dockerfile, "docker/Dockerfile");
+        assert!(settings.pull);
+        assert_eq!(settings.platform.as_deref(), Some("linux/arm64"));
+    }
+
+    #[test]
+    fn flags_override_the_file() {
+        let file = FileConfig {
+            image: Some("registry/web:dev".into()),
+            dockerfile: Some("docker/Dockerfile".into()),
+            pull: Some(false),
+            platform: Some("linux/arm64".into()),
+        };
+
+        let settings = resolve_settings(
+            &cli(&[
+                "--image",
+                "registry/web:v2",
+                "--dockerfile",
+                "Dockerfile.prod",
+                "--platform",
+                "linux/amd64",
+            ]),
+            file,
+        )
+        .unwrap();
+
+        assert_eq!(settings.image, "registry/web:v2");
+        assert_eq!(settings.dockerfile, "Dockerfile.prod");
+        assert_eq!(settings.platform.as_deref(), Some("linux/amd64"));
+    }
+
+    #[test]
+    fn defaults_apply_without_a_file() {
+        let dir = std::env::temp_dir();
+        let file = load_file_config(&dir.join("nao-existe")).unwrap();
+
+        let settings =
+            resolve_settings(&cli(&["--image", "registry/web:v1"]), file)
+                .unwrap();
+
+        assert_eq!(settings.dockerfile, "Dockerfile");
+        assert!(!settings.pull);
+        assert_eq!(settings.platform, None);
+        assert!(resolve_settings(&cli(&[]), FileConfig::default()).is_err());
+    }
+
+    #[test]
+    fn unknown_file_keys_are_rejected() {
+        let dir = context_with_config("unknown", "imagem = \"x\"\n");
+
+        let result = load_file_config(&dir);
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(result.is_err());
+    }
+
+    /// Diretório temporário com `files` criados na ordem dada.
+    fn context_with(name: &str, files: &[(&str, &str)]) -> std::path::PathBuf {
+        let dir = std::env::temp_dir()
+            .join(format!("paastel-build-{}-{name}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        for (path, contents) in files {
+            let path = dir.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        }
+        dir
+    }
+
+    const FILES: &[(&str, &str)] = &[
+        ("Dockerfile", "FROM scratch\n"),
+        ("src/main.rs", "fn main() {}\n"),
+        ("src/lib.rs", "\n"),
+        ("README.md", "# app\n"),
+    ];
+
+    #[test]
+    fn reproducible_context_is_byte_identical() {
+        let dir = context_with("twice", FILES);
+
+        let first = build_context_tar_gz(&dir, true).unwrap();
+        // mtime diferente não pode mudar o tarball
+        fs::File::options()
+            .write(true)
+            .open(dir.join("src/main.rs"))
+            .unwrap()
+            .set_modified(std::time::SystemTime::UNIX_EPOCH)
+            .unwrap();
+        let second = build_context_tar_gz(&dir, true).unwrap();
+
+        assert_eq!(first, second);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn reproducible_context_ignores_creation_order() {
+        let mut reversed = FILES.to_vec();
+        reversed.reverse();
+        let a = context_with("order-a", FILES);
+        let b = context_with("order-b", &reversed);
+
+        assert_eq!(
+            build_context_tar_gz(&a, true).unwrap(),
+            build_context_tar_gz(&b, true).unwrap()
+        );
+        fs::remove_dir_all(a).unwrap();
+        fs::remove_dir_all(b).unwrap();
+    }
+
+    fn output(text: &str) -> Result<BuildInfo, bollard::errors::Error> {
+        Ok(BuildInfo { stream: Some(text.to_string()), ..Default::default() })
+    }
+
+    /// Eventos encaminhados por `forward_build_events` para `chunks`.
+    async fn forward(
+        chunks: Vec<Result<BuildInfo, bollard::errors::Error>>,
+    ) -> (Result<()>, Vec<BuildEvent>) {
+        let (tx, mut rx) = mpsc::channel(BUILD_EVENT_BUFFER);
+        let result =
+            forward_build_events(futures_util::stream::iter(chunks), tx).await;
+        let mut events = Vec::new();
+        while let Some(event) = rx.recv().await {
+            events.push(event);
+        }
+        (result, events)
+    }
+
+    #[tokio::test]
+    async fn build_output_and_errors_are_forwarded_in_order() {
+        let (result, events) = forward(vec![
+            output("Step 1/2 : FROM scratch\n"),
+            Ok(BuildInfo::default()),
+            Ok(BuildInfo {
+                stream: Some("Step 2/2 : RUN false\n".to_string()),
+                error: Some("exit code 1".to_string()),
+                ..Default::default()
+            }),
+        ])
+        .await;
+
+        assert!(result.is_ok());
+        assert_eq!(
+            events,
+            [
+                BuildEvent::Output("Step 1/2 : FROM scratch\n".to_string()),
+                BuildEvent::Output("Step 2/2 : RUN false\n".to_string()),
+                BuildEvent::Error("exit code 1".to_string()),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn stream_errors_stop_forwarding_and_are_returned() {
+        let (result, events) = forward(vec![
+            output("antes\n"),
+            Err(bollard::errors::Error::DockerStreamError {
+                error: "conexão perdida".to_string(),
+            }),
+            output("depois\n"),
+        ])
+        .await;
+
+        let err = format!("{:#}", result.unwrap_err());
+        assert!(err.starts_with("Erro durante o stream do build"), "{err}");
+        assert!(err.contains("conexão perdida"), "{err}");
+        assert_eq!(events, [BuildEvent::Output("antes\n".to_string())]);
+    }
+
+    #[tokio::test]
+    async fn dropped_receivers_end_forwarding_quietly() {
+        let (tx, rx) = mpsc::channel(1);
+        drop(rx);
+
+        let result = forward_build_events(
+            futures_util::stream::iter(vec![output("a"), output("b")]),
+            tx,
+        )
+        .await;
+
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn push_digest_is_read_from_the_final_status_line() {
+        assert_eq!(
+            parse_push_digest("dev: digest: sha256:0af3 size: 1234"),
+            Some("sha256:0af3")
+        );
+        for status in [
+            "Pushing",
+            "Layer already exists",
+            "dev: digest: md5:0af3 size: 1234",
+            "dev: digest: ",
+        ] {
+            assert_eq!(parse_push_digest(status), None, "{status:?}");
+        }
+    }
+
+    #[test]
+    fn quiet_has_a_short_flag() {
+        assert!(cli(&["--image", "app:dev", "-q"]).quiet);
+        assert!(!cli(&["--image", "app:dev"]).quiet);
+    }
+}
@@ -1,19 +1,48 @@
+use std::collections::BTreeMap;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::SystemTime;
 
 use anyhow::{Context, Result};
-use clap::{Parser, Subcommand};
-use reqwest::Client;
+use clap::{Args, CommandFactory, Parser, Subcommand, ValueEnum};
+use clap_complete::Shell;
+use reqwest::header::RETRY_AFTER;
+use reqwest::{Client, RequestBuilder, Response, StatusCode};
 use serde::{Deserialize, Serialize};
 
 /// Global CLI configuration stored in ~/.config/paastel/config.toml
+///
+/// Credentials live in named `[profiles.<name>]` tables; `auth` is the
+/// active one while the CLI runs and is written back to its profile by
+/// `save_config`.
 #[derive(Debug, Serialize, Deserialize, Default)]
 struct Config {
+    /// Profile used when `--profile` is not given
     #[serde(default)]
+    current_profile: String,
+    #[serde(default)]
+    profiles: BTreeMap<String, AuthConfig>,
+    /// Active profile. On disk, only read from pre-profile configs (a
+    /// flat `[auth]` table), which are migrated to the "default" profile.
+    #[serde(default, skip_serializing)]
     auth: AuthConfig,
+    /// Name of the profile `auth` was loaded from
+    #[serde(skip)]
+    active_profile: String,
+    #[serde(default)]
+    cli: CliConfig,
 }
 
-#[derive(Debug, Serialize, Deserialize, Default)]
+/// Interactive conveniences, all off by default.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+struct CliConfig {
+    /// Number list output and remember it, so `--last N` can refer to a row
+    #[serde(default)]
+    remember_lists: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 struct AuthConfig {
     /// Full GraphQL endpoint, e.g. "http://localhost:3000/graphql"
     #[serde(default)]
@@ -41,11 +70,60 @@ struct SessionContext {
     team_slug: Option<String>,
 }
 
+/// `--org`/`--team` given on the command line; never persisted.
+#[derive(Debug, Default)]
+struct ContextOverride {
+    org: Option<String>,
+    team: Option<String>,
+}
+
+impl SessionContext {
+    /// The context for this command: a flag wins over the session value.
+    ///
+    /// Overriding the organization also drops the session's team, which
+    /// belongs to the other organization.
+    fn with_overrides(mut self, overrides: &ContextOverride) -> Self {
+        if let Some(org) = &overrides.org {
+            self.organization_id = None;
+            self.organization_slug = Some(org.clone());
+            self.team_id = None;
+            self.team_slug = None;
+        }
+        if let Some(team) = &overrides.team {
+            self.team_id = None;
+            self.team_slug = Some(team.clone());
+        }
+        self
+    }
+}
+
 /// Root CLI
 #[derive(Parser, Debug)]
 #[command(name = "paastel")]
 #[command(about = "PaaStel CLI - manage orgs, teams and apps", long_about = None)]
 struct Cli {
+    /// Organization slug for this command only (overrides the session)
+    #[arg(long, global = true, value_name = "SLUG")]
+    org: Option<String>,
+
+    /// Team slug for this command only (overrides the session)
+    #[arg(long, global = true, value_name = "SLUG")]
+    team: Option<String>,
+
+    /// Config profile for this command only (see `auth profiles`)
+    #[arg(long, global = true, value_name = "NAME")]
+    profile: Option<String>,
+
+    /// Output format (lists, `org create`, `team create`, `context show`)
+    #[arg(
+        short,
+        long,
+        global = true,
+        value_enum,
+        default_value_t = OutputFormat::Table
+    )]
+    output: OutputFormat,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -71,15 +149,81 @@ enum Commands {
     /// Application commands
     #[command(subcommand)]
     App(AppCommand),
+
+    /// Release commands
+    #[command(subcommand)]
+    Release(ReleaseCommand),
+
+    /// App secret commands
+    #[command(subcommand)]
+    Secret(SecretCommand),
+
+    /// Build commands
+    #[command(subcommand)]
+    Build(BuildCommand),
+
+    /// Deploy commands
+    #[command(subcommand)]
+    Deploy(DeployCommand),
+
+    /// Local configuration commands (never contact the server)
+    #[command(subcommand)]
+    Config(ConfigCommand),
+
+    /// Print a shell completion script
+    ///
+    /// For bash, slugs after `org use --slug`, `team use --slug`, `--team`
+    /// and `--app` are completed from the server:
+    /// `source <(paastel completions bash)`
+    Completions { shell: Shell },
+
+    /// Print slugs matching a prefix (used by the completion scripts)
+    #[command(name = "__complete", hide = true)]
+    Complete {
+        kind: SlugKind,
+        #[arg(default_value = "")]
+        prefix: String,
+    },
+
+    /// Check config, connectivity, login and selected org/team
+    Doctor,
+
+    /// Search organizations, teams and apps you have access to
+    Search {
+        /// Text to match against names and slugs
+        term: String,
+        /// Max results shown per group
+        #[arg(long, default_value_t = 10)]
+        limit: usize,
+    },
 }
 
 #[derive(Subcommand, Debug)]
 enum AuthCommand {
+    /// Log in with an existing account and store the token locally
+    ///
+    /// This calls the GraphQL mutation `authenticate` and saves the
+    /// returned access token in config.toml.
+    Login {
+        /// Account email
+        #[arg(long)]
+        email: Option<String>,
+        /// Account password (prompted if omitted)
+        #[arg(long)]
+        password: Option<String>,
+        /// GraphQL endpoint (defaults to the configured one, then
+        /// $PAASTEL_BASE_URL, then http://localhost:3000/graphql)
+        #[arg(long)]
+        base_url: Option<String>,
+        /// Token lifetime in days (server default and max: 90)
+        #[arg(long, value_name = "DAYS")]
+        ttl_days: Option<i64>,
+    },
     /// Register a new user (bootstrap) and store the token locally
     ///
     /// This calls the GraphQL mutation `registerUser` and saves the
     /// returned access token in config.toml.
-    Login {
+    Register {
         /// User name
         #[arg(long)]
         name: Option<String>,
@@ -89,14 +233,42 @@ enum AuthCommand {
         /// Password used to register
         #[arg(long)]
         password: Option<String>,
-        /// GraphQL endpoint (override default)
+        /// GraphQL endpoint (defaults to the configured one, then
+        /// $PAASTEL_BASE_URL, then http://localhost:3000/graphql)
         #[arg(long)]
         base_url: Option<String>,
+        /// Token lifetime in days (server default and max: 90)
+        #[arg(long, value_name = "DAYS")]
+        ttl_days: Option<i64>,
+    },
+    /// Change the password of the logged-in user
+    ///
+    /// Prompts (hidden) for the current and new password unless given as
+    /// flags. Other sessions are revoked unless `--keep-sessions` is set.
+    Passwd {
+        /// Current password (prompted if omitted)
+        #[arg(long)]
+        current: Option<String>,
+        /// New password (prompted twice if omitted)
+        #[arg(long)]
+        new: Option<String>,
+        /// Do not revoke the other auth tokens of this user
+        #[arg(long)]
+        keep_sessions: bool,
     },
     /// Remove local authentication
     Logout,
     /// Show current authentication status
     Status,
+    /// Print the logged-in user, as seen by the server
+    Whoami,
+    /// List config profiles; the current one is marked with `*`
+    Profiles,
+    /// Switch the current profile (clears the org/team context)
+    UseProfile {
+        /// Profile name; log in afterwards if it is new
+        name: String,
+    },
 }
 
 #[derive(Subcommand, Debug)]
@@ -110,6 +282,12 @@ enum OrgCommand {
         #[arg(long)]
         description: Option<String>,
     },
+    /// List organizations you belong to, with your role
+    List {
+        /// Same as `--output json`
+        #[arg(long)]
+        json: bool,
+    },
     /// Set current organization in the local session
     Use {
         /// Organization ID
@@ -119,6 +297,15 @@ enum OrgCommand {
         #[arg(long)]
         slug: Option<String>,
     },
+    /// Delete an organization (owners only; it must have no apps left)
+    Delete {
+        /// Organization slug
+        #[arg(long)]
+        slug: String,
+        /// Do not ask for confirmation
+        #[arg(long, short = 'y')]
+        yes: bool,
+    },
 }
 
 #[derive(Subcommand, Debug)]
@@ -132,6 +319,12 @@ enum TeamCommand {
         #[arg(long)]
         description: Option<String>,
     },
+    /// List teams of the current organization (requires auth + org)
+    List {
+        /// Same as `--output json`
+        #[arg(long)]
+        json: bool,
+    },
     /// Set current team in the local session
     Use {
         /// Team ID
@@ -153,26 +346,371 @@ enum ContextCommand {
 
 #[derive(Subcommand, Debug)]
 enum AppCommand {
-    /// Create a new application in the current org/team (requires auth + org + team)
+    /// Create a new application in the current org (requires auth + org)
     ///
-    /// NOTE: Not implemented in the GraphQL schema yet. This command
-    /// will currently return an error.
+    /// The app is attached to the current team, if one is selected.
     Create {
         #[arg(long)]
         name: String,
         #[arg(long)]
         slug: String,
-        /// Optional runtime (example: nodejs-22)
+        /// Git repository URL
+        #[arg(long)]
+        repo_url: Option<String>,
+    },
+    /// List apps of the current organization (requires auth + org)
+    List,
+    /// Show one app of the current organization (requires auth + org)
+    Info {
+        /// App slug
+        #[arg(required_unless_present = "last", conflicts_with = "last")]
+        slug: Option<String>,
+        /// Row number from the last `app list` (needs `cli.remember_lists`)
+        #[arg(long, value_name = "N")]
+        last: Option<usize>,
+    },
+    /// Write a starter Dockerfile and .dockerignore in the current directory
+    Scaffold {
+        /// Runtime template to use
+        #[arg(long, value_enum, default_value = "nodejs-22")]
+        runtime: ScaffoldRuntime,
+        /// Overwrite existing files
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+/// `--watch` for status lists.
+#[derive(Args, Debug)]
+struct WatchArgs {
+    /// Keep refreshing until every listed item has finished (or Ctrl-C)
+    #[arg(short, long)]
+    watch: bool,
+    /// Seconds between refreshes with --watch
+    #[arg(long, value_name = "SECS", default_value_t = 2, requires = "watch")]
+    interval: u64,
+}
+
+/// `--since`/`--until` for build/deploy lists.
+#[derive(Args, Debug)]
+struct TimeRangeArgs {
+    /// Only items created at or after this time: a duration ago (`24h`,
+    /// `7d`) or an RFC 3339 timestamp
+    #[arg(long, value_name = "WHEN", value_parser = parse_time_bound)]
+    since: Option<SystemTime>,
+    /// Only items created before this time (same formats as --since)
+    #[arg(long, value_name = "WHEN", value_parser = parse_time_bound)]
+    until: Option<SystemTime>,
+}
+
+impl TimeRangeArgs {
+    /// The bounds as RFC 3339 strings for the API, checked to be in order.
+    fn to_rfc3339(&self) -> Result<(Option<String>, Option<String>)> {
+        if let (Some(since), Some(until)) = (self.since, self.until)
+            && since >= until
+        {
+            anyhow::bail!("--since must be before --until");
+        }
+        let format =
+            |t: SystemTime| humantime::format_rfc3339_seconds(t).to_string();
+        Ok((self.since.map(format), self.until.map(format)))
+    }
+}
+
+/// `24h`/`7d` (that long ago) or an RFC 3339 timestamp.
+fn parse_time_bound(raw: &str) -> Result<SystemTime, String> {
+    if let Ok(ago) = humantime::parse_duration(raw) {
+        return SystemTime::now()
+            .checked_sub(ago)
+            .ok_or_else(|| format!("'{raw}' is too far in the past"));
+    }
+    humantime::parse_rfc3339_weak(raw).map_err(|_| {
+        format!(
+            "'{raw}' is neither a duration (e.g. 24h, 7d) nor an RFC 3339 \
+             timestamp (e.g. 2025-11-16T12:00:00Z)"
+        )
+    })
+}
+
+/// How list commands print their rows.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum OutputFormat {
+    /// Aligned columns
+    Table,
+    /// Aligned columns, including the extra ones
+    Wide,
+    /// Only the slug (or id) of each row, one per line
+    Name,
+    /// Pretty-printed JSON
+    Json,
+    /// YAML
+    Yaml,
+}
+
+impl OutputFormat {
+    /// JSON/YAML: stdout carries only the document, so it can be piped;
+    /// human messages go to stderr.
+    fn is_structured(self) -> bool {
+        matches!(self, OutputFormat::Json | OutputFormat::Yaml)
+    }
+}
+
+/// Resource kinds whose slugs can be completed dynamically.
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum SlugKind {
+    Org,
+    Team,
+    App,
+}
+
+impl SlugKind {
+    fn label(self) -> &'static str {
+        match self {
+            SlugKind::Org => "organization",
+            SlugKind::Team => "team",
+            SlugKind::App => "app",
+        }
+    }
+}
+
+/// Runtimes with an embedded starter template for `app scaffold`.
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum ScaffoldRuntime {
+    #[value(name = "nodejs-22")]
+    Nodejs22,
+    #[value(name = "python-3.12")]
+    Python312,
+    #[value(name = "go-1.22")]
+    Go122,
+}
+
+impl ScaffoldRuntime {
+    /// `(Dockerfile, .dockerignore)` contents for this runtime.
+    fn templates(self) -> (&'static str, &'static str) {
+        match self {
+            Self::Nodejs22 => (
+                include_str!("../../templates/scaffold/nodejs-22/Dockerfile"),
+                include_str!(
+                    "../../templates/scaffold/nodejs-22/.dockerignore"
+                ),
+            ),
+            Self::Python312 => (
+                include_str!(
+                    "../../templates/scaffold/python-3.12/Dockerfile"
+                ),
+                include_str!(
+                    "../../templates/scaffold/python-3.12/.dockerignore"
+                ),
+            ),
+            Self::Go122 => (
+                include_str!("../../templates/scaffold/go-1.22/Dockerfile"),
+                include_str!("../../templates/scaffold/go-1.22/.dockerignore"),
+            ),
+        }
+    }
+}
+
+#[derive(Subcommand, Debug)]
+enum ReleaseCommand {
+    /// Create a release from a succeeded build (requires authentication)
+    ///
+    /// The release points at the build's image and commit. With
+    /// `--deploy --env <env>` it is deployed right away.
+    Promote {
+        /// Build job ID
+        #[arg(long)]
+        build: i64,
+        /// Release version (defaults to `build-<id>`)
+        #[arg(long)]
+        version: Option<String>,
+        /// Release notes
+        #[arg(long)]
+        changelog: Option<String>,
+        /// Deploy the new release after creating it
+        #[arg(long, requires = "env")]
+        deploy: bool,
+        /// Target environment for `--deploy`
+        #[arg(long)]
+        env: Option<String>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum BuildCommand {
+    /// List the latest builds of an app (requires authentication)
+    List {
+        /// App slug
+        #[arg(long)]
+        app: String,
+        /// How many builds to show (server default: 20)
+        #[arg(long)]
+        limit: Option<i64>,
+        #[command(flatten)]
+        range: TimeRangeArgs,
+        #[command(flatten)]
+        watch: WatchArgs,
+    },
+    /// Print the output of a build (requires authentication)
+    Logs {
+        /// Build job ID
+        id: i64,
+        /// Only print the last N lines
+        #[arg(long, value_name = "N")]
+        lines: Option<usize>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum DeployCommand {
+    /// List the latest deploys of an app (requires authentication)
+    List {
+        /// App slug
+        #[arg(long)]
+        app: String,
+        /// Only deploys to this environment
+        #[arg(long)]
+        env: Option<String>,
+        /// How many deploys to show (server default: 20)
+        #[arg(long)]
+        limit: Option<i64>,
+        #[command(flatten)]
+        range: TimeRangeArgs,
+        #[command(flatten)]
+        watch: WatchArgs,
+    },
+    /// Deploy a release to one or more environments (requires authentication)
+    ///
+    /// All deploys are created together, or none when an environment
+    /// already has a deploy in progress (unless `--skip-busy`).
+    Create {
+        /// App slug
+        #[arg(long)]
+        app: String,
+        /// Release ID
+        #[arg(long)]
+        release: i64,
+        /// Target environments, comma-separated (e.g. staging,production)
+        #[arg(long, value_delimiter = ',', required = true)]
+        env: Vec<String>,
+        /// Skip environments with a deploy in progress instead of failing
+        #[arg(long)]
+        skip_busy: bool,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum SecretCommand {
+    /// Create or overwrite app secrets (requires authentication)
+    ///
+    /// Give `KEY=VALUE` pairs and/or `--from-file .env`, applied together,
+    /// or a single `--key` with `--value`/`--value-file`. Multi-line values
+    /// (certificates, JSON) are best read with `--value-file`. Values are
+    /// never printed.
+    Set {
+        /// App slug (within the current organization)
         #[arg(long)]
-        runtime: Option<String>,
+        app: String,
+        /// Environment (e.g. dev, staging, prod)
+        #[arg(long, default_value = "default")]
+        env: String,
+        /// Secrets to set
+        #[arg(value_name = "KEY=VALUE", conflicts_with = "key")]
+        pairs: Vec<String>,
+        /// Read `KEY=VALUE` lines from a dotenv file
+        #[arg(long, value_name = "PATH", conflicts_with = "key")]
+        from_file: Option<PathBuf>,
+        /// Secret name (environment variable name)
+        #[arg(long)]
+        key: Option<String>,
+        /// Secret value
+        #[arg(long, conflicts_with = "value_file", requires = "key")]
+        value: Option<String>,
+        /// Read the value of `--key` from a file
+        #[arg(long, value_name = "PATH", requires = "key")]
+        value_file: Option<PathBuf>,
+        /// How the app consumes the secret [default: file with
+        /// `--value-file`, string otherwise]
+        #[arg(long = "type", value_enum)]
+        kind: Option<SecretType>,
+    },
+    /// List the secrets of an app environment
+    List {
+        /// App slug (within the current organization)
+        #[arg(long)]
+        app: String,
+        /// Environment (e.g. dev, staging, prod)
+        #[arg(long, default_value = "default")]
+        env: String,
+        /// Also print the values (masked unless you may manage secrets)
+        #[arg(long)]
+        show_values: bool,
     },
+    /// Delete an app secret
+    Unset {
+        /// App slug (within the current organization)
+        #[arg(long)]
+        app: String,
+        /// Environment (e.g. dev, staging, prod)
+        #[arg(long, default_value = "default")]
+        env: String,
+        /// Secret name
+        key: String,
+    },
+}
+
+/// Secret types understood by the server.
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum SecretType {
+    /// Environment variable value
+    String,
+    /// File contents, written to disk at deploy time
+    File,
+}
+
+#[derive(Subcommand, Debug)]
+enum ConfigCommand {
+    /// Print the resolved config and session file locations
+    Path,
 }
 
 // ---------------------------
 // Helpers for config/session
 // ---------------------------
 
+/// Environment variable overriding the config directory.
+const CONFIG_DIR_ENV: &str = "PAASTEL_CONFIG_DIR";
+
+/// Environment variable with the GraphQL endpoint used on first login.
+const BASE_URL_ENV: &str = "PAASTEL_BASE_URL";
+
+const DEFAULT_BASE_URL: &str = "http://localhost:3000/graphql";
+
+/// Profile created when migrating a pre-profile config.
+const DEFAULT_PROFILE: &str = "default";
+
+/// `--profile` of this invocation, set once in `main`.
+static PROFILE_OVERRIDE: OnceLock<String> = OnceLock::new();
+
+/// Pick the GraphQL endpoint for `auth login`.
+///
+/// Precedence: `--base-url` > existing config > `PAASTEL_BASE_URL` >
+/// localhost default.
+fn resolve_base_url(
+    flag: Option<String>,
+    configured: &str,
+    env: Option<String>,
+) -> String {
+    flag.filter(|v| !v.is_empty())
+        .or_else(|| Some(configured.to_string()).filter(|v| !v.is_empty()))
+        .or_else(|| env.filter(|v| !v.is_empty()))
+        .unwrap_or_else(|| DEFAULT_BASE_URL.to_string())
+}
+
 fn paastel_config_dir() -> Result<PathBuf> {
+    if let Some(dir) = std::env::var_os(CONFIG_DIR_ENV) {
+        return Ok(PathBuf::from(dir));
+    }
+
     let base =
         dirs::config_dir().context("Could not determine config directory")?;
     Ok(base.join("paastel"))
@@ -186,17 +724,41 @@ fn session_path() -> Result<PathBuf> {
     Ok(paastel_config_dir()?.join("session.toml"))
 }
 
+fn last_list_path() -> Result<PathBuf> {
+    Ok(paastel_config_dir()?.join("last-list.toml"))
+}
+
 fn load_config() -> Result<Config> {
     let path = config_path()?;
-    if !path.exists() {
-        return Ok(Config::default());
+    let mut cfg: Config = if path.exists() {
+        let data = fs::read_to_string(&path).with_context(|| {
+            format!("Failed to read config file at {}", path.display())
+        })?;
+        toml::from_str(&data).context("Failed to parse config.toml")?
+    } else {
+        Config::default()
+    };
+
+    if cfg.current_profile.is_empty() {
+        cfg.current_profile = DEFAULT_PROFILE.to_string();
     }
 
-    let data = fs::read_to_string(&path).with_context(|| {
-        format!("Failed to read config file at {}", path.display())
-    })?;
-    let cfg: Config =
-        toml::from_str(&data).context("Failed to parse config.toml")?;
+    // Pre-profile configs have a single flat `[auth]` table: save it as
+    // the current profile.
+    if cfg.profiles.is_empty()
+        && !(cfg.auth.base_url.is_empty() && cfg.auth.token.is_empty())
+    {
+        cfg.active_profile = cfg.current_profile.clone();
+        save_config(&cfg)?;
+        cfg.profiles.insert(cfg.current_profile.clone(), cfg.auth.clone());
+    }
+
+    cfg.active_profile = PROFILE_OVERRIDE
+        .get()
+        .cloned()
+        .unwrap_or_else(|| cfg.current_profile.clone());
+    cfg.auth =
+        cfg.profiles.get(&cfg.active_profile).cloned().unwrap_or_default();
     Ok(cfg)
 }
 
@@ -207,8 +769,25 @@ fn save_config(cfg: &Config) -> Result<()> {
     })?;
 
     let path = config_path()?;
-    let data =
-        toml::to_string_pretty(cfg).context("Failed to serialize config")?;
+    let mut profiles = cfg.profiles.clone();
+    let active = match cfg.active_profile.as_str() {
+        "" => DEFAULT_PROFILE,
+        name => name,
+    };
+    profiles.insert(active.to_string(), cfg.auth.clone());
+    let current_profile = match cfg.current_profile.as_str() {
+        "" => active,
+        name => name,
+    };
+    let on_disk = Config {
+        current_profile: current_profile.to_string(),
+        profiles,
+        auth: AuthConfig::default(),
+        active_profile: String::new(),
+        cli: cfg.cli.clone(),
+    };
+    let data = toml::to_string_pretty(&on_disk)
+        .context("Failed to serialize config")?;
     fs::write(&path, data).with_context(|| {
         format!("Failed to write config file at {}", path.display())
     })?;
@@ -244,6 +823,117 @@ fn save_session(sess: &Session) -> Result<()> {
     Ok(())
 }
 
+// ------------------------
+// Last list (`--last N`)
+// ------------------------
+
+/// How long a remembered list can be referred to.
+const LAST_LIST_TTL_SECS: u64 = 10 * 60;
+
+/// Rows of the last list command, stored in ~/.config/paastel/last-list.toml
+#[derive(Debug, Serialize, Deserialize)]
+struct LastList {
+    kind: String,
+    organization_id: i64,
+    /// Unix time the list was printed
+    saved_at: u64,
+    slugs: Vec<String>,
+}
+
+fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+fn save_last_list(
+    kind: &str,
+    organization_id: i64,
+    slugs: Vec<String>,
+) -> Result<()> {
+    let dir = paastel_config_dir()?;
+    fs::create_dir_all(&dir).with_context(|| {
+        format!("Failed to create config dir at {}", dir.display())
+    })?;
+
+    let last = LastList {
+        kind: kind.to_string(),
+        organization_id,
+        saved_at: unix_now(),
+        slugs,
+    };
+    let path = last_list_path()?;
+    let data =
+        toml::to_string_pretty(&last).context("Failed to serialize list")?;
+    fs::write(&path, data).with_context(|| {
+        format!("Failed to write list file at {}", path.display())
+    })?;
+    Ok(())
+}
+
+/// Slug of row `index` (1-based) of the remembered `kind` list.
+fn last_list_slug(
+    kind: &str,
+    organization_id: i64,
+    index: usize,
+) -> Result<String> {
+    let path = last_list_path()?;
+    let stale = || {
+        anyhow::anyhow!(
+            "No recent `{kind} list` to refer to. Run `paastel {kind} list` \
+             first (with `remember_lists = true` under [cli] in config.toml)."
+        )
+    };
+    if !path.exists() {
+        return Err(stale());
+    }
+
+    let data = fs::read_to_string(&path).with_context(|| {
+        format!("Failed to read list file at {}", path.display())
+    })?;
+    let last: LastList =
+        toml::from_str(&data).context("Failed to parse last-list.toml")?;
+
+    resolve_last_index(&last, kind, organization_id, index, unix_now())
+        .ok_or_else(stale)?
+}
+
+/// Pick row `index` of `last`, if it is the same list and still fresh.
+fn resolve_last_index(
+    last: &LastList,
+    kind: &str,
+    organization_id: i64,
+    index: usize,
+    now: u64,
+) -> Option<Result<String>> {
+    if last.kind != kind
+        || last.organization_id != organization_id
+        || now.saturating_sub(last.saved_at) > LAST_LIST_TTL_SECS
+    {
+        return None;
+    }
+
+    let slug = index.checked_sub(1).and_then(|i| last.slugs.get(i));
+    Some(slug.cloned().ok_or_else(|| {
+        anyhow::anyhow!(
+            "No row {index} in the last `{kind} list` ({} rows)",
+            last.slugs.len()
+        )
+    }))
+}
+
+/// Prefix each row of a rendered table with its 1-based number.
+fn numbered(table: &str) -> String {
+    table
+        .lines()
+        .enumerate()
+        .map(|(i, line)| match i {
+            0 => format!("{:<4}{line}\n", "#"),
+            _ => format!("{i:<4}{line}\n"),
+        })
+        .collect()
+}
+
 // -------------
 // GraphQL types
 // -------------
@@ -279,11 +969,14 @@ struct RegisterUserInput<'a> {
     name: &'a str,
     email: &'a str,
     password: &'a str,
+    #[serde(rename = "ttlDays", skip_serializing_if = "Option::is_none")]
+    ttl_days: Option<i64>,
 }
 
 #[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
 struct RegisterUserData {
-    registerUser: RegisterUserPayload,
+    register_user: RegisterUserPayload,
 }
 
 #[derive(Debug, Deserialize)]
@@ -292,9 +985,24 @@ struct RegisterUserPayload {
     token: AccessToken,
 }
 
+// ---- authenticate ----
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AuthenticateVariables<'a> {
+    email: &'a str,
+    password: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ttl_days: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AuthenticateData {
+    authenticate: RegisterUserPayload,
+}
+
 #[derive(Debug, Deserialize)]
 struct GqlUser {
-    id: i32,
     name: String,
     email: String,
 }
@@ -302,7 +1010,6 @@ struct GqlUser {
 #[derive(Debug, Deserialize)]
 struct AccessToken {
     token: String,
-    description: Option<String>,
 }
 
 // ---- createOrganization ----
@@ -321,11 +1028,12 @@ struct CreateOrganizationInput<'a> {
 }
 
 #[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
 struct CreateOrganizationData {
-    createOrganization: OrganizationResponse,
+    create_organization: OrganizationResponse,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 struct OrganizationResponse {
     id: i32,
     name: String,
@@ -341,8 +1049,9 @@ struct CreateTeamVariables<'a> {
 }
 
 #[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
 struct CreateTeamInput<'a> {
-    organizationId: i32,
+    organization_id: i32,
     name: &'a str,
     slug: &'a str,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -350,557 +1059,4690 @@ struct CreateTeamInput<'a> {
 }
 
 #[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
 struct CreateTeamData {
-    createTeam: TeamResponse,
+    create_team: TeamResponse,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
 struct TeamResponse {
     id: i32,
-    organizationId: i32,
+    organization_id: i32,
     name: String,
     slug: String,
     description: Option<String>,
 }
 
-// -----------------
-// GraphQL documents
-// -----------------
+// ---- promoteBuild ----
 
-static REGISTER_USER_MUTATION: &str = r#"
-mutation RegisterUser($input: RegisterUserInput!) {
-  registerUser(input: $input) {
-    user {
-      id
-      name
-      email
-    }
-    token {
-      token
-      description
-    }
-  }
+#[derive(Debug, Serialize)]
+struct PromoteBuildVariables<'a> {
+    input: PromoteBuildInput<'a>,
 }
-"#;
 
-static CREATE_ORGANIZATION_MUTATION: &str = r#"
-mutation CreateOrganization($input: CreateOrganizationInput!) {
-  createOrganization(input: $input) {
-    id
-    name
-    slug
-    description
-  }
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PromoteBuildInput<'a> {
+    build_id: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    version: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    changelog: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    organization_id: Option<i64>,
 }
-"#;
 
-static CREATE_TEAM_MUTATION: &str = r#"
-mutation CreateTeam($input: CreateTeamInput!) {
-  createTeam(input: $input) {
-    id
-    organizationId
-    name
-    slug
-    description
-  }
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PromoteBuildData {
+    promote_build: ReleaseResponse,
 }
-"#;
 
-// -----------------
-// API call helpers
-// -----------------
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ReleaseResponse {
+    id: i64,
+    app_id: i64,
+    version: String,
+    image_ref: Option<String>,
+}
+
+// ---- changePassword ----
+
+#[derive(Debug, Serialize)]
+struct ChangePasswordVariables<'a> {
+    input: ChangePasswordInput<'a>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ChangePasswordInput<'a> {
+    current_password: &'a str,
+    new_password: &'a str,
+    revoke_other_sessions: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ChangePasswordData {
+    change_password: ChangePasswordResponse,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ChangePasswordResponse {
+    revoked_sessions: i64,
+}
+
+// ---- revokeToken ----
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RevokeTokenData {
+    revoke_token: bool,
+}
+
+// ---- createDeploy ----
+
+#[derive(Debug, Serialize)]
+struct CreateDeployVariables<'a> {
+    input: CreateDeployInput<'a>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CreateDeployInput<'a> {
+    app_id: i64,
+    release_id: i64,
+    environment: &'a str,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CreateDeployData {
+    create_deploy: DeployResponse,
+}
+
+#[derive(Debug, Deserialize)]
+struct DeployResponse {
+    id: i64,
+    environment: String,
+    status: String,
+}
+
+// ---- deploys ----
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DeploysVariables<'a> {
+    app_id: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    environment: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    limit: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    since: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    until: Option<&'a str>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DeploysData {
+    deploys: Vec<DeployRow>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DeployRow {
+    id: i64,
+    release_id: i64,
+    environment: String,
+    status: String,
+    status_changed_at: String,
+}
+
+// ---- createDeploys ----
+
+#[derive(Debug, Serialize)]
+struct CreateDeploysVariables<'a> {
+    input: CreateDeploysInput<'a>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CreateDeploysInput<'a> {
+    app_id: i64,
+    release_id: i64,
+    environments: &'a [String],
+    skip_busy: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CreateDeploysData {
+    create_deploys: CreateDeploysResponse,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CreateDeploysResponse {
+    deploys: Vec<DeployResponse>,
+    skipped_environments: Vec<String>,
+}
+
+// ---- setAppSecret ----
+
+#[derive(Debug, Serialize)]
+struct SetAppSecretVariables<'a> {
+    input: SetAppSecretInput<'a>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SetAppSecretInput<'a> {
+    app_id: i64,
+    environment: &'a str,
+    key: &'a str,
+    value: &'a str,
+    #[serde(rename = "type")]
+    kind: &'static str,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SetAppSecretData {
+    set_app_secret: AppSecretResponse,
+}
+
+#[derive(Debug, Deserialize)]
+struct AppSecretResponse {
+    key: String,
+    environment: String,
+    #[serde(rename = "type")]
+    kind: String,
+    size: i64,
+}
+
+// ---- updateAppSecretsBulk ----
+
+#[derive(Debug, Serialize)]
+struct SyncAppSecretsVariables<'a> {
+    input: SyncAppSecretsInput<'a>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SyncAppSecretsInput<'a> {
+    app_id: i64,
+    environment: &'a str,
+    secrets: Vec<SecretEntryInput<'a>>,
+    prune: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct SecretEntryInput<'a> {
+    key: &'a str,
+    value: &'a str,
+    #[serde(rename = "type")]
+    kind: &'static str,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SyncAppSecretsData {
+    update_app_secrets_bulk: SecretsSyncSummary,
+}
+
+#[derive(Debug, Deserialize)]
+struct SecretsSyncSummary {
+    added: Vec<String>,
+    updated: Vec<String>,
+}
+
+// ---- appSecrets ----
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AppSecretsVariables<'a> {
+    app_id: i64,
+    environment: &'a str,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AppSecretsData {
+    app_secrets: Vec<SecretRow>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SecretRow {
+    key: String,
+    #[serde(rename = "type")]
+    kind: String,
+    /// Dropped before printing unless `--show-values`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    value: Option<String>,
+    updated_at: String,
+}
+
+// ---- deleteAppSecret ----
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DeleteAppSecretVariables<'a> {
+    app_id: i64,
+    environment: &'a str,
+    key: &'a str,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DeleteAppSecretData {
+    delete_app_secret: bool,
+}
+
+// ---- createApp ----
+
+#[derive(Debug, Serialize)]
+struct CreateAppVariables<'a> {
+    input: CreateAppInput<'a>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CreateAppInput<'a> {
+    organization_id: i64,
+    team_id: Option<i64>,
+    name: &'a str,
+    slug: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    repo_url: Option<&'a str>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CreateAppData {
+    create_app: AppResponse,
+}
+
+// ---- myOrganizations ----
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MyOrganizationsData {
+    my_organizations: Vec<MyOrganizationResponse>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct MyOrganizationResponse {
+    organization: OrganizationResponse,
+    role: String,
+}
+
+// ---- teams ----
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TeamsVariables {
+    organization_id: i64,
+    limit: i64,
+    offset: i64,
+}
+
+#[derive(Debug, Deserialize)]
+struct TeamsData {
+    teams: Vec<TeamResponse>,
+}
+
+// ---- apps ----
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AppsVariables {
+    organization_id: i64,
+    limit: i64,
+    offset: i64,
+}
+
+#[derive(Debug, Deserialize)]
+struct AppsData {
+    apps: Vec<AppResponse>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AppResponse {
+    id: i64,
+    team_id: Option<i64>,
+    name: String,
+    slug: String,
+    repo_url: Option<String>,
+    updated_at: String,
+}
+
+// ---- builds ----
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BuildsVariables<'a> {
+    app_id: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    limit: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    since: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    until: Option<&'a str>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BuildsData {
+    builds: Vec<BuildRow>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BuildRow {
+    id: i64,
+    status: String,
+    trigger: String,
+    commit_sha: Option<String>,
+    created_at: String,
+}
+
+// ---- buildLogs ----
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BuildLogsVariables {
+    build_id: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tail: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BuildLogsData {
+    build_logs: Vec<BuildLogChunk>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BuildLogChunk {
+    content: String,
+}
+
+// ---- search ----
+
+#[derive(Debug, Serialize)]
+struct SearchVariables<'a> {
+    term: &'a str,
+    limit: i64,
+}
+
+#[derive(Debug, Deserialize)]
+struct SearchData {
+    search: Vec<SearchHit>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "__typename")]
+enum SearchHit {
+    Organization { id: i64, name: String, slug: String },
+    Team { id: i64, name: String, slug: String },
+    App { id: i64, name: String, slug: String },
+}
+
+// ---- doctor ----
+
+#[derive(Debug, Serialize)]
+struct IdVariables {
+    id: i64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PingData {
+    api_version: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct MeData {
+    me: GqlUser,
+}
+
+#[derive(Debug, Deserialize)]
+struct OrganizationData {
+    organization: Option<OrganizationRef>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OrganizationRef {
+    slug: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct TeamData {
+    team: Option<TeamRef>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TeamRef {
+    slug: String,
+    organization_id: i64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BySlugVariables<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    organization_id: Option<i64>,
+    slug: &'a str,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct OrganizationBySlugData {
+    organization_by_slug: Option<IdRef>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TeamBySlugData {
+    team_by_slug: Option<IdRef>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AppBySlugData {
+    app_by_slug: Option<IdRef>,
+}
+
+#[derive(Debug, Deserialize)]
+struct IdRef {
+    id: i64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SlugPathVariables<'a> {
+    organization_slug: &'a str,
+    team_slug: &'a str,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TeamBySlugPathData {
+    team_by_slug_path: Option<TeamIds>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TeamIds {
+    id: i64,
+    organization_id: i64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DeleteOrganizationData {
+    delete_organization: IdRef,
+}
+
+// -----------------
+// GraphQL documents
+// -----------------
+
+static PING_QUERY: &str = r#"
+query Ping {
+  apiVersion
+}
+"#;
+
+static ME_QUERY: &str = r#"
+query Me {
+  me {
+    id
+    name
+    email
+  }
+}
+"#;
+
+static ORGANIZATION_QUERY: &str = r#"
+query Organization($id: Int!) {
+  organization(id: $id) {
+    slug
+  }
+}
+"#;
+
+static ORGANIZATION_BY_SLUG_QUERY: &str = r#"
+query OrganizationBySlug($slug: String!) {
+  organizationBySlug(slug: $slug) {
+    id
+  }
+}
+"#;
+
+static TEAM_BY_SLUG_QUERY: &str = r#"
+query TeamBySlug($organizationId: Int!, $slug: String!) {
+  teamBySlug(organizationId: $organizationId, slug: $slug) {
+    id
+  }
+}
+"#;
+
+static APP_BY_SLUG_QUERY: &str = r#"
+query AppBySlug($organizationId: Int!, $slug: String!) {
+  appBySlug(organizationId: $organizationId, slug: $slug) {
+    id
+  }
+}
+"#;
+
+static TEAM_BY_SLUG_PATH_QUERY: &str = r#"
+query TeamBySlugPath($organizationSlug: String!, $teamSlug: String!) {
+  teamBySlugPath(organizationSlug: $organizationSlug, teamSlug: $teamSlug) {
+    id
+    organizationId
+  }
+}
+"#;
+
+static TEAM_QUERY: &str = r#"
+query Team($id: Int!) {
+  team(id: $id) {
+    slug
+    organizationId
+  }
+}
+"#;
+
+static REGISTER_USER_MUTATION: &str = r#"
+mutation RegisterUser($input: RegisterUserInput!) {
+  registerUser(input: $input) {
+    user {
+      id
+      name
+      email
+    }
+    token {
+      token
+      description
+    }
+  }
+}
+"#;
+
+static AUTHENTICATE_MUTATION: &str = r#"
+mutation Authenticate($email: String!, $password: String!, $ttlDays: Int) {
+  authenticate(email: $email, password: $password, ttlDays: $ttlDays) {
+    user {
+      id
+      name
+      email
+    }
+    token {
+      token
+      description
+    }
+  }
+}
+"#;
+
+static CREATE_ORGANIZATION_MUTATION: &str = r#"
+mutation CreateOrganization($input: CreateOrganizationInput!) {
+  createOrganization(input: $input) {
+    id
+    name
+    slug
+    description
+  }
+}
+"#;
+
+static CREATE_TEAM_MUTATION: &str = r#"
+mutation CreateTeam($input: CreateTeamInput!) {
+  createTeam(input: $input) {
+    id
+    organizationId
+    name
+    slug
+    description
+  }
+}
+"#;
+
+static PROMOTE_BUILD_MUTATION: &str = r#"
+mutation PromoteBuild($input: PromoteBuildInput!) {
+  promoteBuild(input: $input) {
+    id
+    appId
+    version
+    imageRef
+  }
+}
+"#;
+
+static CHANGE_PASSWORD_MUTATION: &str = r#"
+mutation ChangePassword($input: ChangePasswordInput!) {
+  changePassword(input: $input) {
+    revokedSessions
+  }
+}
+"#;
+
+static CREATE_DEPLOYS_MUTATION: &str = r#"
+mutation CreateDeploys($input: CreateDeploysInput!) {
+  createDeploys(input: $input) {
+    deploys {
+      id
+      environment
+      status
+    }
+    skippedEnvironments
+  }
+}
+"#;
+
+static DELETE_ORGANIZATION_MUTATION: &str = r#"
+mutation DeleteOrganization($id: Int!) {
+  deleteOrganization(id: $id) {
+    id
+  }
+}
+"#;
+
+static REVOKE_TOKEN_MUTATION: &str = r#"
+mutation RevokeToken {
+  revokeToken
+}
+"#;
+
+static BUILDS_QUERY: &str = r#"
+query Builds($appId: Int!, $limit: Int, $since: DateTime, $until: DateTime) {
+  builds(appId: $appId, limit: $limit, since: $since, until: $until) {
+    id
+    status
+    trigger
+    commitSha
+    createdAt
+  }
+}
+"#;
+
+static DEPLOYS_QUERY: &str = r#"
+query Deploys(
+  $appId: Int!
+  $environment: String
+  $limit: Int
+  $since: DateTime
+  $until: DateTime
+) {
+  deploys(
+    appId: $appId
+    environment: $environment
+    limit: $limit
+    since: $since
+    until: $until
+  ) {
+    id
+    releaseId
+    environment
+    status
+    statusChangedAt
+  }
+}
+"#;
+
+static BUILD_LOGS_QUERY: &str = r#"
+query BuildLogs($buildId: Int!, $tail: Int) {
+  buildLogs(buildId: $buildId, tail: $tail) {
+    content
+  }
+}
+"#;
+
+static SEARCH_QUERY: &str = r#"
+query Search($term: String!, $limit: Int!) {
+  search(term: $term, limit: $limit) {
+    __typename
+    ... on Organization { id name slug }
+    ... on Team { id name slug }
+    ... on App { id name slug }
+  }
+}
+"#;
+
+static CREATE_DEPLOY_MUTATION: &str = r#"
+mutation CreateDeploy($input: CreateDeployInput!) {
+  createDeploy(input: $input) {
+    id
+    environment
+    status
+  }
+}
+"#;
+
+static CREATE_APP_MUTATION: &str = r#"
+mutation CreateApp($input: CreateAppInput!) {
+  createApp(input: $input) {
+    id
+    teamId
+    name
+    slug
+    repoUrl
+    updatedAt
+  }
+}
+"#;
+
+static MY_ORGANIZATIONS_QUERY: &str = r#"
+query MyOrganizations {
+  myOrganizations {
+    organization {
+      id
+      name
+      slug
+      description
+    }
+    role
+  }
+}
+"#;
+
+static TEAMS_QUERY: &str = r#"
+query Teams($organizationId: Int!, $limit: Int!, $offset: Int!) {
+  teams(organizationId: $organizationId, limit: $limit, offset: $offset) {
+    id
+    organizationId
+    name
+    slug
+    description
+  }
+}
+"#;
+
+static APPS_QUERY: &str = r#"
+query Apps($organizationId: Int!, $limit: Int!, $offset: Int!) {
+  apps(organizationId: $organizationId, limit: $limit, offset: $offset) {
+    id
+    teamId
+    name
+    slug
+    repoUrl
+    updatedAt
+  }
+}
+"#;
+
+static APP_SECRETS_QUERY: &str = r#"
+query AppSecrets($appId: Int!, $environment: String!) {
+  appSecrets(appId: $appId, environment: $environment) {
+    key
+    type
+    value
+    updatedAt
+  }
+}
+"#;
+
+static UPDATE_APP_SECRETS_BULK_MUTATION: &str = r#"
+mutation UpdateAppSecretsBulk($input: SyncAppSecretsInput!) {
+  updateAppSecretsBulk(input: $input) {
+    added
+    updated
+  }
+}
+"#;
+
+static DELETE_APP_SECRET_MUTATION: &str = r#"
+mutation DeleteAppSecret($appId: Int!, $environment: String!, $key: String!) {
+  deleteAppSecret(appId: $appId, environment: $environment, key: $key)
+}
+"#;
+
+static SET_APP_SECRET_MUTATION: &str = r#"
+mutation SetAppSecret($input: SetAppSecretInput!) {
+  setAppSecret(input: $input) {
+    key
+    environment
+    type
+    size
+  }
+}
+"#;
+
+// -----------------
+// API call helpers
+// -----------------
+
+/// Attempts made while the server answers `429 Too Many Requests`.
+const MAX_RATE_LIMITED_ATTEMPTS: u32 = 3;
+/// Longest `Retry-After` the CLI waits for before giving up.
+const MAX_RETRY_AFTER_SECS: u64 = 30;
+/// Page size used to fetch complete lists; the server's maximum.
+const LIST_PAGE_SIZE: i64 = 100;
+
+trait SendRetrying {
+    /// `send()`, but on `429` wait as told by `Retry-After` and retry.
+    ///
+    /// The 429 response is returned as is after a few attempts, when the
+    /// wait would be too long, or when the request body cannot be cloned.
+    async fn send_retrying(self) -> reqwest::Result<Response>;
+}
+
+impl SendRetrying for RequestBuilder {
+    async fn send_retrying(self) -> reqwest::Result<Response> {
+        let mut attempt = 1;
+        loop {
+            let Some(request) = self.try_clone() else {
+                return self.send().await;
+            };
+            let res = request.send().await?;
+            if res.status() != StatusCode::TOO_MANY_REQUESTS
+                || attempt >= MAX_RATE_LIMITED_ATTEMPTS
+            {
+                return Ok(res);
+            }
+
+            let Some(wait) =
+                retry_after(&res).filter(|s| *s <= MAX_RETRY_AFTER_SECS)
+            else {
+                return Ok(res);
+            };
+            eprintln!("Rate limited by the server, retrying in {wait}s...");
+            tokio::time::sleep(std::time::Duration::from_secs(wait)).await;
+            attempt += 1;
+        }
+    }
+}
+
+/// `Retry-After` in seconds (the HTTP-date form is not supported).
+fn retry_after(res: &Response) -> Option<u64> {
+    res.headers().get(RETRY_AFTER)?.to_str().ok()?.trim().parse().ok()
+}
+
+/// Turn a decoded GraphQL response into its data, flattening any errors.
+///
+/// The HTTP status must already have been checked by the caller.
+fn into_result<D>(resp: GqlResponse<D>) -> Result<D> {
+    if let Some(errors) = resp.errors {
+        let msg = errors
+            .into_iter()
+            .map(|e| e.message)
+            .collect::<Vec<_>>()
+            .join("; ");
+        anyhow::bail!("GraphQL error(s): {msg}");
+    }
+
+    resp.data
+        .ok_or_else(|| anyhow::anyhow!("Missing data in GraphQL response"))
+}
+
+async fn gql_authenticate(
+    client: &Client,
+    base_url: &str,
+    email: &str,
+    password: &str,
+    ttl_days: Option<i64>,
+) -> Result<RegisterUserPayload> {
+    let req_body = GqlRequest {
+        query: AUTHENTICATE_MUTATION,
+        variables: Some(AuthenticateVariables { email, password, ttl_days }),
+    };
+
+    let res = client
+        .post(base_url)
+        .json(&req_body)
+        .send_retrying()
+        .await
+        .context("Failed to send authenticate GraphQL request")?;
+
+    if !res.status().is_success() {
+        anyhow::bail!("authenticate failed with HTTP status {}", res.status());
+    }
+
+    let gql: GqlResponse<AuthenticateData> = res
+        .json()
+        .await
+        .context("Failed to parse GraphQL response for authenticate")?;
+
+    let data = into_result(gql)?;
+    Ok(data.authenticate)
+}
 
 async fn gql_register_user(
     client: &Client,
-    base_url: &str,
-    name: &str,
-    email: &str,
-    password: &str,
-) -> Result<RegisterUserPayload> {
-    let req_body = GqlRequest {
-        query: REGISTER_USER_MUTATION,
-        variables: Some(RegisterUserVariables {
-            input: RegisterUserInput { name, email, password },
-        }),
+    base_url: &str,
+    name: &str,
+    email: &str,
+    password: &str,
+    ttl_days: Option<i64>,
+) -> Result<RegisterUserPayload> {
+    let req_body = GqlRequest {
+        query: REGISTER_USER_MUTATION,
+        variables: Some(RegisterUserVariables {
+            input: RegisterUserInput { name, email, password, ttl_days },
+        }),
+    };
+
+    let res = client
+        .post(base_url)
+        .json(&req_body)
+        .send_retrying()
+        .await
+        .context("Failed to send registerUser GraphQL request")?;
+
+    if !res.status().is_success() {
+        anyhow::bail!("registerUser failed with HTTP status {}", res.status());
+    }
+
+    let gql: GqlResponse<RegisterUserData> = res
+        .json()
+        .await
+        .context("Failed to parse GraphQL response for registerUser")?;
+
+    let data = into_result(gql)?;
+    Ok(data.register_user)
+}
+
+async fn gql_create_org(
+    client: &Client,
+    cfg: &Config,
+    name: &str,
+    slug: &str,
+    description: Option<&str>,
+) -> Result<OrganizationResponse> {
+    let req_body = GqlRequest {
+        query: CREATE_ORGANIZATION_MUTATION,
+        variables: Some(CreateOrganizationVariables {
+            input: CreateOrganizationInput { name, slug, description },
+        }),
+    };
+
+    let res = client
+        .post(&cfg.auth.base_url)
+        .bearer_auth(&cfg.auth.token)
+        .json(&req_body)
+        .send_retrying()
+        .await
+        .context("Failed to send createOrganization GraphQL request")?;
+
+    if !res.status().is_success() {
+        anyhow::bail!(
+            "createOrganization failed with HTTP status {}",
+            res.status()
+        );
+    }
+
+    let gql: GqlResponse<CreateOrganizationData> = res
+        .json()
+        .await
+        .context("Failed to parse GraphQL response for createOrganization")?;
+
+    let data = into_result(gql)?;
+    Ok(data.create_organization)
+}
+
+async fn gql_create_team(
+    client: &Client,
+    cfg: &Config,
+    org_id: i64,
+    name: &str,
+    slug: &str,
+    description: Option<&str>,
+) -> Result<TeamResponse> {
+    let req_body = GqlRequest {
+        query: CREATE_TEAM_MUTATION,
+        variables: Some(CreateTeamVariables {
+            input: CreateTeamInput {
+                organization_id: org_id as i32,
+                name,
+                slug,
+                description,
+            },
+        }),
+    };
+
+    let res = client
+        .post(&cfg.auth.base_url)
+        .bearer_auth(&cfg.auth.token)
+        .json(&req_body)
+        .send_retrying()
+        .await
+        .context("Failed to send createTeam GraphQL request")?;
+
+    if !res.status().is_success() {
+        anyhow::bail!("createTeam failed with HTTP status {}", res.status());
+    }
+
+    let gql: GqlResponse<CreateTeamData> = res
+        .json()
+        .await
+        .context("Failed to parse GraphQL response for createTeam")?;
+
+    let data = into_result(gql)?;
+    Ok(data.create_team)
+}
+
+async fn gql_promote_build(
+    client: &Client,
+    cfg: &Config,
+    input: PromoteBuildInput<'_>,
+) -> Result<ReleaseResponse> {
+    let req_body = GqlRequest {
+        query: PROMOTE_BUILD_MUTATION,
+        variables: Some(PromoteBuildVariables { input }),
+    };
+
+    let res = client
+        .post(&cfg.auth.base_url)
+        .bearer_auth(&cfg.auth.token)
+        .json(&req_body)
+        .send_retrying()
+        .await
+        .context("Failed to send promoteBuild GraphQL request")?;
+
+    if !res.status().is_success() {
+        anyhow::bail!("promoteBuild failed with HTTP status {}", res.status());
+    }
+
+    let gql: GqlResponse<PromoteBuildData> = res
+        .json()
+        .await
+        .context("Failed to parse GraphQL response for promoteBuild")?;
+
+    let data = into_result(gql)?;
+    Ok(data.promote_build)
+}
+
+async fn gql_change_password(
+    client: &Client,
+    cfg: &Config,
+    input: ChangePasswordInput<'_>,
+) -> Result<ChangePasswordResponse> {
+    let req_body = GqlRequest {
+        query: CHANGE_PASSWORD_MUTATION,
+        variables: Some(ChangePasswordVariables { input }),
+    };
+
+    let res = client
+        .post(&cfg.auth.base_url)
+        .bearer_auth(&cfg.auth.token)
+        .json(&req_body)
+        .send_retrying()
+        .await
+        .context("Failed to send changePassword GraphQL request")?;
+
+    if !res.status().is_success() {
+        anyhow::bail!(
+            "changePassword failed with HTTP status {}",
+            res.status()
+        );
+    }
+
+    let gql: GqlResponse<ChangePasswordData> = res
+        .json()
+        .await
+        .context("Failed to parse GraphQL response for changePassword")?;
+
+    let data = into_result(gql)?;
+    Ok(data.change_password)
+}
+
+async fn gql_create_deploys(
+    client: &Client,
+    cfg: &Config,
+    input: CreateDeploysInput<'_>,
+) -> Result<CreateDeploysResponse> {
+    let req_body = GqlRequest {
+        query: CREATE_DEPLOYS_MUTATION,
+        variables: Some(CreateDeploysVariables { input }),
+    };
+
+    let res = client
+        .post(&cfg.auth.base_url)
+        .bearer_auth(&cfg.auth.token)
+        .json(&req_body)
+        .send_retrying()
+        .await
+        .context("Failed to send createDeploys GraphQL request")?;
+
+    if !res.status().is_success() {
+        anyhow::bail!(
+            "createDeploys failed with HTTP status {}",
+            res.status()
+        );
+    }
+
+    let gql: GqlResponse<CreateDeploysData> = res
+        .json()
+        .await
+        .context("Failed to parse GraphQL response for createDeploys")?;
+
+    let data = into_result(gql)?;
+    Ok(data.create_deploys)
+}
+
+async fn gql_delete_organization(
+    client: &Client,
+    cfg: &Config,
+    id: i64,
+) -> Result<i64> {
+    let req_body = GqlRequest {
+        query: DELETE_ORGANIZATION_MUTATION,
+        variables: Some(IdVariables { id }),
+    };
+
+    let res = client
+        .post(&cfg.auth.base_url)
+        .bearer_auth(&cfg.auth.token)
+        .json(&req_body)
+        .send_retrying()
+        .await
+        .context("Failed to send deleteOrganization GraphQL request")?;
+
+    if !res.status().is_success() {
+        anyhow::bail!(
+            "deleteOrganization failed with HTTP status {}",
+            res.status()
+        );
+    }
+
+    let gql: GqlResponse<DeleteOrganizationData> = res
+        .json()
+        .await
+        .context("Failed to parse GraphQL response for deleteOrganization")?;
+
+    let data = into_result(gql)?;
+    Ok(data.delete_organization.id)
+}
+
+async fn gql_revoke_token(client: &Client, cfg: &Config) -> Result<bool> {
+    let req_body: GqlRequest<()> =
+        GqlRequest { query: REVOKE_TOKEN_MUTATION, variables: None };
+
+    let res = client
+        .post(&cfg.auth.base_url)
+        .bearer_auth(&cfg.auth.token)
+        .json(&req_body)
+        .send_retrying()
+        .await
+        .context("Failed to send revokeToken GraphQL request")?;
+
+    if !res.status().is_success() {
+        anyhow::bail!("revokeToken failed with HTTP status {}", res.status());
+    }
+
+    let gql: GqlResponse<RevokeTokenData> = res
+        .json()
+        .await
+        .context("Failed to parse GraphQL response for revokeToken")?;
+
+    let data = into_result(gql)?;
+    Ok(data.revoke_token)
+}
+
+async fn gql_create_deploy(
+    client: &Client,
+    cfg: &Config,
+    app_id: i64,
+    release_id: i64,
+    environment: &str,
+) -> Result<DeployResponse> {
+    let req_body = GqlRequest {
+        query: CREATE_DEPLOY_MUTATION,
+        variables: Some(CreateDeployVariables {
+            input: CreateDeployInput { app_id, release_id, environment },
+        }),
+    };
+
+    let res = client
+        .post(&cfg.auth.base_url)
+        .bearer_auth(&cfg.auth.token)
+        .json(&req_body)
+        .send_retrying()
+        .await
+        .context("Failed to send createDeploy GraphQL request")?;
+
+    if !res.status().is_success() {
+        anyhow::bail!("createDeploy failed with HTTP status {}", res.status());
+    }
+
+    let gql: GqlResponse<CreateDeployData> = res
+        .json()
+        .await
+        .context("Failed to parse GraphQL response for createDeploy")?;
+
+    let data = into_result(gql)?;
+    Ok(data.create_deploy)
+}
+
+async fn gql_create_app(
+    client: &Client,
+    cfg: &Config,
+    input: CreateAppInput<'_>,
+) -> Result<AppResponse> {
+    let req_body = GqlRequest {
+        query: CREATE_APP_MUTATION,
+        variables: Some(CreateAppVariables { input }),
+    };
+
+    let res = client
+        .post(&cfg.auth.base_url)
+        .bearer_auth(&cfg.auth.token)
+        .json(&req_body)
+        .send_retrying()
+        .await
+        .context("Failed to send createApp GraphQL request")?;
+
+    if !res.status().is_success() {
+        anyhow::bail!("createApp failed with HTTP status {}", res.status());
+    }
+
+    let gql: GqlResponse<CreateAppData> = res
+        .json()
+        .await
+        .context("Failed to parse GraphQL response for createApp")?;
+
+    let data = into_result(gql)?;
+    Ok(data.create_app)
+}
+
+async fn gql_apps(
+    client: &Client,
+    cfg: &Config,
+    organization_id: i64,
+) -> Result<Vec<AppResponse>> {
+    let mut apps = Vec::new();
+    loop {
+        let req_body = GqlRequest {
+            query: APPS_QUERY,
+            variables: Some(AppsVariables {
+                organization_id,
+                limit: LIST_PAGE_SIZE,
+                offset: apps.len() as i64,
+            }),
+        };
+
+        let res = client
+            .post(&cfg.auth.base_url)
+            .bearer_auth(&cfg.auth.token)
+            .json(&req_body)
+            .send_retrying()
+            .await
+            .context("Failed to send apps GraphQL request")?;
+
+        if !res.status().is_success() {
+            anyhow::bail!("apps failed with HTTP status {}", res.status());
+        }
+
+        let gql: GqlResponse<AppsData> = res
+            .json()
+            .await
+            .context("Failed to parse GraphQL response for apps")?;
+
+        let page = into_result(gql)?.apps;
+        let last = (page.len() as i64) < LIST_PAGE_SIZE;
+        apps.extend(page);
+        if last {
+            return Ok(apps);
+        }
+    }
+}
+
+async fn gql_my_organizations(
+    client: &Client,
+    cfg: &Config,
+) -> Result<Vec<MyOrganizationResponse>> {
+    let req_body: GqlRequest<()> =
+        GqlRequest { query: MY_ORGANIZATIONS_QUERY, variables: None };
+
+    let res = client
+        .post(&cfg.auth.base_url)
+        .bearer_auth(&cfg.auth.token)
+        .json(&req_body)
+        .send_retrying()
+        .await
+        .context("Failed to send myOrganizations GraphQL request")?;
+
+    if !res.status().is_success() {
+        anyhow::bail!(
+            "myOrganizations failed with HTTP status {}",
+            res.status()
+        );
+    }
+
+    let gql: GqlResponse<MyOrganizationsData> = res
+        .json()
+        .await
+        .context("Failed to parse GraphQL response for myOrganizations")?;
+
+    let data = into_result(gql)?;
+    Ok(data.my_organizations)
+}
+
+async fn gql_teams(
+    client: &Client,
+    cfg: &Config,
+    organization_id: i64,
+) -> Result<Vec<TeamResponse>> {
+    let mut teams = Vec::new();
+    loop {
+        let req_body = GqlRequest {
+            query: TEAMS_QUERY,
+            variables: Some(TeamsVariables {
+                organization_id,
+                limit: LIST_PAGE_SIZE,
+                offset: teams.len() as i64,
+            }),
+        };
+
+        let res = client
+            .post(&cfg.auth.base_url)
+            .bearer_auth(&cfg.auth.token)
+            .json(&req_body)
+            .send_retrying()
+            .await
+            .context("Failed to send teams GraphQL request")?;
+
+        if !res.status().is_success() {
+            anyhow::bail!("teams failed with HTTP status {}", res.status());
+        }
+
+        let gql: GqlResponse<TeamsData> = res
+            .json()
+            .await
+            .context("Failed to parse GraphQL response for teams")?;
+
+        let page = into_result(gql)?.teams;
+        let last = (page.len() as i64) < LIST_PAGE_SIZE;
+        teams.extend(page);
+        if last {
+            return Ok(teams);
+        }
+    }
+}
+
+async fn gql_set_app_secret(
+    client: &Client,
+    cfg: &Config,
+    input: SetAppSecretInput<'_>,
+) -> Result<AppSecretResponse> {
+    let req_body = GqlRequest {
+        query: SET_APP_SECRET_MUTATION,
+        variables: Some(SetAppSecretVariables { input }),
+    };
+
+    let res = client
+        .post(&cfg.auth.base_url)
+        .bearer_auth(&cfg.auth.token)
+        .json(&req_body)
+        .send_retrying()
+        .await
+        .context("Failed to send setAppSecret GraphQL request")?;
+
+    if !res.status().is_success() {
+        anyhow::bail!("setAppSecret failed with HTTP status {}", res.status());
+    }
+
+    let gql: GqlResponse<SetAppSecretData> = res
+        .json()
+        .await
+        .context("Failed to parse GraphQL response for setAppSecret")?;
+
+    let data = into_result(gql)?;
+    Ok(data.set_app_secret)
+}
+
+async fn gql_update_app_secrets_bulk(
+    client: &Client,
+    cfg: &Config,
+    input: SyncAppSecretsInput<'_>,
+) -> Result<SecretsSyncSummary> {
+    let req_body = GqlRequest {
+        query: UPDATE_APP_SECRETS_BULK_MUTATION,
+        variables: Some(SyncAppSecretsVariables { input }),
+    };
+
+    let res = client
+        .post(&cfg.auth.base_url)
+        .bearer_auth(&cfg.auth.token)
+        .json(&req_body)
+        .send_retrying()
+        .await
+        .context("Failed to send updateAppSecretsBulk GraphQL request")?;
+
+    if !res.status().is_success() {
+        anyhow::bail!(
+            "updateAppSecretsBulk failed with HTTP status {}",
+            res.status()
+        );
+    }
+
+    let gql: GqlResponse<SyncAppSecretsData> = res.json().await.context(
+        "Failed to parse GraphQL response for updateAppSecretsBulk",
+    )?;
+
+    let data = into_result(gql)?;
+    Ok(data.update_app_secrets_bulk)
+}
+
+async fn gql_app_secrets(
+    client: &Client,
+    cfg: &Config,
+    app_id: i64,
+    environment: &str,
+) -> Result<Vec<SecretRow>> {
+    let req_body = GqlRequest {
+        query: APP_SECRETS_QUERY,
+        variables: Some(AppSecretsVariables { app_id, environment }),
+    };
+
+    let res = client
+        .post(&cfg.auth.base_url)
+        .bearer_auth(&cfg.auth.token)
+        .json(&req_body)
+        .send_retrying()
+        .await
+        .context("Failed to send appSecrets GraphQL request")?;
+
+    if !res.status().is_success() {
+        anyhow::bail!("appSecrets failed with HTTP status {}", res.status());
+    }
+
+    let gql: GqlResponse<AppSecretsData> = res
+        .json()
+        .await
+        .context("Failed to parse GraphQL response for appSecrets")?;
+
+    let data = into_result(gql)?;
+    Ok(data.app_secrets)
+}
+
+async fn gql_delete_app_secret(
+    client: &Client,
+    cfg: &Config,
+    app_id: i64,
+    environment: &str,
+    key: &str,
+) -> Result<bool> {
+    let req_body = GqlRequest {
+        query: DELETE_APP_SECRET_MUTATION,
+        variables: Some(DeleteAppSecretVariables { app_id, environment, key }),
+    };
+
+    let res = client
+        .post(&cfg.auth.base_url)
+        .bearer_auth(&cfg.auth.token)
+        .json(&req_body)
+        .send_retrying()
+        .await
+        .context("Failed to send deleteAppSecret GraphQL request")?;
+
+    if !res.status().is_success() {
+        anyhow::bail!(
+            "deleteAppSecret failed with HTTP status {}",
+            res.status()
+        );
+    }
+
+    let gql: GqlResponse<DeleteAppSecretData> = res
+        .json()
+        .await
+        .context("Failed to parse GraphQL response for deleteAppSecret")?;
+
+    let data = into_result(gql)?;
+    Ok(data.delete_app_secret)
+}
+
+async fn gql_builds(
+    client: &Client,
+    cfg: &Config,
+    variables: BuildsVariables<'_>,
+) -> Result<Vec<BuildRow>> {
+    let req_body =
+        GqlRequest { query: BUILDS_QUERY, variables: Some(variables) };
+
+    let res = client
+        .post(&cfg.auth.base_url)
+        .bearer_auth(&cfg.auth.token)
+        .json(&req_body)
+        .send_retrying()
+        .await
+        .context("Failed to send builds GraphQL request")?;
+
+    if !res.status().is_success() {
+        anyhow::bail!("builds failed with HTTP status {}", res.status());
+    }
+
+    let gql: GqlResponse<BuildsData> = res
+        .json()
+        .await
+        .context("Failed to parse GraphQL response for builds")?;
+
+    let data = into_result(gql)?;
+    Ok(data.builds)
+}
+
+async fn gql_deploys(
+    client: &Client,
+    cfg: &Config,
+    variables: DeploysVariables<'_>,
+) -> Result<Vec<DeployRow>> {
+    let req_body =
+        GqlRequest { query: DEPLOYS_QUERY, variables: Some(variables) };
+
+    let res = client
+        .post(&cfg.auth.base_url)
+        .bearer_auth(&cfg.auth.token)
+        .json(&req_body)
+        .send_retrying()
+        .await
+        .context("Failed to send deploys GraphQL request")?;
+
+    if !res.status().is_success() {
+        anyhow::bail!("deploys failed with HTTP status {}", res.status());
+    }
+
+    let gql: GqlResponse<DeploysData> = res
+        .json()
+        .await
+        .context("Failed to parse GraphQL response for deploys")?;
+
+    let data = into_result(gql)?;
+    Ok(data.deploys)
+}
+
+async fn gql_build_logs(
+    client: &Client,
+    cfg: &Config,
+    build_id: i64,
+    tail: Option<i64>,
+) -> Result<Vec<BuildLogChunk>> {
+    let req_body = GqlRequest {
+        query: BUILD_LOGS_QUERY,
+        variables: Some(BuildLogsVariables { build_id, tail }),
+    };
+
+    let res = client
+        .post(&cfg.auth.base_url)
+        .bearer_auth(&cfg.auth.token)
+        .json(&req_body)
+        .send_retrying()
+        .await
+        .context("Failed to send buildLogs GraphQL request")?;
+
+    if !res.status().is_success() {
+        anyhow::bail!("buildLogs failed with HTTP status {}", res.status());
+    }
+
+    let gql: GqlResponse<BuildLogsData> = res
+        .json()
+        .await
+        .context("Failed to parse GraphQL response for buildLogs")?;
+
+    let data = into_result(gql)?;
+    Ok(data.build_logs)
+}
+
+async fn gql_search(
+    client: &Client,
+    cfg: &Config,
+    term: &str,
+    limit: i64,
+) -> Result<Vec<SearchHit>> {
+    let req_body = GqlRequest {
+        query: SEARCH_QUERY,
+        variables: Some(SearchVariables { term, limit }),
+    };
+
+    let res = client
+        .post(&cfg.auth.base_url)
+        .bearer_auth(&cfg.auth.token)
+        .json(&req_body)
+        .send_retrying()
+        .await
+        .context("Failed to send search GraphQL request")?;
+
+    if !res.status().is_success() {
+        anyhow::bail!("search failed with HTTP status {}", res.status());
+    }
+
+    let gql: GqlResponse<SearchData> = res
+        .json()
+        .await
+        .context("Failed to parse GraphQL response for search")?;
+
+    let data = into_result(gql)?;
+    Ok(data.search)
+}
+
+async fn gql_ping(client: &Client, base_url: &str) -> Result<String> {
+    let req_body: GqlRequest<()> =
+        GqlRequest { query: PING_QUERY, variables: None };
+
+    let res = client
+        .post(base_url)
+        .json(&req_body)
+        .send_retrying()
+        .await
+        .context("Failed to send apiVersion GraphQL request")?;
+
+    if !res.status().is_success() {
+        anyhow::bail!("apiVersion failed with HTTP status {}", res.status());
+    }
+
+    let gql: GqlResponse<PingData> = res
+        .json()
+        .await
+        .context("Failed to parse GraphQL response for apiVersion")?;
+
+    let data = into_result(gql)?;
+    Ok(data.api_version)
+}
+
+async fn gql_me(client: &Client, cfg: &Config) -> Result<GqlUser> {
+    let req_body: GqlRequest<()> =
+        GqlRequest { query: ME_QUERY, variables: None };
+
+    let res = client
+        .post(&cfg.auth.base_url)
+        .bearer_auth(&cfg.auth.token)
+        .json(&req_body)
+        .send_retrying()
+        .await
+        .context("Failed to send me GraphQL request")?;
+
+    if !res.status().is_success() {
+        anyhow::bail!("me failed with HTTP status {}", res.status());
+    }
+
+    let gql: GqlResponse<MeData> =
+        res.json().await.context("Failed to parse GraphQL response for me")?;
+
+    let data = into_result(gql)?;
+    Ok(data.me)
+}
+
+async fn gql_organization(
+    client: &Client,
+    cfg: &Config,
+    id: i64,
+) -> Result<Option<OrganizationRef>> {
+    let req_body = GqlRequest {
+        query: ORGANIZATION_QUERY,
+        variables: Some(IdVariables { id }),
+    };
+
+    let res = client
+        .post(&cfg.auth.base_url)
+        .bearer_auth(&cfg.auth.token)
+        .json(&req_body)
+        .send_retrying()
+        .await
+        .context("Failed to send organization GraphQL request")?;
+
+    if !res.status().is_success() {
+        anyhow::bail!("organization failed with HTTP status {}", res.status());
+    }
+
+    let gql: GqlResponse<OrganizationData> = res
+        .json()
+        .await
+        .context("Failed to parse GraphQL response for organization")?;
+
+    let data = into_result(gql)?;
+    Ok(data.organization)
+}
+
+async fn gql_team(
+    client: &Client,
+    cfg: &Config,
+    id: i64,
+) -> Result<Option<TeamRef>> {
+    let req_body =
+        GqlRequest { query: TEAM_QUERY, variables: Some(IdVariables { id }) };
+
+    let res = client
+        .post(&cfg.auth.base_url)
+        .bearer_auth(&cfg.auth.token)
+        .json(&req_body)
+        .send_retrying()
+        .await
+        .context("Failed to send team GraphQL request")?;
+
+    if !res.status().is_success() {
+        anyhow::bail!("team failed with HTTP status {}", res.status());
+    }
+
+    let gql: GqlResponse<TeamData> = res
+        .json()
+        .await
+        .context("Failed to parse GraphQL response for team")?;
+
+    let data = into_result(gql)?;
+    Ok(data.team)
+}
+
+async fn gql_organization_by_slug(
+    client: &Client,
+    cfg: &Config,
+    slug: &str,
+) -> Result<Option<IdRef>> {
+    let req_body = GqlRequest {
+        query: ORGANIZATION_BY_SLUG_QUERY,
+        variables: Some(BySlugVariables { organization_id: None, slug }),
+    };
+
+    let res = client
+        .post(&cfg.auth.base_url)
+        .bearer_auth(&cfg.auth.token)
+        .json(&req_body)
+        .send_retrying()
+        .await
+        .context("Failed to send organizationBySlug GraphQL request")?;
+
+    if !res.status().is_success() {
+        anyhow::bail!(
+            "organizationBySlug failed with HTTP status {}",
+            res.status()
+        );
+    }
+
+    let gql: GqlResponse<OrganizationBySlugData> = res
+        .json()
+        .await
+        .context("Failed to parse GraphQL response for organizationBySlug")?;
+
+    let data = into_result(gql)?;
+    Ok(data.organization_by_slug)
+}
+
+async fn gql_team_by_slug(
+    client: &Client,
+    cfg: &Config,
+    organization_id: i64,
+    slug: &str,
+) -> Result<Option<IdRef>> {
+    let req_body = GqlRequest {
+        query: TEAM_BY_SLUG_QUERY,
+        variables: Some(BySlugVariables {
+            organization_id: Some(organization_id),
+            slug,
+        }),
+    };
+
+    let res = client
+        .post(&cfg.auth.base_url)
+        .bearer_auth(&cfg.auth.token)
+        .json(&req_body)
+        .send_retrying()
+        .await
+        .context("Failed to send teamBySlug GraphQL request")?;
+
+    if !res.status().is_success() {
+        anyhow::bail!("teamBySlug failed with HTTP status {}", res.status());
+    }
+
+    let gql: GqlResponse<TeamBySlugData> = res
+        .json()
+        .await
+        .context("Failed to parse GraphQL response for teamBySlug")?;
+
+    let data = into_result(gql)?;
+    Ok(data.team_by_slug)
+}
+
+async fn gql_app_by_slug(
+    client: &Client,
+    cfg: &Config,
+    organization_id: i64,
+    slug: &str,
+) -> Result<Option<IdRef>> {
+    let req_body = GqlRequest {
+        query: APP_BY_SLUG_QUERY,
+        variables: Some(BySlugVariables {
+            organization_id: Some(organization_id),
+            slug,
+        }),
+    };
+
+    let res = client
+        .post(&cfg.auth.base_url)
+        .bearer_auth(&cfg.auth.token)
+        .json(&req_body)
+        .send_retrying()
+        .await
+        .context("Failed to send appBySlug GraphQL request")?;
+
+    if !res.status().is_success() {
+        anyhow::bail!("appBySlug failed with HTTP status {}", res.status());
+    }
+
+    let gql: GqlResponse<AppBySlugData> = res
+        .json()
+        .await
+        .context("Failed to parse GraphQL response for appBySlug")?;
+
+    let data = into_result(gql)?;
+    Ok(data.app_by_slug)
+}
+
+async fn gql_team_by_slug_path(
+    client: &Client,
+    cfg: &Config,
+    organization_slug: &str,
+    team_slug: &str,
+) -> Result<Option<TeamIds>> {
+    let req_body = GqlRequest {
+        query: TEAM_BY_SLUG_PATH_QUERY,
+        variables: Some(SlugPathVariables { organization_slug, team_slug }),
+    };
+
+    let res = client
+        .post(&cfg.auth.base_url)
+        .bearer_auth(&cfg.auth.token)
+        .json(&req_body)
+        .send_retrying()
+        .await
+        .context("Failed to send teamBySlugPath GraphQL request")?;
+
+    if !res.status().is_success() {
+        anyhow::bail!(
+            "teamBySlugPath failed with HTTP status {}",
+            res.status()
+        );
+    }
+
+    let gql: GqlResponse<TeamBySlugPathData> = res
+        .json()
+        .await
+        .context("Failed to parse GraphQL response for teamBySlugPath")?;
+
+    let data = into_result(gql)?;
+    Ok(data.team_by_slug_path)
+}
+
+// --------------------
+// Command dispatcher
+// --------------------
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let cli = Cli::parse();
+    if let Some(profile) = cli.profile {
+        PROFILE_OVERRIDE.set(profile).ok();
+    }
+    let http_client = Client::new();
+    let overrides = ContextOverride { org: cli.org, team: cli.team };
+    let output = cli.output;
+
+    match cli.command {
+        Commands::Auth(cmd) => handle_auth(cmd, &http_client).await?,
+        Commands::Org(cmd) => handle_org(cmd, &http_client, output).await?,
+        Commands::Team(cmd) => {
+            handle_team(cmd, &http_client, &overrides, output).await?
+        }
+        Commands::Context(cmd) => handle_context(cmd, &overrides, output)?,
+        Commands::App(cmd) => {
+            handle_app(cmd, &http_client, &overrides, output).await?
+        }
+        Commands::Release(cmd) => {
+            handle_release(cmd, &http_client, &overrides).await?
+        }
+        Commands::Secret(cmd) => {
+            handle_secret(cmd, &http_client, &overrides, output).await?
+        }
+        Commands::Build(cmd) => {
+            handle_build(cmd, &http_client, output).await?
+        }
+        Commands::Deploy(cmd) => {
+            handle_deploy(cmd, &http_client, output).await?
+        }
+        Commands::Config(cmd) => handle_config(cmd)?,
+        Commands::Completions { shell } => handle_completions(shell),
+        Commands::Complete { kind, prefix } => {
+            handle_complete(kind, &prefix).await
+        }
+        Commands::Search { term, limit } => {
+            handle_search(&term, limit, &http_client).await?
+        }
+        Commands::Doctor => handle_doctor(&http_client, &overrides).await?,
+    }
+
+    Ok(())
+}
+
+// -------------
+// Auth handler
+// -------------
+
+async fn handle_auth(cmd: AuthCommand, client: &Client) -> Result<()> {
+    match cmd {
+        AuthCommand::Login { email, password, base_url, ttl_days } => {
+            let email = match email {
+                Some(v) => v,
+                None => prompt("Email: ")?,
+            };
+
+            let password = match password {
+                Some(v) => v,
+                None => prompt_password("Password: ")?,
+            };
+
+            let mut cfg = load_config().unwrap_or_default();
+
+            cfg.auth.base_url = resolve_base_url(
+                base_url,
+                &cfg.auth.base_url,
+                std::env::var(BASE_URL_ENV).ok(),
+            );
+
+            let payload = gql_authenticate(
+                client,
+                &cfg.auth.base_url,
+                &email,
+                &password,
+                ttl_days,
+            )
+            .await?;
+
+            cfg.auth.token = payload.token.token;
+            save_config(&cfg)?;
+            // clear session when logging in/registering
+            save_session(&Session::default())?;
+
+            println!(
+                "Logged in as {} ({})",
+                payload.user.name, payload.user.email
+            );
+        }
+        AuthCommand::Register {
+            name,
+            email,
+            password,
+            base_url,
+            ttl_days,
+        } => {
+            let name = match name {
+                Some(v) => v,
+                None => prompt("Name: ")?,
+            };
+
+            let email = match email {
+                Some(v) => v,
+                None => prompt("Email: ")?,
+            };
+
+            let password = match password {
+                Some(v) => v,
+                None => prompt_password("Password: ")?,
+            };
+
+            let mut cfg = load_config().unwrap_or_default();
+
+            cfg.auth.base_url = resolve_base_url(
+                base_url,
+                &cfg.auth.base_url,
+                std::env::var(BASE_URL_ENV).ok(),
+            );
+
+            let payload = gql_register_user(
+                client,
+                &cfg.auth.base_url,
+                &name,
+                &email,
+                &password,
+                ttl_days,
+            )
+            .await?;
+
+            cfg.auth.token = payload.token.token;
+            save_config(&cfg)?;
+            // clear session when logging in/registering
+            save_session(&Session::default())?;
+
+            println!(
+                "User registered and logged in as {} ({})",
+                payload.user.name, payload.user.email
+            );
+        }
+        AuthCommand::Passwd { current, new, keep_sessions } => {
+            let cfg = ensure_authenticated()?;
+
+            let current = match current {
+                Some(v) => v,
+                None => prompt_password("Current password: ")?,
+            };
+
+            let new = match new {
+                Some(v) => v,
+                None => {
+                    let first = prompt_password("New password: ")?;
+                    let again = prompt_password("Confirm new password: ")?;
+                    if first != again {
+                        anyhow::bail!("Passwords do not match");
+                    }
+                    first
+                }
+            };
+
+            let payload = gql_change_password(
+                client,
+                &cfg,
+                ChangePasswordInput {
+                    current_password: &current,
+                    new_password: &new,
+                    revoke_other_sessions: !keep_sessions,
+                },
+            )
+            .await?;
+
+            println!("Password changed.");
+            if keep_sessions {
+                println!("Other sessions were kept.");
+            } else {
+                println!(
+                    "Revoked {} other session(s); this one stays logged in.",
+                    payload.revoked_sessions
+                );
+            }
+        }
+        AuthCommand::Logout => {
+            let mut cfg = load_config().unwrap_or_default();
+            if !cfg.auth.token.is_empty() {
+                // Local state is cleared even if the server is unreachable.
+                if let Err(e) = gql_revoke_token(client, &cfg).await {
+                    eprintln!(
+                        "Warning: could not revoke the token on the server: {e:#}"
+                    );
+                }
+            }
+            cfg.auth.token.clear();
+            save_config(&cfg)?;
+            println!("Logged out. Token removed from config.toml");
+        }
+        AuthCommand::Status => {
+            let cfg = load_config().unwrap_or_default();
+            println!("Profile: {}", cfg.active_profile);
+            if cfg.auth.token.is_empty() {
+                println!("Not authenticated. Run `paastel auth login` first.");
+            } else {
+                println!("Authenticated.");
+                println!("GraphQL endpoint: {}", cfg.auth.base_url);
+                println!("Token: present");
+            }
+        }
+        AuthCommand::Whoami => {
+            let cfg = ensure_authenticated()?;
+            let me = gql_me(client, &cfg).await?;
+            println!("{} <{}>", me.name, me.email);
+        }
+        AuthCommand::Profiles => {
+            let mut cfg = load_config()?;
+            // A freshly selected profile has no entry until its first login.
+            cfg.profiles.entry(cfg.current_profile.clone()).or_default();
+            for (name, auth) in &cfg.profiles {
+                let marker =
+                    if *name == cfg.current_profile { "*" } else { " " };
+                let state = if auth.token.is_empty() {
+                    "logged out"
+                } else {
+                    "logged in"
+                };
+                println!("{marker} {name}  {}  ({state})", auth.base_url);
+            }
+        }
+        AuthCommand::UseProfile { name } => {
+            let mut cfg = load_config()?;
+            if cfg.current_profile == name {
+                println!("Already using profile '{name}'.");
+                return Ok(());
+            }
+
+            let known = cfg.profiles.contains_key(&name);
+            cfg.current_profile = name.clone();
+            save_config(&cfg)?;
+            // org/team ids belong to the previous profile's server
+            save_session(&Session::default())?;
+
+            if known {
+                println!("Switched to profile '{name}'.");
+            } else {
+                println!(
+                    "Switched to new profile '{name}'. Run `paastel auth login` to use it."
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+// -------------
+// Org handler
+// -------------
+
+const ORG_COLUMNS: &[Column<MyOrganizationResponse>] = &[
+    Column {
+        header: "ID",
+        wide_only: false,
+        value: |m| m.organization.id.to_string(),
+    },
+    Column {
+        header: "SLUG",
+        wide_only: false,
+        value: |m| m.organization.slug.clone(),
+    },
+    Column {
+        header: "NAME",
+        wide_only: false,
+        value: |m| m.organization.name.clone(),
+    },
+    Column { header: "ROLE", wide_only: false, value: |m| m.role.clone() },
+    Column {
+        header: "DESCRIPTION",
+        wide_only: true,
+        value: |m| {
+            m.organization.description.clone().unwrap_or_else(|| "-".into())
+        },
+    },
+];
+
+async fn handle_org(
+    cmd: OrgCommand,
+    client: &Client,
+    output: OutputFormat,
+) -> Result<()> {
+    match cmd {
+        OrgCommand::List { json } => {
+            let cfg = ensure_authenticated()?;
+            let orgs = gql_my_organizations(client, &cfg).await?;
+
+            let output = if json { OutputFormat::Json } else { output };
+            print!(
+                "{}",
+                render_list(
+                    output,
+                    ORG_COLUMNS,
+                    |m| m.organization.slug.clone(),
+                    &orgs,
+                )?
+            );
+        }
+        OrgCommand::Create { name, slug, description } => {
+            let cfg = ensure_authenticated()?;
+            let org = gql_create_org(
+                client,
+                &cfg,
+                &name,
+                &slug,
+                description.as_deref(),
+            )
+            .await?;
+
+            match render(&org, output)? {
+                Some(doc) => print!("{doc}"),
+                None => println!(
+                    "Organization created: {} (id: {}, slug: {})",
+                    org.name, org.id, org.slug
+                ),
+            }
+
+            // set as current context
+            let mut sess = load_session().unwrap_or_default();
+            sess.context.organization_id = Some(org.id as i64);
+            sess.context.organization_slug = Some(org.slug);
+            // when we change org, we can reset team
+            sess.context.team_id = None;
+            sess.context.team_slug = None;
+            save_session(&sess)?;
+            note(output, "Organization set as current context.");
+        }
+        OrgCommand::Use { id, slug } => {
+            let cfg = ensure_authenticated()?;
+            if cfg.auth.token.is_empty() {
+                anyhow::bail!(
+                    "You must be authenticated to use an organization."
+                );
+            }
+
+            let mut sess = load_session().unwrap_or_default();
+
+            match (id, slug) {
+                (Some(id), _) => {
+                    sess.context.organization_id = Some(id);
+                    sess.context.organization_slug = None;
+                }
+                (None, Some(slug)) => {
+                    let org = gql_organization_by_slug(client, &cfg, &slug)
+                        .await?
+                        .ok_or_else(|| {
+                            anyhow::anyhow!(
+                                "No organization '{slug}' (or you are not a member)."
+                            )
+                        })?;
+                    sess.context.organization_id = Some(org.id);
+                    sess.context.organization_slug = Some(slug);
+                }
+                _ => {
+                    anyhow::bail!("You must provide either --id or --slug.");
+                }
+            }
+
+            // when org changes, we usually reset team
+            sess.context.team_id = None;
+            sess.context.team_slug = None;
+
+            save_session(&sess)?;
+            println!("Organization context updated.");
+        }
+        OrgCommand::Delete { slug, yes } => {
+            let cfg = ensure_authenticated()?;
+            let org = gql_organization_by_slug(client, &cfg, &slug)
+                .await?
+                .ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "No organization '{slug}' (or you are not a member)."
+                    )
+                })?;
+
+            if !yes {
+                let answer =
+                    prompt(&format!("Delete organization '{slug}'? [y/N] "))?;
+                if !matches!(answer.to_lowercase().as_str(), "y" | "yes") {
+                    println!("Aborted.");
+                    return Ok(());
+                }
+            }
+
+            let id = gql_delete_organization(client, &cfg, org.id).await?;
+
+            // forget it if it was the current context
+            let mut sess = load_session().unwrap_or_default();
+            if sess.context.organization_id == Some(id)
+                || sess.context.organization_slug.as_deref()
+                    == Some(slug.as_str())
+            {
+                sess.context = SessionContext::default();
+                save_session(&sess)?;
+            }
+            println!("Organization '{slug}' deleted.");
+        }
+    }
+
+    Ok(())
+}
+
+// -------------
+// Team handler
+// -------------
+
+const TEAM_COLUMNS: &[Column<TeamResponse>] = &[
+    Column { header: "ID", wide_only: false, value: |t| t.id.to_string() },
+    Column { header: "SLUG", wide_only: false, value: |t| t.slug.clone() },
+    Column { header: "NAME", wide_only: false, value: |t| t.name.clone() },
+    Column {
+        header: "DESCRIPTION",
+        wide_only: true,
+        value: |t| t.description.clone().unwrap_or_else(|| "-".into()),
+    },
+];
+
+async fn handle_team(
+    cmd: TeamCommand,
+    client: &Client,
+    overrides: &ContextOverride,
+    output: OutputFormat,
+) -> Result<()> {
+    match cmd {
+        TeamCommand::List { json } => {
+            let cfg = ensure_authenticated()?;
+            let ctx = current_context(client, &cfg, overrides).await?;
+            let org_id = ctx.organization_id.ok_or_else(|| {
+                anyhow::anyhow!(
+                    "No organization selected. Use `paastel org use` or --org."
+                )
+            })?;
+
+            let teams = gql_teams(client, &cfg, org_id).await?;
+            let output = if json { OutputFormat::Json } else { output };
+            print!(
+                "{}",
+                render_list(output, TEAM_COLUMNS, |t| t.slug.clone(), &teams)?
+            );
+        }
+        TeamCommand::Create { name, slug, description } => {
+            let cfg = ensure_authenticated()?;
+            let ctx = current_context(client, &cfg, overrides).await?;
+
+            let org_id = ctx.organization_id.ok_or_else(|| {
+                anyhow::anyhow!(
+                    "No organization selected. Use `paastel org use` first."
+                )
+            })?;
+
+            let team = gql_create_team(
+                client,
+                &cfg,
+                org_id,
+                &name,
+                &slug,
+                description.as_deref(),
+            )
+            .await?;
+
+            match render(&team, output)? {
+                Some(doc) => print!("{doc}"),
+                None => println!(
+                    "Team created: {} (id: {}, slug: {})",
+                    team.name, team.id, team.slug
+                ),
+            }
+
+            let mut sess = load_session().unwrap_or_default();
+            sess.context.team_id = Some(team.id as i64);
+            sess.context.team_slug = Some(team.slug);
+            save_session(&sess)?;
+            note(output, "Team set as current context.");
+        }
+        TeamCommand::Use { id, slug } => {
+            let cfg = ensure_authenticated()?;
+            let mut sess = load_session().unwrap_or_default();
+
+            if sess.context.organization_id.is_none()
+                && sess.context.organization_slug.is_none()
+            {
+                anyhow::bail!(
+                    "No organization selected. Use `paastel org use` first."
+                );
+            }
+
+            match (id, slug) {
+                (Some(id), _) => {
+                    sess.context.team_id = Some(id);
+                    sess.context.team_slug = None;
+                }
+                (None, Some(slug)) => {
+                    let ctx = current_context(client, &cfg, overrides).await?;
+                    let org_id = ctx.organization_id.ok_or_else(|| {
+                        anyhow::anyhow!(
+                            "No organization selected. Use `paastel org use` first."
+                        )
+                    })?;
+                    let team = gql_team_by_slug(client, &cfg, org_id, &slug)
+                        .await?
+                        .ok_or_else(|| {
+                            anyhow::anyhow!(
+                                "No team '{slug}' in the current organization."
+                            )
+                        })?;
+                    sess.context.team_id = Some(team.id);
+                    sess.context.team_slug = Some(slug);
+                }
+                _ => {
+                    anyhow::bail!("You must provide either --id or --slug.");
+                }
+            }
+
+            save_session(&sess)?;
+            println!("Team context updated.");
+        }
+    }
+
+    Ok(())
+}
+
+// ----------------
+// Context handler
+// ----------------
+
+/// `context show` as JSON/YAML.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ContextView<'a> {
+    profile: &'a str,
+    authenticated: bool,
+    endpoint: Option<&'a str>,
+    organization_id: Option<i64>,
+    organization_slug: Option<&'a str>,
+    team_id: Option<i64>,
+    team_slug: Option<&'a str>,
+}
+
+fn handle_context(
+    cmd: ContextCommand,
+    overrides: &ContextOverride,
+    output: OutputFormat,
+) -> Result<()> {
+    match cmd {
+        ContextCommand::Show => {
+            let cfg = load_config().unwrap_or_default();
+            let mut sess = load_session().unwrap_or_default();
+            sess.context = sess.context.with_overrides(overrides);
+
+            let view = ContextView {
+                profile: &cfg.active_profile,
+                authenticated: !cfg.auth.token.is_empty(),
+                endpoint: Some(cfg.auth.base_url.as_str())
+                    .filter(|url| !url.is_empty()),
+                organization_id: sess.context.organization_id,
+                organization_slug: sess.context.organization_slug.as_deref(),
+                team_id: sess.context.team_id,
+                team_slug: sess.context.team_slug.as_deref(),
+            };
+            if let Some(doc) = render(&view, output)? {
+                print!("{doc}");
+                return Ok(());
+            }
+
+            println!("Auth:");
+            if cfg.auth.token.is_empty() {
+                println!("  Status      : not authenticated");
+            } else {
+                println!("  Status      : authenticated");
+                println!("  Endpoint    : {}", cfg.auth.base_url);
+            }
+
+            println!();
+            println!("Context:");
+            match (
+                &sess.context.organization_id,
+                &sess.context.organization_slug,
+            ) {
+                (Some(id), Some(slug)) => {
+                    println!("  Organization: {} (id: {})", slug, id);
+                }
+                (Some(id), None) => {
+                    println!("  Organization: (id: {})", id);
+                }
+                (None, Some(slug)) => {
+                    println!("  Organization: {} (id: unknown)", slug);
+                }
+                (None, None) => {
+                    println!("  Organization: (not set)");
+                }
+            };
+
+            match (&sess.context.team_id, &sess.context.team_slug) {
+                (Some(id), Some(slug)) => {
+                    println!("  Team        : {} (id: {})", slug, id);
+                }
+                (Some(id), None) => {
+                    println!("  Team        : (id: {})", id);
+                }
+                (None, Some(slug)) => {
+                    println!("  Team        : {} (id: unknown)", slug);
+                }
+                (None, None) => {
+                    println!("  Team        : (not set)");
+                }
+            };
+        }
+        ContextCommand::Clear => {
+            let path = session_path()?;
+            if path.exists() {
+                fs::remove_file(&path).with_context(|| {
+                    format!("Failed to remove session file {}", path.display())
+                })?;
+                println!("Session cleared.");
+            } else {
+                println!("Session not found. Nothing to clear.");
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// ---------------
+// Config handler
+// ---------------
+
+fn handle_config(cmd: ConfigCommand) -> Result<()> {
+    match cmd {
+        ConfigCommand::Path => {
+            let source = if std::env::var_os(CONFIG_DIR_ENV).is_some() {
+                CONFIG_DIR_ENV
+            } else {
+                "default"
+            };
+
+            let exists = |path: &PathBuf| {
+                if path.exists() { "exists" } else { "missing" }
+            };
+
+            let config = config_path()?;
+            let session = session_path()?;
+
+            println!(
+                "Config dir  : {} ({source})",
+                paastel_config_dir()?.display()
+            );
+            println!(
+                "Config file : {} ({})",
+                config.display(),
+                exists(&config)
+            );
+            println!(
+                "Session file: {} ({})",
+                session.display(),
+                exists(&session)
+            );
+        }
+    }
+
+    Ok(())
+}
+
+// -------------
+// App handler
+// -------------
+
+/// Columns of `app list`.
+const APP_COLUMNS: &[Column<AppResponse>] = &[
+    Column { header: "SLUG", wide_only: false, value: |a| a.slug.clone() },
+    Column { header: "NAME", wide_only: false, value: |a| a.name.clone() },
+    Column {
+        header: "TEAM",
+        wide_only: false,
+        value: |a| a.team_id.map_or("-".into(), |id| id.to_string()),
+    },
+    Column { header: "ID", wide_only: true, value: |a| a.id.to_string() },
+    Column {
+        header: "REPO",
+        wide_only: true,
+        value: |a| a.repo_url.clone().unwrap_or_else(|| "-".into()),
+    },
+    Column {
+        header: "UPDATED",
+        wide_only: true,
+        value: |a| a.updated_at.clone(),
+    },
+];
+
+async fn handle_app(
+    cmd: AppCommand,
+    client: &Client,
+    overrides: &ContextOverride,
+    output: OutputFormat,
+) -> Result<()> {
+    match cmd {
+        AppCommand::List => {
+            let cfg = ensure_authenticated()?;
+            let ctx = current_context(client, &cfg, overrides).await?;
+            let org_id = ctx.organization_id.ok_or_else(|| {
+                anyhow::anyhow!(
+                    "No organization selected. Use `paastel org use` or --org."
+                )
+            })?;
+
+            let apps = gql_apps(client, &cfg, org_id).await?;
+            let table =
+                render_list(output, APP_COLUMNS, |a| a.slug.clone(), &apps)?;
+            let tabular =
+                matches!(output, OutputFormat::Table | OutputFormat::Wide);
+            if cfg.cli.remember_lists && tabular {
+                let slugs = apps.iter().map(|a| a.slug.clone()).collect();
+                save_last_list("app", org_id, slugs)?;
+                print!("{}", numbered(&table));
+            } else {
+                print!("{table}");
+            }
+            Ok(())
+        }
+        AppCommand::Info { slug, last } => {
+            let cfg = ensure_authenticated()?;
+            let ctx = current_context(client, &cfg, overrides).await?;
+            let org_id = ctx.organization_id.ok_or_else(|| {
+                anyhow::anyhow!(
+                    "No organization selected. Use `paastel org use` or --org."
+                )
+            })?;
+
+            let slug = match (slug, last) {
+                (Some(slug), _) => slug,
+                (None, Some(index)) => last_list_slug("app", org_id, index)?,
+                (None, None) => anyhow::bail!("Give an app slug or --last N"),
+            };
+
+            let app = gql_apps(client, &cfg, org_id)
+                .await?
+                .into_iter()
+                .find(|a| a.slug == slug)
+                .ok_or_else(|| {
+                    anyhow::anyhow!("No app '{slug}' in this organization")
+                })?;
+
+            println!("App     : {} ({})", app.name, app.slug);
+            println!("ID      : {}", app.id);
+            println!(
+                "Team    : {}",
+                app.team_id.map_or("-".into(), |id| id.to_string())
+            );
+            println!("Repo    : {}", app.repo_url.as_deref().unwrap_or("-"));
+            println!("Updated : {}", app.updated_at);
+            Ok(())
+        }
+        AppCommand::Scaffold { runtime, force } => {
+            let dir = std::env::current_dir()
+                .context("Could not determine current directory")?;
+            for path in scaffold(&dir, runtime, force)? {
+                println!("Wrote {}", path.display());
+            }
+            Ok(())
+        }
+        AppCommand::Create { name, slug, repo_url } => {
+            let cfg = ensure_authenticated()?;
+            let ctx = current_context(client, &cfg, overrides).await?;
+            let org_id = ctx.organization_id.ok_or_else(|| {
+                anyhow::anyhow!(
+                    "No organization selected. Use `paastel org use` or --org."
+                )
+            })?;
+
+            let app = gql_create_app(
+                client,
+                &cfg,
+                CreateAppInput {
+                    organization_id: org_id,
+                    team_id: ctx.team_id,
+                    name: &name,
+                    slug: &slug,
+                    repo_url: repo_url.as_deref(),
+                },
+            )
+            .await?;
+
+            let team = ctx.team_slug.unwrap_or_else(|| "no team".into());
+            println!(
+                "App created: {} (id: {}, slug: {}, {team})",
+                app.name, app.id, app.slug
+            );
+            Ok(())
+        }
+    }
+}
+
+// ----------------
+// Release handler
+// ----------------
+
+async fn handle_release(
+    cmd: ReleaseCommand,
+    client: &Client,
+    overrides: &ContextOverride,
+) -> Result<()> {
+    match cmd {
+        ReleaseCommand::Promote { build, version, changelog, deploy, env } => {
+            let cfg = ensure_authenticated()?;
+            let ctx = current_context(client, &cfg, overrides).await?;
+
+            let release = gql_promote_build(
+                client,
+                &cfg,
+                PromoteBuildInput {
+                    build_id: build,
+                    version: version.as_deref(),
+                    changelog: changelog.as_deref(),
+                    organization_id: ctx.organization_id,
+                },
+            )
+            .await?;
+
+            println!(
+                "Release created: {} (id: {}, image: {})",
+                release.version,
+                release.id,
+                release.image_ref.as_deref().unwrap_or("-")
+            );
+
+            if deploy {
+                let env = env.ok_or_else(|| {
+                    anyhow::anyhow!("--env is required with --deploy")
+                })?;
+
+                let deploy = gql_create_deploy(
+                    client,
+                    &cfg,
+                    release.app_id,
+                    release.id,
+                    &env,
+                )
+                .await?;
+
+                println!(
+                    "Deploy requested: {} to {} ({})",
+                    deploy.id,
+                    deploy.environment,
+                    status_badge(&deploy.status)
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// ---------------
+// Deploy handler
+// ---------------
+
+const DEPLOY_COLUMNS: &[Column<DeployRow>] = &[
+    Column { header: "ID", wide_only: false, value: |d| d.id.to_string() },
+    Column {
+        header: "ENV",
+        wide_only: false,
+        value: |d| d.environment.clone(),
+    },
+    Column {
+        header: "RELEASE",
+        wide_only: false,
+        value: |d| d.release_id.to_string(),
+    },
+    Column {
+        header: "STATUS",
+        wide_only: false,
+        value: |d| status_badge(&d.status),
+    },
+    Column {
+        header: "CHANGED",
+        wide_only: true,
+        value: |d| d.status_changed_at.clone(),
+    },
+];
+
+async fn handle_deploy(
+    cmd: DeployCommand,
+    client: &Client,
+    output: OutputFormat,
+) -> Result<()> {
+    match cmd {
+        DeployCommand::List { app, env, limit, range, watch } => {
+            let cfg = ensure_authenticated()?;
+            let (since, until) = range.to_rfc3339()?;
+            let app_id =
+                resolve_slug(client, &cfg, SlugKind::App, &app).await?;
+
+            watch_list(&watch, async || {
+                let deploys = gql_deploys(
+                    client,
+                    &cfg,
+                    DeploysVariables {
+                        app_id,
+                        environment: env.as_deref(),
+                        limit,
+                        since: since.as_deref(),
+                        until: until.as_deref(),
+                    },
+                )
+                .await?;
+                let done = deploys.iter().all(|d| is_finished(&d.status));
+                let table = render_list(
+                    output,
+                    DEPLOY_COLUMNS,
+                    |d| d.id.to_string(),
+                    &deploys,
+                )?;
+                Ok((table, done))
+            })
+            .await?;
+        }
+        DeployCommand::Create { app, release, env, skip_busy } => {
+            let cfg = ensure_authenticated()?;
+            let app_id =
+                resolve_slug(client, &cfg, SlugKind::App, &app).await?;
+
+            let result = gql_create_deploys(
+                client,
+                &cfg,
+                CreateDeploysInput {
+                    app_id,
+                    release_id: release,
+                    environments: &env,
+                    skip_busy,
+                },
+            )
+            .await?;
+
+            for deploy in &result.deploys {
+                println!(
+                    "Deploy requested: {} to {} ({})",
+                    deploy.id,
+                    deploy.environment,
+                    status_badge(&deploy.status)
+                );
+            }
+            for env in &result.skipped_environments {
+                println!("Skipped {env}: a deploy is already in progress");
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// ---------------
+// Secret handler
+// ---------------
+
+/// Same limit as the server, checked before uploading.
+const MAX_SECRET_VALUE_BYTES: usize = 64 * 1024;
+
+async fn handle_secret(
+    cmd: SecretCommand,
+    client: &Client,
+    overrides: &ContextOverride,
+    output: OutputFormat,
+) -> Result<()> {
+    match cmd {
+        SecretCommand::Set {
+            app,
+            env,
+            key: Some(key),
+            value,
+            value_file,
+            kind,
+            ..
+        } => {
+            let cfg = ensure_authenticated()?;
+
+            let kind = kind.unwrap_or(if value_file.is_some() {
+                SecretType::File
+            } else {
+                SecretType::String
+            });
+            let value = match (value, value_file) {
+                (Some(value), _) => value,
+                (None, Some(path)) => {
+                    fs::read_to_string(&path).with_context(|| {
+                        format!("Failed to read {}", path.display())
+                    })?
+                }
+                (None, None) => {
+                    anyhow::bail!("Provide --value or --value-file.")
+                }
+            };
+            check_secret_size(&key, &value)?;
+
+            let app_id = resolve_app(client, &cfg, overrides, &app).await?;
+            let secret = gql_set_app_secret(
+                client,
+                &cfg,
+                SetAppSecretInput {
+                    app_id,
+                    environment: &env,
+                    key: &key,
+                    value: &value,
+                    kind: kind.as_api(),
+                },
+            )
+            .await?;
+
+            println!(
+                "Secret {} set for {app} ({}, {}, {} bytes)",
+                secret.key,
+                secret.environment,
+                secret.kind.to_ascii_lowercase(),
+                secret.size
+            );
+        }
+        SecretCommand::Set { app, env, pairs, from_file, kind, .. } => {
+            let cfg = ensure_authenticated()?;
+
+            // Later entries win: the file first, then the arguments.
+            let mut secrets: Vec<(String, String)> = Vec::new();
+            if let Some(path) = &from_file {
+                let contents =
+                    fs::read_to_string(path).with_context(|| {
+                        format!("Failed to read {}", path.display())
+                    })?;
+                secrets.extend(parse_dotenv(&contents).with_context(
+                    || format!("Invalid dotenv file {}", path.display()),
+                )?);
+            }
+            for pair in &pairs {
+                let (key, value) = pair.split_once('=').ok_or_else(|| {
+                    anyhow::anyhow!("Expected KEY=VALUE, got '{pair}'")
+                })?;
+                secrets.push((key.trim().to_string(), value.to_string()));
+            }
+            let mut unique: Vec<(String, String)> = Vec::new();
+            for (key, value) in secrets {
+                unique.retain(|(k, _)| *k != key);
+                unique.push((key, value));
+            }
+            if unique.is_empty() {
+                anyhow::bail!(
+                    "Nothing to set: give KEY=VALUE pairs, --from-file or --key."
+                );
+            }
+            for (key, value) in &unique {
+                check_secret_size(key, value)?;
+            }
+
+            let kind = kind.unwrap_or(SecretType::String).as_api();
+            let app_id = resolve_app(client, &cfg, overrides, &app).await?;
+            let summary = gql_update_app_secrets_bulk(
+                client,
+                &cfg,
+                SyncAppSecretsInput {
+                    app_id,
+                    environment: &env,
+                    secrets: unique
+                        .iter()
+                        .map(|(key, value)| SecretEntryInput {
+                            key,
+                            value,
+                            kind,
+                        })
+                        .collect(),
+                    prune: false,
+                },
+            )
+            .await?;
+
+            let unchanged =
+                unique.len() - summary.added.len() - summary.updated.len();
+            println!(
+                "Secrets of {app} ({env}): {} added, {} updated, {unchanged} unchanged",
+                summary.added.len(),
+                summary.updated.len()
+            );
+            for key in &summary.added {
+                println!("  + {key}");
+            }
+            for key in &summary.updated {
+                println!("  ~ {key}");
+            }
+        }
+        SecretCommand::List { app, env, show_values } => {
+            let cfg = ensure_authenticated()?;
+            let app_id = resolve_app(client, &cfg, overrides, &app).await?;
+
+            let mut secrets =
+                gql_app_secrets(client, &cfg, app_id, &env).await?;
+            if !show_values {
+                for secret in &mut secrets {
+                    secret.value = None;
+                }
+            }
+            let columns = if show_values {
+                SECRET_COLUMNS_WITH_VALUES
+            } else {
+                SECRET_COLUMNS
+            };
+            print!(
+                "{}",
+                render_list(output, columns, |s| s.key.clone(), &secrets)?
+            );
+        }
+        SecretCommand::Unset { app, env, key } => {
+            let cfg = ensure_authenticated()?;
+            let app_id = resolve_app(client, &cfg, overrides, &app).await?;
+
+            if gql_delete_app_secret(client, &cfg, app_id, &env, &key).await? {
+                println!("Secret {key} removed from {app} ({env})");
+            } else {
+                println!("No secret {key} in {app} ({env}); nothing to do");
+            }
+        }
+    }
+
+    Ok(())
+}
+
+const SECRET_COLUMNS: &[Column<SecretRow>] = &[
+    Column { header: "KEY", wide_only: false, value: |s| s.key.clone() },
+    Column {
+        header: "TYPE",
+        wide_only: false,
+        value: |s| s.kind.to_ascii_lowercase(),
+    },
+    Column {
+        header: "UPDATED",
+        wide_only: true,
+        value: |s| s.updated_at.clone(),
+    },
+];
+
+/// [`SECRET_COLUMNS`] plus the values, for `--show-values`.
+const SECRET_COLUMNS_WITH_VALUES: &[Column<SecretRow>] = &[
+    Column { header: "KEY", wide_only: false, value: |s| s.key.clone() },
+    Column {
+        header: "TYPE",
+        wide_only: false,
+        value: |s| s.kind.to_ascii_lowercase(),
+    },
+    Column {
+        header: "VALUE",
+        wide_only: false,
+        value: |s| s.value.clone().unwrap_or_default(),
+    },
+    Column {
+        header: "UPDATED",
+        wide_only: true,
+        value: |s| s.updated_at.clone(),
+    },
+];
+
+impl SecretType {
+    /// Name of the `SecretKind` enum value in the API.
+    fn as_api(self) -> &'static str {
+        match self {
+            SecretType::String => "STRING",
+            SecretType::File => "FILE",
+        }
+    }
+}
+
+fn check_secret_size(key: &str, value: &str) -> Result<()> {
+    if value.len() > MAX_SECRET_VALUE_BYTES {
+        anyhow::bail!(
+            "Secret {key} is {} bytes; the limit is {MAX_SECRET_VALUE_BYTES}.",
+            value.len()
+        );
+    }
+    Ok(())
+}
+
+/// Id of the app `slug` in the current organization.
+async fn resolve_app(
+    client: &Client,
+    cfg: &Config,
+    overrides: &ContextOverride,
+    slug: &str,
+) -> Result<i64> {
+    let ctx = current_context(client, cfg, overrides).await?;
+    let org_id = ctx.organization_id.ok_or_else(|| {
+        anyhow::anyhow!(
+            "No organization selected. Use `paastel org use` or --org."
+        )
+    })?;
+
+    let app = gql_app_by_slug(client, cfg, org_id, slug).await?.ok_or_else(
+        || anyhow::anyhow!("No app '{slug}' in this organization"),
+    )?;
+    Ok(app.id)
+}
+
+/// `KEY=VALUE` entries of a dotenv file, in order.
+///
+/// Blank lines, `#` comments and an `export ` prefix are skipped. Values
+/// may be wrapped in single quotes (taken literally) or double quotes
+/// (`\n` is a newline; `\` before any other character keeps it as is).
+/// `$VAR` is not expanded: the server resolves references between secrets
+/// itself.
+fn parse_dotenv(contents: &str) -> Result<Vec<(String, String)>> {
+    let mut entries = Vec::new();
+    for (number, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let (key, raw) = line.split_once('=').ok_or_else(|| {
+            anyhow::anyhow!("line {}: expected KEY=VALUE", number + 1)
+        })?;
+        let key = key.trim();
+        if key.is_empty() {
+            anyhow::bail!("line {}: missing key", number + 1);
+        }
+
+        let raw = raw.trim();
+        let value = if let Some(inner) =
+            raw.strip_prefix('"').and_then(|r| r.strip_suffix('"'))
+        {
+            let mut value = String::with_capacity(inner.len());
+            let mut chars = inner.chars();
+            while let Some(c) = chars.next() {
+                if c != '\\' {
+                    value.push(c);
+                    continue;
+                }
+                match chars.next() {
+                    Some('n') => value.push('\n'),
+                    Some(escaped) => value.push(escaped),
+                    None => value.push('\\'),
+                }
+            }
+            value
+        } else if let Some(inner) =
+            raw.strip_prefix('\'').and_then(|r| r.strip_suffix('\''))
+        {
+            inner.to_string()
+        } else {
+            // Unquoted: a ` #` starts a trailing comment.
+            raw.split(" #").next().unwrap_or_default().trim_end().to_string()
+        };
+        entries.push((key.to_string(), value));
+    }
+    Ok(entries)
+}
+
+// --------------
+// Build handler
+// --------------
+
+const BUILD_COLUMNS: &[Column<BuildRow>] = &[
+    Column { header: "ID", wide_only: false, value: |b| b.id.to_string() },
+    Column {
+        header: "STATUS",
+        wide_only: false,
+        value: |b| status_badge(&b.status),
+    },
+    Column {
+        header: "COMMIT",
+        wide_only: false,
+        value: |b| {
+            b.commit_sha
+                .as_deref()
+                .map_or("-".into(), |sha| sha.chars().take(12).collect())
+        },
+    },
+    Column {
+        header: "TRIGGER",
+        wide_only: true,
+        value: |b| b.trigger.to_ascii_lowercase(),
+    },
+    Column {
+        header: "CREATED",
+        wide_only: true,
+        value: |b| b.created_at.clone(),
+    },
+];
+
+async fn handle_build(
+    cmd: BuildCommand,
+    client: &Client,
+    output: OutputFormat,
+) -> Result<()> {
+    match cmd {
+        BuildCommand::List { app, limit, range, watch } => {
+            let cfg = ensure_authenticated()?;
+            let (since, until) = range.to_rfc3339()?;
+            let app_id =
+                resolve_slug(client, &cfg, SlugKind::App, &app).await?;
+
+            watch_list(&watch, async || {
+                let builds = gql_builds(
+                    client,
+                    &cfg,
+                    BuildsVariables {
+                        app_id,
+                        limit,
+                        since: since.as_deref(),
+                        until: until.as_deref(),
+                    },
+                )
+                .await?;
+                let done = builds.iter().all(|b| is_finished(&b.status));
+                let table = render_list(
+                    output,
+                    BUILD_COLUMNS,
+                    |b| b.id.to_string(),
+                    &builds,
+                )?;
+                Ok((table, done))
+            })
+            .await?;
+        }
+        BuildCommand::Logs { id, lines } => {
+            let cfg = ensure_authenticated()?;
+
+            // N chunks usually hold at least N lines; lines split across
+            // chunks can make the output a bit shorter.
+            let tail = lines.map(|n| n.max(1) as i64);
+            let chunks = gql_build_logs(client, &cfg, id, tail).await?;
+            let output: String =
+                chunks.into_iter().map(|c| c.content).collect();
+
+            print!("{}", last_lines(&output, lines));
+        }
+    }
+
+    Ok(())
+}
+
+/// The last `n` lines of `text` (all of it when `n` is `None`).
+fn last_lines(text: &str, n: Option<usize>) -> &str {
+    let Some(n) = n else {
+        return text;
+    };
+    if n == 0 {
+        return "";
+    }
+
+    let body = text.strip_suffix('\n').unwrap_or(text);
+    match body.rmatch_indices('\n').nth(n - 1) {
+        Some((idx, _)) => &text[idx + 1..],
+        None => text,
+    }
+}
+
+/// Write the runtime's Dockerfile and .dockerignore into `dir`.
+///
+/// Nothing is written unless `force` is set or neither file exists yet.
+fn scaffold(
+    dir: &Path,
+    runtime: ScaffoldRuntime,
+    force: bool,
+) -> Result<Vec<PathBuf>> {
+    let (dockerfile, dockerignore) = runtime.templates();
+    let files = [
+        (dir.join("Dockerfile"), dockerfile),
+        (dir.join(".dockerignore"), dockerignore),
+    ];
+
+    if !force {
+        let existing = files
+            .iter()
+            .filter(|(path, _)| path.exists())
+            .map(|(path, _)| path.display().to_string())
+            .collect::<Vec<_>>();
+
+        if !existing.is_empty() {
+            anyhow::bail!(
+                "Refusing to overwrite {}. Use --force to replace.",
+                existing.join(", ")
+            );
+        }
+    }
+
+    let mut written = Vec::new();
+    for (path, contents) in files {
+        fs::write(&path, contents)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        written.push(path);
+    }
+
+    Ok(written)
+}
+
+// -------------------
+// Completion handlers
+// -------------------
+
+/// Bash glue routing slug arguments to `paastel __complete`.
+///
+/// Appended after the clap-generated script; falls back to `_paastel`.
+static BASH_DYNAMIC_COMPLETION: &str = r#"
+_paastel_dynamic() {
+    local cur prev kind=""
+    cur="${COMP_WORDS[COMP_CWORD]}"
+    prev="${COMP_WORDS[COMP_CWORD-1]}"
+    case "$prev" in
+        --slug)
+            if [[ "${COMP_WORDS[2]}" == "use" ]]; then
+                case "${COMP_WORDS[1]}" in
+                    org|team) kind="${COMP_WORDS[1]}" ;;
+                esac
+            fi
+            ;;
+        --org) kind="org" ;;
+        --team) kind="team" ;;
+        --app) kind="app" ;;
+    esac
+    if [[ -n "$kind" ]]; then
+        COMPREPLY=( $(compgen -W "$(paastel __complete "$kind" "$cur" 2>/dev/null)" -- "$cur") )
+        return 0
+    fi
+    _paastel "$@"
+}
+complete -F _paastel_dynamic -o nosort -o bashdefault -o default paastel
+"#;
+
+fn handle_completions(shell: Shell) {
+    let mut cmd = Cli::command();
+    clap_complete::generate(
+        shell,
+        &mut cmd,
+        "paastel",
+        &mut std::io::stdout(),
+    );
+
+    if shell == Shell::Bash {
+        print!("{BASH_DYNAMIC_COMPLETION}");
+    }
+}
+
+/// Print slugs of `kind` starting with `prefix`, one per line.
+///
+/// Runs on every <TAB>, so any failure (no login, server down, slow
+/// network) just prints nothing.
+async fn handle_complete(kind: SlugKind, prefix: &str) {
+    let Ok(cfg) = load_config() else {
+        return;
+    };
+    if cfg.auth.token.is_empty() || cfg.auth.base_url.is_empty() {
+        return;
+    }
+    // `search` needs a non-empty term
+    if prefix.is_empty() {
+        return;
+    }
+
+    let Ok(client) =
+        Client::builder().timeout(std::time::Duration::from_secs(2)).build()
+    else {
+        return;
+    };
+    let Ok(hits) = gql_search(&client, &cfg, prefix, SEARCH_FETCH_LIMIT).await
+    else {
+        return;
+    };
+
+    for slug in completion_slugs(kind, prefix, hits) {
+        println!("{slug}");
+    }
+}
+
+/// Slugs of the requested kind that start with `prefix` (search matches
+/// anywhere in name or slug).
+fn completion_slugs(
+    kind: SlugKind,
+    prefix: &str,
+    hits: Vec<SearchHit>,
+) -> Vec<String> {
+    hits.into_iter()
+        .filter_map(|hit| match (kind, hit) {
+            (SlugKind::Org, SearchHit::Organization { slug, .. })
+            | (SlugKind::Team, SearchHit::Team { slug, .. })
+            | (SlugKind::App, SearchHit::App { slug, .. }) => Some(slug),
+            _ => None,
+        })
+        .filter(|slug| slug.starts_with(prefix))
+        .collect()
+}
+
+// ---------------
+// Search handler
+// ---------------
+
+/// Server-side cap per kind; used to tell how many hits were not shown.
+const SEARCH_FETCH_LIMIT: i64 = 100;
+
+async fn handle_search(
+    term: &str,
+    limit: usize,
+    client: &Client,
+) -> Result<()> {
+    let cfg = ensure_authenticated()?;
+    let hits = gql_search(client, &cfg, term, SEARCH_FETCH_LIMIT).await?;
+
+    if hits.is_empty() {
+        println!("No results for '{term}'.");
+        return Ok(());
+    }
+
+    let mut orgs = Vec::new();
+    let mut teams = Vec::new();
+    let mut apps = Vec::new();
+    for hit in hits {
+        match hit {
+            SearchHit::Organization { id, name, slug } => {
+                orgs.push((id, name, slug))
+            }
+            SearchHit::Team { id, name, slug } => teams.push((id, name, slug)),
+            SearchHit::App { id, name, slug } => apps.push((id, name, slug)),
+        }
+    }
+
+    for (title, rows) in
+        [("Organizations", orgs), ("Teams", teams), ("Apps", apps)]
+    {
+        if rows.is_empty() {
+            continue;
+        }
+
+        println!("{title}:");
+        for (id, name, slug) in rows.iter().take(limit) {
+            println!("  {slug:<24} {name} (id: {id})");
+        }
+        if rows.len() > limit {
+            let more = rows.len() - limit;
+            let plus =
+                if rows.len() as i64 >= SEARCH_FETCH_LIMIT { "+" } else { "" };
+            println!("  …and {more}{plus} more");
+        }
+    }
+
+    Ok(())
+}
+
+// ------------
+// List output
+// ------------
+
+/// A column of a list command, declared once per command.
+struct Column<T> {
+    header: &'static str,
+    /// Only shown with `--output wide`
+    wide_only: bool,
+    value: fn(&T) -> String,
+}
+
+/// Render `rows` in `format`: aligned columns (`wide` adds the
+/// `wide_only` ones), just `name` of each row, one per line, or the rows
+/// as a JSON/YAML list.
+fn render_list<T: Serialize>(
+    format: OutputFormat,
+    columns: &[Column<T>],
+    name: fn(&T) -> String,
+    rows: &[T],
+) -> Result<String> {
+    if let Some(doc) = render(&rows, format)? {
+        return Ok(doc);
+    }
+    if format == OutputFormat::Name {
+        return Ok(rows.iter().map(|row| name(row) + "\n").collect());
+    }
+
+    let columns: Vec<&Column<T>> = columns
+        .iter()
+        .filter(|c| format == OutputFormat::Wide || !c.wide_only)
+        .collect();
+    let cells: Vec<Vec<String>> = rows
+        .iter()
+        .map(|row| columns.iter().map(|c| (c.value)(row)).collect())
+        .collect();
+
+    let widths: Vec<usize> = columns
+        .iter()
+        .enumerate()
+        .map(|(i, c)| {
+            cells
+                .iter()
+                .map(|r| r[i].chars().count())
+                .chain([c.header.len()])
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    let line = |values: Vec<&str>| {
+        let mut line = values
+            .iter()
+            .zip(&widths)
+            .map(|(v, w)| format!("{v:<w$}"))
+            .collect::<Vec<_>>()
+            .join("  ");
+        line.truncate(line.trim_end().len());
+        line + "\n"
+    };
+
+    let mut out = line(columns.iter().map(|c| c.header).collect());
+    for row in &cells {
+        out += &line(row.iter().map(String::as_str).collect());
+    }
+    Ok(out)
+}
+
+/// `value` as a JSON or YAML document (newline-terminated); `None` for
+/// the human formats, which each command prints its own way.
+fn render<T: Serialize>(
+    value: &T,
+    format: OutputFormat,
+) -> Result<Option<String>> {
+    let doc = match format {
+        OutputFormat::Json => serde_json::to_string_pretty(value)? + "\n",
+        OutputFormat::Yaml => serde_yaml::to_string(value)?,
+        OutputFormat::Table | OutputFormat::Wide | OutputFormat::Name => {
+            return Ok(None);
+        }
+    };
+    Ok(Some(doc))
+}
+
+/// A human status line: stdout normally, stderr with JSON/YAML output so
+/// the document stays pipeable.
+fn note(format: OutputFormat, message: &str) {
+    if format.is_structured() {
+        eprintln!("{message}");
+    } else {
+        println!("{message}");
+    }
+}
+
+// ------------
+// Watch mode
+// ------------
+
+/// Whether a build/deploy status (as returned by the API) is final.
+fn is_finished(status: &str) -> bool {
+    matches!(
+        status.to_ascii_lowercase().as_str(),
+        "succeeded" | "failed" | "canceled"
+    )
+}
+
+/// Print the output of `frame`, once or — with `--watch` — again every
+/// `--interval` seconds until it reports that everything it lists has
+/// finished, or the user hits Ctrl-C.
+///
+/// On a TTY each refresh clears the screen and redraws in place; other
+/// outputs (pipes, CI logs) get one snapshot per refresh instead.
+async fn watch_list(
+    args: &WatchArgs,
+    mut frame: impl AsyncFnMut() -> Result<(String, bool)>,
+) -> Result<()> {
+    use std::io::{IsTerminal, Write};
+
+    let (mut text, mut done) = frame().await?;
+    if !args.watch {
+        print!("{text}");
+        return Ok(());
+    }
+
+    let tty = std::io::stdout().is_terminal();
+    let interval = std::time::Duration::from_secs(args.interval.max(1));
+    loop {
+        if tty {
+            // Cursor home + clear screen.
+            print!("\x1b[H\x1b[2J");
+            println!("Every {}s, Ctrl-C to stop\n", interval.as_secs());
+        }
+        print!("{text}");
+        if !tty {
+            println!();
+        }
+        std::io::stdout().flush().ok();
+
+        if done {
+            return Ok(());
+        }
+
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => return Ok(()),
+            _ = tokio::time::sleep(interval) => {}
+        }
+        (text, done) = frame().await?;
+    }
+}
+
+// ---------------
+// Doctor handler
+// ---------------
+
+/// Collects `paastel doctor` results, one line per check.
+#[derive(Default)]
+struct Doctor {
+    failures: usize,
+}
+
+impl Doctor {
+    fn pass(&self, what: &str) {
+        println!("{}", badge("✓", "[ok]", "32", what));
+    }
+
+    fn fail(&mut self, what: &str, hint: &str) {
+        self.failures += 1;
+        println!("{}", badge("✗", "[x]", "31", what));
+        println!("    hint: {hint}");
+    }
+
+    fn skip(&self, what: &str) {
+        println!("{}", badge("-", "[--]", "90", what));
+    }
+}
+
+/// Run every check, reporting each even when an earlier one failed;
+/// checks that need the server are skipped when it is unreachable.
+async fn handle_doctor(
+    client: &Client,
+    overrides: &ContextOverride,
+) -> Result<()> {
+    let mut doctor = Doctor::default();
+
+    let cfg = match load_config() {
+        Ok(cfg) => {
+            doctor
+                .pass(&format!("config file ({})", config_path()?.display()));
+            cfg
+        }
+        Err(e) => {
+            doctor.fail(
+                &format!("config file: {e:#}"),
+                "fix or remove the file, then run `paastel auth login`",
+            );
+            Config::default()
+        }
+    };
+
+    let sess = match load_session() {
+        Ok(sess) => {
+            doctor.pass(&format!(
+                "session file ({})",
+                session_path()?.display()
+            ));
+            sess
+        }
+        Err(e) => {
+            doctor.fail(
+                &format!("session file: {e:#}"),
+                "run `paastel context clear` and select org/team again",
+            );
+            Session::default()
+        }
+    };
+
+    let reachable = if cfg.auth.base_url.is_empty() {
+        doctor.fail(
+            "server: no base_url configured",
+            "run `paastel auth login --base-url <url>`",
+        );
+        false
+    } else {
+        match gql_ping(client, &cfg.auth.base_url).await {
+            Ok(version) => {
+                doctor.pass(&format!(
+                    "server {} (API {version})",
+                    cfg.auth.base_url
+                ));
+                true
+            }
+            Err(e) => {
+                doctor.fail(
+                    &format!("server {}: {e:#}", cfg.auth.base_url),
+                    "check the URL and that the server is running",
+                );
+                false
+            }
+        }
+    };
+
+    let authenticated = if cfg.auth.token.is_empty() {
+        doctor.fail("login: no token", "run `paastel auth login`");
+        false
+    } else if !reachable {
+        doctor.skip("login: server unreachable");
+        false
+    } else {
+        match gql_me(client, &cfg).await {
+            Ok(user) => {
+                doctor.pass(&format!(
+                    "logged in as {} <{}>",
+                    user.name, user.email
+                ));
+                true
+            }
+            Err(e) => {
+                doctor.fail(
+                    &format!("login: {e:#}"),
+                    "the token is invalid or revoked; run `paastel auth login`",
+                );
+                false
+            }
+        }
+    };
+
+    if authenticated {
+        let ctx = sess.context.with_overrides(overrides);
+        doctor_context(&mut doctor, client, &cfg, ctx).await;
+    } else {
+        doctor.skip("organization/team: not logged in");
+    }
+
+    if doctor.failures > 0 {
+        anyhow::bail!("{} check(s) failed", doctor.failures);
+    }
+
+    Ok(())
+}
+
+/// Check that the selected organization and team still exist.
+async fn doctor_context(
+    doctor: &mut Doctor,
+    client: &Client,
+    cfg: &Config,
+    ctx: SessionContext,
+) {
+    let org_id = match (ctx.organization_id, &ctx.organization_slug) {
+        (Some(id), _) => id,
+        (None, Some(slug)) => {
+            match resolve_slug(client, cfg, SlugKind::Org, slug).await {
+                Ok(id) => id,
+                Err(e) => {
+                    doctor.fail(
+                        &format!("organization: {e:#}"),
+                        "select another one with `paastel org use`",
+                    );
+                    return;
+                }
+            }
+        }
+        (None, None) => {
+            doctor.skip("organization: none selected");
+            return;
+        }
+    };
+
+    match gql_organization(client, cfg, org_id).await {
+        Ok(Some(org)) => {
+            doctor.pass(&format!("organization {} (id: {org_id})", org.slug))
+        }
+        Ok(None) => {
+            doctor.fail(
+                &format!("organization {org_id} no longer exists"),
+                "select another one with `paastel org use`",
+            );
+            return;
+        }
+        Err(e) => {
+            doctor.fail(
+                &format!("organization {org_id}: {e:#}"),
+                "retry, or select another one with `paastel org use`",
+            );
+            return;
+        }
+    }
+
+    let team_id = match (ctx.team_id, &ctx.team_slug) {
+        (Some(id), _) => id,
+        (None, Some(slug)) => {
+            match resolve_slug(client, cfg, SlugKind::Team, slug).await {
+                Ok(id) => id,
+                Err(e) => {
+                    doctor.fail(
+                        &format!("team: {e:#}"),
+                        "select another one with `paastel team use`",
+                    );
+                    return;
+                }
+            }
+        }
+        (None, None) => {
+            doctor.skip("team: none selected");
+            return;
+        }
+    };
+
+    match gql_team(client, cfg, team_id).await {
+        Ok(Some(team)) if team.organization_id == org_id => {
+            doctor.pass(&format!("team {} (id: {team_id})", team.slug))
+        }
+        Ok(Some(team)) => doctor.fail(
+            &format!("team {} belongs to another organization", team.slug),
+            "select a team of this organization with `paastel team use`",
+        ),
+        Ok(None) => doctor.fail(
+            &format!("team {team_id} no longer exists"),
+            "select another one with `paastel team use`",
+        ),
+        Err(e) => doctor.fail(
+            &format!("team {team_id}: {e:#}"),
+            "retry, or select another one with `paastel team use`",
+        ),
+    }
+}
+
+// -------------------------
+// Small utility functions
+// -------------------------
+
+/// Render a build/deploy/release status (as returned by the API) with a
+/// glyph: ✓ succeeded, ✗ failed, ● running, ○ pending, ⊘ canceled.
+///
+/// Colors are used only on a TTY without `NO_COLOR`; non-TTY output and
+/// `TERM=dumb` get an ASCII marker instead.
+fn status_badge(status: &str) -> String {
+    let label = status.to_ascii_lowercase();
+    let (glyph, ascii, color) = match label.as_str() {
+        "succeeded" | "built" => ("✓", "[ok]", "32"),
+        "failed" => ("✗", "[x]", "31"),
+        "running" | "building" => ("●", "[..]", "33"),
+        "pending" => ("○", "[  ]", "90"),
+        "canceled" => ("⊘", "[--]", "35"),
+        _ => return label,
     };
 
-    let res = client
-        .post(base_url)
-        .json(&req_body)
-        .send()
-        .await
-        .context("Failed to send registerUser GraphQL request")?;
+    badge(glyph, ascii, color, &label)
+}
 
-    if !res.status().is_success() {
-        anyhow::bail!("registerUser failed with HTTP status {}", res.status());
+/// `glyph label` in the ANSI `color`, degraded like [`status_badge`].
+fn badge(glyph: &str, ascii: &str, color: &str, label: &str) -> String {
+    use std::io::IsTerminal;
+
+    let dumb = std::env::var("TERM").is_ok_and(|t| t == "dumb");
+    if dumb || !std::io::stdout().is_terminal() {
+        return format!("{ascii} {label}");
+    }
+    if std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty()) {
+        return format!("{glyph} {label}");
     }
 
-    let gql: GqlResponse<RegisterUserData> = res
-        .json()
-        .await
-        .context("Failed to parse GraphQL response for registerUser")?;
+    format!("\x1b[{color}m{glyph} {label}\x1b[0m")
+}
 
-    if let Some(errors) = gql.errors {
-        let msg = errors
-            .into_iter()
-            .map(|e| e.message)
-            .collect::<Vec<_>>()
-            .join("; ");
-        anyhow::bail!("GraphQL error(s): {msg}");
+/// Session context with `--org`/`--team` applied and slugs resolved to
+/// ids through the API.
+async fn current_context(
+    client: &Client,
+    cfg: &Config,
+    overrides: &ContextOverride,
+) -> Result<SessionContext> {
+    let sess = load_session().unwrap_or_default();
+    let mut ctx = sess.context.with_overrides(overrides);
+
+    // Both known by slug only: one lookup resolves the pair, and the team
+    // slug is looked up within the right organization.
+    if let (None, None, Some(org_slug), Some(team_slug)) = (
+        ctx.organization_id,
+        ctx.team_id,
+        &ctx.organization_slug,
+        &ctx.team_slug,
+    ) {
+        let team = gql_team_by_slug_path(client, cfg, org_slug, team_slug)
+            .await?
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "No team '{team_slug}' in organization '{org_slug}'."
+                )
+            })?;
+        ctx.organization_id = Some(team.organization_id);
+        ctx.team_id = Some(team.id);
     }
 
-    let data = gql
-        .data
-        .ok_or_else(|| anyhow::anyhow!("Missing data in GraphQL response"))?;
-    Ok(data.registerUser)
+    if let (None, Some(slug)) = (ctx.organization_id, &ctx.organization_slug) {
+        ctx.organization_id =
+            Some(resolve_slug(client, cfg, SlugKind::Org, slug).await?);
+    }
+    if let (None, Some(slug)) = (ctx.team_id, &ctx.team_slug) {
+        ctx.team_id =
+            Some(resolve_slug(client, cfg, SlugKind::Team, slug).await?);
+    }
+
+    Ok(ctx)
 }
 
-async fn gql_create_org(
+/// Id of the organization/team/app whose slug is exactly `slug`.
+async fn resolve_slug(
     client: &Client,
     cfg: &Config,
-    name: &str,
+    kind: SlugKind,
     slug: &str,
-    description: Option<&str>,
-) -> Result<OrganizationResponse> {
-    let req_body = GqlRequest {
-        query: CREATE_ORGANIZATION_MUTATION,
-        variables: Some(CreateOrganizationVariables {
-            input: CreateOrganizationInput { name, slug, description },
-        }),
-    };
+) -> Result<i64> {
+    let hits = gql_search(client, cfg, slug, SEARCH_FETCH_LIMIT).await?;
 
-    let res = client
-        .post(&cfg.auth.base_url)
-        .bearer_auth(&cfg.auth.token)
-        .json(&req_body)
-        .send()
-        .await
-        .context("Failed to send createOrganization GraphQL request")?;
+    let ids: Vec<i64> = hits
+        .into_iter()
+        .filter_map(|hit| match (kind, hit) {
+            (SlugKind::Org, SearchHit::Organization { id, slug: s, .. })
+            | (SlugKind::Team, SearchHit::Team { id, slug: s, .. })
+            | (SlugKind::App, SearchHit::App { id, slug: s, .. })
+                if s == slug =>
+            {
+                Some(id)
+            }
+            _ => None,
+        })
+        .collect();
 
-    if !res.status().is_success() {
+    match ids.as_slice() {
+        [id] => Ok(*id),
+        [] => anyhow::bail!("No {} found with slug '{slug}'.", kind.label()),
+        _ => anyhow::bail!(
+            "Slug '{slug}' matches several {} entries; use an id.",
+            kind.label()
+        ),
+    }
+}
+
+fn ensure_authenticated() -> Result<Config> {
+    let cfg = load_config().unwrap_or_default();
+    if cfg.auth.token.is_empty() {
         anyhow::bail!(
-            "createOrganization failed with HTTP status {}",
-            res.status()
+            "You must be authenticated. Run `paastel auth login` first."
+        );
+    }
+    if cfg.auth.base_url.is_empty() {
+        anyhow::bail!(
+            "GraphQL endpoint is not configured. Set it during login or in config.toml."
         );
     }
+    Ok(cfg)
+}
 
-    let gql: GqlResponse<CreateOrganizationData> = res
-        .json()
-        .await
-        .context("Failed to parse GraphQL response for createOrganization")?;
+fn prompt(label: &str) -> Result<String> {
+    use std::io::{self, Write};
 
-    if let Some(errors) = gql.errors {
-        let msg = errors
-            .into_iter()
-            .map(|e| e.message)
-            .collect::<Vec<_>>()
-            .join("; ");
-        anyhow::bail!("GraphQL error(s): {msg}");
+    print!("{label}");
+    io::stdout().flush().ok();
+
+    let mut buf = String::new();
+    io::stdin().read_line(&mut buf).context("Failed to read from stdin")?;
+    Ok(buf.trim().to_string())
+}
+
+/// Read a password without echo; piped input (no TTY) is read as a
+/// visible line, like `prompt`. Surrounding whitespace is trimmed either way.
+fn prompt_password(label: &str) -> Result<String> {
+    use std::io::IsTerminal;
+
+    if !std::io::stdin().is_terminal() {
+        return prompt(label);
     }
 
-    let data = gql
-        .data
-        .ok_or_else(|| anyhow::anyhow!("Missing data in GraphQL response"))?;
-    Ok(data.createOrganization)
+    let password = rpassword::prompt_password(label)
+        .context("Failed to read password")?;
+    Ok(password.trim().to_string())
 }
 
-async fn gql_create_team(
-    client: &Client,
-    cfg: &Config,
-    org_id: i64,
-    name: &str,
-    slug: &str,
-    description: Option<&str>,
-) -> Result<TeamResponse> {
-    let req_body = GqlRequest {
-        query: CREATE_TEAM_MUTATION,
-        variables: Some(CreateTeamVariables {
-            input: CreateTeamInput {
-                organizationId: org_id as i32,
-                name,
-                slug,
-                description,
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use super::*;
+
+    fn response(json: &str) -> GqlResponse<serde_json::Value> {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn into_result_returns_the_data() {
+        let data =
+            into_result(response(r#"{"data": {"me": {"id": 1}}}"#)).unwrap();
+
+        assert_eq!(data["me"]["id"], 1);
+    }
+
+    #[test]
+    fn into_result_joins_every_error_message() {
+        let err = into_result(response(
+            r#"{
+                "data": null,
+                "errors": [{"message": "slug taken"}, {"message": "bad email"}]
+            }"#,
+        ))
+        .unwrap_err();
+
+        assert_eq!(err.to_string(), "GraphQL error(s): slug taken; bad email");
+    }
+
+    #[test]
+    fn into_result_prefers_errors_over_partial_data() {
+        let err = into_result(response(
+            r#"{"data": {"me": null}, "errors": [{"message": "denied"}]}"#,
+        ))
+        .unwrap_err();
+
+        assert_eq!(err.to_string(), "GraphQL error(s): denied");
+    }
+
+    #[test]
+    fn into_result_reports_missing_data() {
+        let err = into_result(response("{}")).unwrap_err();
+
+        assert_eq!(err.to_string(), "Missing data in GraphQL response");
+    }
+
+    /// Fresh, empty directory under the system temp dir.
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir()
+            .join(format!("paastel-cli-{}-{name}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn scaffold_writes_each_runtime_template() {
+        for (runtime, base_image) in [
+            (ScaffoldRuntime::Nodejs22, "FROM node:22-alpine"),
+            (ScaffoldRuntime::Python312, "FROM python:3.12-slim"),
+            (ScaffoldRuntime::Go122, "FROM golang:1.22-alpine"),
+        ] {
+            let dir = scratch_dir(&format!("{runtime:?}"));
+
+            let written = scaffold(&dir, runtime, false).unwrap();
+
+            assert_eq!(
+                written,
+                vec![dir.join("Dockerfile"), dir.join(".dockerignore")]
+            );
+            let dockerfile =
+                fs::read_to_string(dir.join("Dockerfile")).unwrap();
+            assert!(dockerfile.starts_with(base_image), "{runtime:?}");
+            let dockerignore =
+                fs::read_to_string(dir.join(".dockerignore")).unwrap();
+            assert!(dockerignore.lines().any(|line| line == ".git"));
+            fs::remove_dir_all(&dir).unwrap();
+        }
+    }
+
+    #[test]
+    fn scaffold_refuses_to_overwrite_without_force() {
+        let dir = scratch_dir("guard");
+        fs::write(dir.join(".dockerignore"), "mine\n").unwrap();
+
+        let err = scaffold(&dir, ScaffoldRuntime::Go122, false).unwrap_err();
+
+        assert!(err.to_string().contains(".dockerignore"), "{err}");
+        assert!(!dir.join("Dockerfile").exists());
+        assert_eq!(
+            fs::read_to_string(dir.join(".dockerignore")).unwrap(),
+            "mine\n"
+        );
+
+        scaffold(&dir, ScaffoldRuntime::Go122, true).unwrap();
+        assert_ne!(
+            fs::read_to_string(dir.join(".dockerignore")).unwrap(),
+            "mine\n"
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn base_url_precedence() {
+        let flag = || Some("https://flag/graphql".to_string());
+        let env = || Some("https://env/graphql".to_string());
+
+        assert_eq!(
+            resolve_base_url(flag(), "https://config/graphql", env()),
+            "https://flag/graphql"
+        );
+        assert_eq!(
+            resolve_base_url(None, "https://config/graphql", env()),
+            "https://config/graphql"
+        );
+        assert_eq!(resolve_base_url(None, "", env()), "https://env/graphql");
+        assert_eq!(
+            resolve_base_url(Some(String::new()), "", Some(String::new())),
+            DEFAULT_BASE_URL
+        );
+    }
+
+    #[test]
+    fn completion_keeps_slugs_of_the_kind_with_the_prefix() {
+        let hits: Vec<SearchHit> = serde_json::from_str(
+            r#"[
+                {"__typename": "Organization", "id": 1, "name": "Pay", "slug": "pay"},
+                {"__typename": "Team", "id": 2, "name": "Payments", "slug": "payments"},
+                {"__typename": "App", "id": 3, "name": "Paywall", "slug": "paywall"},
+                {"__typename": "App", "id": 4, "name": "Pay API", "slug": "api-pay"}
+            ]"#,
+        )
+        .unwrap();
+
+        assert_eq!(completion_slugs(SlugKind::App, "pay", hits), ["paywall"]);
+    }
+
+    #[test]
+    fn complete_helper_parses_kind_and_prefix() {
+        let cli = Cli::try_parse_from(["paastel", "__complete", "team", "pa"])
+            .unwrap();
+
+        assert!(matches!(
+            cli.command,
+            Commands::Complete { kind: SlugKind::Team, ref prefix } if prefix == "pa"
+        ));
+    }
+
+    #[test]
+    fn bash_glue_falls_back_to_the_generated_function() {
+        let mut script = Vec::new();
+        clap_complete::generate(
+            Shell::Bash,
+            &mut Cli::command(),
+            "paastel",
+            &mut script,
+        );
+        let script = String::from_utf8(script).unwrap();
+
+        assert!(script.contains("_paastel()"));
+        assert!(BASH_DYNAMIC_COMPLETION.contains("_paastel \"$@\""));
+        assert!(
+            BASH_DYNAMIC_COMPLETION
+                .contains("paastel __complete \"$kind\" \"$cur\"")
+        );
+    }
+
+    fn session_context() -> SessionContext {
+        SessionContext {
+            organization_id: Some(1),
+            organization_slug: Some("acme".into()),
+            team_id: Some(10),
+            team_slug: Some("core".into()),
+        }
+    }
+
+    #[test]
+    fn no_overrides_keep_the_session_context() {
+        let ctx =
+            session_context().with_overrides(&ContextOverride::default());
+
+        assert_eq!(ctx.organization_id, Some(1));
+        assert_eq!(ctx.team_id, Some(10));
+    }
+
+    #[test]
+    fn org_override_replaces_the_org_and_drops_its_team() {
+        let overrides =
+            ContextOverride { org: Some("globex".into()), team: None };
+
+        let ctx = session_context().with_overrides(&overrides);
+
+        assert_eq!(ctx.organization_id, None);
+        assert_eq!(ctx.organization_slug.as_deref(), Some("globex"));
+        assert_eq!(ctx.team_id, None);
+        assert_eq!(ctx.team_slug, None);
+    }
+
+    #[test]
+    fn team_override_keeps_the_org() {
+        let overrides =
+            ContextOverride { org: None, team: Some("web".into()) };
+
+        let ctx = session_context().with_overrides(&overrides);
+
+        assert_eq!(ctx.organization_id, Some(1));
+        assert_eq!(ctx.team_id, None);
+        assert_eq!(ctx.team_slug.as_deref(), Some("web"));
+    }
+
+    #[test]
+    fn context_flags_are_global() {
+        let cli = Cli::try_parse_from([
+            "paastel", "context", "show", "--org", "globex", "--team", "web",
+        ])
+        .unwrap();
+
+        assert_eq!(cli.org.as_deref(), Some("globex"));
+        assert_eq!(cli.team.as_deref(), Some("web"));
+    }
+
+    fn sample_apps() -> Vec<AppResponse> {
+        vec![
+            AppResponse {
+                id: 1,
+                team_id: Some(7),
+                name: "Web".into(),
+                slug: "web".into(),
+                repo_url: Some("https://git.example.com/web".into()),
+                updated_at: "2026-01-02T03:04:05Z".into(),
             },
-        }),
-    };
+            AppResponse {
+                id: 22,
+                team_id: None,
+                name: "Background worker".into(),
+                slug: "worker".into(),
+                repo_url: None,
+                updated_at: "2026-02-03T04:05:06Z".into(),
+            },
+        ]
+    }
 
-    let res = client
-        .post(&cfg.auth.base_url)
-        .bearer_auth(&cfg.auth.token)
-        .json(&req_body)
-        .send()
-        .await
-        .context("Failed to send createTeam GraphQL request")?;
+    fn render_apps(format: OutputFormat) -> String {
+        render_list(format, APP_COLUMNS, |a| a.slug.clone(), &sample_apps())
+            .unwrap()
+    }
 
-    if !res.status().is_success() {
-        anyhow::bail!("createTeam failed with HTTP status {}", res.status());
+    #[test]
+    fn table_output_aligns_the_default_columns() {
+        assert_eq!(
+            render_apps(OutputFormat::Table),
+            "SLUG    NAME               TEAM\n\
+             web     Web                7\n\
+             worker  Background worker  -\n"
+        );
     }
 
-    let gql: GqlResponse<CreateTeamData> = res
-        .json()
-        .await
-        .context("Failed to parse GraphQL response for createTeam")?;
+    #[test]
+    fn wide_output_adds_the_wide_only_columns() {
+        let out = render_apps(OutputFormat::Wide);
+        let lines: Vec<&str> = out.lines().collect();
 
-    if let Some(errors) = gql.errors {
-        let msg = errors
-            .into_iter()
-            .map(|e| e.message)
-            .collect::<Vec<_>>()
-            .join("; ");
-        anyhow::bail!("GraphQL error(s): {msg}");
+        let header: Vec<&str> = lines[0].split_whitespace().collect();
+        let updated = lines[0].find("UPDATED").unwrap();
+
+        assert_eq!(header, ["SLUG", "NAME", "TEAM", "ID", "REPO", "UPDATED"]);
+        assert_eq!(&lines[1][updated..], "2026-01-02T03:04:05Z");
+        assert_eq!(&lines[2][updated..], "2026-02-03T04:05:06Z");
+        assert!(lines[2].contains("  22  -  "));
+        assert_eq!(lines.len(), 3);
     }
 
-    let data = gql
-        .data
-        .ok_or_else(|| anyhow::anyhow!("Missing data in GraphQL response"))?;
-    Ok(data.createTeam)
-}
+    #[test]
+    fn name_output_prints_one_slug_per_line() {
+        assert_eq!(render_apps(OutputFormat::Name), "web\nworker\n");
+        assert_eq!(
+            render_list(
+                OutputFormat::Table,
+                APP_COLUMNS,
+                |a| a.slug.clone(),
+                &[]
+            )
+            .unwrap(),
+            "SLUG  NAME  TEAM\n"
+        );
+    }
 
-// --------------------
-// Command dispatcher
-// --------------------
+    #[test]
+    fn output_flag_is_global() {
+        let cli =
+            Cli::try_parse_from(["paastel", "app", "list", "-o", "wide"])
+                .unwrap();
 
-#[tokio::main]
-async fn main() -> Result<()> {
-    let cli = Cli::parse();
-    let http_client = Client::new();
+        assert_eq!(cli.output, OutputFormat::Wide);
+    }
 
-    match cli.command {
-        Commands::Auth(cmd) => handle_auth(cmd, &http_client).await?,
-        Commands::Org(cmd) => handle_org(cmd, &http_client).await?,
-        Commands::Team(cmd) => handle_team(cmd, &http_client).await?,
-        Commands::Context(cmd) => handle_context(cmd)?,
-        Commands::App(cmd) => handle_app(cmd, &http_client).await?,
+    fn last_app_list() -> LastList {
+        LastList {
+            kind: "app".into(),
+            organization_id: 1,
+            saved_at: 1_000,
+            slugs: vec!["api".into(), "web".into()],
+        }
     }
 
-    Ok(())
-}
+    #[test]
+    fn last_index_resolves_to_the_listed_slug() {
+        let last = last_app_list();
 
-// -------------
-// Auth handler
-// -------------
+        let first = resolve_last_index(&last, "app", 1, 1, 1_000);
+        let second = resolve_last_index(&last, "app", 1, 2, 1_000 + 600);
 
-async fn handle_auth(cmd: AuthCommand, client: &Client) -> Result<()> {
-    match cmd {
-        AuthCommand::Login { name, email, password, base_url } => {
-            let name = match name {
-                Some(v) => v,
-                None => prompt("Name: ")?,
-            };
+        assert_eq!(first.unwrap().unwrap(), "api");
+        assert_eq!(second.unwrap().unwrap(), "web");
+    }
 
-            let email = match email {
-                Some(v) => v,
-                None => prompt("Email: ")?,
-            };
+    #[test]
+    fn last_index_out_of_range_is_an_error() {
+        let last = last_app_list();
 
-            let password = match password {
-                Some(v) => v,
-                None => prompt_password("Password: ")?,
-            };
+        for index in [0, 3] {
+            let err = resolve_last_index(&last, "app", 1, index, 1_000)
+                .unwrap()
+                .unwrap_err();
 
-            let mut cfg = load_config().unwrap_or_default();
+            assert!(err.to_string().contains("(2 rows)"), "{err}");
+        }
+    }
+
+    #[test]
+    fn other_kinds_orgs_and_stale_lists_are_ignored() {
+        let last = last_app_list();
+
+        assert!(resolve_last_index(&last, "team", 1, 1, 1_000).is_none());
+        assert!(resolve_last_index(&last, "app", 2, 1, 1_000).is_none());
+        assert!(resolve_last_index(&last, "app", 1, 1, 1_000 + 601).is_none());
+    }
+
+    #[test]
+    fn numbered_prefixes_rows_but_not_the_header() {
+        assert_eq!(
+            numbered("SLUG  NAME\napi   API\nweb   Web\n"),
+            "#   SLUG  NAME\n1   api   API\n2   web   Web\n"
+        );
+    }
+
+    #[test]
+    fn app_info_takes_a_slug_or_last() {
+        let parse = |args: &[&str]| {
+            Cli::try_parse_from(["paastel", "app", "info"].iter().chain(args))
+        };
+
+        assert!(parse(&["web"]).is_ok());
+        assert!(parse(&["--last", "2"]).is_ok());
+        assert!(parse(&[]).is_err());
+        assert!(parse(&["web", "--last", "2"]).is_err());
+    }
+
+    #[test]
+    fn create_app_input_sends_a_null_team_and_omits_the_repo() {
+        let input = CreateAppInput {
+            organization_id: 1,
+            team_id: None,
+            name: "Web",
+            slug: "web",
+            repo_url: None,
+        };
+
+        assert_eq!(
+            serde_json::to_value(&input).unwrap(),
+            serde_json::json!({
+                "organizationId": 1,
+                "teamId": null,
+                "name": "Web",
+                "slug": "web",
+            })
+        );
+    }
+
+    #[test]
+    fn last_lines_keeps_the_trailing_newline() {
+        let text = "one\ntwo\nthree\n";
+
+        assert_eq!(last_lines(text, None), text);
+        assert_eq!(last_lines(text, Some(2)), "two\nthree\n");
+        assert_eq!(last_lines(text, Some(3)), text);
+        assert_eq!(last_lines(text, Some(10)), text);
+        assert_eq!(last_lines(text, Some(0)), "");
+    }
+
+    #[test]
+    fn last_lines_handles_a_missing_final_newline() {
+        assert_eq!(last_lines("one\ntwo\nthree", Some(1)), "three");
+        assert_eq!(last_lines("one\ntwo\nthree", Some(2)), "two\nthree");
+    }
 
-            if let Some(base) = base_url {
-                cfg.auth.base_url = base;
-            } else if cfg.auth.base_url.is_empty() {
-                // default GraphQL endpoint
-                cfg.auth.base_url =
-                    "http://localhost:3000/graphql".to_string();
-            }
+    #[test]
+    fn org_list_shows_the_callers_role() {
+        let data: MyOrganizationsData = serde_json::from_str(
+            r#"{"myOrganizations": [
+                {"organization": {"id": 1, "name": "Acme", "slug": "acme",
+                                  "description": null},
+                 "role": "OWNER"},
+                {"organization": {"id": 12, "name": "Zeta Labs",
+                                  "slug": "zeta", "description": "R&D"},
+                 "role": "MEMBER"}
+            ]}"#,
+        )
+        .unwrap();
 
-            let payload = gql_register_user(
-                client,
-                &cfg.auth.base_url,
-                &name,
-                &email,
-                &password,
-            )
-            .await?;
+        let out = render_list(
+            OutputFormat::Table,
+            ORG_COLUMNS,
+            |m| m.organization.slug.clone(),
+            &data.my_organizations,
+        )
+        .unwrap();
 
-            cfg.auth.token = payload.token.token;
-            save_config(&cfg)?;
-            // clear session when logging in/registering
-            save_session(&Session::default())?;
+        assert_eq!(
+            out,
+            "ID  SLUG  NAME       ROLE\n\
+             1   acme  Acme       OWNER\n\
+             12  zeta  Zeta Labs  MEMBER\n"
+        );
+    }
 
-            println!(
-                "User registered and logged in as {} ({})",
-                payload.user.name, payload.user.email
-            );
-        }
-        AuthCommand::Logout => {
-            let mut cfg = load_config().unwrap_or_default();
-            cfg.auth.token.clear();
-            save_config(&cfg)?;
-            println!("Logged out. Token removed from config.toml");
+    #[test]
+    fn watch_stops_on_terminal_statuses_only() {
+        for status in ["SUCCEEDED", "failed", "Canceled"] {
+            assert!(is_finished(status), "{status}");
         }
-        AuthCommand::Status => {
-            let cfg = load_config().unwrap_or_default();
-            if cfg.auth.token.is_empty() {
-                println!("Not authenticated. Run `paastel auth login` first.");
-            } else {
-                println!("Authenticated.");
-                println!("GraphQL endpoint: {}", cfg.auth.base_url);
-                println!("Token: present");
-            }
+        for status in ["PENDING", "RUNNING", "queued", ""] {
+            assert!(!is_finished(status), "{status}");
         }
     }
-    Ok(())
-}
 
-// -------------
-// Org handler
-// -------------
+    #[test]
+    fn profile_flag_is_global() {
+        let cli = Cli::try_parse_from([
+            "paastel",
+            "app",
+            "list",
+            "--profile",
+            "staging",
+        ])
+        .unwrap();
 
-async fn handle_org(cmd: OrgCommand, client: &Client) -> Result<()> {
-    match cmd {
-        OrgCommand::Create { name, slug, description } => {
-            let cfg = ensure_authenticated()?;
-            let org = gql_create_org(
-                client,
-                &cfg,
-                &name,
-                &slug,
-                description.as_deref(),
-            )
-            .await?;
+        assert_eq!(cli.profile.as_deref(), Some("staging"));
+    }
 
-            println!(
-                "Organization created: {} (id: {}, slug: {})",
-                org.name, org.id, org.slug
-            );
+    #[test]
+    fn flat_auth_tables_are_read_but_never_written() {
+        let legacy: Config = toml::from_str(
+            "[auth]\nbase_url = \"http://old/graphql\"\ntoken = \"t\"\n",
+        )
+        .unwrap();
 
-            // set as current context
-            let mut sess = load_session().unwrap_or_default();
-            sess.context.organization_id = Some(org.id as i64);
-            sess.context.organization_slug = Some(org.slug);
-            // when we change org, we can reset team
-            sess.context.team_id = None;
-            sess.context.team_slug = None;
-            save_session(&sess)?;
-            println!("Organization set as current context.");
-        }
-        OrgCommand::Use { id, slug } => {
-            let cfg = ensure_authenticated()?;
-            if cfg.auth.token.is_empty() {
-                anyhow::bail!(
-                    "You must be authenticated to use an organization."
-                );
-            }
+        assert!(legacy.profiles.is_empty());
+        assert_eq!(legacy.auth.token, "t");
+        assert!(!toml::to_string(&legacy).unwrap().contains("[auth]"));
+    }
 
-            let mut sess = load_session().unwrap_or_default();
+    #[test]
+    fn json_output_is_the_full_rows_in_api_field_names() {
+        let doc: serde_json::Value =
+            serde_json::from_str(&render_apps(OutputFormat::Json)).unwrap();
 
-            match (id, slug) {
-                (Some(id), _) => {
-                    sess.context.organization_id = Some(id);
-                    sess.context.organization_slug = None;
-                }
-                (None, Some(slug)) => {
-                    sess.context.organization_slug = Some(slug);
-                    sess.context.organization_id = None;
-                }
-                _ => {
-                    anyhow::bail!("You must provide either --id or --slug.");
-                }
-            }
+        assert_eq!(doc.as_array().unwrap().len(), 2);
+        assert_eq!(doc[0]["slug"], "web");
+        assert_eq!(doc[0]["teamId"], 7);
+        assert_eq!(doc[1]["repoUrl"], serde_json::Value::Null);
+    }
 
-            // when org changes, we usually reset team
-            sess.context.team_id = None;
-            sess.context.team_slug = None;
+    #[test]
+    fn yaml_output_round_trips() {
+        let doc: serde_json::Value =
+            serde_yaml::from_str(&render_apps(OutputFormat::Yaml)).unwrap();
 
-            save_session(&sess)?;
-            println!("Organization context updated.");
-        }
+        assert_eq!(doc[1]["name"], "Background worker");
+        assert_eq!(doc[1]["updatedAt"], "2026-02-03T04:05:06Z");
     }
 
-    Ok(())
-}
+    #[test]
+    fn only_json_and_yaml_are_rendered_as_documents() {
+        let team = TeamResponse {
+            id: 3,
+            organization_id: 1,
+            name: "Web".into(),
+            slug: "web".into(),
+            description: None,
+        };
 
-// -------------
-// Team handler
-// -------------
+        assert!(render(&team, OutputFormat::Json).unwrap().is_some());
+        assert!(render(&team, OutputFormat::Yaml).unwrap().is_some());
+        for format in
+            [OutputFormat::Table, OutputFormat::Wide, OutputFormat::Name]
+        {
+            assert!(render(&team, format).unwrap().is_none());
+            assert!(!format.is_structured());
+        }
+    }
 
-async fn handle_team(cmd: TeamCommand, client: &Client) -> Result<()> {
-    match cmd {
-        TeamCommand::Create { name, slug, description } => {
-            let cfg = ensure_authenticated()?;
-            let sess = load_session().unwrap_or_default();
+    /// `raw` parsed as `--since`, as seconds before now.
+    fn seconds_ago(raw: &str) -> u64 {
+        let bound = parse_time_bound(raw).unwrap();
+        SystemTime::now().duration_since(bound).unwrap().as_secs()
+    }
 
-            let org_id = sess.context.organization_id.ok_or_else(|| {
-                anyhow::anyhow!(
-                    "No organization selected. Use `paastel org use` first."
-                )
-            })?;
+    fn build_list_range(args: &[&str]) -> TimeRangeArgs {
+        let cli = Cli::try_parse_from(
+            ["paastel", "build", "list", "--app", "web"].iter().chain(args),
+        )
+        .unwrap();
+        match cli.command {
+            Commands::Build(BuildCommand::List { range, .. }) => range,
+            other => panic!("unexpected command {other:?}"),
+        }
+    }
 
-            let team = gql_create_team(
-                client,
-                &cfg,
-                org_id,
-                &name,
-                &slug,
-                description.as_deref(),
-            )
-            .await?;
+    #[test]
+    fn time_bound_accepts_human_durations() {
+        assert!((24 * 3600..24 * 3600 + 5).contains(&seconds_ago("24h")));
+        assert!((7 * 86400..7 * 86400 + 5).contains(&seconds_ago("7d")));
+        assert!((90 * 60..90 * 60 + 5).contains(&seconds_ago("1h 30m")));
+    }
 
-            println!(
-                "Team created: {} (id: {}, slug: {})",
-                team.name, team.id, team.slug
-            );
+    #[test]
+    fn time_bound_accepts_rfc3339_timestamps() {
+        assert_eq!(
+            parse_time_bound("2025-11-16T12:00:00Z").unwrap(),
+            UNIX_EPOCH + Duration::from_secs(1_763_294_400)
+        );
+    }
 
-            let mut sess = sess;
-            sess.context.team_id = Some(team.id as i64);
-            sess.context.team_slug = Some(team.slug);
-            save_session(&sess)?;
-            println!("Team set as current context.");
+    #[test]
+    fn time_bound_rejects_anything_else() {
+        for raw in ["", "yesterday", "24", "2025-13-01T00:00:00Z"] {
+            assert!(parse_time_bound(raw).is_err(), "{raw:?} accepted");
         }
-        TeamCommand::Use { id, slug } => {
-            let _cfg = ensure_authenticated()?;
-            let mut sess = load_session().unwrap_or_default();
-
-            if sess.context.organization_id.is_none()
-                && sess.context.organization_slug.is_none()
-            {
-                anyhow::bail!(
-                    "No organization selected. Use `paastel org use` first."
-                );
-            }
+    }
 
-            match (id, slug) {
-                (Some(id), _) => {
-                    sess.context.team_id = Some(id);
-                    sess.context.team_slug = None;
-                }
-                (None, Some(slug)) => {
-                    sess.context.team_slug = Some(slug);
-                    sess.context.team_id = None;
-                }
-                _ => {
-                    anyhow::bail!("You must provide either --id or --slug.");
-                }
-            }
+    #[test]
+    fn range_is_sent_as_rfc3339() {
+        let range = build_list_range(&[
+            "--since",
+            "2025-11-16T12:00:00Z",
+            "--until",
+            "2025-11-17T00:00:00Z",
+        ]);
 
-            save_session(&sess)?;
-            println!("Team context updated.");
-        }
+        assert_eq!(
+            range.to_rfc3339().unwrap(),
+            (
+                Some("2025-11-16T12:00:00Z".to_string()),
+                Some("2025-11-17T00:00:00Z".to_string())
+            )
+        );
     }
 
-    Ok(())
-}
+    #[test]
+    fn range_bounds_are_optional() {
+        assert_eq!(build_list_range(&[]).to_rfc3339().unwrap(), (None, None));
 
-// ----------------
-// Context handler
-// ----------------
+        let (since, until) =
+            build_list_range(&["--since", "1h"]).to_rfc3339().unwrap();
+        assert!(since.is_some());
+        assert_eq!(until, None);
+    }
 
-fn handle_context(cmd: ContextCommand) -> Result<()> {
-    match cmd {
-        ContextCommand::Show => {
-            let cfg = load_config().unwrap_or_default();
-            let sess = load_session().unwrap_or_default();
+    #[test]
+    fn range_must_be_ordered() {
+        let range = build_list_range(&["--since", "1h", "--until", "2h"]);
 
-            println!("Auth:");
-            if cfg.auth.token.is_empty() {
-                println!("  Status      : not authenticated");
-            } else {
-                println!("  Status      : authenticated");
-                println!("  Endpoint    : {}", cfg.auth.base_url);
-            }
+        assert!(range.to_rfc3339().is_err());
+    }
 
-            println!();
-            println!("Context:");
-            match (
-                &sess.context.organization_id,
-                &sess.context.organization_slug,
-            ) {
-                (Some(id), Some(slug)) => {
-                    println!("  Organization: {} (id: {})", slug, id);
-                }
-                (Some(id), None) => {
-                    println!("  Organization: (id: {})", id);
-                }
-                (None, Some(slug)) => {
-                    println!("  Organization: {} (id: unknown)", slug);
-                }
-                (None, None) => {
-                    println!("  Organization: (not set)");
-                }
-            };
+    #[test]
+    fn dotenv_files_are_parsed_in_order() {
+        let entries = parse_dotenv(
+            "# database\n\
+             \n\
+             export DATABASE_URL=postgres://db/web\n\
+             API_KEY = abc123  # rotated monthly\n\
+             GREETING=\"hello\\nworld \\\"quoted\\\"\"\n\
+             LITERAL='no $EXPANSION \\n here'\n\
+             EMPTY=\n",
+        )
+        .unwrap();
 
-            match (&sess.context.team_id, &sess.context.team_slug) {
-                (Some(id), Some(slug)) => {
-                    println!("  Team        : {} (id: {})", slug, id);
-                }
-                (Some(id), None) => {
-                    println!("  Team        : (id: {})", id);
-                }
-                (None, Some(slug)) => {
-                    println!("  Team        : {} (id: unknown)", slug);
-                }
-                (None, None) => {
-                    println!("  Team        : (not set)");
-                }
-            };
-        }
-        ContextCommand::Clear => {
-            let path = session_path()?;
-            if path.exists() {
-                fs::remove_file(&path).with_context(|| {
-                    format!("Failed to remove session file {}", path.display())
-                })?;
-                println!("Session cleared.");
-            } else {
-                println!("Session not found. Nothing to clear.");
-            }
-        }
+        assert_eq!(
+            entries,
+            [
+                ("DATABASE_URL".into(), "postgres://db/web".into()),
+                ("API_KEY".into(), "abc123".into()),
+                ("GREETING".into(), "hello\nworld \"quoted\"".into()),
+                ("LITERAL".into(), "no $EXPANSION \\n here".into()),
+                ("EMPTY".into(), String::new()),
+            ]
+        );
     }
 
-    Ok(())
-}
+    #[test]
+    fn dotenv_errors_name_the_line() {
+        let missing_eq = parse_dotenv("A=1\nnot a pair\n").unwrap_err();
+        let missing_key = parse_dotenv("\n=value\n").unwrap_err();
 
-// -------------
-// App handler
-// -------------
+        assert_eq!(missing_eq.to_string(), "line 2: expected KEY=VALUE");
+        assert_eq!(missing_key.to_string(), "line 2: missing key");
+    }
 
-async fn handle_app(cmd: AppCommand, _client: &Client) -> Result<()> {
-    match cmd {
-        AppCommand::Create { .. } => {
-            anyhow::bail!(
-                "App creation is not implemented in the GraphQL schema yet. \
-                 Once you add a createApp mutation, we can wire it here."
-            );
+    fn secret_row(value: Option<&str>) -> SecretRow {
+        SecretRow {
+            key: "API_KEY".into(),
+            kind: "STRING".into(),
+            value: value.map(Into::into),
+            updated_at: "2026-01-02T03:04:05Z".into(),
         }
     }
-}
 
-// -------------------------
-// Small utility functions
-// -------------------------
+    #[test]
+    fn secret_values_are_only_listed_when_asked() {
+        let hidden = render_list(
+            OutputFormat::Json,
+            SECRET_COLUMNS,
+            |s| s.key.clone(),
+            &[secret_row(None)],
+        )
+        .unwrap();
+        let shown = render_list(
+            OutputFormat::Table,
+            SECRET_COLUMNS_WITH_VALUES,
+            |s| s.key.clone(),
+            &[secret_row(Some("s3cr3t"))],
+        )
+        .unwrap();
 
-fn ensure_authenticated() -> Result<Config> {
-    let cfg = load_config().unwrap_or_default();
-    if cfg.auth.token.is_empty() {
-        anyhow::bail!(
-            "You must be authenticated. Run `paastel auth login` first."
+        assert!(!hidden.contains("value"), "{hidden}");
+        assert_eq!(
+            shown,
+            "KEY      TYPE    VALUE\n\
+             API_KEY  string  s3cr3t\n"
         );
     }
-    if cfg.auth.base_url.is_empty() {
-        anyhow::bail!(
-            "GraphQL endpoint is not configured. Set it during login or in config.toml."
+
+    #[test]
+    fn org_create_renders_yaml() {
+        let org: OrganizationResponse = serde_json::from_str(
+            r#"{"id": 7, "name": "Acme", "slug": "acme", "description": null}"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            render(&org, OutputFormat::Yaml).unwrap().unwrap(),
+            "id: 7\nname: Acme\nslug: acme\ndescription: null\n"
         );
+        assert_eq!(render(&org, OutputFormat::Table).unwrap(), None);
     }
-    Ok(cfg)
-}
 
-fn prompt(label: &str) -> Result<String> {
-    use std::io::{self, Write};
+    #[test]
+    fn app_list_renders_yaml() {
+        let apps: Vec<AppResponse> = serde_json::from_str(
+            r#"[
+                {"id": 1, "teamId": 3, "name": "Web", "slug": "web",
+                 "repoUrl": "https://github.com/acme/web.git",
+                 "updatedAt": "2025-11-16T12:00:00Z"},
+                {"id": 2, "teamId": null, "name": "Worker", "slug": "worker",
+                 "repoUrl": null, "updatedAt": "2025-11-17T08:30:00Z"}
+            ]"#,
+        )
+        .unwrap();
 
-    print!("{label}");
-    io::stdout().flush().ok();
+        let yaml = render_list(
+            OutputFormat::Yaml,
+            APP_COLUMNS,
+            |a| a.slug.clone(),
+            &apps,
+        )
+        .unwrap();
 
-    let mut buf = String::new();
-    io::stdin().read_line(&mut buf).context("Failed to read from stdin")?;
-    Ok(buf.trim().to_string())
-}
+        assert_eq!(
+            yaml,
+            "- id: 1
+  teamId: 3
+  name: Web
+  slug: web
+  repoUrl: https://github.com/acme/web.git
+  updatedAt: 2025-11-16T12:00:00Z
+- id: 2
+  teamId: null
+  name: Worker
+  slug: worker
+  repoUrl: null
+  updatedAt: 2025-11-17T08:30:00Z
+"
+        );
+    }
 
-fn prompt_password(label: &str) -> Result<String> {
-    // For now, simple prompt. You can switch to rpassword crate if you want hidden input.
-    prompt(label)
+    #[test]
+    fn empty_list_renders_as_an_empty_yaml_sequence() {
+        let apps: Vec<AppResponse> = Vec::new();
+
+        assert_eq!(
+            render_list(
+                OutputFormat::Yaml,
+                APP_COLUMNS,
+                |a| a.slug.clone(),
+                &apps
+            )
+            .unwrap(),
+            "[]\n"
+        );
+    }
 }
@@ -1,13 +1,13 @@
 use std::env;
 use std::fs;
-use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
 
 use anyhow::{Context, Result, anyhow};
 
-/// Default root directory for all bare repos.
-/// Can be overridden with PAASTEL_GIT_ROOT.
-const DEFAULT_GIT_ROOT: &str = "/var/lib/paastel/git";
+use paastel::git::{
+    check_namespace, git_namespaces, git_root, init_bare_repo,
+    sanitize_repo_path,
+};
 
 fn main() {
     if let Err(err) = run() {
@@ -29,10 +29,9 @@ fn run() -> Result<()> {
 
     let (git_cmd, repo_path_raw) = parse_git_command(&original_command)?;
 
-    let root = env::var("PAASTEL_GIT_ROOT")
-        .unwrap_or_else(|_| DEFAULT_GIT_ROOT.to_string());
     let repo_rel = sanitize_repo_path(&repo_path_raw)?;
-    let repo_full = Path::new(&root).join(repo_rel);
+    check_namespace(&repo_rel, git_namespaces().as_deref())?;
+    let repo_full = git_root().join(repo_rel);
 
     if let Some(parent) = repo_full.parent() {
         fs::create_dir_all(parent).with_context(|| {
@@ -92,44 +91,3 @@ fn parse_git_command(cmd: &str) -> Result<(&'static str, String)> {
 
     Err(anyhow!("Unsupported git command: {cmd}"))
 }
-
-/// Very small sanitization for the repository path.
-/// We do not allow path traversal ("..") and strip leading slashes.
-/// This function returns a relative path to be appended to the GIT_ROOT.
-fn sanitize_repo_path(raw: &str) -> Result<PathBuf> {
-    if raw.contains("..") {
-        return Err(anyhow!(
-            "Repository path cannot contain '..' (got: {raw})"
-        ));
-    }
-
-    let trimmed = raw.trim_start_matches('/');
-    if trimmed.is_empty() {
-        return Err(anyhow!("Invalid repository path: {raw}"));
-    }
-
-    Ok(PathBuf::from(trimmed))
-}
-
-/// Initialize a bare git repository at the given path.
-///
-/// Equivalent to: `git init --bare /var/lib/paastel/git/kovi/devsecops/app.git`
-fn init_bare_repo(path: &Path) -> Result<()> {
-    println!("Initializing bare repository at {}", path.display());
-
-    let status = Command::new("git")
-        .arg("init")
-        .arg("--bare")
-        .arg(path)
-        .stdin(Stdio::null())
-        .stdout(Stdio::inherit())
-        .stderr(Stdio::inherit())
-        .status()
-        .context("Failed to run `git init --bare`")?;
-
-    if !status.success() {
-        return Err(anyhow!("`git init --bare` failed with status: {status}"));
-    }
-
-    Ok(())
-}
@@ -0,0 +1,378 @@
+//! Server configuration, read from `PAASTEL_*` environment variables once at
+//! startup. Only depends on std + anyhow.
+
+use std::path::PathBuf;
+
+use anyhow::{Result, bail};
+
+use crate::git::{
+    DEFAULT_GIT_ROOT, GIT_NAMESPACES_ENV, GIT_ROOT_ENV, parse_namespaces,
+};
+
+/// Initial Postgres connection attempts (`PAASTEL_DB_CONNECT_ATTEMPTS`).
+const DEFAULT_DB_CONNECT_ATTEMPTS: u64 = 10;
+/// Pause between them (`PAASTEL_DB_CONNECT_INTERVAL_SECS`).
+const DEFAULT_DB_CONNECT_INTERVAL_SECS: u64 = 2;
+
+/// Rate limit window (`PAASTEL_RATE_LIMIT_WINDOW_SECS`).
+const DEFAULT_RATE_LIMIT_WINDOW_SECS: u64 = 60;
+
+/// Seconds between housekeeping runs (`PAASTEL_REAPER_INTERVAL_SECS`).
+const DEFAULT_REAPER_INTERVAL_SECS: u64 = 60;
+/// Running deploys older than this are failed (`PAASTEL_DEPLOY_TIMEOUT_SECS`).
+const DEFAULT_DEPLOY_TIMEOUT_SECS: u64 = 3600;
+/// Running builds older than this are failed (`PAASTEL_BUILD_TIMEOUT_SECS`).
+const DEFAULT_BUILD_TIMEOUT_SECS: u64 = 3600;
+
+/// Organization invites expire after this many days
+/// (`PAASTEL_INVITE_TTL_DAYS`).
+const DEFAULT_INVITE_TTL_DAYS: u64 = 7;
+
+/// Default access token marker, so secret scanners can spot leaked tokens.
+const DEFAULT_TOKEN_PREFIX: &str = "pst_";
+
+/// Log output format (`PAASTEL_LOG_FORMAT`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LogFormat {
+    /// Human-readable lines, for local development
+    #[default]
+    Pretty,
+    /// One JSON object per line, for log aggregators
+    Json,
+}
+
+/// Typed server settings.
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// Postgres connection string (`DATABASE_URL`, required)
+    pub database_url: String,
+    /// Base64 32-byte key encrypting app secrets at rest
+    /// (`PAASTEL_SECRET_KEY`, required)
+    pub secret_key: String,
+    pub log_format: LogFormat,
+    pub db_connect_attempts: u64,
+    pub db_connect_interval_secs: u64,
+    /// Reject anonymous operations (`PAASTEL_REQUIRE_OP_NAME`)
+    pub require_op_name: bool,
+    /// Requests per client per window; `0` disables rate limiting
+    /// (`PAASTEL_RATE_LIMIT`)
+    pub rate_limit: u32,
+    pub rate_limit_window_secs: u64,
+    pub reaper_interval_secs: u64,
+    pub deploy_timeout_secs: u64,
+    pub build_timeout_secs: u64,
+    /// Reject deploys to environments without secrets instead of only
+    /// logging them (`PAASTEL_REQUIRE_SECRETS`)
+    pub require_secrets: bool,
+    /// Log password reset tokens in plaintext; development only, while no
+    /// mailer exists (`PAASTEL_DEV_LOG_RESET_TOKENS`)
+    pub dev_log_reset_tokens: bool,
+    /// Marker put in front of new access tokens (`PAASTEL_TOKEN_PREFIX`)
+    pub token_prefix: String,
+    /// Default per-organization app limit (`PAASTEL_MAX_APPS_PER_ORG`);
+    /// `None` means unlimited
+    pub max_apps_per_org: Option<usize>,
+    /// Days before an organization invite expires
+    /// (`PAASTEL_INVITE_TTL_DAYS`)
+    pub invite_ttl_days: u64,
+    /// Root directory of the bare repos served over HTTP
+    /// (`PAASTEL_GIT_ROOT`)
+    pub git_root: PathBuf,
+    /// Namespaces repos may live under (`PAASTEL_GIT_NAMESPACES`);
+    /// `None` means any
+    pub git_namespaces: Option<Vec<String>>,
+}
+
+impl Config {
+    /// Read the configuration from the process environment.
+    pub fn from_env() -> Result<Self> {
+        Self::from_vars(|name| std::env::var(name).ok())
+    }
+
+    /// Read the configuration through `lookup`.
+    ///
+    /// Every variable is checked before failing, so the error lists all
+    /// invalid ones at once.
+    pub fn from_vars(lookup: impl Fn(&str) -> Option<String>) -> Result<Self> {
+        let mut vars = Vars { lookup, errors: Vec::new() };
+
+        let config = Self {
+            database_url: vars.required("DATABASE_URL"),
+            secret_key: vars.required("PAASTEL_SECRET_KEY"),
+            log_format: vars.log_format("PAASTEL_LOG_FORMAT"),
+            db_connect_attempts: vars.positive(
+                "PAASTEL_DB_CONNECT_ATTEMPTS",
+                DEFAULT_DB_CONNECT_ATTEMPTS,
+            ),
+            db_connect_interval_secs: vars.positive(
+                "PAASTEL_DB_CONNECT_INTERVAL_SECS",
+                DEFAULT_DB_CONNECT_INTERVAL_SECS,
+            ),
+            require_op_name: vars.flag("PAASTEL_REQUIRE_OP_NAME"),
+            rate_limit: vars.count("PAASTEL_RATE_LIMIT").unwrap_or(0) as u32,
+            rate_limit_window_secs: vars.positive(
+                "PAASTEL_RATE_LIMIT_WINDOW_SECS",
+                DEFAULT_RATE_LIMIT_WINDOW_SECS,
+            ),
+            reaper_interval_secs: vars.positive(
+                "PAASTEL_REAPER_INTERVAL_SECS",
+                DEFAULT_REAPER_INTERVAL_SECS,
+            ),
+            deploy_timeout_secs: vars.positive(
+                "PAASTEL_DEPLOY_TIMEOUT_SECS",
+                DEFAULT_DEPLOY_TIMEOUT_SECS,
+            ),
+            build_timeout_secs: vars.positive(
+                "PAASTEL_BUILD_TIMEOUT_SECS",
+                DEFAULT_BUILD_TIMEOUT_SECS,
+            ),
+            require_secrets: vars.flag("PAASTEL_REQUIRE_SECRETS"),
+            dev_log_reset_tokens: vars.flag("PAASTEL_DEV_LOG_RESET_TOKENS"),
+            token_prefix: vars.token_prefix("PAASTEL_TOKEN_PREFIX"),
+            max_apps_per_org: vars
+                .count("PAASTEL_MAX_APPS_PER_ORG")
+                .map(|n| n as usize),
+            invite_ttl_days: vars
+                .positive("PAASTEL_INVITE_TTL_DAYS", DEFAULT_INVITE_TTL_DAYS),
+            git_root: vars
+                .get(GIT_ROOT_ENV)
+                .unwrap_or_else(|| DEFAULT_GIT_ROOT.to_string())
+                .into(),
+            git_namespaces: (vars.lookup)(GIT_NAMESPACES_ENV)
+                .map(|raw| parse_namespaces(&raw)),
+        };
+
+        if !vars.errors.is_empty() {
+            bail!(
+                "Invalid configuration:\n  - {}",
+                vars.errors.join("\n  - ")
+            );
+        }
+        Ok(config)
+    }
+}
+
+/// Variable reader that records problems instead of failing on the first.
+struct Vars<F> {
+    lookup: F,
+    errors: Vec<String>,
+}
+
+impl<F: Fn(&str) -> Option<String>> Vars<F> {
+    /// Trimmed value of `name`; unset and blank are the same.
+    fn get(&self, name: &str) -> Option<String> {
+        (self.lookup)(name)
+            .map(|raw| raw.trim().to_string())
+            .filter(|v| !v.is_empty())
+    }
+
+    fn required(&mut self, name: &str) -> String {
+        self.get(name).unwrap_or_else(|| {
+            self.errors.push(format!("{name} must be set"));
+            String::new()
+        })
+    }
+
+    /// A positive integer (seconds, attempts...), or `default`.
+    fn positive(&mut self, name: &str, default: u64) -> u64 {
+        let Some(raw) = self.get(name) else {
+            return default;
+        };
+        match raw.parse::<u64>() {
+            Ok(n) if n > 0 => n,
+            _ => {
+                self.errors.push(format!(
+                    "{name} must be a positive integer (got '{raw}')"
+                ));
+                default
+            }
+        }
+    }
+
+    /// A non-negative count that fits in `u32`; `None` when unset.
+    fn count(&mut self, name: &str) -> Option<u64> {
+        let raw = self.get(name)?;
+        match raw.parse::<u32>() {
+            Ok(n) => Some(u64::from(n)),
+            Err(_) => {
+                self.errors.push(format!(
+                    "{name} must be a non-negative integer (got '{raw}')"
+                ));
+                None
+            }
+        }
+    }
+
+    /// `1`/`true` or `0`/`false`; unset means off.
+    fn flag(&mut self, name: &str) -> bool {
+        match self.get(name).as_deref() {
+            None | Some("0" | "false") => false,
+            Some("1" | "true") => true,
+            Some(raw) => {
+                self.errors.push(format!(
+                    "{name} must be 1/true or 0/false (got '{raw}')"
+                ));
+                false
+            }
+        }
+    }
+
+    /// `pretty` or `json`; unset means pretty.
+    fn log_format(&mut self, name: &str) -> LogFormat {
+        match self.get(name).as_deref() {
+            None | Some("pretty") => LogFormat::Pretty,
+            Some("json") => LogFormat::Json,
+            Some(raw) => {
+                self.errors.push(format!(
+                    "{name} must be pretty or json (got '{raw}')"
+                ));
+                LogFormat::Pretty
+            }
+        }
+    }
+
+    /// Letters, digits, `_` and `-` only, so tokens stay header-safe.
+    fn token_prefix(&mut self, name: &str) -> String {
+        let Some(raw) = (self.lookup)(name) else {
+            return DEFAULT_TOKEN_PREFIX.to_string();
+        };
+        if !raw
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        {
+            self.errors.push(format!(
+                "{name} may only contain letters, digits, '_' and '-'"
+            ));
+            return DEFAULT_TOKEN_PREFIX.to_string();
+        }
+        raw
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn config(vars: &[(&str, &str)]) -> Result<Config> {
+        let vars: HashMap<String, String> =
+            vars.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        Config::from_vars(|name| vars.get(name).cloned())
+    }
+
+    #[test]
+    fn defaults_apply_when_only_the_database_is_set() {
+        let config = config(&[
+            ("DATABASE_URL", "postgres://db"),
+            ("PAASTEL_SECRET_KEY", "a2V5"),
+        ])
+        .unwrap();
+
+        assert_eq!(config.database_url, "postgres://db");
+        assert_eq!(config.secret_key, "a2V5");
+        assert_eq!(config.log_format, LogFormat::Pretty);
+        assert_eq!(config.db_connect_attempts, DEFAULT_DB_CONNECT_ATTEMPTS);
+        assert_eq!(
+            config.db_connect_interval_secs,
+            DEFAULT_DB_CONNECT_INTERVAL_SECS
+        );
+        assert!(!config.require_op_name);
+        assert_eq!(config.rate_limit, 0);
+        assert_eq!(
+            config.rate_limit_window_secs,
+            DEFAULT_RATE_LIMIT_WINDOW_SECS
+        );
+        assert_eq!(config.reaper_interval_secs, DEFAULT_REAPER_INTERVAL_SECS);
+        assert_eq!(config.deploy_timeout_secs, DEFAULT_DEPLOY_TIMEOUT_SECS);
+        assert_eq!(config.build_timeout_secs, DEFAULT_BUILD_TIMEOUT_SECS);
+        assert!(!config.require_secrets);
+        assert!(!config.dev_log_reset_tokens);
+        assert_eq!(config.token_prefix, DEFAULT_TOKEN_PREFIX);
+        assert_eq!(config.max_apps_per_org, None);
+        assert_eq!(config.invite_ttl_days, DEFAULT_INVITE_TTL_DAYS);
+        assert_eq!(config.git_root, PathBuf::from(DEFAULT_GIT_ROOT));
+        assert_eq!(config.git_namespaces, None);
+    }
+
+    #[test]
+    fn values_are_trimmed_and_parsed() {
+        let config = config(&[
+            ("DATABASE_URL", " postgres://db "),
+            ("PAASTEL_SECRET_KEY", " a2V5 "),
+            ("PAASTEL_LOG_FORMAT", "json"),
+            ("PAASTEL_DB_CONNECT_ATTEMPTS", "3"),
+            ("PAASTEL_REQUIRE_OP_NAME", "true"),
+            ("PAASTEL_RATE_LIMIT", " 120 "),
+            ("PAASTEL_REQUIRE_SECRETS", "1"),
+            ("PAASTEL_DEV_LOG_RESET_TOKENS", "true"),
+            ("PAASTEL_TOKEN_PREFIX", "acme_"),
+            ("PAASTEL_MAX_APPS_PER_ORG", "0"),
+            ("PAASTEL_INVITE_TTL_DAYS", "30"),
+            ("PAASTEL_GIT_ROOT", "/srv/git"),
+            ("PAASTEL_GIT_NAMESPACES", "acme, globex"),
+        ])
+        .unwrap();
+
+        assert_eq!(config.database_url, "postgres://db");
+        assert_eq!(config.secret_key, "a2V5");
+        assert_eq!(config.log_format, LogFormat::Json);
+        assert_eq!(config.db_connect_attempts, 3);
+        assert!(config.require_op_name);
+        assert_eq!(config.rate_limit, 120);
+        assert!(config.require_secrets);
+        assert!(config.dev_log_reset_tokens);
+        assert_eq!(config.token_prefix, "acme_");
+        assert_eq!(config.max_apps_per_org, Some(0));
+        assert_eq!(config.invite_ttl_days, 30);
+        assert_eq!(config.git_root, PathBuf::from("/srv/git"));
+        assert_eq!(
+            config.git_namespaces.as_deref(),
+            Some(&["acme".to_string(), "globex".to_string()][..])
+        );
+    }
+
+    #[test]
+    fn missing_required_values_are_reported() {
+        let blank = [("DATABASE_URL", "  "), ("PAASTEL_SECRET_KEY", "")];
+        for vars in [&[][..], &blank[..]] {
+            let err = config(vars).unwrap_err().to_string();
+
+            assert!(err.contains("DATABASE_URL must be set"), "{err}");
+            assert!(err.contains("PAASTEL_SECRET_KEY must be set"), "{err}");
+        }
+    }
+
+    #[test]
+    fn every_invalid_value_is_reported_at_once() {
+        let err = config(&[
+            ("PAASTEL_DB_CONNECT_ATTEMPTS", "0"),
+            ("PAASTEL_DEPLOY_TIMEOUT_SECS", "1h"),
+            ("PAASTEL_RATE_LIMIT", "-1"),
+            ("PAASTEL_MAX_APPS_PER_ORG", "lots"),
+            ("PAASTEL_REQUIRE_SECRETS", "yes"),
+            ("PAASTEL_TOKEN_PREFIX", "pst "),
+            ("PAASTEL_LOG_FORMAT", "xml"),
+        ])
+        .unwrap_err()
+        .to_string();
+
+        for expected in [
+            "DATABASE_URL must be set",
+            "PAASTEL_SECRET_KEY must be set",
+            "PAASTEL_DB_CONNECT_ATTEMPTS must be a positive integer \
+             (got '0')",
+            "PAASTEL_DEPLOY_TIMEOUT_SECS must be a positive integer \
+             (got '1h')",
+            "PAASTEL_RATE_LIMIT must be a non-negative integer (got '-1')",
+            "PAASTEL_MAX_APPS_PER_ORG must be a non-negative integer \
+             (got 'lots')",
+            "PAASTEL_REQUIRE_SECRETS must be 1/true or 0/false (got 'yes')",
+            "PAASTEL_TOKEN_PREFIX may only contain",
+            "PAASTEL_LOG_FORMAT must be pretty or json (got 'xml')",
+        ] {
+            assert!(err.contains(expected), "{expected:?} not in {err}");
+        }
+        assert!(err.starts_with("Invalid configuration:\n  - "));
+    }
+}
@@ -0,0 +1,176 @@
+use crate::domain::models::{BuildStatus, BuildStep};
+
+/// Steps in execution order, split into stages that run one after the
+/// other.
+///
+/// An ungrouped step is a stage of its own; steps sharing a `group` form
+/// one stage and run in parallel. Stages are ordered by the position of
+/// their first step.
+pub fn stages(steps: &[BuildStep]) -> Vec<Vec<&BuildStep>> {
+    let mut ordered: Vec<&BuildStep> = steps.iter().collect();
+    ordered.sort_by_key(|s| (s.position, s.id));
+
+    let mut stages: Vec<Vec<&BuildStep>> = Vec::new();
+    for step in ordered {
+        let existing = step.group.and_then(|group| {
+            stages.iter_mut().find(|stage| stage[0].group == Some(group))
+        });
+        match existing {
+            Some(stage) => stage.push(step),
+            None => stages.push(vec![step]),
+        }
+    }
+    stages
+}
+
+/// Status of one stage: failed as soon as any of its steps failed, even
+/// while others are still running.
+pub fn stage_status(stage: &[&BuildStep]) -> BuildStatus {
+    let any = |status| stage.iter().any(|s| s.status == status);
+
+    if any(BuildStatus::Failed) {
+        BuildStatus::Failed
+    } else if any(BuildStatus::Canceled) {
+        BuildStatus::Canceled
+    } else if any(BuildStatus::Running) {
+        BuildStatus::Running
+    } else if stage.iter().all(|s| s.status == BuildStatus::Succeeded) {
+        BuildStatus::Succeeded
+    } else if any(BuildStatus::Succeeded) {
+        // Part of a parallel group finished, the rest not started yet.
+        BuildStatus::Running
+    } else {
+        BuildStatus::Pending
+    }
+}
+
+/// Status of a build as implied by its steps; `None` without steps.
+///
+/// The first stage that did not succeed decides: a failed or canceled
+/// stage ends the build, a running or pending one means it is still in
+/// progress.
+pub fn rollup_status(steps: &[BuildStep]) -> Option<BuildStatus> {
+    if steps.is_empty() {
+        return None;
+    }
+
+    let stages = stages(steps);
+    let started =
+        stages.iter().flatten().any(|s| s.status != BuildStatus::Pending);
+    for stage in &stages {
+        match stage_status(stage) {
+            BuildStatus::Succeeded => continue,
+            BuildStatus::Pending if started => {
+                return Some(BuildStatus::Running);
+            }
+            status => return Some(status),
+        }
+    }
+    Some(BuildStatus::Succeeded)
+}
+
+#[cfg(test)]
+mod tests {
+    use time::OffsetDateTime;
+
+    use super::*;
+
+    use BuildStatus::{Canceled, Failed, Pending, Running, Succeeded};
+
+    /// `(position, group, status)` per step; ids follow the slice order.
+    fn steps(specs: &[(i32, Option<i32>, BuildStatus)]) -> Vec<BuildStep> {
+        specs
+            .iter()
+            .enumerate()
+            .map(|(i, &(position, group, status))| BuildStep {
+                id: i as i64 + 1,
+                build_id: 1,
+                position,
+                group,
+                name: format!("step-{i}"),
+                status,
+                created_at: OffsetDateTime::UNIX_EPOCH,
+                started_at: None,
+                finished_at: None,
+                logs_url: None,
+                error_message: None,
+            })
+            .collect()
+    }
+
+    fn stage_ids(steps: &[BuildStep]) -> Vec<Vec<i64>> {
+        stages(steps)
+            .iter()
+            .map(|stage| stage.iter().map(|s| s.id).collect())
+            .collect()
+    }
+
+    #[test]
+    fn ungrouped_steps_are_sequential_stages() {
+        let steps = steps(&[
+            (2, None, Pending),
+            (0, None, Pending),
+            (1, None, Pending),
+        ]);
+
+        assert_eq!(stage_ids(&steps), [[2], [3], [1]]);
+    }
+
+    #[test]
+    fn grouped_steps_share_a_stage() {
+        // checkout -> (lint | test) -> package
+        let steps = steps(&[
+            (0, None, Pending),
+            (1, Some(1), Pending),
+            (2, Some(1), Pending),
+            (3, None, Pending),
+        ]);
+
+        assert_eq!(stage_ids(&steps), vec![vec![1], vec![2, 3], vec![4]]);
+    }
+
+    #[test]
+    fn a_failed_step_fails_its_group_while_others_run() {
+        let steps = steps(&[(0, Some(1), Running), (1, Some(1), Failed)]);
+        let stages = stages(&steps);
+
+        assert_eq!(stage_status(&stages[0]), Failed);
+        assert_eq!(rollup_status(&steps), Some(Failed));
+    }
+
+    #[test]
+    fn a_partly_finished_group_is_running() {
+        let steps = steps(&[(0, Some(1), Succeeded), (1, Some(1), Pending)]);
+
+        assert_eq!(stage_status(&stages(&steps)[0]), Running);
+        assert_eq!(rollup_status(&steps), Some(Running));
+    }
+
+    #[test]
+    fn rollup_of_mixed_sequential_and_grouped_steps() {
+        let cases = [
+            ([Pending, Pending, Pending, Pending], Some(Pending)),
+            ([Succeeded, Running, Pending, Pending], Some(Running)),
+            // next stage not picked up yet: still in progress
+            ([Succeeded, Succeeded, Succeeded, Pending], Some(Running)),
+            ([Succeeded, Succeeded, Failed, Pending], Some(Failed)),
+            ([Succeeded, Canceled, Succeeded, Pending], Some(Canceled)),
+            ([Succeeded, Succeeded, Succeeded, Succeeded], Some(Succeeded)),
+        ];
+
+        for (statuses, expected) in cases {
+            let steps = steps(&[
+                (0, None, statuses[0]),
+                (1, Some(7), statuses[1]),
+                (2, Some(7), statuses[2]),
+                (3, None, statuses[3]),
+            ]);
+            assert_eq!(rollup_status(&steps), expected, "{statuses:?}");
+        }
+    }
+
+    #[test]
+    fn no_steps_no_status() {
+        assert_eq!(rollup_status(&[]), None);
+    }
+}
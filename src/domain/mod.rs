@@ -1 +1,3 @@
+pub mod build_steps;
 pub mod models;
+pub mod secrets;
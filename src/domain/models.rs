@@ -36,16 +36,42 @@ pub enum AppRole {
     Viewer,
 }
 
+/// How a secret is meant to be consumed by the app.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type,
+)]
+#[sqlx(type_name = "secret_kind", rename_all = "lowercase")]
+pub enum SecretKind {
+    /// Environment variable value
+    String,
+    /// File contents (certificates, JSON...), written to disk on deploy
+    File,
+}
+
 #[derive(
     Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type,
 )]
 #[sqlx(type_name = "release_status", rename_all = "lowercase")]
 pub enum ReleaseStatus {
     Pending,
+    Building,
     Built,
     Failed,
 }
 
+impl ReleaseStatus {
+    /// Whether a release may move from `self` to `next`:
+    /// `pending → building → built | failed`. A pending release may also be
+    /// finalized directly (e.g. promoted from an existing build).
+    pub fn can_transition_to(self, next: Self) -> bool {
+        use ReleaseStatus::*;
+        matches!(
+            (self, next),
+            (Pending, Building) | (Pending | Building, Built | Failed)
+        )
+    }
+}
+
 #[derive(
     Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type,
 )]
@@ -91,6 +117,8 @@ pub struct AuthToken {
     pub created_at: OffsetDateTime,
     pub last_used_at: Option<OffsetDateTime>,
     pub revoked_at: Option<OffsetDateTime>,
+    /// `None` for tokens that never expire
+    pub expires_at: Option<OffsetDateTime>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -98,6 +126,30 @@ pub struct NewAuthToken {
     pub user_id: i64,
     pub token: String,
     pub description: Option<String>,
+    pub expires_at: Option<OffsetDateTime>,
+}
+
+// ---------------- App deploy tokens ----------------
+
+/// Token scoped to one app, used by CI to trigger its builds and deploys.
+///
+/// Requests made with it act as `created_by`, limited to `app_id`.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct AppDeployToken {
+    pub id: i64,
+    pub app_id: i64,
+    pub token_hash: String,
+    pub created_by: i64,
+    pub created_at: OffsetDateTime,
+    pub last_used_at: Option<OffsetDateTime>,
+    pub revoked_at: Option<OffsetDateTime>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NewAppDeployToken {
+    pub app_id: i64,
+    pub token_hash: String,
+    pub created_by: i64,
 }
 
 // ---------- Organizations ----------
@@ -108,9 +160,12 @@ pub struct Organization {
     pub name: String,
     pub slug: String,
     pub description: Option<String>,
+    pub created_by: Option<i64>,
     pub created_at: OffsetDateTime,
     pub updated_at: OffsetDateTime,
     pub deleted_at: Option<OffsetDateTime>,
+    /// Custom app limit; `None` falls back to the instance default
+    pub max_apps: Option<i32>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -118,6 +173,7 @@ pub struct NewOrganization {
     pub name: String,
     pub slug: String,
     pub description: Option<String>,
+    pub created_by: Option<i64>,
 }
 
 // ---------- Users ----------
@@ -133,6 +189,8 @@ pub struct User {
     pub updated_at: OffsetDateTime,
     pub last_login_at: Option<OffsetDateTime>,
     pub deleted_at: Option<OffsetDateTime>,
+    /// Platform administrator (not tied to any organization)
+    pub is_admin: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -142,6 +200,26 @@ pub struct NewUser {
     pub password_hash: String,
 }
 
+// ---------- Password resets ----------
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct PasswordReset {
+    pub id: i64,
+    pub user_id: i64,
+    /// SHA-256 of the token sent to the user (never the token itself)
+    pub token_hash: String,
+    pub expires_at: OffsetDateTime,
+    pub used_at: Option<OffsetDateTime>,
+    pub created_at: OffsetDateTime,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NewPasswordReset {
+    pub user_id: i64,
+    pub token_hash: String,
+    pub expires_at: OffsetDateTime,
+}
+
 // ---------- Organization memberships ----------
 
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
@@ -152,6 +230,29 @@ pub struct OrganizationMembership {
     pub created_at: OffsetDateTime,
 }
 
+/// Invitation of an email without an account yet. Once registered, the
+/// user accepts (becoming a member) or declines it; either way it is
+/// deleted.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct OrganizationInvite {
+    pub id: i64,
+    pub organization_id: i64,
+    pub email: String,
+    pub role: OrgRole,
+    pub invited_by: Option<i64>,
+    pub created_at: OffsetDateTime,
+    pub expires_at: OffsetDateTime,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NewOrganizationInvite {
+    pub organization_id: i64,
+    pub email: String,
+    pub role: OrgRole,
+    pub invited_by: Option<i64>,
+    pub expires_at: OffsetDateTime,
+}
+
 // ---------- Teams ----------
 
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
@@ -161,6 +262,7 @@ pub struct Team {
     pub name: String,
     pub slug: String,
     pub description: Option<String>,
+    pub created_by: Option<i64>,
     pub created_at: OffsetDateTime,
     pub updated_at: OffsetDateTime,
     pub deleted_at: Option<OffsetDateTime>,
@@ -172,6 +274,7 @@ pub struct NewTeam {
     pub name: String,
     pub slug: String,
     pub description: Option<String>,
+    pub created_by: Option<i64>,
 }
 
 // ---------- Team memberships ----------
@@ -232,6 +335,7 @@ pub struct AppSecret {
     pub created_by: Option<i64>,
     pub created_at: OffsetDateTime,
     pub updated_at: OffsetDateTime,
+    pub kind: SecretKind,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -240,7 +344,30 @@ pub struct NewAppSecret {
     pub environment: String,
     pub key: String,
     pub value: String,
+    pub kind: SecretKind,
+    pub created_by: Option<i64>,
+}
+
+/// Previous value of a secret, recorded when it is overwritten.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct AppSecretVersion {
+    pub id: i64,
+    pub app_id: i64,
+    pub environment: String,
+    pub key: String,
+    pub version: i32,
+    pub value: String,
+    pub kind: SecretKind,
     pub created_by: Option<i64>,
+    pub replaced_at: OffsetDateTime,
+}
+
+/// Keys touched by a bulk secret sync.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AppSecretSyncSummary {
+    pub added: Vec<String>,
+    pub updated: Vec<String>,
+    pub removed: Vec<String>,
 }
 
 // ---------- Releases ----------
@@ -259,6 +386,8 @@ pub struct Release {
     pub created_at: OffsetDateTime,
     pub updated_at: OffsetDateTime,
     pub changelog: Option<String>,
+    pub status_changed_at: OffsetDateTime,
+    pub status_changed_by: Option<i64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -291,6 +420,8 @@ pub struct Deploy {
     pub pipeline_url: Option<String>,
     pub logs_url: Option<String>,
     pub error_message: Option<String>,
+    pub status_changed_at: OffsetDateTime,
+    pub status_changed_by: Option<i64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -307,6 +438,28 @@ pub struct NewDeploy {
     pub error_message: Option<String>,
 }
 
+/// Outcome of creating deploys for several environments at once.
+#[derive(Debug, Clone)]
+pub struct DeployBatch {
+    pub created: Vec<Deploy>,
+    /// Environments that already had a pending or running deploy
+    pub busy: Vec<String>,
+}
+
+// ---------- Deploy status events ----------
+
+/// One status transition of a deploy (written by a database trigger)
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct DeployStatusEvent {
+    pub id: i64,
+    pub deploy_id: i64,
+    /// `None` for the initial status
+    pub from_status: Option<DeployStatus>,
+    pub to_status: DeployStatus,
+    pub changed_by: Option<i64>,
+    pub created_at: OffsetDateTime,
+}
+
 // ---------- Build jobs ----------
 
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
@@ -355,6 +508,9 @@ pub struct BuildStep {
     pub id: i64,
     pub build_id: i64,
     pub position: i32,
+    /// Steps sharing a group run in parallel; `None` runs alone
+    #[sqlx(rename = "step_group")]
+    pub group: Option<i32>,
     pub name: String,
     pub status: BuildStatus,
     pub created_at: OffsetDateTime,
@@ -368,6 +524,7 @@ pub struct BuildStep {
 pub struct NewBuildStep {
     pub build_id: i64,
     pub position: i32,
+    pub group: Option<i32>,
     pub name: String,
     pub status: BuildStatus,
     pub logs_url: Option<String>,
@@ -0,0 +1,102 @@
+//! Repository layout shared by the SSH (`paastel-git-shell`) and HTTP
+//! git endpoints. Only depends on std + anyhow.
+
+use std::env;
+use std::path::{Component, Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use anyhow::{Context, Result, anyhow};
+
+/// Default root directory for all bare repos.
+/// Can be overridden with PAASTEL_GIT_ROOT.
+pub const DEFAULT_GIT_ROOT: &str = "/var/lib/paastel/git";
+
+pub const GIT_ROOT_ENV: &str = "PAASTEL_GIT_ROOT";
+
+/// Comma-separated first path segments (namespaces) repos may live under.
+/// Unset means any namespace.
+pub const GIT_NAMESPACES_ENV: &str = "PAASTEL_GIT_NAMESPACES";
+
+/// Root directory of the bare repos (`PAASTEL_GIT_ROOT`).
+pub fn git_root() -> PathBuf {
+    env::var(GIT_ROOT_ENV)
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from(DEFAULT_GIT_ROOT))
+}
+
+/// Namespace allow-list from `PAASTEL_GIT_NAMESPACES`; `None` when unset.
+pub fn git_namespaces() -> Option<Vec<String>> {
+    env::var(GIT_NAMESPACES_ENV).ok().map(|raw| parse_namespaces(&raw))
+}
+
+/// Split a comma-separated namespace list.
+pub fn parse_namespaces(raw: &str) -> Vec<String> {
+    raw.split(',').map(|ns| ns.trim().to_string()).collect()
+}
+
+/// Very small sanitization for the repository path.
+/// We do not allow path traversal ("..") and strip leading slashes.
+/// This function returns a relative path to be appended to the GIT_ROOT.
+pub fn sanitize_repo_path(raw: &str) -> Result<PathBuf> {
+    if raw.contains("..") {
+        return Err(anyhow!(
+            "Repository path cannot contain '..' (got: {raw})"
+        ));
+    }
+
+    let trimmed = raw.trim_start_matches('/');
+    if trimmed.is_empty() {
+        return Err(anyhow!("Invalid repository path: {raw}"));
+    }
+
+    Ok(PathBuf::from(trimmed))
+}
+
+/// First segment of a sanitized repo path (`kovi` in `kovi/devsecops/app.git`).
+pub fn repo_namespace(repo_rel: &Path) -> Option<&str> {
+    match repo_rel.components().next()? {
+        Component::Normal(first) => first.to_str(),
+        _ => None,
+    }
+}
+
+/// Reject repos outside the namespace allow-list, if any.
+pub fn check_namespace(
+    repo_rel: &Path,
+    allowed: Option<&[String]>,
+) -> Result<()> {
+    let Some(allowed) = allowed else {
+        return Ok(());
+    };
+
+    let namespace = repo_namespace(repo_rel).unwrap_or_default();
+    if allowed.iter().any(|ns| ns == namespace) {
+        Ok(())
+    } else {
+        Err(anyhow!("Namespace '{namespace}' is not allowed on this server"))
+    }
+}
+
+/// Initialize a bare git repository at the given path.
+///
+/// Equivalent to: `git init --bare /var/lib/paastel/git/kovi/devsecops/app.git`
+pub fn init_bare_repo(path: &Path) -> Result<()> {
+    eprintln!("Initializing bare repository at {}", path.display());
+
+    let status = Command::new("git")
+        .arg("init")
+        .arg("--bare")
+        .arg("--quiet")
+        .arg(path)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::inherit())
+        .status()
+        .context("Failed to run `git init --bare`")?;
+
+    if !status.success() {
+        return Err(anyhow!("`git init --bare` failed with status: {status}"));
+    }
+
+    Ok(())
+}
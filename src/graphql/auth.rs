@@ -4,4 +4,6 @@ use crate::domain::models::User;
 #[derive(Clone, Debug)]
 pub struct CurrentUser {
     pub user: User,
+    /// Auth token used for this request
+    pub token_id: i64,
 }
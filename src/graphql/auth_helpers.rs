@@ -1,16 +1,67 @@
 use async_graphql::{Context, Error as GqlError, Result as GqlResult};
 use axum::http::{self, header::AUTHORIZATION};
 
+use crate::domain::models::{App, AppRole, OrgRole, User};
 use crate::graphql::auth::CurrentUser;
+use crate::graphql::errors::forbidden;
 use crate::graphql::state::AppState;
+use crate::infrastructure::password::hash_token;
 use crate::infrastructure::repositories::{
-    AuthTokenRepository, UserRepository,
+    AppDeployTokenRepository, AppMembershipRepository, AuthTokenRepository,
+    OrganizationMembershipRepository, UserRepository,
 };
 
+/// Any membership in an organization is enough to see its nodes.
+pub const ANY_ORG_ROLE: &[OrgRole] =
+    &[OrgRole::Owner, OrgRole::Admin, OrgRole::Member, OrgRole::Billing];
+/// App roles allowed to create deploys.
+pub const DEPLOY_ROLES: &[AppRole] =
+    &[AppRole::Owner, AppRole::Maintainer, AppRole::Deployer];
+/// App roles allowed to read and change secrets.
+pub const MANAGE_SECRETS_ROLES: &[AppRole] =
+    &[AppRole::Owner, AppRole::Maintainer];
+/// App roles allowed to add/remove app members.
+pub const MANAGE_MEMBERS_ROLES: &[AppRole] = &[AppRole::Owner];
+/// App roles allowed to delete the app.
+pub const DELETE_APP_ROLES: &[AppRole] = &[AppRole::Owner];
+
 /// Get the currently authenticated user from the Authorization header.
 ///
 /// Expected header: `Authorization: Bearer <token>`
+///
+/// App deploy tokens are rejected here; operations they may perform use
+/// [`get_app_actor`] instead.
 pub async fn get_current_user(ctx: &Context<'_>) -> GqlResult<CurrentUser> {
+    match authenticate(ctx).await? {
+        Actor::User(current) => Ok(current),
+        Actor::AppToken { .. } => Err(forbidden(
+            "App deploy tokens can only trigger builds and deploys",
+        )),
+    }
+}
+
+/// The user acting on `app_id`: the token's user, or the creator of an
+/// app deploy token issued for that very app.
+///
+/// The usual role checks still apply to the returned user, so a deploy
+/// token stops working once its creator loses access to the app.
+pub async fn get_app_actor(ctx: &Context<'_>, app_id: i64) -> GqlResult<User> {
+    match authenticate(ctx).await? {
+        Actor::User(current) => Ok(current.user),
+        Actor::AppToken { app_id: scope, user } if scope == app_id => Ok(user),
+        Actor::AppToken { .. } => {
+            Err(forbidden("This deploy token belongs to another app"))
+        }
+    }
+}
+
+/// Who sent the request.
+enum Actor {
+    User(CurrentUser),
+    AppToken { app_id: i64, user: User },
+}
+
+async fn authenticate(ctx: &Context<'_>) -> GqlResult<Actor> {
     // Read raw headers from async-graphql context
     let headers = ctx
         .data_opt::<http::HeaderMap>()
@@ -28,19 +79,116 @@ pub async fn get_current_user(ctx: &Context<'_>) -> GqlResult<CurrentUser> {
 
     let state = ctx.data::<AppState>()?;
     let token_repo = AuthTokenRepository::new(state.pool.clone());
-    let user_repo = UserRepository::new(state.pool.clone());
 
     let token = token_repo
         .find_valid_by_token(token_str)
         .await
+        .map_err(|e| GqlError::new(e.to_string()))?;
+    if let Some(token) = token {
+        let user = token_user(state, token.user_id).await?;
+        return Ok(Actor::User(CurrentUser { user, token_id: token.id }));
+    }
+
+    let app_token = AppDeployTokenRepository::new(state.pool.clone())
+        .find_valid_by_hash(&hash_token(token_str))
+        .await
         .map_err(|e| GqlError::new(e.to_string()))?
         .ok_or_else(|| GqlError::new("Invalid or revoked token"))?;
+    let user = token_user(state, app_token.created_by).await?;
+
+    Ok(Actor::AppToken { app_id: app_token.app_id, user })
+}
 
-    let user = user_repo
-        .find_by_id(token.user_id)
+async fn token_user(state: &AppState, user_id: i64) -> GqlResult<User> {
+    UserRepository::new(state.pool.clone())
+        .find_by_id(user_id)
         .await
         .map_err(|e| GqlError::new(e.to_string()))?
-        .ok_or_else(|| GqlError::new("User not found for token"))?;
+        .ok_or_else(|| GqlError::new("User not found for token"))
+}
+
+/// Check whether `user` holds one of `roles` in the given organization.
+///
+/// Platform admins are always allowed.
+pub async fn has_org_role(
+    ctx: &Context<'_>,
+    user: &User,
+    organization_id: i64,
+    roles: &[OrgRole],
+) -> GqlResult<bool> {
+    if user.is_admin {
+        return Ok(true);
+    }
+
+    let state = ctx.data::<AppState>()?;
+    let memberships =
+        OrganizationMembershipRepository::new(state.pool.clone())
+            .list_by_user(user.id)
+            .await
+            .map_err(|e| GqlError::new(e.to_string()))?;
+
+    Ok(memberships.iter().any(|m| {
+        m.organization_id == organization_id && roles.contains(&m.role)
+    }))
+}
+
+/// Check whether `user` holds one of `roles` on the given app.
+///
+/// Owners/admins of the app's organization (and platform admins) are
+/// always allowed.
+pub async fn has_app_role(
+    ctx: &Context<'_>,
+    user: &User,
+    app: &App,
+    roles: &[AppRole],
+) -> GqlResult<bool> {
+    if has_org_role(
+        ctx,
+        user,
+        app.organization_id,
+        &[OrgRole::Owner, OrgRole::Admin],
+    )
+    .await?
+    {
+        return Ok(true);
+    }
+
+    let state = ctx.data::<AppState>()?;
+    let memberships = AppMembershipRepository::new(state.pool.clone())
+        .list_by_user(user.id)
+        .await
+        .map_err(|e| GqlError::new(e.to_string()))?;
+
+    Ok(memberships
+        .iter()
+        .any(|m| m.app_id == app.id && roles.contains(&m.role)))
+}
+
+/// The role `user` effectively holds on `app`, if any.
+///
+/// Owners/admins of the app's organization (and platform admins) act as
+/// app owners, mirroring [`has_app_role`].
+pub async fn effective_app_role(
+    ctx: &Context<'_>,
+    user: &User,
+    app: &App,
+) -> GqlResult<Option<AppRole>> {
+    if has_org_role(
+        ctx,
+        user,
+        app.organization_id,
+        &[OrgRole::Owner, OrgRole::Admin],
+    )
+    .await?
+    {
+        return Ok(Some(AppRole::Owner));
+    }
+
+    let state = ctx.data::<AppState>()?;
+    let memberships = AppMembershipRepository::new(state.pool.clone())
+        .list_by_user(user.id)
+        .await
+        .map_err(|e| GqlError::new(e.to_string()))?;
 
-    Ok(CurrentUser { user })
+    Ok(memberships.iter().find(|m| m.app_id == app.id).map(|m| m.role))
 }
@@ -0,0 +1,44 @@
+use async_graphql::{Error as GqlError, ErrorExtensions};
+
+/// Build a GraphQL error tagged with a machine-readable `extensions.code`.
+fn coded(code: &'static str, message: impl Into<String>) -> GqlError {
+    GqlError::new(message.into()).extend_with(|_, ext| ext.set("code", code))
+}
+
+/// A plan/usage limit would be exceeded by the request.
+pub fn quota_exceeded(message: impl Into<String>) -> GqlError {
+    coded("QUOTA_EXCEEDED", message)
+}
+
+/// The caller could not be identified (wrong credentials).
+pub fn unauthenticated(message: impl Into<String>) -> GqlError {
+    coded("UNAUTHENTICATED", message)
+}
+
+/// The caller is authenticated but not allowed to do this.
+pub fn forbidden(message: impl Into<String>) -> GqlError {
+    coded("FORBIDDEN", message)
+}
+
+/// The request conflicts with the current state of the resource.
+pub fn conflict(message: impl Into<String>) -> GqlError {
+    coded("CONFLICT", message)
+}
+
+/// A deploy targets an environment without any secrets.
+pub fn missing_secrets(app_id: i64, environment: &str) -> GqlError {
+    GqlError::new(format!(
+        "Environment '{environment}' of app {app_id} has no secrets; \
+         set them before deploying"
+    ))
+    .extend_with(|_, ext| {
+        ext.set("code", "MISSING_SECRETS");
+        ext.set("appId", app_id);
+        ext.set("environment", environment);
+    })
+}
+
+/// The request itself is malformed or violates a server policy.
+pub fn bad_request(message: impl Into<String>) -> GqlError {
+    coded("BAD_REQUEST", message)
+}
@@ -0,0 +1,133 @@
+use std::collections::HashMap;
+
+use async_graphql::Error as GqlError;
+use async_graphql::dataloader::Loader;
+use sqlx::PgPool;
+
+use crate::domain::models::{Organization, User};
+use crate::infrastructure::repositories::{
+    OrganizationRepository, UserRepository,
+};
+
+/// Batches organization lookups by id within a request.
+pub struct OrganizationLoader {
+    repo: OrganizationRepository,
+}
+
+impl OrganizationLoader {
+    pub fn new(pool: PgPool) -> Self {
+        Self { repo: OrganizationRepository::new(pool) }
+    }
+}
+
+impl Loader<i64> for OrganizationLoader {
+    type Value = Organization;
+    type Error = GqlError;
+
+    async fn load(
+        &self,
+        keys: &[i64],
+    ) -> Result<HashMap<i64, Organization>, GqlError> {
+        let orgs = self
+            .repo
+            .find_by_ids(keys)
+            .await
+            .map_err(|e| GqlError::new(e.to_string()))?;
+
+        Ok(orgs.into_iter().map(|org| (org.id, org)).collect())
+    }
+}
+
+/// Batches user lookups by id within a request.
+pub struct UserLoader {
+    repo: UserRepository,
+}
+
+impl UserLoader {
+    pub fn new(pool: PgPool) -> Self {
+        Self { repo: UserRepository::new(pool) }
+    }
+}
+
+impl Loader<i64> for UserLoader {
+    type Value = User;
+    type Error = GqlError;
+
+    async fn load(
+        &self,
+        keys: &[i64],
+    ) -> Result<HashMap<i64, User>, GqlError> {
+        let users = self
+            .repo
+            .find_by_ids(keys)
+            .await
+            .map_err(|e| GqlError::new(e.to_string()))?;
+
+        Ok(users.into_iter().map(|user| (user.id, user)).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use async_graphql::dataloader::DataLoader;
+    use sqlx::query_scalar;
+
+    use super::*;
+
+    /// Counts how many batches reach the wrapped loader.
+    struct Counting {
+        inner: OrganizationLoader,
+        batches: AtomicUsize,
+    }
+
+    impl Loader<i64> for Counting {
+        type Value = Organization;
+        type Error = GqlError;
+
+        async fn load(
+            &self,
+            keys: &[i64],
+        ) -> Result<HashMap<i64, Organization>, GqlError> {
+            self.batches.fetch_add(1, Ordering::SeqCst);
+            self.inner.load(keys).await
+        }
+    }
+
+    async fn org(pool: &PgPool, slug: &str) -> i64 {
+        query_scalar(
+            "INSERT INTO organizations (name, slug) VALUES ($1, $1) RETURNING id",
+        )
+        .bind(slug)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+    }
+
+    #[sqlx::test]
+    async fn concurrent_lookups_share_one_batch(pool: PgPool) {
+        let acme = org(&pool, "acme").await;
+        let globex = org(&pool, "globex").await;
+        let loader = DataLoader::new(
+            Counting {
+                inner: OrganizationLoader::new(pool),
+                batches: AtomicUsize::new(0),
+            },
+            tokio::spawn,
+        );
+
+        let (a, b, again, missing) = tokio::join!(
+            loader.load_one(acme),
+            loader.load_one(globex),
+            loader.load_one(acme),
+            loader.load_one(-1),
+        );
+
+        assert_eq!(a.unwrap().unwrap().slug, "acme");
+        assert_eq!(b.unwrap().unwrap().slug, "globex");
+        assert_eq!(again.unwrap().unwrap().slug, "acme");
+        assert!(missing.unwrap().is_none());
+        assert_eq!(loader.loader().batches.load(Ordering::SeqCst), 1);
+    }
+}
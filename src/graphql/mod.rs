@@ -1,6 +1,15 @@
 pub mod auth;
 pub mod auth_helpers;
+pub mod errors;
+pub mod loaders;
 pub mod mutation;
+pub mod node;
+pub mod pagination;
 pub mod query;
+pub mod quota;
+pub mod schema;
 pub mod state;
+#[cfg(test)]
+pub(crate) mod test_support;
 pub mod types;
+pub mod validation;
@@ -1,18 +1,59 @@
+use std::collections::HashSet;
+
 use async_graphql::{Context, Object, Result as GqlResult};
 use rand::RngCore;
+use time::{Duration, OffsetDateTime};
 
-use crate::domain::models::{NewAuthToken, NewOrganization, NewTeam, NewUser};
-use crate::graphql::auth_helpers::get_current_user;
+use crate::domain::models::{
+    AppRole, BuildStatus, BuildTrigger, DeployStatus, NewApp,
+    NewAppDeployToken, NewAppSecret, NewAuthToken, NewBuildJob, NewDeploy,
+    NewOrganization, NewOrganizationInvite, NewPasswordReset, NewRelease,
+    NewTeam, NewUser, OrgRole, OrganizationMembership, Release, ReleaseStatus,
+    Team, TeamMembership, TeamRole, User,
+};
+use crate::domain::secrets::MAX_SECRET_VALUE_BYTES;
+use crate::graphql::auth_helpers::{
+    DEPLOY_ROLES, MANAGE_SECRETS_ROLES, get_app_actor, get_current_user,
+    has_app_role, has_org_role,
+};
+use crate::graphql::errors::{
+    bad_request, conflict, forbidden, missing_secrets, unauthenticated,
+};
+use crate::graphql::quota::ensure_app_quota;
 use crate::graphql::state::AppState;
 use crate::graphql::types::{
-    AccessTokenGql, CreateOrganizationInput, CreateTeamInput, OrganizationGql,
-    RegisterUserInput, RegisterUserPayload, TeamGql,
+    AccessTokenGql, AppGql, AppSecretGql, AppSecretsSyncSummaryGql,
+    BuildJobGql, ChangePasswordInput, ChangePasswordPayload, CreateAppInput,
+    CreateDeployInput, CreateDeploysInput, CreateDeploysPayload,
+    CreateOrganizationInput, CreateTeamInput, DeployGql, InviteOutcomeGql,
+    InviteUserPayload, OrgRoleGql, OrganizationGql, OrganizationMembershipGql,
+    PromoteBuildInput, RegisterUserInput, RegisterUserPayload, ReleaseGql,
+    ResetPasswordInput, SetAppSecretInput, SyncAppSecretsInput, TeamGql,
+    TeamMembershipGql, TeamRoleGql, UpdateAppInput, UpdateOrganizationInput,
+    UpdateTeamInput,
+};
+use crate::graphql::validation::{ValidationErrors, normalize_email};
+use crate::infrastructure::password::{
+    hash_password, hash_token, verify_password,
 };
 use crate::infrastructure::repositories::{
-    AuthTokenRepository, OrganizationRepository, TeamRepository,
-    UserRepository,
+    AppDeployTokenRepository, AppRepository, AppSecretRepository,
+    AuthTokenRepository, BuildJobRepository, DeployRepository,
+    OrganizationInviteRepository, OrganizationMembershipRepository,
+    OrganizationRepository, PasswordResetRepository, ReleaseRepository,
+    TeamMembershipRepository, TeamRepository, UserRepository,
+    is_unique_violation,
 };
 
+/// How long after deletion an organization owner may still restore it.
+const ORG_RESTORE_GRACE_DAYS: i64 = 30;
+
+/// Lifetime of a password reset token.
+const PASSWORD_RESET_TTL_MINUTES: i64 = 30;
+
+/// Lifetime of new access tokens, and the longest one callers may ask for.
+const MAX_TOKEN_TTL_DAYS: i64 = 90;
+
 pub struct MutationRoot;
 
 #[Object]
@@ -26,17 +67,23 @@ impl MutationRoot {
         ctx: &Context<'_>,
         input: RegisterUserInput,
     ) -> GqlResult<RegisterUserPayload> {
+        let email = normalize_email(&input.email);
+        let mut errors = ValidationErrors::new();
+        errors.validate_name("name", &input.name);
+        errors.validate_email("email", &email);
+        errors.validate_password("password", &input.password);
+        let expires_at = token_expires_at(&mut errors, input.ttl_days);
+        errors.into_result()?;
+
         let state = ctx.data::<AppState>()?;
 
         let user_repo = UserRepository::new(state.pool.clone());
         let token_repo = AuthTokenRepository::new(state.pool.clone());
 
-        // TODO: hash password properly (argon2, bcrypt, etc.)
-        let new_user = NewUser {
-            name: input.name,
-            email: input.email,
-            password_hash: input.password, // placeholder
-        };
+        let password_hash = hash_password(&input.password)
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?;
+
+        let new_user = NewUser { name: input.name, email, password_hash };
 
         let user = user_repo
             .create(new_user)
@@ -44,12 +91,13 @@ impl MutationRoot {
             .map_err(|e| async_graphql::Error::new(e.to_string()))?;
 
         // generate random token (32 bytes hex)
-        let token_string = generate_token_string();
+        let token_string = generate_access_token(&state.config.token_prefix);
 
         let new_token = NewAuthToken {
             user_id: user.id,
             token: token_string.clone(),
             description: Some("CLI default token".to_string()),
+            expires_at: Some(expires_at),
         };
 
         token_repo
@@ -62,10 +110,197 @@ impl MutationRoot {
             token: AccessTokenGql {
                 token: token_string,
                 description: Some("CLI default token".to_string()),
+                expires_at: Some(expires_at),
+            },
+        })
+    }
+
+    /// Log in with email and password and get a fresh access token.
+    ///
+    /// Unknown emails, inactive users and wrong passwords all fail with the
+    /// same `UNAUTHENTICATED` error. The token lives `ttlDays` days
+    /// (default and max: 90).
+    async fn authenticate(
+        &self,
+        ctx: &Context<'_>,
+        email: String,
+        password: String,
+        ttl_days: Option<i64>,
+    ) -> GqlResult<RegisterUserPayload> {
+        let mut errors = ValidationErrors::new();
+        let expires_at = token_expires_at(&mut errors, ttl_days);
+        errors.into_result()?;
+
+        let state = ctx.data::<AppState>()?;
+        let user_repo = UserRepository::new(state.pool.clone());
+        let token_repo = AuthTokenRepository::new(state.pool.clone());
+
+        let user = user_repo
+            .find_by_email(&normalize_email(&email))
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?
+            .filter(|u| u.is_active)
+            .filter(|u| verify_password(&password, &u.password_hash))
+            .ok_or_else(|| unauthenticated("Invalid credentials"))?;
+
+        let token_string = generate_access_token(&state.config.token_prefix);
+        token_repo
+            .create(NewAuthToken {
+                user_id: user.id,
+                token: token_string.clone(),
+                description: Some("CLI login".to_string()),
+                expires_at: Some(expires_at),
+            })
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?;
+
+        user_repo
+            .record_login(user.id)
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?;
+
+        Ok(RegisterUserPayload {
+            user: user.into(),
+            token: AccessTokenGql {
+                token: token_string,
+                description: Some("CLI login".to_string()),
+                expires_at: Some(expires_at),
             },
         })
     }
 
+    /// Revoke the token used for this request (server-side logout).
+    async fn revoke_token(&self, ctx: &Context<'_>) -> GqlResult<bool> {
+        let current = get_current_user(ctx).await?;
+        let state = ctx.data::<AppState>()?;
+
+        AuthTokenRepository::new(state.pool.clone())
+            .revoke(current.token_id)
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))
+    }
+
+    /// Start a password reset for the given email.
+    ///
+    /// Always returns `true`, so callers cannot probe which emails exist.
+    async fn request_password_reset(
+        &self,
+        ctx: &Context<'_>,
+        email: String,
+    ) -> GqlResult<bool> {
+        let state = ctx.data::<AppState>()?;
+        let user_repo = UserRepository::new(state.pool.clone());
+        let reset_repo = PasswordResetRepository::new(state.pool.clone());
+
+        let user = user_repo
+            .find_by_email(&normalize_email(&email))
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?;
+
+        let Some(user) = user.filter(|u| u.is_active) else {
+            return Ok(true);
+        };
+
+        let token = generate_token_string();
+        reset_repo
+            .create(NewPasswordReset {
+                user_id: user.id,
+                token_hash: hash_token(&token),
+                expires_at: OffsetDateTime::now_utc()
+                    + Duration::minutes(PASSWORD_RESET_TTL_MINUTES),
+            })
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?;
+
+        // TODO: send by email once a mailer exists. The token is a
+        // credential, so it only reaches the logs on development servers
+        // that opt in.
+        if state.config.dev_log_reset_tokens {
+            tracing::warn!(
+                user_id = user.id,
+                reset_token = %token,
+                "password reset requested (PAASTEL_DEV_LOG_RESET_TOKENS)"
+            );
+        } else {
+            tracing::info!(user_id = user.id, "password reset requested");
+        }
+
+        Ok(true)
+    }
+
+    /// Set a new password using a token from `requestPasswordReset`.
+    ///
+    /// The token is single-use; on success every auth token of the user is
+    /// revoked, so existing CLI sessions must log in again.
+    async fn reset_password(
+        &self,
+        ctx: &Context<'_>,
+        input: ResetPasswordInput,
+    ) -> GqlResult<bool> {
+        let mut errors = ValidationErrors::new();
+        errors.validate_password("newPassword", &input.new_password);
+        errors.into_result()?;
+
+        let state = ctx.data::<AppState>()?;
+        let reset_repo = PasswordResetRepository::new(state.pool.clone());
+
+        let password_hash = hash_password(&input.new_password)
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?;
+
+        reset_repo
+            .reset_password(&hash_token(&input.token), &password_hash)
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?
+            .ok_or_else(|| {
+                async_graphql::Error::new("Invalid or expired reset token")
+            })?;
+
+        Ok(true)
+    }
+
+    /// Change the password of the authenticated user.
+    async fn change_password(
+        &self,
+        ctx: &Context<'_>,
+        input: ChangePasswordInput,
+    ) -> GqlResult<ChangePasswordPayload> {
+        let current = get_current_user(ctx).await?;
+
+        let mut errors = ValidationErrors::new();
+        errors.validate_password("newPassword", &input.new_password);
+        errors.into_result()?;
+
+        if !verify_password(
+            &input.current_password,
+            &current.user.password_hash,
+        ) {
+            return Err(forbidden("Current password is incorrect"));
+        }
+
+        let state = ctx.data::<AppState>()?;
+        let user_repo = UserRepository::new(state.pool.clone());
+        let token_repo = AuthTokenRepository::new(state.pool.clone());
+
+        let password_hash = hash_password(&input.new_password)
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?;
+
+        user_repo
+            .update_password_hash(current.user.id, &password_hash)
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?;
+
+        let revoked_sessions = if input.revoke_other_sessions {
+            token_repo
+                .revoke_all_for_user(current.user.id, Some(current.token_id))
+                .await
+                .map_err(|e| async_graphql::Error::new(e.to_string()))?
+        } else {
+            0
+        };
+
+        Ok(ChangePasswordPayload { revoked_sessions: revoked_sessions as i64 })
+    }
+
     /// Create a new organization.
     async fn create_organization(
         &self,
@@ -73,43 +308,272 @@ impl MutationRoot {
         input: CreateOrganizationInput,
     ) -> GqlResult<OrganizationGql> {
         // ensure we have a valid user
-        let _current = get_current_user(ctx).await?;
+        let current = get_current_user(ctx).await?;
+
+        let mut errors = ValidationErrors::new();
+        errors.validate_name("name", &input.name);
+        errors.validate_slug("slug", &input.slug);
+        errors.into_result()?;
 
         let state = ctx.data::<AppState>()?;
-        let repo = OrganizationRepository::new(state.pool.clone());
 
         let new_org = NewOrganization {
             name: input.name,
             slug: input.slug,
             description: input.description,
+            created_by: Some(current.user.id),
         };
 
-        let org = repo
-            .create(new_org)
+        // the creator becomes the owner; both rows or neither
+        let mut tx = state
+            .pool
+            .begin()
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?;
+        let org = OrganizationRepository::create_tx(&mut tx, new_org)
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?;
+        OrganizationMembershipRepository::upsert_membership_tx(
+            &mut tx,
+            org.id,
+            current.user.id,
+            OrgRole::Owner,
+        )
+        .await
+        .map_err(|e| async_graphql::Error::new(e.to_string()))?;
+        tx.commit()
             .await
             .map_err(|e| async_graphql::Error::new(e.to_string()))?;
 
         Ok(org.into())
     }
 
+    /// Add a user to an organization, or change the role of a member.
+    ///
+    /// Requires owner/admin; only owners may grant or take away `OWNER`,
+    /// and the last owner cannot be demoted.
+    async fn add_organization_member(
+        &self,
+        ctx: &Context<'_>,
+        organization_id: i64,
+        user_id: i64,
+        role: OrgRoleGql,
+    ) -> GqlResult<OrganizationMembershipGql> {
+        let current = get_current_user(ctx).await?;
+
+        let membership = set_org_member(
+            ctx,
+            &current.user,
+            organization_id,
+            user_id,
+            role.into(),
+        )
+        .await?;
+
+        Ok(membership.into())
+    }
+
+    /// Add a user to an organization by email.
+    ///
+    /// Emails without an account get an invite instead, which they can
+    /// accept once registered (see `myInvitations`); it expires after
+    /// `PAASTEL_INVITE_TTL_DAYS`. Same permission rules as
+    /// `addOrganizationMember`.
+    async fn invite_user_to_organization(
+        &self,
+        ctx: &Context<'_>,
+        organization_id: i64,
+        email: String,
+        role: OrgRoleGql,
+    ) -> GqlResult<InviteUserPayload> {
+        let current = get_current_user(ctx).await?;
+        let role = OrgRole::from(role);
+        let email = normalize_email(&email);
+
+        let mut errors = ValidationErrors::new();
+        errors.validate_email("email", &email);
+        errors.into_result()?;
+
+        let state = ctx.data::<AppState>()?;
+        let user = UserRepository::new(state.pool.clone())
+            .find_by_email(&email)
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?;
+
+        if let Some(user) = user {
+            let membership = set_org_member(
+                ctx,
+                &current.user,
+                organization_id,
+                user.id,
+                role,
+            )
+            .await?;
+            return Ok(InviteUserPayload {
+                outcome: InviteOutcomeGql::Added,
+                membership: Some(membership.into()),
+            });
+        }
+
+        ensure_can_manage_members(
+            ctx,
+            &current.user,
+            organization_id,
+            role == OrgRole::Owner,
+        )
+        .await?;
+
+        OrganizationRepository::new(state.pool.clone())
+            .find_by_id(organization_id)
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?
+            .ok_or_else(|| {
+                async_graphql::Error::new("Organization not found")
+            })?;
+
+        OrganizationInviteRepository::new(state.pool.clone())
+            .upsert(NewOrganizationInvite {
+                organization_id,
+                email,
+                role,
+                invited_by: Some(current.user.id),
+                expires_at: OffsetDateTime::now_utc()
+                    + Duration::days(state.config.invite_ttl_days as i64),
+            })
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?;
+
+        Ok(InviteUserPayload {
+            outcome: InviteOutcomeGql::Invited,
+            membership: None,
+        })
+    }
+
+    /// Accept one of `myInvitations`, joining the organization with the
+    /// invited role (an existing membership keeps its role).
+    async fn accept_invitation(
+        &self,
+        ctx: &Context<'_>,
+        id: i64,
+    ) -> GqlResult<OrganizationMembershipGql> {
+        let current = get_current_user(ctx).await?;
+        let state = ctx.data::<AppState>()?;
+
+        let membership = OrganizationInviteRepository::new(state.pool.clone())
+            .accept(id, &normalize_email(&current.user.email), current.user.id)
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?
+            .ok_or_else(|| {
+                async_graphql::Error::new("Invitation not found or expired")
+            })?;
+
+        Ok(membership.into())
+    }
+
+    /// Decline (delete) one of `myInvitations`. Returns `false` if there
+    /// was no such invitation.
+    async fn decline_invitation(
+        &self,
+        ctx: &Context<'_>,
+        id: i64,
+    ) -> GqlResult<bool> {
+        let current = get_current_user(ctx).await?;
+        let state = ctx.data::<AppState>()?;
+
+        OrganizationInviteRepository::new(state.pool.clone())
+            .decline(id, &normalize_email(&current.user.email))
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))
+    }
+
+    /// Remove a user from an organization.
+    ///
+    /// Same rules as `addOrganizationMember`; the last owner cannot be
+    /// removed. Returns `false` if the user was not a member.
+    async fn remove_organization_member(
+        &self,
+        ctx: &Context<'_>,
+        organization_id: i64,
+        user_id: i64,
+    ) -> GqlResult<bool> {
+        let current = get_current_user(ctx).await?;
+
+        let state = ctx.data::<AppState>()?;
+        let repo = OrganizationMembershipRepository::new(state.pool.clone());
+        let members = repo
+            .list_by_organization(organization_id)
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?;
+        let Some(existing) = members.iter().find(|m| m.user_id == user_id)
+        else {
+            ensure_can_manage_members(
+                ctx,
+                &current.user,
+                organization_id,
+                false,
+            )
+            .await?;
+            return Ok(false);
+        };
+        ensure_can_manage_members(
+            ctx,
+            &current.user,
+            organization_id,
+            existing.role == OrgRole::Owner,
+        )
+        .await?;
+
+        if is_last_owner(&members, user_id) {
+            return Err(conflict(
+                "Cannot remove the last owner of the organization",
+            ));
+        }
+
+        repo.delete_membership(organization_id, user_id)
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?;
+
+        Ok(true)
+    }
+
     /// Create a new team inside an organization.
+    ///
+    /// Only owners/admins of the organization may create teams in it.
     async fn create_team(
         &self,
         ctx: &Context<'_>,
         input: CreateTeamInput,
     ) -> GqlResult<TeamGql> {
         // ensure we have a valid user
-        let _current = get_current_user(ctx).await?;
+        let current = get_current_user(ctx).await?;
+
+        let mut errors = ValidationErrors::new();
+        errors.validate_name("name", &input.name);
+        errors.validate_slug("slug", &input.slug);
+        errors.into_result()?;
+
+        if !has_org_role(
+            ctx,
+            &current.user,
+            input.organization_id,
+            &[OrgRole::Owner, OrgRole::Admin],
+        )
+        .await?
+        {
+            return Err(forbidden(
+                "not authorized to create teams in this organization",
+            ));
+        }
 
         let state = ctx.data::<AppState>()?;
         let repo = TeamRepository::new(state.pool.clone());
 
-        // Here you could check if the organization exists or if the user has permissions.
         let new_team = NewTeam {
             organization_id: input.organization_id,
             name: input.name,
             slug: input.slug,
             description: input.description,
+            created_by: Some(current.user.id),
         };
 
         let team = repo
@@ -119,11 +583,3085 @@ impl MutationRoot {
 
         Ok(team.into())
     }
-}
 
-fn generate_token_string() -> String {
-    // 32 random bytes -> hex string (64 chars)
-    let mut bytes = [0u8; 32];
-    rand::rng().fill_bytes(&mut bytes);
-    hex::encode(bytes)
+    /// Rename an organization or change its description.
+    ///
+    /// Allowed for organization owners/admins. The slug cannot change.
+    /// With `expectedUpdatedAt`, a concurrent modification is reported as
+    /// `CONFLICT` instead of being silently overwritten.
+    async fn update_organization(
+        &self,
+        ctx: &Context<'_>,
+        input: UpdateOrganizationInput,
+    ) -> GqlResult<OrganizationGql> {
+        let current = get_current_user(ctx).await?;
+
+        let mut errors = ValidationErrors::new();
+        if let Some(name) = &input.name {
+            errors.validate_name("name", name);
+        }
+        errors.into_result()?;
+
+        if !has_org_role(
+            ctx,
+            &current.user,
+            input.id,
+            &[OrgRole::Owner, OrgRole::Admin],
+        )
+        .await?
+        {
+            return Err(forbidden("Not allowed to update this organization"));
+        }
+
+        let state = ctx.data::<AppState>()?;
+        let org_repo = OrganizationRepository::new(state.pool.clone());
+
+        let org = org_repo
+            .find_by_id(input.id)
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?
+            .ok_or_else(|| {
+                async_graphql::Error::new("Organization not found")
+            })?;
+
+        let org = org_repo
+            .update(
+                org.id,
+                input.name.as_deref(),
+                input.description.as_deref(),
+                input.expected_updated_at,
+            )
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?
+            .ok_or_else(|| {
+                conflict(
+                    "Organization was modified, please refresh and try again",
+                )
+            })?;
+
+        Ok(org.into())
+    }
+
+    /// Rename a team or change its description.
+    ///
+    /// Allowed for owners/admins of the team's organization. With
+    /// `expectedUpdatedAt`, a concurrent modification is reported as
+    /// `CONFLICT` instead of being silently overwritten.
+    async fn update_team(
+        &self,
+        ctx: &Context<'_>,
+        input: UpdateTeamInput,
+    ) -> GqlResult<TeamGql> {
+        let current = get_current_user(ctx).await?;
+
+        let mut errors = ValidationErrors::new();
+        if let Some(name) = &input.name {
+            errors.validate_name("name", name);
+        }
+        errors.into_result()?;
+
+        let state = ctx.data::<AppState>()?;
+        let team_repo = TeamRepository::new(state.pool.clone());
+
+        let team = team_repo
+            .find_by_id(input.id)
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?
+            .ok_or_else(|| async_graphql::Error::new("Team not found"))?;
+
+        if !has_org_role(
+            ctx,
+            &current.user,
+            team.organization_id,
+            &[OrgRole::Owner, OrgRole::Admin],
+        )
+        .await?
+        {
+            return Err(forbidden("Not allowed to update this team"));
+        }
+
+        let team = team_repo
+            .update(
+                team.id,
+                input.name.as_deref(),
+                input.description.as_deref(),
+                input.expected_updated_at,
+            )
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?
+            .ok_or_else(|| {
+                conflict("Team was modified, please refresh and try again")
+            })?;
+
+        Ok(team.into())
+    }
+
+    /// Create an app in an organization.
+    ///
+    /// App slugs are unique per organization: reusing one returns
+    /// `CONFLICT`, while other organizations may use the same slug.
+    async fn create_app(
+        &self,
+        ctx: &Context<'_>,
+        input: CreateAppInput,
+    ) -> GqlResult<AppGql> {
+        let current = get_current_user(ctx).await?;
+
+        let mut errors = ValidationErrors::new();
+        errors.validate_name("name", &input.name);
+        errors.validate_slug("slug", &input.slug);
+        if let Some(repo_url) = &input.repo_url {
+            errors.validate_repo_url("repoUrl", repo_url);
+        }
+        errors.into_result()?;
+
+        let state = ctx.data::<AppState>()?;
+        let org = OrganizationRepository::new(state.pool.clone())
+            .find_by_id(input.organization_id)
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?
+            .ok_or_else(|| {
+                async_graphql::Error::new("Organization not found")
+            })?;
+
+        let allowed = has_org_role(
+            ctx,
+            &current.user,
+            org.id,
+            &[OrgRole::Owner, OrgRole::Admin, OrgRole::Member],
+        )
+        .await?;
+        if !allowed {
+            return Err(forbidden(
+                "Not allowed to create apps in this organization",
+            ));
+        }
+
+        let slug_taken = || {
+            conflict(format!(
+                "An app with slug '{}' already exists in this organization",
+                input.slug
+            ))
+        };
+
+        let repo = AppRepository::new(state.pool.clone());
+        let existing = repo
+            .find_by_slug(org.id, &input.slug)
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?;
+        if existing.is_some() {
+            return Err(slug_taken());
+        }
+
+        ensure_app_quota(ctx, &org).await?;
+
+        let new_app = NewApp {
+            organization_id: org.id,
+            team_id: input.team_id,
+            name: input.name,
+            slug: input.slug.clone(),
+            repo_url: input.repo_url,
+            created_by: Some(current.user.id),
+        };
+
+        // The precheck can race with a concurrent create (and does not see
+        // soft-deleted apps); the (organization_id, slug) constraint decides.
+        let app = repo.create(new_app).await.map_err(|e| {
+            if is_unique_violation(&e) {
+                slug_taken()
+            } else {
+                async_graphql::Error::new(e.to_string())
+            }
+        })?;
+
+        Ok(app.into())
+    }
+
+    /// Create or overwrite a secret of an app environment.
+    ///
+    /// Values may span several lines; `type: FILE` marks contents meant to
+    /// be written to disk at deploy time. The value is not echoed back.
+    async fn set_app_secret(
+        &self,
+        ctx: &Context<'_>,
+        input: SetAppSecretInput,
+    ) -> GqlResult<AppSecretGql> {
+        let current = get_current_user(ctx).await?;
+
+        let mut errors = ValidationErrors::new();
+        errors.validate_name("environment", &input.environment);
+        errors.validate_secret_key("key", &input.key);
+        if input.value.len() > MAX_SECRET_VALUE_BYTES {
+            errors.add(
+                "value",
+                format!("must be at most {MAX_SECRET_VALUE_BYTES} bytes"),
+            );
+        }
+        errors.into_result()?;
+
+        let state = ctx.data::<AppState>()?;
+        let app = AppRepository::new(state.pool.clone())
+            .find_by_id(input.app_id)
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?
+            .ok_or_else(|| async_graphql::Error::new("App not found"))?;
+
+        if !has_app_role(ctx, &current.user, &app, MANAGE_SECRETS_ROLES)
+            .await?
+        {
+            return Err(forbidden(
+                "Not allowed to manage secrets of this app",
+            ));
+        }
+
+        let secret =
+            AppSecretRepository::new(state.pool.clone(), state.cipher.clone())
+                .upsert_secret(NewAppSecret {
+                    app_id: app.id,
+                    environment: input.environment,
+                    key: input.key,
+                    value: input.value,
+                    kind: input.kind.into(),
+                    created_by: Some(current.user.id),
+                })
+                .await
+                .map_err(|e| async_graphql::Error::new(e.to_string()))?;
+
+        Ok(secret.into())
+    }
+
+    /// Delete a secret of an app environment. Its last value stays
+    /// available through `appSecretHistory`.
+    ///
+    /// Returns `false` if there was no such secret.
+    async fn delete_app_secret(
+        &self,
+        ctx: &Context<'_>,
+        app_id: i64,
+        #[graphql(default_with = "\"default\".to_string()")]
+        environment: String,
+        key: String,
+    ) -> GqlResult<bool> {
+        let current = get_current_user(ctx).await?;
+        let state = ctx.data::<AppState>()?;
+
+        let app = AppRepository::new(state.pool.clone())
+            .find_by_id(app_id)
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?
+            .ok_or_else(|| async_graphql::Error::new("App not found"))?;
+
+        if !has_app_role(ctx, &current.user, &app, MANAGE_SECRETS_ROLES)
+            .await?
+        {
+            return Err(forbidden(
+                "Not allowed to manage secrets of this app",
+            ));
+        }
+
+        AppSecretRepository::new(state.pool.clone(), state.cipher.clone())
+            .delete_secret(app.id, &environment, &key)
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))
+    }
+
+    /// Make the secrets of an app environment match the given set.
+    ///
+    /// New keys are added and changed values updated; with `prune`, keys
+    /// not listed are deleted. Applied atomically. Replaced and deleted
+    /// values stay available through `appSecretHistory`.
+    async fn update_app_secrets_bulk(
+        &self,
+        ctx: &Context<'_>,
+        input: SyncAppSecretsInput,
+    ) -> GqlResult<AppSecretsSyncSummaryGql> {
+        let current = get_current_user(ctx).await?;
+
+        let mut errors = ValidationErrors::new();
+        errors.validate_name("environment", &input.environment);
+        let mut seen = HashSet::new();
+        for (i, secret) in input.secrets.iter().enumerate() {
+            let field = format!("secrets.{i}");
+            errors.validate_secret_key(&format!("{field}.key"), &secret.key);
+            if !seen.insert(secret.key.as_str()) {
+                errors
+                    .add(&format!("{field}.key"), "is listed more than once");
+            }
+            if secret.value.len() > MAX_SECRET_VALUE_BYTES {
+                errors.add(
+                    &format!("{field}.value"),
+                    format!("must be at most {MAX_SECRET_VALUE_BYTES} bytes"),
+                );
+            }
+        }
+        errors.into_result()?;
+
+        let state = ctx.data::<AppState>()?;
+        let app = AppRepository::new(state.pool.clone())
+            .find_by_id(input.app_id)
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?
+            .ok_or_else(|| async_graphql::Error::new("App not found"))?;
+
+        if !has_app_role(ctx, &current.user, &app, MANAGE_SECRETS_ROLES)
+            .await?
+        {
+            return Err(forbidden(
+                "Not allowed to manage secrets of this app",
+            ));
+        }
+
+        let desired = input
+            .secrets
+            .into_iter()
+            .map(|s| NewAppSecret {
+                app_id: app.id,
+                environment: input.environment.clone(),
+                key: s.key,
+                value: s.value,
+                kind: s.kind.into(),
+                created_by: Some(current.user.id),
+            })
+            .collect();
+
+        let summary =
+            AppSecretRepository::new(state.pool.clone(), state.cipher.clone())
+                .sync_secrets(app.id, &input.environment, desired, input.prune)
+                .await
+                .map_err(|e| async_graphql::Error::new(e.to_string()))?;
+
+        Ok(summary.into())
+    }
+
+    /// Restore a previous value of a secret from its history.
+    ///
+    /// The value being replaced is itself recorded as a new version, so a
+    /// rollback can be undone.
+    async fn rollback_secret(
+        &self,
+        ctx: &Context<'_>,
+        app_id: i64,
+        #[graphql(default_with = "\"default\".to_string()")]
+        environment: String,
+        key: String,
+        version: i32,
+    ) -> GqlResult<AppSecretGql> {
+        let current = get_current_user(ctx).await?;
+        let state = ctx.data::<AppState>()?;
+
+        let app = AppRepository::new(state.pool.clone())
+            .find_by_id(app_id)
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?
+            .ok_or_else(|| async_graphql::Error::new("App not found"))?;
+
+        if !has_app_role(ctx, &current.user, &app, MANAGE_SECRETS_ROLES)
+            .await?
+        {
+            return Err(forbidden(
+                "Not allowed to manage secrets of this app",
+            ));
+        }
+
+        let secret_repo =
+            AppSecretRepository::new(state.pool.clone(), state.cipher.clone());
+        let previous = secret_repo
+            .find_version(app.id, &environment, &key, version)
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?
+            .ok_or_else(|| {
+                async_graphql::Error::new(format!(
+                    "Secret '{key}' has no version {version} in '{environment}'"
+                ))
+            })?;
+
+        let secret = secret_repo
+            .upsert_secret(NewAppSecret {
+                app_id: app.id,
+                environment: previous.environment,
+                key: previous.key,
+                value: previous.value,
+                kind: previous.kind,
+                created_by: Some(current.user.id),
+            })
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?;
+
+        Ok(secret.into())
+    }
+
+    /// Soft-delete a team.
+    ///
+    /// Allowed for organization owners/admins and team leads. The team's
+    /// apps are kept, but no new apps can be attached to it.
+    async fn delete_team(
+        &self,
+        ctx: &Context<'_>,
+        id: i64,
+    ) -> GqlResult<TeamGql> {
+        let current = get_current_user(ctx).await?;
+        let state = ctx.data::<AppState>()?;
+        let team_repo = TeamRepository::new(state.pool.clone());
+
+        let team = team_repo
+            .find_by_id(id)
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?
+            .ok_or_else(|| async_graphql::Error::new("Team not found"))?;
+
+        let org_admin = has_org_role(
+            ctx,
+            &current.user,
+            team.organization_id,
+            &[OrgRole::Owner, OrgRole::Admin],
+        )
+        .await?;
+        let team_lead = TeamMembershipRepository::new(state.pool.clone())
+            .list_by_team(team.id)
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?
+            .iter()
+            .any(|m| m.user_id == current.user.id && m.role == TeamRole::Lead);
+        if !org_admin && !team_lead {
+            return Err(forbidden("Not allowed to delete this team"));
+        }
+
+        let team = team_repo
+            .soft_delete(team.id)
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?
+            .ok_or_else(|| async_graphql::Error::new("Team not found"))?;
+
+        Ok(team.into())
+    }
+
+    /// Add a member of the organization to one of its teams, or change
+    /// their team role.
+    ///
+    /// Allowed for organization owners/admins and team leads/maintainers;
+    /// maintainers cannot grant or take away `LEAD`.
+    async fn add_team_member(
+        &self,
+        ctx: &Context<'_>,
+        team_id: i64,
+        user_id: i64,
+        role: TeamRoleGql,
+    ) -> GqlResult<TeamMembershipGql> {
+        let current = get_current_user(ctx).await?;
+        let state = ctx.data::<AppState>()?;
+        let role = TeamRole::from(role);
+
+        let team = TeamRepository::new(state.pool.clone())
+            .find_by_id(team_id)
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?
+            .ok_or_else(|| async_graphql::Error::new("Team not found"))?;
+
+        let repo = TeamMembershipRepository::new(state.pool.clone());
+        let members = repo
+            .list_by_team(team.id)
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?;
+        let touches_lead = role == TeamRole::Lead
+            || members
+                .iter()
+                .any(|m| m.user_id == user_id && m.role == TeamRole::Lead);
+        ensure_can_manage_team_members(
+            ctx,
+            &current.user,
+            &team,
+            &members,
+            touches_lead,
+        )
+        .await?;
+
+        let is_org_member =
+            OrganizationMembershipRepository::new(state.pool.clone())
+                .list_by_user(user_id)
+                .await
+                .map_err(|e| async_graphql::Error::new(e.to_string()))?
+                .iter()
+                .any(|m| m.organization_id == team.organization_id);
+        if !is_org_member {
+            return Err(bad_request(
+                "User must be a member of the organization before joining \
+                 one of its teams",
+            ));
+        }
+
+        let membership = repo
+            .upsert_membership(team.id, user_id, role)
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?;
+
+        Ok(membership.into())
+    }
+
+    /// Remove a user from a team.
+    ///
+    /// Same rules as `addTeamMember`. Returns `false` if the user was not
+    /// a member.
+    async fn remove_team_member(
+        &self,
+        ctx: &Context<'_>,
+        team_id: i64,
+        user_id: i64,
+    ) -> GqlResult<bool> {
+        let current = get_current_user(ctx).await?;
+        let state = ctx.data::<AppState>()?;
+
+        let team = TeamRepository::new(state.pool.clone())
+            .find_by_id(team_id)
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?
+            .ok_or_else(|| async_graphql::Error::new("Team not found"))?;
+
+        let repo = TeamMembershipRepository::new(state.pool.clone());
+        let members = repo
+            .list_by_team(team.id)
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?;
+        let existing = members.iter().find(|m| m.user_id == user_id);
+        let touches_lead = existing.is_some_and(|m| m.role == TeamRole::Lead);
+        ensure_can_manage_team_members(
+            ctx,
+            &current.user,
+            &team,
+            &members,
+            touches_lead,
+        )
+        .await?;
+        if existing.is_none() {
+            return Ok(false);
+        }
+
+        repo.delete_membership(team.id, user_id)
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?;
+
+        Ok(true)
+    }
+
+    /// Rename an app or change its repository URL.
+    ///
+    /// With `expectedUpdatedAt`, a concurrent modification is reported as
+    /// `CONFLICT` instead of being silently overwritten. `rebuild` queues
+    /// a build from the new `repoUrl` once the app is updated.
+    async fn update_app(
+        &self,
+        ctx: &Context<'_>,
+        input: UpdateAppInput,
+    ) -> GqlResult<AppGql> {
+        let current = get_current_user(ctx).await?;
+
+        let mut errors = ValidationErrors::new();
+        if let Some(name) = &input.name {
+            errors.validate_name("name", name);
+        }
+        match &input.repo_url {
+            Some(repo_url) => errors.validate_repo_url("repoUrl", repo_url),
+            None if input.rebuild => {
+                errors.add("rebuild", "requires a new repoUrl");
+            }
+            None => {}
+        }
+        errors.into_result()?;
+
+        let state = ctx.data::<AppState>()?;
+        let app_repo = AppRepository::new(state.pool.clone());
+
+        let app = app_repo
+            .find_by_id(input.id)
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?
+            .ok_or_else(|| async_graphql::Error::new("App not found"))?;
+
+        let allowed = has_app_role(
+            ctx,
+            &current.user,
+            &app,
+            &[AppRole::Owner, AppRole::Maintainer],
+        )
+        .await?;
+        if !allowed {
+            return Err(forbidden("Not allowed to update this app"));
+        }
+
+        let updated = app_repo
+            .update(
+                app.id,
+                input.name.as_deref(),
+                input.repo_url.as_deref(),
+                input.expected_updated_at,
+            )
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?
+            .ok_or_else(|| {
+                conflict("App was modified, please refresh and try again")
+            })?;
+
+        if input.rebuild {
+            BuildJobRepository::new(state.pool.clone())
+                .create(NewBuildJob {
+                    app_id: updated.id,
+                    release_id: None,
+                    trigger: BuildTrigger::Api,
+                    triggered_by: Some(current.user.id),
+                    commit_sha: None,
+                    branch: None,
+                    tag: None,
+                    image_ref: None,
+                    runner_name: None,
+                    runner_type: None,
+                    logs_url: None,
+                    pipeline_url: None,
+                    error_message: None,
+                })
+                .await
+                .map_err(|e| async_graphql::Error::new(e.to_string()))?;
+        }
+
+        Ok(updated.into())
+    }
+
+    /// Create a release from a succeeded build, pointing at its image.
+    async fn promote_build(
+        &self,
+        ctx: &Context<'_>,
+        input: PromoteBuildInput,
+    ) -> GqlResult<ReleaseGql> {
+        let state = ctx.data::<AppState>()?;
+        let build = BuildJobRepository::new(state.pool.clone())
+            .find_by_id(input.build_id)
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?
+            .ok_or_else(|| async_graphql::Error::new("Build not found"))?;
+        let user = get_app_actor(ctx, build.app_id).await?;
+
+        let app = AppRepository::new(state.pool.clone())
+            .find_by_id(build.app_id)
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?
+            .ok_or_else(|| async_graphql::Error::new("App not found"))?;
+
+        if !has_app_role(ctx, &user, &app, DEPLOY_ROLES).await? {
+            return Err(forbidden("Not allowed to deploy this app"));
+        }
+
+        if build.status != BuildStatus::Succeeded {
+            return Err(async_graphql::Error::new(format!(
+                "Build {} has not succeeded",
+                build.id
+            )));
+        }
+
+        let image_ref = build.image_ref.clone().ok_or_else(|| {
+            async_graphql::Error::new(format!(
+                "Build {} did not produce an image",
+                build.id
+            ))
+        })?;
+
+        if input.organization_id.is_some_and(|id| id != app.organization_id) {
+            return Err(async_graphql::Error::new(format!(
+                "Build {} does not belong to the selected organization",
+                build.id
+            )));
+        }
+
+        let new_release = NewRelease {
+            app_id: build.app_id,
+            version: input
+                .version
+                .unwrap_or_else(|| format!("build-{}", build.id)),
+            commit_sha: build.commit_sha,
+            branch: build.branch,
+            tag: build.tag,
+            image_ref: None,
+            created_by: Some(user.id),
+            changelog: input.changelog,
+        };
+
+        // a pending release without its image must never be left behind
+        let mut tx = state
+            .pool
+            .begin()
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?;
+        let release = ReleaseRepository::create_tx(&mut tx, new_release)
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?;
+        let release = ReleaseRepository::update_image_ref_tx(
+            &mut tx,
+            release.id,
+            &image_ref,
+            ReleaseStatus::Built,
+            Some(user.id),
+        )
+        .await
+        .map_err(|e| async_graphql::Error::new(e.to_string()))?;
+        tx.commit()
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?;
+
+        Ok(release.into())
+    }
+
+    /// Request a deploy of a built release to an environment.
+    async fn create_deploy(
+        &self,
+        ctx: &Context<'_>,
+        input: CreateDeployInput,
+    ) -> GqlResult<DeployGql> {
+        let user = get_app_actor(ctx, input.app_id).await?;
+
+        let state = ctx.data::<AppState>()?;
+        let deploy_repo = DeployRepository::new(state.pool.clone());
+
+        let release =
+            deployable_release(ctx, &user, input.app_id, input.release_id)
+                .await?;
+
+        ensure_environment_secrets(ctx, release.app_id, &input.environment)
+            .await?;
+
+        let new_deploy = NewDeploy {
+            app_id: input.app_id,
+            release_id: release.id,
+            environment: input.environment,
+            status: DeployStatus::Pending,
+            triggered_by: Some(user.id),
+            target_cluster: None,
+            target_region: None,
+            pipeline_url: None,
+            logs_url: None,
+            error_message: None,
+        };
+
+        let deploy = deploy_repo
+            .create(new_deploy)
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?;
+
+        Ok(deploy.into())
+    }
+
+    /// Deploy a release to several environments in one transaction.
+    ///
+    /// Environments with a pending or running deploy of the app are busy:
+    /// they fail the whole call with `CONFLICT`, or are skipped (and
+    /// reported) with `skipBusy`.
+    async fn create_deploys(
+        &self,
+        ctx: &Context<'_>,
+        input: CreateDeploysInput,
+    ) -> GqlResult<CreateDeploysPayload> {
+        let user = get_app_actor(ctx, input.app_id).await?;
+
+        let mut errors = ValidationErrors::new();
+        if input.environments.is_empty() {
+            errors.add("environments", "must not be empty");
+        }
+        let mut environments: Vec<String> = Vec::new();
+        for (i, env) in input.environments.into_iter().enumerate() {
+            errors.validate_name(&format!("environments.{i}"), &env);
+            if !environments.contains(&env) {
+                environments.push(env);
+            }
+        }
+        errors.into_result()?;
+
+        let release =
+            deployable_release(ctx, &user, input.app_id, input.release_id)
+                .await?;
+        for env in &environments {
+            ensure_environment_secrets(ctx, release.app_id, env).await?;
+        }
+
+        let state = ctx.data::<AppState>()?;
+        let template = NewDeploy {
+            app_id: release.app_id,
+            release_id: release.id,
+            environment: String::new(),
+            status: DeployStatus::Pending,
+            triggered_by: Some(user.id),
+            target_cluster: None,
+            target_region: None,
+            pipeline_url: None,
+            logs_url: None,
+            error_message: None,
+        };
+        let batch = DeployRepository::new(state.pool.clone())
+            .create_for_environments(template, &environments, input.skip_busy)
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?;
+
+        if !batch.busy.is_empty() && !input.skip_busy {
+            return Err(conflict(format!(
+                "A deploy is already in progress for: {}",
+                batch.busy.join(", ")
+            )));
+        }
+
+        Ok(CreateDeploysPayload {
+            deploys: batch.created.into_iter().map(Into::into).collect(),
+            skipped_environments: batch.busy,
+        })
+    }
+
+    /// Queue a build of an app from its repository.
+    ///
+    /// Also accepted with a deploy token of the app (see
+    /// `regenerateAppDeployToken`).
+    async fn trigger_build(
+        &self,
+        ctx: &Context<'_>,
+        app_id: i64,
+        branch: Option<String>,
+    ) -> GqlResult<BuildJobGql> {
+        let user = get_app_actor(ctx, app_id).await?;
+
+        let state = ctx.data::<AppState>()?;
+        let app = AppRepository::new(state.pool.clone())
+            .find_by_id(app_id)
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?
+            .ok_or_else(|| async_graphql::Error::new("App not found"))?;
+
+        if !has_app_role(ctx, &user, &app, DEPLOY_ROLES).await? {
+            return Err(forbidden("Not allowed to build this app"));
+        }
+
+        let build = BuildJobRepository::new(state.pool.clone())
+            .create(NewBuildJob {
+                app_id: app.id,
+                release_id: None,
+                trigger: BuildTrigger::Api,
+                triggered_by: Some(user.id),
+                commit_sha: None,
+                branch,
+                tag: None,
+                image_ref: None,
+                runner_name: None,
+                runner_type: None,
+                logs_url: None,
+                pipeline_url: None,
+                error_message: None,
+            })
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?;
+
+        Ok(build.into())
+    }
+
+    /// Issue a new deploy token for an app, revoking the previous one.
+    ///
+    /// The token is returned only once. CI can use it as a bearer token for
+    /// `triggerBuild`, `promoteBuild`, `createDeploy` and `createDeploys` on
+    /// this app, acting as the app owner who issued it.
+    async fn regenerate_app_deploy_token(
+        &self,
+        ctx: &Context<'_>,
+        app_id: i64,
+    ) -> GqlResult<String> {
+        let current = get_current_user(ctx).await?;
+
+        let state = ctx.data::<AppState>()?;
+        let app = AppRepository::new(state.pool.clone())
+            .find_by_id(app_id)
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?
+            .ok_or_else(|| async_graphql::Error::new("App not found"))?;
+
+        if !has_app_role(ctx, &current.user, &app, &[AppRole::Owner]).await? {
+            return Err(forbidden("Only app owners can manage deploy tokens"));
+        }
+
+        let token = generate_access_token(&state.config.token_prefix);
+        AppDeployTokenRepository::new(state.pool.clone())
+            .regenerate(NewAppDeployToken {
+                app_id: app.id,
+                token_hash: hash_token(&token),
+                created_by: current.user.id,
+            })
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?;
+
+        Ok(token)
+    }
+
+    /// Move a team and its apps to another organization.
+    ///
+    /// The caller must be an owner/admin of both organizations.
+    async fn transfer_team(
+        &self,
+        ctx: &Context<'_>,
+        team_id: i64,
+        target_organization_id: i64,
+    ) -> GqlResult<TeamGql> {
+        let current = get_current_user(ctx).await?;
+
+        let state = ctx.data::<AppState>()?;
+        let team_repo = TeamRepository::new(state.pool.clone());
+        let org_repo = OrganizationRepository::new(state.pool.clone());
+
+        let team = team_repo
+            .find_by_id(team_id)
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?
+            .ok_or_else(|| async_graphql::Error::new("Team not found"))?;
+
+        org_repo
+            .find_by_id(target_organization_id)
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?
+            .ok_or_else(|| {
+                async_graphql::Error::new("Target organization not found")
+            })?;
+
+        let admin_roles = [OrgRole::Owner, OrgRole::Admin];
+        for organization_id in [team.organization_id, target_organization_id] {
+            if !has_org_role(ctx, &current.user, organization_id, &admin_roles)
+                .await?
+            {
+                return Err(forbidden(
+                    "Not allowed to transfer teams between these organizations",
+                ));
+            }
+        }
+
+        let team = team_repo
+            .transfer(team.id, target_organization_id)
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?;
+
+        Ok(team.into())
+    }
+
+    /// Soft-delete an organization.
+    ///
+    /// Only owners may delete, and only once every app of the organization
+    /// has been deleted; teams are left as they are.
+    async fn delete_organization(
+        &self,
+        ctx: &Context<'_>,
+        id: i64,
+    ) -> GqlResult<OrganizationGql> {
+        let current = get_current_user(ctx).await?;
+
+        let state = ctx.data::<AppState>()?;
+        let repo = OrganizationRepository::new(state.pool.clone());
+
+        let org = repo
+            .find_by_id(id)
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?
+            .ok_or_else(|| {
+                async_graphql::Error::new("Organization not found")
+            })?;
+
+        let is_owner =
+            OrganizationMembershipRepository::new(state.pool.clone())
+                .list_by_user(current.user.id)
+                .await
+                .map_err(|e| async_graphql::Error::new(e.to_string()))?
+                .iter()
+                .any(|m| {
+                    m.organization_id == org.id && m.role == OrgRole::Owner
+                });
+        if !is_owner {
+            return Err(forbidden("Only owners can delete this organization"));
+        }
+
+        let apps = AppRepository::new(state.pool.clone())
+            .list_by_organization(org.id)
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?;
+        if !apps.is_empty() {
+            let slugs: Vec<&str> =
+                apps.iter().map(|app| app.slug.as_str()).collect();
+            return Err(conflict(format!(
+                "Organization '{}' still has {} app(s): {}; delete them first",
+                org.slug,
+                apps.len(),
+                slugs.join(", ")
+            )));
+        }
+
+        let org = repo
+            .soft_delete(org.id)
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?
+            .ok_or_else(|| {
+                async_graphql::Error::new("Organization not found")
+            })?;
+
+        Ok(org.into())
+    }
+
+    /// Restore a soft-deleted organization.
+    ///
+    /// Platform admins may always restore; owners of the organization may
+    /// restore it within the grace window. With `restore_children`, teams
+    /// and apps deleted together with the organization come back as well.
+    async fn restore_organization(
+        &self,
+        ctx: &Context<'_>,
+        id: i64,
+        restore_children: Option<bool>,
+    ) -> GqlResult<OrganizationGql> {
+        let current = get_current_user(ctx).await?;
+
+        let state = ctx.data::<AppState>()?;
+        let repo = OrganizationRepository::new(state.pool.clone());
+
+        let org = repo
+            .find_deleted_by_id(id)
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?
+            .ok_or_else(|| {
+                async_graphql::Error::new("Deleted organization not found")
+            })?;
+
+        if !current.user.is_admin {
+            let memberships =
+                OrganizationMembershipRepository::new(state.pool.clone())
+                    .list_by_user(current.user.id)
+                    .await
+                    .map_err(|e| async_graphql::Error::new(e.to_string()))?;
+
+            let is_owner = memberships.iter().any(|m| {
+                m.organization_id == org.id && m.role == OrgRole::Owner
+            });
+            let within_grace = org.deleted_at.is_some_and(|deleted_at| {
+                OffsetDateTime::now_utc() - deleted_at
+                    <= Duration::days(ORG_RESTORE_GRACE_DAYS)
+            });
+
+            if !(is_owner && within_grace) {
+                return Err(forbidden(
+                    "Not allowed to restore this organization",
+                ));
+            }
+        }
+
+        let org = repo
+            .restore(org.id, restore_children.unwrap_or(false))
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?
+            .ok_or_else(|| {
+                async_graphql::Error::new("Deleted organization not found")
+            })?;
+
+        Ok(org.into())
+    }
+}
+
+/// Fail unless `user` may change the members of the organization:
+/// owners/admins, or only owners when an `OWNER` membership is involved.
+async fn ensure_can_manage_members(
+    ctx: &Context<'_>,
+    user: &User,
+    organization_id: i64,
+    touches_owner: bool,
+) -> GqlResult<()> {
+    let roles: &[OrgRole] = if touches_owner {
+        &[OrgRole::Owner]
+    } else {
+        &[OrgRole::Owner, OrgRole::Admin]
+    };
+    if !has_org_role(ctx, user, organization_id, roles).await? {
+        return Err(forbidden(if touches_owner {
+            "Only owners can manage the owners of this organization"
+        } else {
+            "Not allowed to manage members of this organization"
+        }));
+    }
+    Ok(())
+}
+
+/// Give `user_id` the `role` in the organization, as `actor`.
+///
+/// Checks [`ensure_can_manage_members`], refuses to demote the last owner
+/// and that the user exists.
+async fn set_org_member(
+    ctx: &Context<'_>,
+    actor: &User,
+    organization_id: i64,
+    user_id: i64,
+    role: OrgRole,
+) -> GqlResult<OrganizationMembership> {
+    let state = ctx.data::<AppState>()?;
+    let repo = OrganizationMembershipRepository::new(state.pool.clone());
+    let members = repo
+        .list_by_organization(organization_id)
+        .await
+        .map_err(|e| async_graphql::Error::new(e.to_string()))?;
+    let existing = members.iter().find(|m| m.user_id == user_id);
+    let touches_owner = role == OrgRole::Owner
+        || existing.is_some_and(|m| m.role == OrgRole::Owner);
+    ensure_can_manage_members(ctx, actor, organization_id, touches_owner)
+        .await?;
+
+    if role != OrgRole::Owner && is_last_owner(&members, user_id) {
+        return Err(conflict(
+            "Cannot demote the last owner of the organization",
+        ));
+    }
+
+    UserRepository::new(state.pool.clone())
+        .find_by_id(user_id)
+        .await
+        .map_err(|e| async_graphql::Error::new(e.to_string()))?
+        .ok_or_else(|| async_graphql::Error::new("User not found"))?;
+
+    repo.upsert_membership(organization_id, user_id, role)
+        .await
+        .map_err(|e| async_graphql::Error::new(e.to_string()))
+}
+
+/// Fail unless `user` may change the members of `team`: owners/admins of
+/// its organization, or leads/maintainers among `members` (leads only when
+/// a `LEAD` membership is involved).
+async fn ensure_can_manage_team_members(
+    ctx: &Context<'_>,
+    user: &User,
+    team: &Team,
+    members: &[TeamMembership],
+    touches_lead: bool,
+) -> GqlResult<()> {
+    if has_org_role(
+        ctx,
+        user,
+        team.organization_id,
+        &[OrgRole::Owner, OrgRole::Admin],
+    )
+    .await?
+    {
+        return Ok(());
+    }
+
+    let role = members.iter().find(|m| m.user_id == user.id).map(|m| m.role);
+    match role {
+        Some(TeamRole::Lead) => Ok(()),
+        Some(TeamRole::Maintainer) if !touches_lead => Ok(()),
+        Some(TeamRole::Maintainer) => {
+            Err(forbidden("Only leads can manage the leads of this team"))
+        }
+        _ => Err(forbidden("Not allowed to manage members of this team")),
+    }
+}
+
+/// Whether `user_id` is the only owner among `members`.
+fn is_last_owner(members: &[OrganizationMembership], user_id: i64) -> bool {
+    let mut owners = members.iter().filter(|m| m.role == OrgRole::Owner);
+    owners.next().is_some_and(|m| m.user_id == user_id)
+        && owners.next().is_none()
+}
+
+/// The release to deploy, checked to be built, to belong to `app_id` and
+/// that `user` may deploy the app.
+async fn deployable_release(
+    ctx: &Context<'_>,
+    user: &User,
+    app_id: i64,
+    release_id: i64,
+) -> GqlResult<Release> {
+    let state = ctx.data::<AppState>()?;
+
+    let release = ReleaseRepository::new(state.pool.clone())
+        .find_by_id(release_id)
+        .await
+        .map_err(|e| async_graphql::Error::new(e.to_string()))?
+        .ok_or_else(|| async_graphql::Error::new("Release not found"))?;
+
+    if release.app_id != app_id {
+        return Err(async_graphql::Error::new(
+            "Release does not belong to this app",
+        ));
+    }
+
+    let app = AppRepository::new(state.pool.clone())
+        .find_by_id(release.app_id)
+        .await
+        .map_err(|e| async_graphql::Error::new(e.to_string()))?
+        .ok_or_else(|| async_graphql::Error::new("App not found"))?;
+    if !has_app_role(ctx, user, &app, DEPLOY_ROLES).await? {
+        return Err(forbidden("Not allowed to deploy this app"));
+    }
+
+    if release.status != ReleaseStatus::Built {
+        return Err(async_graphql::Error::new(format!(
+            "Release {} is not built",
+            release.id
+        )));
+    }
+
+    Ok(release)
+}
+
+/// Guard against deploying to an environment with no secrets at all,
+/// which usually means they were never configured.
+///
+/// Fails with `MISSING_SECRETS` when `PAASTEL_REQUIRE_SECRETS` is on,
+/// logs a warning otherwise.
+async fn ensure_environment_secrets(
+    ctx: &Context<'_>,
+    app_id: i64,
+    environment: &str,
+) -> GqlResult<()> {
+    let state = ctx.data::<AppState>()?;
+    let repo =
+        AppSecretRepository::new(state.pool.clone(), state.cipher.clone());
+    let required = state.config.require_secrets;
+
+    check_environment_secrets(&repo, app_id, environment, required).await
+}
+
+async fn check_environment_secrets(
+    repo: &AppSecretRepository,
+    app_id: i64,
+    environment: &str,
+    required: bool,
+) -> GqlResult<()> {
+    let secrets = repo
+        .list_by_app_env(app_id, environment)
+        .await
+        .map_err(|e| async_graphql::Error::new(e.to_string()))?;
+    if !secrets.is_empty() {
+        return Ok(());
+    }
+
+    if required {
+        return Err(missing_secrets(app_id, environment));
+    }
+
+    tracing::warn!(
+        app_id,
+        environment,
+        "deploying to environment without secrets"
+    );
+    Ok(())
+}
+
+/// Expiry of a new access token living `ttl_days` (default: the max).
+fn token_expires_at(
+    errors: &mut ValidationErrors,
+    ttl_days: Option<i64>,
+) -> OffsetDateTime {
+    let days = ttl_days.unwrap_or(MAX_TOKEN_TTL_DAYS);
+    if !(1..=MAX_TOKEN_TTL_DAYS).contains(&days) {
+        errors.add(
+            "ttlDays",
+            format!("must be between 1 and {MAX_TOKEN_TTL_DAYS}"),
+        );
+    }
+
+    OffsetDateTime::now_utc() + Duration::days(days)
+}
+
+/// New personal access token: `<prefix><64 hex chars>`.
+///
+/// Tokens are stored and looked up verbatim, so tokens issued before the
+/// prefix (or under another prefix) keep working.
+fn generate_access_token(prefix: &str) -> String {
+    format!("{prefix}{}", generate_token_string())
+}
+
+fn generate_token_string() -> String {
+    // 32 random bytes -> hex string (64 chars)
+    let mut bytes = [0u8; 32];
+    rand::rng().fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use serde_json::{Value, json};
+    use sqlx::PgPool;
+
+    use super::{MAX_SECRET_VALUE_BYTES, check_environment_secrets};
+    use crate::config::Config;
+    use crate::domain::models::{NewAppSecret, SecretKind};
+    use crate::graphql::schema::{AppSchema, build_schema};
+    use crate::graphql::state::AppState;
+    use crate::graphql::test_support::{
+        TEST_PASSWORD, add_app_member, add_org_member, authed_request,
+        create_test_app, create_test_org, create_test_team, data, error_code,
+        register_test_user, request, test_cipher, test_config, test_schema,
+        test_state,
+    };
+    use crate::infrastructure::password::hash_token;
+    use crate::infrastructure::repositories::AppSecretRepository;
+
+    fn create_app(organization_id: i64, slug: &str) -> String {
+        format!(
+            r#"mutation {{
+                createApp(input: {{
+                    organizationId: {organization_id}, name: "{slug}", slug: "{slug}"
+                }}) {{ id slug }}
+            }}"#
+        )
+    }
+
+    #[sqlx::test]
+    async fn register_user_returns_a_working_token(pool: PgPool) {
+        let schema = test_schema(pool);
+
+        let token = register_test_user(&schema, "ada@example.com").await;
+        let query = r#"{ search(term: "acme") { __typename } }"#;
+
+        let found = data(authed_request(&schema, query, &token).await);
+        let rejected = authed_request(&schema, query, "not-a-token").await;
+
+        assert_eq!(found["search"], serde_json::json!([]));
+        assert!(!rejected.errors.is_empty());
+    }
+
+    #[sqlx::test]
+    async fn register_user_rejects_invalid_input(pool: PgPool) {
+        let schema = test_schema(pool);
+
+        let response = request(
+            &schema,
+            r#"mutation {
+                registerUser(input: {
+                    name: " ", email: "not-an-email", password: "short"
+                }) { user { id } }
+            }"#,
+        )
+        .await;
+
+        assert_eq!(error_code(&response).as_deref(), Some("BAD_USER_INPUT"));
+    }
+
+    #[sqlx::test]
+    async fn register_user_rejects_a_taken_email(pool: PgPool) {
+        let schema = test_schema(pool);
+        register_test_user(&schema, "ada@example.com").await;
+
+        let response = request(
+            &schema,
+            &format!(
+                r#"mutation {{
+                    registerUser(input: {{
+                        name: "Other", email: "ada@example.com",
+                        password: "{TEST_PASSWORD}"
+                    }}) {{ user {{ id }} }}
+                }}"#
+            ),
+        )
+        .await;
+
+        assert!(!response.errors.is_empty());
+    }
+
+    #[sqlx::test]
+    async fn create_organization_records_the_creator(pool: PgPool) {
+        let schema = test_schema(pool.clone());
+        let token = register_test_user(&schema, "ada@example.com").await;
+
+        let created = data(
+            authed_request(
+                &schema,
+                r#"mutation {
+                    createOrganization(input: { name: "Acme", slug: "acme" }) {
+                        id slug createdBy
+                    }
+                }"#,
+                &token,
+            )
+            .await,
+        );
+        assert_eq!(created["createOrganization"]["slug"], "acme");
+
+        let creator: Option<i64> = sqlx::query_scalar(
+            "SELECT created_by FROM organizations WHERE slug = 'acme'",
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(
+            created["createOrganization"]["createdBy"],
+            creator.expect("creator recorded")
+        );
+    }
+
+    #[sqlx::test]
+    async fn create_organization_requires_authentication(pool: PgPool) {
+        let schema = test_schema(pool);
+
+        let response = request(
+            &schema,
+            r#"mutation {
+                createOrganization(input: { name: "Acme", slug: "acme" }) {
+                    id
+                }
+            }"#,
+        )
+        .await;
+
+        assert!(!response.errors.is_empty());
+    }
+
+    #[sqlx::test]
+    async fn create_organization_rejects_a_taken_slug(pool: PgPool) {
+        let schema = test_schema(pool);
+        let token = register_test_user(&schema, "ada@example.com").await;
+        let create = r#"mutation {
+            createOrganization(input: { name: "Acme", slug: "acme" }) { id }
+        }"#;
+
+        data(authed_request(&schema, create, &token).await);
+        let response = authed_request(&schema, create, &token).await;
+
+        assert!(!response.errors.is_empty());
+    }
+
+    #[sqlx::test]
+    async fn create_app_rejects_a_slug_taken_in_the_org(pool: PgPool) {
+        let schema = test_schema(pool.clone());
+        let token = register_test_user(&schema, "ada@example.com").await;
+        let acme = create_test_org(&pool, "acme").await;
+        add_org_member(&pool, acme, "ada@example.com", "member").await;
+
+        let created = data(
+            authed_request(&schema, &create_app(acme, "web"), &token).await,
+        );
+        let taken =
+            authed_request(&schema, &create_app(acme, "web"), &token).await;
+
+        assert_eq!(created["createApp"]["slug"], "web");
+        assert_eq!(error_code(&taken).as_deref(), Some("CONFLICT"));
+    }
+
+    #[sqlx::test]
+    async fn create_app_allows_the_same_slug_in_another_org(pool: PgPool) {
+        let schema = test_schema(pool.clone());
+        let token = register_test_user(&schema, "ada@example.com").await;
+        for slug in ["acme", "globex"] {
+            let org = create_test_org(&pool, slug).await;
+            add_org_member(&pool, org, "ada@example.com", "member").await;
+
+            let created =
+                authed_request(&schema, &create_app(org, "web"), &token).await;
+
+            assert_eq!(data(created)["createApp"]["slug"], "web");
+        }
+    }
+
+    #[sqlx::test]
+    async fn create_app_requires_membership(pool: PgPool) {
+        let schema = test_schema(pool.clone());
+        let token = register_test_user(&schema, "ada@example.com").await;
+        let acme = create_test_org(&pool, "acme").await;
+
+        let response =
+            authed_request(&schema, &create_app(acme, "web"), &token).await;
+
+        assert_eq!(error_code(&response).as_deref(), Some("FORBIDDEN"));
+    }
+
+    const PEM: &str = "-----BEGIN CERTIFICATE-----
+MIIBszCCAVmgAwIBAgIUXn3R
+dGVzdCBjZXJ0aWZpY2F0ZQ==
+-----END CERTIFICATE-----";
+
+    #[sqlx::test]
+    async fn multi_line_secrets_round_trip(pool: PgPool) {
+        let schema = test_schema(pool.clone());
+        let token = register_test_user(&schema, "ada@example.com").await;
+        let acme = create_test_org(&pool, "acme").await;
+        let web = create_test_app(&pool, acme, "web").await;
+        add_app_member(&pool, web, "ada@example.com", "owner").await;
+
+        let set = data(
+            authed_request(
+                &schema,
+                &format!(
+                    r#"mutation {{
+                        setAppSecret(input: {{
+                            appId: {web}, environment: "production",
+                            key: "TLS_CERT", type: FILE, value: """{PEM}"""
+                        }}) {{ type size }}
+                    }}"#
+                ),
+                &token,
+            )
+            .await,
+        );
+        let resolved = data(
+            authed_request(
+                &schema,
+                &format!(
+                    r#"{{
+                        getResolvedAppSecrets(
+                            appId: {web}, environment: "production"
+                        ) {{ key value type }}
+                    }}"#
+                ),
+                &token,
+            )
+            .await,
+        );
+
+        assert_eq!(set["setAppSecret"]["type"], "FILE");
+        assert_eq!(set["setAppSecret"]["size"], PEM.len());
+        let secret = &resolved["getResolvedAppSecrets"][0];
+        assert_eq!(secret["key"], "TLS_CERT");
+        assert_eq!(secret["value"], PEM);
+        assert_eq!(secret["type"], "FILE");
+    }
+
+    #[sqlx::test]
+    async fn oversized_secrets_are_rejected(pool: PgPool) {
+        let schema = test_schema(pool.clone());
+        let token = register_test_user(&schema, "ada@example.com").await;
+        let acme = create_test_org(&pool, "acme").await;
+        let web = create_test_app(&pool, acme, "web").await;
+        add_app_member(&pool, web, "ada@example.com", "owner").await;
+        let value = "x".repeat(MAX_SECRET_VALUE_BYTES + 1);
+
+        let response = authed_request(
+            &schema,
+            &format!(
+                r#"mutation {{
+                    setAppSecret(input: {{
+                        appId: {web}, key: "BLOB", value: "{value}"
+                    }}) {{ size }}
+                }}"#
+            ),
+            &token,
+        )
+        .await;
+
+        assert_eq!(error_code(&response).as_deref(), Some("BAD_USER_INPUT"));
+    }
+
+    #[sqlx::test]
+    async fn empty_environments_block_deploys_only_when_required(
+        pool: PgPool,
+    ) {
+        let acme = create_test_org(&pool, "acme").await;
+        let web = create_test_app(&pool, acme, "web").await;
+        let repo = AppSecretRepository::new(pool, test_cipher());
+
+        let lenient =
+            check_environment_secrets(&repo, web, "production", false).await;
+        let strict = check_environment_secrets(&repo, web, "production", true)
+            .await
+            .unwrap_err();
+
+        assert!(lenient.is_ok());
+        let ext = strict.extensions.unwrap();
+        assert_eq!(ext.get("code"), Some(&"MISSING_SECRETS".into()));
+        assert_eq!(ext.get("appId"), Some(&web.into()));
+        assert_eq!(ext.get("environment"), Some(&"production".into()));
+    }
+
+    #[sqlx::test]
+    async fn environments_with_secrets_pass_the_guard(pool: PgPool) {
+        let acme = create_test_org(&pool, "acme").await;
+        let web = create_test_app(&pool, acme, "web").await;
+        let repo = AppSecretRepository::new(pool, test_cipher());
+        repo.upsert_secret(NewAppSecret {
+            app_id: web,
+            environment: "production".to_string(),
+            key: "DATABASE_URL".to_string(),
+            value: "postgres://db/web".to_string(),
+            kind: SecretKind::String,
+            created_by: None,
+        })
+        .await
+        .unwrap();
+
+        let production =
+            check_environment_secrets(&repo, web, "production", true).await;
+        let staging =
+            check_environment_secrets(&repo, web, "staging", true).await;
+
+        assert!(production.is_ok());
+        assert!(staging.is_err());
+    }
+
+    fn sync_secrets(app_id: i64, secrets: &str, prune: bool) -> String {
+        format!(
+            r#"mutation {{
+                updateAppSecretsBulk(input: {{
+                    appId: {app_id}, secrets: [{secrets}], prune: {prune}
+                }}) {{ added updated removed }}
+            }}"#
+        )
+    }
+
+    #[sqlx::test]
+    async fn bulk_sync_adds_updates_and_prunes(pool: PgPool) {
+        let schema = test_schema(pool.clone());
+        let token = register_test_user(&schema, "ada@example.com").await;
+        let acme = create_test_org(&pool, "acme").await;
+        let web = create_test_app(&pool, acme, "web").await;
+        add_app_member(&pool, web, "ada@example.com", "maintainer").await;
+        let sync = async |secrets: &str, prune: bool| {
+            let query = sync_secrets(web, secrets, prune);
+            data(authed_request(&schema, &query, &token).await)
+                ["updateAppSecretsBulk"]
+                .clone()
+        };
+
+        let added =
+            sync(r#"{key: "A", value: "1"}, {key: "B", value: "2"}"#, true)
+                .await;
+        let updated = sync(
+            r#"{key: "A", value: "1"}, {key: "B", value: "3"},
+               {key: "C", value: "4"}"#,
+            false,
+        )
+        .await;
+        let pruned = sync(r#"{key: "A", value: "1"}"#, true).await;
+        let remaining = data(
+            authed_request(
+                &schema,
+                &format!(
+                    r#"{{
+                        getResolvedAppSecrets(appId: {web}, environment: "default") {{
+                            key value
+                        }}
+                    }}"#
+                ),
+                &token,
+            )
+            .await,
+        );
+
+        assert_eq!(
+            added,
+            json!({ "added": ["A", "B"], "updated": [], "removed": [] })
+        );
+        assert_eq!(
+            updated,
+            json!({ "added": ["C"], "updated": ["B"], "removed": [] })
+        );
+        assert_eq!(
+            pruned,
+            json!({ "added": [], "updated": [], "removed": ["B", "C"] })
+        );
+        assert_eq!(
+            remaining["getResolvedAppSecrets"],
+            json!([{ "key": "A", "value": "1" }])
+        );
+    }
+
+    #[sqlx::test]
+    async fn bulk_sync_rejects_duplicate_keys(pool: PgPool) {
+        let schema = test_schema(pool.clone());
+        let token = register_test_user(&schema, "ada@example.com").await;
+        let acme = create_test_org(&pool, "acme").await;
+        let web = create_test_app(&pool, acme, "web").await;
+        add_app_member(&pool, web, "ada@example.com", "maintainer").await;
+        let query = sync_secrets(
+            web,
+            r#"{key: "A", value: "1"}, {key: "A", value: "2"}"#,
+            false,
+        );
+
+        let response = authed_request(&schema, &query, &token).await;
+
+        assert_eq!(error_code(&response).as_deref(), Some("BAD_USER_INPUT"));
+    }
+
+    #[sqlx::test]
+    async fn bulk_sync_requires_secret_management_rights(pool: PgPool) {
+        let schema = test_schema(pool.clone());
+        let token = register_test_user(&schema, "eve@example.com").await;
+        let acme = create_test_org(&pool, "acme").await;
+        let web = create_test_app(&pool, acme, "web").await;
+        add_app_member(&pool, web, "eve@example.com", "deployer").await;
+        let query = sync_secrets(web, r#"{key: "A", value: "1"}"#, false);
+
+        let response = authed_request(&schema, &query, &token).await;
+
+        assert_eq!(error_code(&response).as_deref(), Some("FORBIDDEN"));
+    }
+
+    fn authenticate(email: &str, password: &str) -> String {
+        format!(
+            r#"mutation {{
+                authenticate(email: "{email}", password: "{password}") {{
+                    user {{ email }}
+                    token {{ token }}
+                }}
+            }}"#
+        )
+    }
+
+    #[sqlx::test]
+    async fn authenticate_issues_a_working_token(pool: PgPool) {
+        let schema = test_schema(pool.clone());
+        register_test_user(&schema, "ada@example.com").await;
+
+        let login = data(
+            request(
+                &schema,
+                &authenticate(" ada@example.com ", TEST_PASSWORD),
+            )
+            .await,
+        );
+        let token = login["authenticate"]["token"]["token"].as_str().unwrap();
+        let searched = authed_request(
+            &schema,
+            r#"{ search(term: "acme") { __typename } }"#,
+            token,
+        )
+        .await;
+        let last_login: Option<time::OffsetDateTime> = sqlx::query_scalar(
+            "SELECT last_login_at FROM users WHERE email = 'ada@example.com'",
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+
+        assert_eq!(login["authenticate"]["user"]["email"], "ada@example.com");
+        assert!(searched.errors.is_empty(), "{:?}", searched.errors);
+        assert!(last_login.is_some());
+    }
+
+    #[sqlx::test]
+    async fn authenticate_hides_why_credentials_fail(pool: PgPool) {
+        let schema = test_schema(pool.clone());
+        register_test_user(&schema, "ada@example.com").await;
+        register_test_user(&schema, "bob@example.com").await;
+        sqlx::query(
+            "UPDATE users SET is_active = false WHERE email = 'bob@example.com'",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        for (email, password) in [
+            ("ada@example.com", "wrong password"),
+            ("nobody@example.com", TEST_PASSWORD),
+            ("bob@example.com", TEST_PASSWORD),
+        ] {
+            let response =
+                request(&schema, &authenticate(email, password)).await;
+
+            assert_eq!(response.errors[0].message, "Invalid credentials");
+            assert_eq!(
+                error_code(&response).as_deref(),
+                Some("UNAUTHENTICATED"),
+                "{email}"
+            );
+        }
+    }
+
+    #[sqlx::test]
+    async fn access_tokens_are_prefixed_and_authenticate(pool: PgPool) {
+        let schema = test_schema(pool.clone());
+        let token = register_test_user(&schema, "ada@example.com").await;
+        let query = r#"{ search(term: "acme") { __typename } }"#;
+
+        let response = authed_request(&schema, query, &token).await;
+
+        let hex = token.strip_prefix("pst_").expect("pst_ prefix");
+        assert_eq!(hex.len(), 64);
+        assert!(hex.chars().all(|c| c.is_ascii_hexdigit()));
+        assert!(response.errors.is_empty(), "{:?}", response.errors);
+    }
+
+    #[sqlx::test]
+    async fn unprefixed_tokens_keep_working(pool: PgPool) {
+        let schema = test_schema(pool.clone());
+        register_test_user(&schema, "ada@example.com").await;
+        let legacy = "0".repeat(64);
+        sqlx::query(
+            r#"
+            INSERT INTO auth_tokens (user_id, token)
+            SELECT id, $1 FROM users WHERE email = 'ada@example.com'
+            "#,
+        )
+        .bind(&legacy)
+        .execute(&pool)
+        .await
+        .unwrap();
+        let query = r#"{ search(term: "acme") { __typename } }"#;
+
+        let response = authed_request(&schema, query, &legacy).await;
+
+        assert!(response.errors.is_empty(), "{:?}", response.errors);
+    }
+
+    #[sqlx::test]
+    async fn access_tokens_expire_after_ttl_days(pool: PgPool) {
+        let schema = test_schema(pool.clone());
+        let register = |ttl: &str| {
+            format!(
+                r#"mutation {{
+                    registerUser(input: {{
+                        name: "Ada", email: "ada{ttl}@example.com",
+                        password: "{TEST_PASSWORD}"{ttl}
+                    }}) {{ token {{ expiresAt }} }}
+                }}"#
+            )
+        };
+
+        let default = data(request(&schema, &register("")).await);
+        let week = data(request(&schema, &register(", ttlDays: 7")).await);
+        let days_left = |payload: &serde_json::Value| {
+            let expires_at = time::OffsetDateTime::parse(
+                payload["registerUser"]["token"]["expiresAt"]
+                    .as_str()
+                    .unwrap(),
+                &time::format_description::well_known::Rfc3339,
+            )
+            .unwrap();
+            (expires_at - time::OffsetDateTime::now_utc()).whole_hours() / 24
+        };
+
+        assert_eq!(days_left(&default), 89);
+        assert_eq!(days_left(&week), 6);
+    }
+
+    #[sqlx::test]
+    async fn token_ttl_must_be_between_1_and_90_days(pool: PgPool) {
+        let schema = test_schema(pool.clone());
+        register_test_user(&schema, "ada@example.com").await;
+
+        for ttl in [0, 91] {
+            let response = request(
+                &schema,
+                &format!(
+                    r#"mutation {{
+                        authenticate(
+                            email: "ada@example.com",
+                            password: "{TEST_PASSWORD}",
+                            ttlDays: {ttl}
+                        ) {{ token {{ token }} }}
+                    }}"#
+                ),
+            )
+            .await;
+
+            assert_eq!(
+                error_code(&response).as_deref(),
+                Some("BAD_USER_INPUT"),
+                "ttlDays: {ttl}"
+            );
+        }
+    }
+
+    #[sqlx::test]
+    async fn revoked_tokens_stop_working(pool: PgPool) {
+        let schema = test_schema(pool.clone());
+        let token = register_test_user(&schema, "ada@example.com").await;
+        let other = data(
+            request(&schema, &authenticate("ada@example.com", TEST_PASSWORD))
+                .await,
+        )["authenticate"]["token"]["token"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        let query = r#"{ search(term: "acme") { __typename } }"#;
+
+        let revoked = data(
+            authed_request(&schema, "mutation { revokeToken }", &token).await,
+        );
+        let with_revoked = authed_request(&schema, query, &token).await;
+        let with_other = authed_request(&schema, query, &other).await;
+
+        assert_eq!(revoked["revokeToken"], true);
+        assert!(!with_revoked.errors.is_empty());
+        assert!(with_other.errors.is_empty(), "{:?}", with_other.errors);
+    }
+
+    /// Insert a built release of `app_id`; returns its id.
+    async fn built_release(pool: &PgPool, app_id: i64) -> i64 {
+        sqlx::query_scalar(
+            r#"
+            INSERT INTO releases (app_id, version, status)
+            VALUES ($1, 'v1', 'built')
+            RETURNING id
+            "#,
+        )
+        .bind(app_id)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+    }
+
+    fn create_deploys(
+        app_id: i64,
+        release_id: i64,
+        environments: &str,
+        skip_busy: bool,
+    ) -> String {
+        format!(
+            r#"mutation {{
+                createDeploys(input: {{
+                    appId: {app_id}, releaseId: {release_id},
+                    environments: {environments}, skipBusy: {skip_busy}
+                }}) {{
+                    deploys {{ environment status }}
+                    skippedEnvironments
+                }}
+            }}"#
+        )
+    }
+
+    #[sqlx::test]
+    async fn create_deploys_targets_every_environment(pool: PgPool) {
+        let schema = test_schema(pool.clone());
+        let token = register_test_user(&schema, "ada@example.com").await;
+        let acme = create_test_org(&pool, "acme").await;
+        let web = create_test_app(&pool, acme, "web").await;
+        add_app_member(&pool, web, "ada@example.com", "deployer").await;
+        let release = built_release(&pool, web).await;
+        let query = create_deploys(
+            web,
+            release,
+            r#"["staging", "production", "staging"]"#,
+            false,
+        );
+
+        let created = data(authed_request(&schema, &query, &token).await);
+
+        assert_eq!(
+            created["createDeploys"],
+            json!({
+                "deploys": [
+                    { "environment": "staging", "status": "PENDING" },
+                    { "environment": "production", "status": "PENDING" },
+                ],
+                "skippedEnvironments": [],
+            })
+        );
+    }
+
+    #[sqlx::test]
+    async fn busy_environments_fail_the_batch_or_are_skipped(pool: PgPool) {
+        let schema = test_schema(pool.clone());
+        let token = register_test_user(&schema, "ada@example.com").await;
+        let acme = create_test_org(&pool, "acme").await;
+        let web = create_test_app(&pool, acme, "web").await;
+        add_app_member(&pool, web, "ada@example.com", "deployer").await;
+        let release = built_release(&pool, web).await;
+        sqlx::query(
+            r#"
+            INSERT INTO deploys (app_id, release_id, environment, status)
+            VALUES ($1, $2, 'production', 'running')
+            "#,
+        )
+        .bind(web)
+        .bind(release)
+        .execute(&pool)
+        .await
+        .unwrap();
+        let environments = r#"["staging", "production"]"#;
+        let deploy_count = async || -> i64 {
+            sqlx::query_scalar("SELECT count(*) FROM deploys")
+                .fetch_one(&pool)
+                .await
+                .unwrap()
+        };
+
+        let strict = authed_request(
+            &schema,
+            &create_deploys(web, release, environments, false),
+            &token,
+        )
+        .await;
+        let after_strict = deploy_count().await;
+        let lenient = data(
+            authed_request(
+                &schema,
+                &create_deploys(web, release, environments, true),
+                &token,
+            )
+            .await,
+        );
+
+        assert_eq!(error_code(&strict).as_deref(), Some("CONFLICT"));
+        assert_eq!(after_strict, 1);
+        assert_eq!(
+            lenient["createDeploys"],
+            json!({
+                "deploys": [{ "environment": "staging", "status": "PENDING" }],
+                "skippedEnvironments": ["production"],
+            })
+        );
+        assert_eq!(deploy_count().await, 2);
+    }
+
+    #[sqlx::test]
+    async fn apps_and_deploys_resolve_the_acting_user(pool: PgPool) {
+        let schema = test_schema(pool.clone());
+        let token = register_test_user(&schema, "ada@example.com").await;
+        let acme = create_test_org(&pool, "acme").await;
+        add_org_member(&pool, acme, "ada@example.com", "owner").await;
+        let query = format!(
+            r#"mutation {{
+                createApp(input: {{
+                    organizationId: {acme}, name: "web", slug: "web"
+                }}) {{ id createdBy {{ email }} }}
+            }}"#
+        );
+
+        let created = data(authed_request(&schema, &query, &token).await);
+        let web = created["createApp"]["id"].as_i64().unwrap();
+        let release = built_release(&pool, web).await;
+        let query = format!(
+            r#"mutation {{
+                createDeploys(input: {{
+                    appId: {web}, releaseId: {release},
+                    environments: ["staging"]
+                }}) {{ deploys {{ triggeredBy {{ email }} }} }}
+            }}"#
+        );
+        let deployed = data(authed_request(&schema, &query, &token).await);
+
+        assert_eq!(
+            created["createApp"]["createdBy"],
+            json!({ "email": "ada@example.com" })
+        );
+        assert_eq!(
+            deployed["createDeploys"]["deploys"],
+            json!([{ "triggeredBy": { "email": "ada@example.com" } }])
+        );
+    }
+
+    fn update_app(app_id: i64, fields: &str) -> String {
+        format!(
+            r#"mutation {{
+                updateApp(input: {{ id: {app_id}, {fields} }}) {{ repoUrl }}
+            }}"#
+        )
+    }
+
+    #[sqlx::test]
+    async fn repo_url_changes_are_validated(pool: PgPool) {
+        let schema = test_schema(pool.clone());
+        let token = register_test_user(&schema, "ada@example.com").await;
+        let acme = create_test_org(&pool, "acme").await;
+        let web = create_test_app(&pool, acme, "web").await;
+        add_app_member(&pool, web, "ada@example.com", "maintainer").await;
+
+        for fields in [r#"repoUrl: "github.com/acme/web""#, "rebuild: true"] {
+            let response =
+                authed_request(&schema, &update_app(web, fields), &token)
+                    .await;
+
+            assert_eq!(
+                error_code(&response).as_deref(),
+                Some("BAD_USER_INPUT"),
+                "{fields}"
+            );
+        }
+    }
+
+    #[sqlx::test]
+    async fn rebuild_queues_a_build_from_the_new_repo(pool: PgPool) {
+        let schema = test_schema(pool.clone());
+        let token = register_test_user(&schema, "ada@example.com").await;
+        let acme = create_test_org(&pool, "acme").await;
+        let web = create_test_app(&pool, acme, "web").await;
+        add_app_member(&pool, web, "ada@example.com", "maintainer").await;
+        let repo = "https://github.com/acme/web.git";
+        let builds = || {
+            sqlx::query_scalar::<_, i64>(
+                "SELECT count(*) FROM build_jobs WHERE app_id = $1",
+            )
+            .bind(web)
+            .fetch_one(&pool)
+        };
+
+        let moved = data(
+            authed_request(
+                &schema,
+                &update_app(web, &format!(r#"repoUrl: "{repo}""#)),
+                &token,
+            )
+            .await,
+        );
+        let queued_after_move = builds().await.unwrap();
+        authed_request(
+            &schema,
+            &update_app(web, &format!(r#"repoUrl: "{repo}", rebuild: true"#)),
+            &token,
+        )
+        .await;
+
+        assert_eq!(moved["updateApp"]["repoUrl"], repo);
+        assert_eq!(queued_after_move, 0);
+        assert_eq!(builds().await.unwrap(), 1);
+    }
+
+    fn delete_organization(id: i64) -> String {
+        format!("mutation {{ deleteOrganization(id: {id}) {{ slug }} }}")
+    }
+
+    #[sqlx::test]
+    async fn owners_delete_organizations_without_apps(pool: PgPool) {
+        let schema = test_schema(pool.clone());
+        let owner = register_test_user(&schema, "ada@example.com").await;
+        let admin = register_test_user(&schema, "bob@example.com").await;
+        let acme = create_test_org(&pool, "acme").await;
+        add_org_member(&pool, acme, "ada@example.com", "owner").await;
+        add_org_member(&pool, acme, "bob@example.com", "admin").await;
+
+        let by_admin =
+            authed_request(&schema, &delete_organization(acme), &admin).await;
+        let deleted = data(
+            authed_request(&schema, &delete_organization(acme), &owner).await,
+        );
+        let again =
+            authed_request(&schema, &delete_organization(acme), &owner).await;
+
+        assert_eq!(error_code(&by_admin).as_deref(), Some("FORBIDDEN"));
+        assert_eq!(deleted["deleteOrganization"], json!({ "slug": "acme" }));
+        assert!(!again.errors.is_empty());
+    }
+
+    #[sqlx::test]
+    async fn organizations_with_apps_cannot_be_deleted(pool: PgPool) {
+        let schema = test_schema(pool.clone());
+        let owner = register_test_user(&schema, "ada@example.com").await;
+        let acme = create_test_org(&pool, "acme").await;
+        add_org_member(&pool, acme, "ada@example.com", "owner").await;
+        create_test_app(&pool, acme, "web").await;
+        create_test_app(&pool, acme, "api").await;
+
+        let response =
+            authed_request(&schema, &delete_organization(acme), &owner).await;
+
+        assert_eq!(error_code(&response).as_deref(), Some("CONFLICT"));
+        assert_eq!(
+            response.errors[0].message,
+            "Organization 'acme' still has 2 app(s): api, web; delete them \
+             first"
+        );
+    }
+
+    fn regenerate_deploy_token(app_id: i64) -> String {
+        format!("mutation {{ regenerateAppDeployToken(appId: {app_id}) }}")
+    }
+
+    fn trigger_build(app_id: i64) -> String {
+        format!(
+            r#"mutation {{
+                triggerBuild(appId: {app_id}, branch: "main") {{
+                    branch triggeredBy
+                }}
+            }}"#
+        )
+    }
+
+    /// Deploy token for `app_id`, issued by its owner `token`.
+    async fn deploy_token(
+        schema: &AppSchema,
+        app_id: i64,
+        token: &str,
+    ) -> String {
+        let issued = data(
+            authed_request(schema, &regenerate_deploy_token(app_id), token)
+                .await,
+        );
+        issued["regenerateAppDeployToken"].as_str().unwrap().to_string()
+    }
+
+    #[sqlx::test]
+    async fn deploy_tokens_only_act_on_their_own_app(pool: PgPool) {
+        let schema = test_schema(pool.clone());
+        let owner = register_test_user(&schema, "ada@example.com").await;
+        let acme = create_test_org(&pool, "acme").await;
+        let web = create_test_app(&pool, acme, "web").await;
+        let api = create_test_app(&pool, acme, "api").await;
+        add_app_member(&pool, web, "ada@example.com", "owner").await;
+        add_app_member(&pool, api, "ada@example.com", "owner").await;
+        let ada_id: i64 =
+            sqlx::query_scalar("SELECT id FROM users WHERE email = $1")
+                .bind("ada@example.com")
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+
+        let ci = deploy_token(&schema, web, &owner).await;
+        let built =
+            data(authed_request(&schema, &trigger_build(web), &ci).await);
+        let other_app =
+            authed_request(&schema, &trigger_build(api), &ci).await;
+        let user_api = authed_request(&schema, "{ me { email } }", &ci).await;
+
+        assert_eq!(
+            built["triggerBuild"],
+            json!({ "branch": "main", "triggeredBy": ada_id })
+        );
+        assert_eq!(error_code(&other_app).as_deref(), Some("FORBIDDEN"));
+        assert_eq!(error_code(&user_api).as_deref(), Some("FORBIDDEN"));
+    }
+
+    #[sqlx::test]
+    async fn regenerating_revokes_the_previous_deploy_token(pool: PgPool) {
+        let schema = test_schema(pool.clone());
+        let owner = register_test_user(&schema, "ada@example.com").await;
+        let maintainer = register_test_user(&schema, "bob@example.com").await;
+        let acme = create_test_org(&pool, "acme").await;
+        let web = create_test_app(&pool, acme, "web").await;
+        add_app_member(&pool, web, "ada@example.com", "owner").await;
+        add_app_member(&pool, web, "bob@example.com", "maintainer").await;
+
+        let old = deploy_token(&schema, web, &owner).await;
+        let new = deploy_token(&schema, web, &owner).await;
+        let by_maintainer = authed_request(
+            &schema,
+            &regenerate_deploy_token(web),
+            &maintainer,
+        )
+        .await;
+
+        assert!(
+            !authed_request(&schema, &trigger_build(web), &old)
+                .await
+                .errors
+                .is_empty()
+        );
+        assert!(
+            authed_request(&schema, &trigger_build(web), &new)
+                .await
+                .errors
+                .is_empty()
+        );
+        assert_eq!(error_code(&by_maintainer).as_deref(), Some("FORBIDDEN"));
+    }
+
+    #[sqlx::test]
+    async fn app_actors_still_need_a_role_on_the_app(pool: PgPool) {
+        let schema = test_schema(pool.clone());
+        let owner = register_test_user(&schema, "ada@example.com").await;
+        let outsider = register_test_user(&schema, "eve@example.com").await;
+        let acme = create_test_org(&pool, "acme").await;
+        let web = create_test_app(&pool, acme, "web").await;
+        add_app_member(&pool, web, "ada@example.com", "owner").await;
+        let ci = deploy_token(&schema, web, &owner).await;
+
+        let non_member =
+            authed_request(&schema, &trigger_build(web), &outsider).await;
+        sqlx::query("DELETE FROM app_memberships WHERE app_id = $1")
+            .bind(web)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let creator_removed =
+            authed_request(&schema, &trigger_build(web), &ci).await;
+
+        assert_eq!(error_code(&non_member).as_deref(), Some("FORBIDDEN"));
+        assert_eq!(error_code(&creator_removed).as_deref(), Some("FORBIDDEN"));
+    }
+
+    #[sqlx::test]
+    async fn org_admins_rename_organizations_and_teams(pool: PgPool) {
+        let schema = test_schema(pool.clone());
+        let admin = register_test_user(&schema, "ada@example.com").await;
+        let acme = create_test_org(&pool, "acme").await;
+        add_org_member(&pool, acme, "ada@example.com", "admin").await;
+        let web = create_test_team(&pool, acme, "web").await;
+        let query = format!(
+            r#"mutation {{
+                updateOrganization(input: {{
+                    id: {acme}, description: "Rockets"
+                }}) {{ name slug description }}
+                updateTeam(input: {{ id: {web}, name: "Frontend" }}) {{
+                    name slug description
+                }}
+            }}"#
+        );
+
+        let updated = data(authed_request(&schema, &query, &admin).await);
+
+        assert_eq!(
+            updated,
+            json!({
+                "updateOrganization": {
+                    "name": "acme", "slug": "acme", "description": "Rockets",
+                },
+                "updateTeam": {
+                    "name": "Frontend", "slug": "web", "description": null,
+                },
+            })
+        );
+    }
+
+    #[sqlx::test]
+    async fn members_cannot_update_organizations_or_teams(pool: PgPool) {
+        let schema = test_schema(pool.clone());
+        let member = register_test_user(&schema, "bob@example.com").await;
+        let acme = create_test_org(&pool, "acme").await;
+        add_org_member(&pool, acme, "bob@example.com", "member").await;
+        let web = create_test_team(&pool, acme, "web").await;
+
+        for query in [
+            format!(
+                r#"mutation {{
+                    updateOrganization(input: {{ id: {acme}, name: "X" }})
+                    {{ id }}
+                }}"#
+            ),
+            format!(
+                r#"mutation {{
+                    updateTeam(input: {{ id: {web}, name: "X" }}) {{ id }}
+                }}"#
+            ),
+        ] {
+            let response = authed_request(&schema, &query, &member).await;
+
+            assert_eq!(error_code(&response).as_deref(), Some("FORBIDDEN"));
+        }
+    }
+
+    async fn create_team(
+        schema: &AppSchema,
+        token: &str,
+        org_id: i64,
+    ) -> async_graphql::Response {
+        let query = format!(
+            r#"mutation {{
+                createTeam(input: {{
+                    organizationId: {org_id}, name: "Web", slug: "web"
+                }}) {{ slug organizationId }}
+            }}"#
+        );
+        authed_request(schema, &query, token).await
+    }
+
+    #[sqlx::test]
+    async fn org_owners_and_admins_create_teams(pool: PgPool) {
+        let schema = test_schema(pool.clone());
+        let acme = create_test_org(&pool, "acme").await;
+        let globex = create_test_org(&pool, "globex").await;
+        let owner = register_test_user(&schema, "ada@example.com").await;
+        let admin = register_test_user(&schema, "bob@example.com").await;
+        add_org_member(&pool, acme, "ada@example.com", "owner").await;
+        add_org_member(&pool, globex, "bob@example.com", "admin").await;
+
+        let by_owner = data(create_team(&schema, &owner, acme).await);
+        let by_admin = data(create_team(&schema, &admin, globex).await);
+
+        assert_eq!(
+            by_owner,
+            json!({ "createTeam": { "slug": "web", "organizationId": acme } })
+        );
+        assert_eq!(
+            by_admin,
+            json!({ "createTeam": { "slug": "web", "organizationId": globex } })
+        );
+    }
+
+    #[sqlx::test]
+    async fn members_and_outsiders_cannot_create_teams(pool: PgPool) {
+        let schema = test_schema(pool.clone());
+        let acme = create_test_org(&pool, "acme").await;
+        let member = register_test_user(&schema, "bob@example.com").await;
+        let outsider = register_test_user(&schema, "eve@example.com").await;
+        add_org_member(&pool, acme, "bob@example.com", "member").await;
+
+        for token in [&member, &outsider] {
+            let response = create_team(&schema, token, acme).await;
+
+            assert_eq!(error_code(&response).as_deref(), Some("FORBIDDEN"));
+            assert_eq!(
+                response.errors[0].message,
+                "not authorized to create teams in this organization"
+            );
+        }
+    }
+
+    #[sqlx::test]
+    async fn organization_creators_become_owners(pool: PgPool) {
+        let schema = test_schema(pool.clone());
+        let token = register_test_user(&schema, "ada@example.com").await;
+
+        data(
+            authed_request(
+                &schema,
+                r#"mutation {
+                    createOrganization(input: { name: "Acme", slug: "acme" }) {
+                        id
+                    }
+                }"#,
+                &token,
+            )
+            .await,
+        );
+        let mine = data(
+            authed_request(
+                &schema,
+                "{ myOrganizations { role organization { slug } } }",
+                &token,
+            )
+            .await,
+        );
+
+        assert_eq!(
+            mine["myOrganizations"],
+            json!([{ "role": "OWNER", "organization": { "slug": "acme" } }])
+        );
+    }
+
+    #[sqlx::test]
+    async fn a_failed_create_adds_no_membership(pool: PgPool) {
+        let schema = test_schema(pool.clone());
+        let ada = register_test_user(&schema, "ada@example.com").await;
+        let bob = register_test_user(&schema, "bob@example.com").await;
+        let create = r#"mutation {
+            createOrganization(input: { name: "Acme", slug: "acme" }) { id }
+        }"#;
+
+        data(authed_request(&schema, create, &ada).await);
+        let response = authed_request(&schema, create, &bob).await;
+
+        assert!(!response.errors.is_empty());
+        let memberships: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM organization_memberships",
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(memberships, 1);
+    }
+
+    async fn user_id(pool: &PgPool, email: &str) -> i64 {
+        sqlx::query_scalar("SELECT id FROM users WHERE email = $1")
+            .bind(email)
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    fn set_member(org_id: i64, user_id: i64, role: &str) -> String {
+        format!(
+            "mutation {{ addOrganizationMember(organizationId: {org_id}, \
+             userId: {user_id}, role: {role}) {{ role }} }}"
+        )
+    }
+
+    #[sqlx::test]
+    async fn admins_manage_members_but_not_owners(pool: PgPool) {
+        let schema = test_schema(pool.clone());
+        let admin = register_test_user(&schema, "ada@example.com").await;
+        register_test_user(&schema, "bob@example.com").await;
+        let acme = create_test_org(&pool, "acme").await;
+        add_org_member(&pool, acme, "ada@example.com", "admin").await;
+        let bob = user_id(&pool, "bob@example.com").await;
+
+        let added = data(
+            authed_request(&schema, &set_member(acme, bob, "MEMBER"), &admin)
+                .await,
+        );
+        let promoted =
+            authed_request(&schema, &set_member(acme, bob, "OWNER"), &admin)
+                .await;
+        let removed = data(
+            authed_request(
+                &schema,
+                &format!(
+                    "mutation {{ removeOrganizationMember(\
+                     organizationId: {acme}, userId: {bob}) }}"
+                ),
+                &admin,
+            )
+            .await,
+        );
+
+        assert_eq!(added["addOrganizationMember"]["role"], "MEMBER");
+        assert_eq!(error_code(&promoted).as_deref(), Some("FORBIDDEN"));
+        assert_eq!(removed["removeOrganizationMember"], true);
+    }
+
+    #[sqlx::test]
+    async fn the_last_owner_stays(pool: PgPool) {
+        let schema = test_schema(pool.clone());
+        let owner = register_test_user(&schema, "ada@example.com").await;
+        let acme = create_test_org(&pool, "acme").await;
+        add_org_member(&pool, acme, "ada@example.com", "owner").await;
+        let ada = user_id(&pool, "ada@example.com").await;
+
+        let demoted =
+            authed_request(&schema, &set_member(acme, ada, "ADMIN"), &owner)
+                .await;
+        let removed = authed_request(
+            &schema,
+            &format!(
+                "mutation {{ removeOrganizationMember(\
+                 organizationId: {acme}, userId: {ada}) }}"
+            ),
+            &owner,
+        )
+        .await;
+
+        assert_eq!(error_code(&demoted).as_deref(), Some("CONFLICT"));
+        assert_eq!(error_code(&removed).as_deref(), Some("CONFLICT"));
+    }
+
+    fn invite(org_id: i64, email: &str, role: &str) -> String {
+        format!(
+            r#"mutation {{
+                inviteUserToOrganization(
+                    organizationId: {org_id}, email: "{email}", role: {role}
+                ) {{ outcome membership {{ role }} }}
+            }}"#
+        )
+    }
+
+    #[sqlx::test]
+    async fn inviting_a_known_email_adds_the_member(pool: PgPool) {
+        let schema = test_schema(pool.clone());
+        let owner = register_test_user(&schema, "ada@example.com").await;
+        register_test_user(&schema, "bob@example.com").await;
+        let acme = create_test_org(&pool, "acme").await;
+        add_org_member(&pool, acme, "ada@example.com", "owner").await;
+
+        let invited = data(
+            authed_request(
+                &schema,
+                &invite(acme, "bob@example.com", "ADMIN"),
+                &owner,
+            )
+            .await,
+        );
+
+        assert_eq!(
+            invited["inviteUserToOrganization"],
+            json!({ "outcome": "ADDED", "membership": { "role": "ADMIN" } })
+        );
+    }
+
+    /// Invite `email` to a new `acme` organization as its owner, then
+    /// register them; returns the schema and their token.
+    async fn invited_newcomer(pool: &PgPool) -> (AppSchema, String) {
+        let schema = test_schema(pool.clone());
+        let owner = register_test_user(&schema, "ada@example.com").await;
+        let acme = create_test_org(pool, "acme").await;
+        add_org_member(pool, acme, "ada@example.com", "owner").await;
+
+        let invited = data(
+            authed_request(
+                &schema,
+                &invite(acme, "new@example.com", "MEMBER"),
+                &owner,
+            )
+            .await,
+        );
+        assert_eq!(
+            invited["inviteUserToOrganization"],
+            json!({ "outcome": "INVITED", "membership": null })
+        );
+        let newcomer = register_test_user(&schema, "new@example.com").await;
+        (schema, newcomer)
+    }
+
+    async fn my_invitation_ids(schema: &AppSchema, token: &str) -> Vec<i64> {
+        let mine = data(
+            authed_request(schema, "{ myInvitations { id } }", token).await,
+        );
+        mine["myInvitations"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|i| i["id"].as_i64().unwrap())
+            .collect()
+    }
+
+    async fn my_org_slugs(schema: &AppSchema, token: &str) -> Value {
+        let mine = data(
+            authed_request(
+                schema,
+                "{ myOrganizations { role organization { slug } } }",
+                token,
+            )
+            .await,
+        );
+        mine["myOrganizations"].clone()
+    }
+
+    #[sqlx::test]
+    async fn invited_users_join_once_they_accept(pool: PgPool) {
+        let (schema, newcomer) = invited_newcomer(&pool).await;
+        assert_eq!(my_org_slugs(&schema, &newcomer).await, json!([]));
+
+        let ids = my_invitation_ids(&schema, &newcomer).await;
+        let accepted = data(
+            authed_request(
+                &schema,
+                &format!(
+                    "mutation {{ acceptInvitation(id: {}) {{ role }} }}",
+                    ids[0]
+                ),
+                &newcomer,
+            )
+            .await,
+        );
+
+        assert_eq!(ids.len(), 1);
+        assert_eq!(accepted["acceptInvitation"]["role"], "MEMBER");
+        assert_eq!(
+            my_org_slugs(&schema, &newcomer).await,
+            json!([{ "role": "MEMBER", "organization": { "slug": "acme" } }])
+        );
+        assert!(my_invitation_ids(&schema, &newcomer).await.is_empty());
+    }
+
+    #[sqlx::test]
+    async fn declined_invitations_are_gone(pool: PgPool) {
+        let (schema, newcomer) = invited_newcomer(&pool).await;
+        let id = my_invitation_ids(&schema, &newcomer).await[0];
+        let decline = format!("mutation {{ declineInvitation(id: {id}) }}");
+
+        let first = data(authed_request(&schema, &decline, &newcomer).await);
+        let second = data(authed_request(&schema, &decline, &newcomer).await);
+
+        assert_eq!(first["declineInvitation"], true);
+        assert_eq!(second["declineInvitation"], false);
+        assert!(my_invitation_ids(&schema, &newcomer).await.is_empty());
+        assert_eq!(my_org_slugs(&schema, &newcomer).await, json!([]));
+    }
+
+    #[sqlx::test]
+    async fn expired_or_foreign_invitations_cannot_be_accepted(pool: PgPool) {
+        let (schema, newcomer) = invited_newcomer(&pool).await;
+        let other = register_test_user(&schema, "eve@example.com").await;
+        let id = my_invitation_ids(&schema, &newcomer).await[0];
+        let accept =
+            format!("mutation {{ acceptInvitation(id: {id}) {{ role }} }}");
+
+        let foreign = authed_request(&schema, &accept, &other).await;
+        sqlx::query(
+            "UPDATE organization_invites \
+             SET expires_at = NOW() - INTERVAL '1 minute'",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        let expired = authed_request(&schema, &accept, &newcomer).await;
+
+        assert!(!foreign.errors.is_empty());
+        assert!(!expired.errors.is_empty());
+        assert!(my_invitation_ids(&schema, &newcomer).await.is_empty());
+        assert_eq!(my_org_slugs(&schema, &newcomer).await, json!([]));
+    }
+
+    #[sqlx::test]
+    async fn members_cannot_invite(pool: PgPool) {
+        let schema = test_schema(pool.clone());
+        let member = register_test_user(&schema, "bob@example.com").await;
+        let acme = create_test_org(&pool, "acme").await;
+        add_org_member(&pool, acme, "bob@example.com", "member").await;
+
+        let response = authed_request(
+            &schema,
+            &invite(acme, "new@example.com", "MEMBER"),
+            &member,
+        )
+        .await;
+
+        assert_eq!(error_code(&response).as_deref(), Some("FORBIDDEN"));
+    }
+
+    fn set_team_member(team_id: i64, user_id: i64, role: &str) -> String {
+        format!(
+            "mutation {{ addTeamMember(teamId: {team_id}, \
+             userId: {user_id}, role: {role}) {{ role }} }}"
+        )
+    }
+
+    #[sqlx::test]
+    async fn maintainers_manage_members_but_not_leads(pool: PgPool) {
+        let schema = test_schema(pool.clone());
+        let admin = register_test_user(&schema, "ada@example.com").await;
+        let maintainer = register_test_user(&schema, "bob@example.com").await;
+        register_test_user(&schema, "cy@example.com").await;
+        let acme = create_test_org(&pool, "acme").await;
+        add_org_member(&pool, acme, "ada@example.com", "admin").await;
+        add_org_member(&pool, acme, "bob@example.com", "member").await;
+        add_org_member(&pool, acme, "cy@example.com", "member").await;
+        let web = create_test_team(&pool, acme, "web").await;
+        let bob = user_id(&pool, "bob@example.com").await;
+        let cy = user_id(&pool, "cy@example.com").await;
+
+        data(
+            authed_request(
+                &schema,
+                &set_team_member(web, bob, "MAINTAINER"),
+                &admin,
+            )
+            .await,
+        );
+        let added = data(
+            authed_request(
+                &schema,
+                &set_team_member(web, cy, "MEMBER"),
+                &maintainer,
+            )
+            .await,
+        );
+        let promoted = authed_request(
+            &schema,
+            &set_team_member(web, cy, "LEAD"),
+            &maintainer,
+        )
+        .await;
+
+        assert_eq!(added["addTeamMember"]["role"], "MEMBER");
+        assert_eq!(error_code(&promoted).as_deref(), Some("FORBIDDEN"));
+    }
+
+    #[sqlx::test]
+    async fn team_members_must_belong_to_the_organization(pool: PgPool) {
+        let schema = test_schema(pool.clone());
+        let admin = register_test_user(&schema, "ada@example.com").await;
+        let outsider = register_test_user(&schema, "eve@example.com").await;
+        let acme = create_test_org(&pool, "acme").await;
+        add_org_member(&pool, acme, "ada@example.com", "admin").await;
+        let web = create_test_team(&pool, acme, "web").await;
+        let eve = user_id(&pool, "eve@example.com").await;
+
+        let added = authed_request(
+            &schema,
+            &set_team_member(web, eve, "MEMBER"),
+            &admin,
+        )
+        .await;
+        let by_outsider = authed_request(
+            &schema,
+            &set_team_member(web, eve, "LEAD"),
+            &outsider,
+        )
+        .await;
+
+        assert_eq!(error_code(&added).as_deref(), Some("BAD_REQUEST"));
+        assert_eq!(error_code(&by_outsider).as_deref(), Some("FORBIDDEN"));
+    }
+
+    async fn succeeded_build(pool: &PgPool, app_id: i64) -> i64 {
+        sqlx::query_scalar(
+            r#"
+            INSERT INTO build_jobs (app_id, status, commit_sha, image_ref)
+            VALUES ($1, 'succeeded', 'abc123', 'registry/web:abc123')
+            RETURNING id
+            "#,
+        )
+        .bind(app_id)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+    }
+
+    fn promote(build_id: i64) -> String {
+        format!(
+            "mutation {{ promoteBuild(input: {{ buildId: {build_id} }}) \
+             {{ version status imageRef }} }}"
+        )
+    }
+
+    #[sqlx::test]
+    async fn promoting_a_build_requires_a_deploy_role(pool: PgPool) {
+        let schema = test_schema(pool.clone());
+        let viewer = register_test_user(&schema, "ada@example.com").await;
+        let outsider = register_test_user(&schema, "eve@example.com").await;
+        let acme = create_test_org(&pool, "acme").await;
+        let web = create_test_app(&pool, acme, "web").await;
+        add_app_member(&pool, web, "ada@example.com", "viewer").await;
+        let build = succeeded_build(&pool, web).await;
+
+        let by_viewer =
+            authed_request(&schema, &promote(build), &viewer).await;
+        let by_outsider =
+            authed_request(&schema, &promote(build), &outsider).await;
+
+        assert_eq!(error_code(&by_viewer).as_deref(), Some("FORBIDDEN"));
+        assert_eq!(error_code(&by_outsider).as_deref(), Some("FORBIDDEN"));
+        let releases: i64 =
+            sqlx::query_scalar("SELECT count(*) FROM releases")
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(releases, 0);
+    }
+
+    #[sqlx::test]
+    async fn deployers_promote_builds_to_built_releases(pool: PgPool) {
+        let schema = test_schema(pool.clone());
+        let token = register_test_user(&schema, "ada@example.com").await;
+        let acme = create_test_org(&pool, "acme").await;
+        let web = create_test_app(&pool, acme, "web").await;
+        add_app_member(&pool, web, "ada@example.com", "deployer").await;
+        let build = succeeded_build(&pool, web).await;
+
+        let promoted =
+            data(authed_request(&schema, &promote(build), &token).await);
+        let again = authed_request(&schema, &promote(build), &token).await;
+
+        let release = &promoted["promoteBuild"];
+        assert_eq!(release["version"], format!("build-{build}"));
+        assert_eq!(release["status"], "BUILT");
+        assert_eq!(release["imageRef"], "registry/web:abc123");
+        // the duplicate version fails and leaves no pending release behind
+        assert!(!again.errors.is_empty());
+        let pending: i64 = sqlx::query_scalar(
+            "SELECT count(*) FROM releases WHERE status = 'pending'",
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(pending, 0);
+    }
+
+    /// Log output of [`reset_request_logs`], shared with the test.
+    #[derive(Clone, Default)]
+    struct Captured(std::sync::Arc<std::sync::Mutex<Vec<u8>>>);
+
+    impl std::io::Write for Captured {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// What `requestPasswordReset` for `email` logs with the given flag.
+    async fn reset_request_logs(
+        pool: PgPool,
+        email: &str,
+        dev_log_reset_tokens: bool,
+    ) -> String {
+        let config = Config { dev_log_reset_tokens, ..test_config() };
+        let schema = build_schema(AppState {
+            config: Arc::new(config),
+            ..test_state(pool)
+        });
+        register_test_user(&schema, "ada@example.com").await;
+
+        let captured = Captured::default();
+        let writer = captured.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_ansi(false)
+            .with_writer(move || writer.clone())
+            .finish();
+        let _guard = tracing::subscriber::set_default(subscriber);
+        data(
+            request(
+                &schema,
+                &format!(
+                    r#"mutation {{ requestPasswordReset(email: "{email}") }}"#
+                ),
+            )
+            .await,
+        );
+
+        String::from_utf8(captured.0.lock().unwrap().clone()).unwrap()
+    }
+
+    #[sqlx::test]
+    async fn reset_tokens_stay_out_of_the_logs(pool: PgPool) {
+        let logs =
+            reset_request_logs(pool.clone(), " ada@example.com ", false).await;
+
+        let resets: i64 =
+            sqlx::query_scalar("SELECT count(*) FROM password_resets")
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(resets, 1);
+        assert!(logs.contains("password reset requested"), "{logs}");
+        assert!(!logs.contains("reset_token"), "{logs}");
+    }
+
+    #[sqlx::test]
+    async fn reset_tokens_are_logged_when_opted_in(pool: PgPool) {
+        let logs =
+            reset_request_logs(pool.clone(), "ada@example.com", true).await;
+
+        let token = logs
+            .split("reset_token=")
+            .nth(1)
+            .and_then(|rest| rest.split_whitespace().next())
+            .unwrap_or_else(|| panic!("no token in {logs}"));
+        let hash: String =
+            sqlx::query_scalar("SELECT token_hash FROM password_resets")
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(hash, hash_token(token));
+    }
+
+    #[sqlx::test]
+    async fn platform_admins_create_teams_anywhere(pool: PgPool) {
+        let schema = test_schema(pool.clone());
+        let acme = create_test_org(&pool, "acme").await;
+        let token = register_test_user(&schema, "root@example.com").await;
+        sqlx::query("UPDATE users SET is_admin = true")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let created = data(create_team(&schema, &token, acme).await);
+
+        assert_eq!(created["createTeam"]["organizationId"], acme);
+    }
+
+    fn transfer_team(team_id: i64, target: i64) -> String {
+        format!(
+            "mutation {{ transferTeam(teamId: {team_id}, \
+             targetOrganizationId: {target}) {{ organizationId }} }}"
+        )
+    }
+
+    #[sqlx::test]
+    async fn transferring_a_team_needs_admin_on_both_sides(pool: PgPool) {
+        let schema = test_schema(pool.clone());
+        let token = register_test_user(&schema, "ada@example.com").await;
+        let acme = create_test_org(&pool, "acme").await;
+        let globex = create_test_org(&pool, "globex").await;
+        add_org_member(&pool, acme, "ada@example.com", "admin").await;
+        add_org_member(&pool, globex, "ada@example.com", "member").await;
+        let web = create_test_team(&pool, acme, "web").await;
+
+        let refused =
+            authed_request(&schema, &transfer_team(web, globex), &token).await;
+        sqlx::query("UPDATE organization_memberships SET role = 'admin'")
+            .execute(&pool)
+            .await
+            .unwrap();
+        let moved = data(
+            authed_request(&schema, &transfer_team(web, globex), &token).await,
+        );
+
+        assert_eq!(error_code(&refused).as_deref(), Some("FORBIDDEN"));
+        assert_eq!(moved["transferTeam"]["organizationId"], globex);
+    }
+
+    #[sqlx::test]
+    async fn only_owners_restore_deleted_organizations(pool: PgPool) {
+        let schema = test_schema(pool.clone());
+        let owner = register_test_user(&schema, "ada@example.com").await;
+        let admin = register_test_user(&schema, "bob@example.com").await;
+        let acme = create_test_org(&pool, "acme").await;
+        add_org_member(&pool, acme, "ada@example.com", "owner").await;
+        add_org_member(&pool, acme, "bob@example.com", "admin").await;
+        sqlx::query("UPDATE organizations SET deleted_at = NOW()")
+            .execute(&pool)
+            .await
+            .unwrap();
+        let restore = format!(
+            "mutation {{ restoreOrganization(id: {acme}) {{ slug }} }}"
+        );
+
+        let by_admin = authed_request(&schema, &restore, &admin).await;
+        let by_owner = data(authed_request(&schema, &restore, &owner).await);
+
+        assert_eq!(error_code(&by_admin).as_deref(), Some("FORBIDDEN"));
+        assert_eq!(by_owner["restoreOrganization"]["slug"], "acme");
+    }
+
+    #[sqlx::test]
+    async fn stale_organization_updates_conflict(pool: PgPool) {
+        let schema = test_schema(pool.clone());
+        let admin = register_test_user(&schema, "ada@example.com").await;
+        let acme = create_test_org(&pool, "acme").await;
+        add_org_member(&pool, acme, "ada@example.com", "admin").await;
+        let web = create_test_team(&pool, acme, "web").await;
+        let seen = data(
+            authed_request(
+                &schema,
+                &format!(
+                    "{{ organization(id: {acme}) {{ updatedAt }} \
+                     team(id: {web}) {{ updatedAt }} }}"
+                ),
+                &admin,
+            )
+            .await,
+        );
+        let update = format!(
+            r#"mutation {{
+                updateOrganization(input: {{
+                    id: {acme}, name: "Acme", expectedUpdatedAt: {org_seen}
+                }}) {{ name }}
+                updateTeam(input: {{
+                    id: {web}, name: "Web", expectedUpdatedAt: {team_seen}
+                }}) {{ name }}
+            }}"#,
+            org_seen = seen["organization"]["updatedAt"],
+            team_seen = seen["team"]["updatedAt"],
+        );
+
+        let first = data(authed_request(&schema, &update, &admin).await);
+        let second = authed_request(&schema, &update, &admin).await;
+
+        assert_eq!(
+            first,
+            json!({
+                "updateOrganization": { "name": "Acme" },
+                "updateTeam": { "name": "Web" },
+            })
+        );
+        assert_eq!(error_code(&second).as_deref(), Some("CONFLICT"));
+        assert_eq!(second.errors.len(), 2);
+    }
+
+    #[sqlx::test]
+    async fn emails_are_matched_case_insensitively(pool: PgPool) {
+        let schema = test_schema(pool.clone());
+        let token = register_test_user(&schema, " Ada@Example.COM ").await;
+        let acme = create_test_org(&pool, "acme").await;
+        add_org_member(&pool, acme, "ada@example.com", "owner").await;
+
+        let login = data(
+            request(&schema, &authenticate("ADA@example.com", TEST_PASSWORD))
+                .await,
+        );
+        let invited = data(
+            authed_request(
+                &schema,
+                &invite(acme, " Bob@Example.com", "MEMBER"),
+                &token,
+            )
+            .await,
+        );
+        let bob = register_test_user(&schema, "BOB@example.com").await;
+        let invitations = data(
+            authed_request(&schema, "{ myInvitations { role } }", &bob).await,
+        );
+
+        assert_eq!(login["authenticate"]["user"]["email"], "ada@example.com");
+        assert_eq!(invited["inviteUserToOrganization"]["outcome"], "INVITED");
+        assert_eq!(
+            invitations["myInvitations"],
+            json!([{ "role": "MEMBER" }])
+        );
+        let emails: Vec<String> =
+            sqlx::query_scalar("SELECT email FROM users ORDER BY id")
+                .fetch_all(&pool)
+                .await
+                .unwrap();
+        assert_eq!(emails, ["ada@example.com", "bob@example.com"]);
+    }
+
+    #[sqlx::test]
+    async fn accounts_stored_with_mixed_case_can_still_log_in(pool: PgPool) {
+        let schema = test_schema(pool.clone());
+        register_test_user(&schema, "ada@example.com").await;
+        sqlx::query("UPDATE users SET email = 'Ada@Example.com'")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let login = data(
+            request(&schema, &authenticate("ada@example.com", TEST_PASSWORD))
+                .await,
+        );
+
+        assert_eq!(login["authenticate"]["user"]["email"], "Ada@Example.com");
+    }
 }
@@ -0,0 +1,81 @@
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+
+/// Object types that can be fetched through `Query.node`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeKind {
+    Organization,
+    Team,
+    App,
+}
+
+impl NodeKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            NodeKind::Organization => "Organization",
+            NodeKind::Team => "Team",
+            NodeKind::App => "App",
+        }
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "Organization" => Some(NodeKind::Organization),
+            "Team" => Some(NodeKind::Team),
+            "App" => Some(NodeKind::App),
+            _ => None,
+        }
+    }
+}
+
+/// Opaque global id: `base64("<Type>:<id>")`.
+pub fn encode_global_id(kind: NodeKind, id: i64) -> String {
+    BASE64.encode(format!("{}:{id}", kind.as_str()))
+}
+
+/// Decode a global id; `None` if it is malformed or of an unknown type.
+pub fn decode_global_id(global_id: &str) -> Option<(NodeKind, i64)> {
+    let bytes = BASE64.decode(global_id).ok()?;
+    let raw = String::from_utf8(bytes).ok()?;
+    let (kind, id) = raw.split_once(':')?;
+
+    Some((NodeKind::parse(kind)?, id.parse().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_a_valid_global_id() {
+        assert_eq!(encode_global_id(NodeKind::App, 123), "QXBwOjEyMw==");
+        assert_eq!(
+            decode_global_id("QXBwOjEyMw=="),
+            Some((NodeKind::App, 123))
+        );
+    }
+
+    #[test]
+    fn round_trips_every_kind() {
+        for kind in [NodeKind::Organization, NodeKind::Team, NodeKind::App] {
+            let id = encode_global_id(kind, 42);
+            assert_eq!(decode_global_id(&id), Some((kind, 42)));
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_global_ids() {
+        for raw in [
+            "",
+            "not base64!",
+            "App:123",
+            &BASE64.encode("App123"),
+            &BASE64.encode("App:abc"),
+            &BASE64.encode("App:"),
+            &BASE64.encode("User:1"),
+            &BASE64.encode([0xff, 0xfe]),
+        ] {
+            assert_eq!(decode_global_id(raw), None, "{raw:?} accepted");
+        }
+    }
+}
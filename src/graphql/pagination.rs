@@ -0,0 +1,100 @@
+use async_graphql::Result as GqlResult;
+
+use crate::graphql::validation::ValidationErrors;
+
+/// Page size used when a list query gets no `limit`.
+pub const DEFAULT_PAGE_SIZE: i64 = 20;
+
+/// Hard cap for any list query; larger requests are clamped.
+pub const MAX_PAGE_SIZE: i64 = 100;
+
+/// Effective page size for a `limit` argument.
+///
+/// Missing -> [`DEFAULT_PAGE_SIZE`], above the cap -> [`MAX_PAGE_SIZE`],
+/// zero or negative -> `BAD_USER_INPUT`.
+pub fn page_limit(field: &str, requested: Option<i64>) -> GqlResult<i64> {
+    let Some(limit) = requested else {
+        return Ok(DEFAULT_PAGE_SIZE);
+    };
+
+    if limit < 1 {
+        let mut errors = ValidationErrors::new();
+        errors.add(field, "must be at least 1");
+        errors.into_result()?;
+    }
+
+    Ok(limit.min(MAX_PAGE_SIZE))
+}
+
+/// Validated `offset` argument: negative -> `BAD_USER_INPUT`.
+pub fn page_offset(field: &str, offset: i64) -> GqlResult<i64> {
+    if offset < 0 {
+        let mut errors = ValidationErrors::new();
+        errors.add(field, "must not be negative");
+        errors.into_result()?;
+    }
+
+    Ok(offset)
+}
+
+#[cfg(test)]
+mod tests {
+    use async_graphql::Value;
+
+    use super::*;
+
+    #[test]
+    fn missing_limit_uses_the_default() {
+        assert_eq!(page_limit("limit", None).unwrap(), DEFAULT_PAGE_SIZE);
+    }
+
+    #[test]
+    fn limits_up_to_the_cap_are_kept() {
+        assert_eq!(page_limit("limit", Some(1)).unwrap(), 1);
+        assert_eq!(
+            page_limit("limit", Some(MAX_PAGE_SIZE)).unwrap(),
+            MAX_PAGE_SIZE
+        );
+    }
+
+    #[test]
+    fn over_cap_limits_are_clamped() {
+        assert_eq!(
+            page_limit("limit", Some(MAX_PAGE_SIZE + 1)).unwrap(),
+            MAX_PAGE_SIZE
+        );
+        assert_eq!(
+            page_limit("limit", Some(1_000_000)).unwrap(),
+            MAX_PAGE_SIZE
+        );
+    }
+
+    #[test]
+    fn non_positive_limits_are_rejected() {
+        for limit in [0, -1, i64::MIN] {
+            let error = page_limit("first", Some(limit)).unwrap_err();
+            let extensions = error.extensions.unwrap();
+
+            assert_eq!(
+                extensions.get("code"),
+                Some(&Value::String("BAD_USER_INPUT".into()))
+            );
+            let Some(Value::Object(fields)) = extensions.get("fields") else {
+                panic!("fields is not an object");
+            };
+            assert!(fields.contains_key("first"));
+        }
+    }
+
+    #[test]
+    fn offsets_must_not_be_negative() {
+        assert_eq!(page_offset("offset", 0).unwrap(), 0);
+        assert_eq!(page_offset("offset", 40).unwrap(), 40);
+
+        let error = page_offset("offset", -1).unwrap_err();
+        assert_eq!(
+            error.extensions.unwrap().get("code"),
+            Some(&Value::String("BAD_USER_INPUT".into()))
+        );
+    }
+}
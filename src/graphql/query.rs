@@ -1,11 +1,56 @@
-use async_graphql::{Context, Object, Result as GqlResult};
+use std::collections::BTreeMap;
 
+use async_graphql::{Context, ID, Object, Result as GqlResult};
+use time::OffsetDateTime;
+
+use crate::domain::models::{AppRole, OrgRole, SecretKind, User};
+use crate::domain::secrets::resolve_secrets;
+use crate::graphql::auth_helpers::{
+    ANY_ORG_ROLE, DELETE_APP_ROLES, DEPLOY_ROLES, MANAGE_MEMBERS_ROLES,
+    MANAGE_SECRETS_ROLES, effective_app_role, get_current_user, has_app_role,
+    has_org_role,
+};
+use crate::graphql::errors::forbidden;
+use crate::graphql::node::{NodeKind, decode_global_id};
+use crate::graphql::pagination::{page_limit, page_offset};
 use crate::graphql::state::AppState;
-use crate::graphql::types::{OrganizationGql, TeamGql};
+use crate::graphql::types::{
+    AppGql, AppPermissionsGql, AppSecretEntryGql, AppSecretVersionGql,
+    BuildJobGql, BuildLogGql, DeployGql, MyOrganizationGql, NodeGql,
+    OrganizationGql, OrganizationInvitationGql, OrganizationMembershipGql,
+    ReleaseGql, ResolvedSecretGql, SearchResultGql, SecretKindGql, TeamGql,
+    TeamMembershipGql, UserGql,
+};
+use crate::graphql::validation::{ValidationErrors, normalize_email};
 use crate::infrastructure::repositories::{
-    OrganizationRepository, TeamRepository,
+    AppRepository, AppSecretRepository, BuildJobRepository,
+    BuildLogRepository, DeployRepository, OrganizationInviteRepository,
+    OrganizationMembershipRepository, OrganizationRepository,
+    ReleaseRepository, TeamMembershipRepository, TeamRepository,
 };
 
+/// Any role on an app is enough to read its builds.
+const ANY_APP_ROLE: &[AppRole] =
+    &[AppRole::Owner, AppRole::Maintainer, AppRole::Deployer, AppRole::Viewer];
+
+/// Whether `user` has any role on the (non-deleted) app `app_id`.
+async fn can_view_app(
+    ctx: &Context<'_>,
+    user: &User,
+    app_id: i64,
+) -> GqlResult<bool> {
+    let state = ctx.data::<AppState>()?;
+    let app = AppRepository::new(state.pool.clone())
+        .find_by_id(app_id)
+        .await
+        .map_err(|e| async_graphql::Error::new(e.to_string()))?;
+
+    match app {
+        Some(app) => has_app_role(ctx, user, &app, ANY_APP_ROLE).await,
+        None => Ok(false),
+    }
+}
+
 pub struct QueryRoot;
 
 #[Object]
@@ -15,11 +60,50 @@ impl QueryRoot {
         "1.0.0"
     }
 
+    /// The authenticated user.
+    async fn me(&self, ctx: &Context<'_>) -> GqlResult<UserGql> {
+        let current = get_current_user(ctx).await?;
+        Ok(current.user.into())
+    }
+
+    /// Organizations the current user is a member of, ordered by name,
+    /// with the user's role in each. Soft-deleted organizations are
+    /// skipped.
+    async fn my_organizations(
+        &self,
+        ctx: &Context<'_>,
+    ) -> GqlResult<Vec<MyOrganizationGql>> {
+        let current = get_current_user(ctx).await?;
+        let state = ctx.data::<AppState>()?;
+
+        let orgs = OrganizationRepository::new(state.pool.clone())
+            .list_for_user(current.user.id)
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?;
+
+        Ok(orgs
+            .into_iter()
+            .map(|(org, role)| MyOrganizationGql {
+                organization: org.into(),
+                role: role.into(),
+            })
+            .collect())
+    }
+
+    /// An organization by id.
+    ///
+    /// Returns `null` for organizations the current user does not belong
+    /// to, like `node`, so ids cannot be probed.
     async fn organization(
         &self,
         ctx: &Context<'_>,
         id: i64,
     ) -> GqlResult<Option<OrganizationGql>> {
+        let current = get_current_user(ctx).await?;
+        if !has_org_role(ctx, &current.user, id, ANY_ORG_ROLE).await? {
+            return Ok(None);
+        }
+
         let state = ctx.data::<AppState>()?;
         let repo = OrganizationRepository::new(state.pool.clone());
 
@@ -31,11 +115,117 @@ impl QueryRoot {
         Ok(org.map(Into::into))
     }
 
+    /// An organization by slug; `null` if there is none or the current
+    /// user does not belong to it.
+    async fn organization_by_slug(
+        &self,
+        ctx: &Context<'_>,
+        slug: String,
+    ) -> GqlResult<Option<OrganizationGql>> {
+        let current = get_current_user(ctx).await?;
+        let state = ctx.data::<AppState>()?;
+
+        let Some(org) = OrganizationRepository::new(state.pool.clone())
+            .find_by_slug(&slug)
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?
+        else {
+            return Ok(None);
+        };
+        if !has_org_role(ctx, &current.user, org.id, ANY_ORG_ROLE).await? {
+            return Ok(None);
+        }
+
+        Ok(Some(org.into()))
+    }
+
+    /// A team by slug within an organization; `null` if there is none or
+    /// the current user does not belong to the organization.
+    async fn team_by_slug(
+        &self,
+        ctx: &Context<'_>,
+        organization_id: i64,
+        slug: String,
+    ) -> GqlResult<Option<TeamGql>> {
+        let current = get_current_user(ctx).await?;
+        if !has_org_role(ctx, &current.user, organization_id, ANY_ORG_ROLE)
+            .await?
+        {
+            return Ok(None);
+        }
+
+        let state = ctx.data::<AppState>()?;
+        let team = TeamRepository::new(state.pool.clone())
+            .find_by_slug(organization_id, &slug)
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?;
+
+        Ok(team.map(Into::into))
+    }
+
+    /// An app by slug within an organization; `null` if there is none or
+    /// the current user does not belong to the organization.
+    async fn app_by_slug(
+        &self,
+        ctx: &Context<'_>,
+        organization_id: i64,
+        slug: String,
+    ) -> GqlResult<Option<AppGql>> {
+        let current = get_current_user(ctx).await?;
+        if !has_org_role(ctx, &current.user, organization_id, ANY_ORG_ROLE)
+            .await?
+        {
+            return Ok(None);
+        }
+
+        let state = ctx.data::<AppState>()?;
+        let app = AppRepository::new(state.pool.clone())
+            .find_by_slug(organization_id, &slug)
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?;
+
+        Ok(app.map(Into::into))
+    }
+
+    /// A team by organization slug + team slug, in one round-trip; `null`
+    /// if either slug doesn't resolve or the current user does not belong
+    /// to the organization.
+    async fn team_by_slug_path(
+        &self,
+        ctx: &Context<'_>,
+        organization_slug: String,
+        team_slug: String,
+    ) -> GqlResult<Option<TeamGql>> {
+        let current = get_current_user(ctx).await?;
+        let state = ctx.data::<AppState>()?;
+
+        let Some(org) = OrganizationRepository::new(state.pool.clone())
+            .find_by_slug(&organization_slug)
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?
+        else {
+            return Ok(None);
+        };
+        if !has_org_role(ctx, &current.user, org.id, ANY_ORG_ROLE).await? {
+            return Ok(None);
+        }
+
+        let team = TeamRepository::new(state.pool.clone())
+            .find_by_slug(org.id, &team_slug)
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?;
+
+        Ok(team.map(Into::into))
+    }
+
+    /// A team by id; `null` unless the current user belongs to its
+    /// organization.
     async fn team(
         &self,
         ctx: &Context<'_>,
         id: i64,
     ) -> GqlResult<Option<TeamGql>> {
+        let current = get_current_user(ctx).await?;
         let state = ctx.data::<AppState>()?;
         let repo = TeamRepository::new(state.pool.clone());
 
@@ -44,6 +234,1839 @@ impl QueryRoot {
             .await
             .map_err(|e| async_graphql::Error::new(e.to_string()))?;
 
-        Ok(team.map(Into::into))
+        let Some(team) = team else {
+            return Ok(None);
+        };
+        if !has_org_role(
+            ctx,
+            &current.user,
+            team.organization_id,
+            ANY_ORG_ROLE,
+        )
+        .await?
+        {
+            return Ok(None);
+        }
+
+        Ok(Some(team.into()))
+    }
+
+    /// Unexpired invitations sent to the current user's email, newest
+    /// first.
+    async fn my_invitations(
+        &self,
+        ctx: &Context<'_>,
+    ) -> GqlResult<Vec<OrganizationInvitationGql>> {
+        let current = get_current_user(ctx).await?;
+        let state = ctx.data::<AppState>()?;
+
+        let invites = OrganizationInviteRepository::new(state.pool.clone())
+            .list_pending_for_email(&normalize_email(&current.user.email))
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?;
+
+        Ok(invites.into_iter().map(Into::into).collect())
+    }
+
+    /// Members of an organization with their roles, oldest first.
+    /// Members only.
+    async fn organization_members(
+        &self,
+        ctx: &Context<'_>,
+        organization_id: i64,
+    ) -> GqlResult<Vec<OrganizationMembershipGql>> {
+        let current = get_current_user(ctx).await?;
+        if !has_org_role(ctx, &current.user, organization_id, ANY_ORG_ROLE)
+            .await?
+        {
+            return Err(async_graphql::Error::new(
+                "Not a member of this organization",
+            ));
+        }
+
+        let state = ctx.data::<AppState>()?;
+        let members =
+            OrganizationMembershipRepository::new(state.pool.clone())
+                .list_by_organization(organization_id)
+                .await
+                .map_err(|e| async_graphql::Error::new(e.to_string()))?;
+
+        Ok(members.into_iter().map(Into::into).collect())
+    }
+
+    /// Members of a team with their roles, oldest first. Members of the
+    /// team's organization only.
+    async fn team_members(
+        &self,
+        ctx: &Context<'_>,
+        team_id: i64,
+    ) -> GqlResult<Vec<TeamMembershipGql>> {
+        let current = get_current_user(ctx).await?;
+        let state = ctx.data::<AppState>()?;
+
+        let team = TeamRepository::new(state.pool.clone())
+            .find_by_id(team_id)
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?
+            .ok_or_else(|| async_graphql::Error::new("Team not found"))?;
+        if !has_org_role(
+            ctx,
+            &current.user,
+            team.organization_id,
+            ANY_ORG_ROLE,
+        )
+        .await?
+        {
+            return Err(async_graphql::Error::new("Team not found"));
+        }
+
+        let members = TeamMembershipRepository::new(state.pool.clone())
+            .list_by_team(team.id)
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?;
+
+        Ok(members.into_iter().map(Into::into).collect())
+    }
+
+    /// Teams of an organization, ordered by name.
+    ///
+    /// `includeDeleted` also returns soft-deleted teams (for recovery) and
+    /// is restricted to organization owners/admins. `limit` defaults to 20
+    /// (capped at 100).
+    async fn teams(
+        &self,
+        ctx: &Context<'_>,
+        organization_id: i64,
+        #[graphql(default = false)] include_deleted: bool,
+        limit: Option<i64>,
+        #[graphql(default = 0)] offset: i64,
+    ) -> GqlResult<Vec<TeamGql>> {
+        let current = get_current_user(ctx).await?;
+        let limit = page_limit("limit", limit)?;
+        let offset = page_offset("offset", offset)?;
+
+        let required: &[OrgRole] = if include_deleted {
+            &[OrgRole::Owner, OrgRole::Admin]
+        } else {
+            ANY_ORG_ROLE
+        };
+        if !has_org_role(ctx, &current.user, organization_id, required).await?
+        {
+            return Err(async_graphql::Error::new(if include_deleted {
+                "Only organization owners/admins can list deleted teams"
+            } else {
+                "Not a member of this organization"
+            }));
+        }
+
+        let state = ctx.data::<AppState>()?;
+        let teams = TeamRepository::new(state.pool.clone())
+            .list_page_by_organization(
+                organization_id,
+                include_deleted,
+                limit,
+                offset,
+            )
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?;
+
+        Ok(teams.into_iter().map(Into::into).collect())
+    }
+
+    /// Apps of an organization, ordered by name.
+    ///
+    /// `limit` defaults to 20 (capped at 100).
+    async fn apps(
+        &self,
+        ctx: &Context<'_>,
+        organization_id: i64,
+        limit: Option<i64>,
+        #[graphql(default = 0)] offset: i64,
+    ) -> GqlResult<Vec<AppGql>> {
+        let current = get_current_user(ctx).await?;
+        let limit = page_limit("limit", limit)?;
+        let offset = page_offset("offset", offset)?;
+        if !has_org_role(ctx, &current.user, organization_id, ANY_ORG_ROLE)
+            .await?
+        {
+            return Err(async_graphql::Error::new(
+                "Not a member of this organization",
+            ));
+        }
+
+        let state = ctx.data::<AppState>()?;
+        let apps = AppRepository::new(state.pool.clone())
+            .list_page_by_organization(organization_id, limit, offset)
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?;
+
+        Ok(apps.into_iter().map(Into::into).collect())
+    }
+
+    /// Soft-deleted organizations, for recovery. Platform admins only.
+    async fn deleted_organizations(
+        &self,
+        ctx: &Context<'_>,
+    ) -> GqlResult<Vec<OrganizationGql>> {
+        let current = get_current_user(ctx).await?;
+        if !current.user.is_admin {
+            return Err(forbidden(
+                "Only platform admins can list deleted organizations",
+            ));
+        }
+
+        let state = ctx.data::<AppState>()?;
+        let repo = OrganizationRepository::new(state.pool.clone());
+
+        let orgs = repo
+            .list_deleted()
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?;
+
+        Ok(orgs.into_iter().map(Into::into).collect())
+    }
+
+    /// Search organizations, teams and apps by name or slug.
+    ///
+    /// Only resources in organizations the current user belongs to are
+    /// returned, at most `limit` of each kind (organizations first; default
+    /// 20, capped at 100).
+    async fn search(
+        &self,
+        ctx: &Context<'_>,
+        term: String,
+        limit: Option<i64>,
+    ) -> GqlResult<Vec<SearchResultGql>> {
+        let current = get_current_user(ctx).await?;
+
+        let term = term.trim();
+        let mut errors = ValidationErrors::new();
+        if term.is_empty() {
+            errors.add("term", "must not be empty");
+        }
+        errors.into_result()?;
+        let limit = page_limit("limit", limit)?;
+
+        let state = ctx.data::<AppState>()?;
+        let user_id = current.user.id;
+
+        let orgs = OrganizationRepository::new(state.pool.clone())
+            .search_for_user(user_id, term, limit)
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?;
+        let teams = TeamRepository::new(state.pool.clone())
+            .search_for_user(user_id, term, limit)
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?;
+        let apps = AppRepository::new(state.pool.clone())
+            .search_for_user(user_id, term, limit)
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?;
+
+        let results = orgs
+            .into_iter()
+            .map(|o| SearchResultGql::Organization(o.into()))
+            .chain(teams.into_iter().map(|t| SearchResultGql::Team(t.into())))
+            .chain(apps.into_iter().map(|a| SearchResultGql::App(a.into())))
+            .collect();
+
+        Ok(results)
+    }
+
+    /// Secrets of an app environment, ordered by key.
+    ///
+    /// Any member of the app may list them; values are masked unless the
+    /// caller may manage the app's secrets (owners/maintainers).
+    async fn app_secrets(
+        &self,
+        ctx: &Context<'_>,
+        app_id: i64,
+        #[graphql(default_with = "\"default\".to_string()")]
+        environment: String,
+    ) -> GqlResult<Vec<AppSecretEntryGql>> {
+        let current = get_current_user(ctx).await?;
+        let state = ctx.data::<AppState>()?;
+
+        let app = AppRepository::new(state.pool.clone())
+            .find_by_id(app_id)
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?
+            .ok_or_else(|| async_graphql::Error::new("App not found"))?;
+
+        let Some(role) = effective_app_role(ctx, &current.user, &app).await?
+        else {
+            return Err(async_graphql::Error::new(
+                "Not allowed to read secrets of this app",
+            ));
+        };
+        let reveal = MANAGE_SECRETS_ROLES.contains(&role);
+
+        let secrets =
+            AppSecretRepository::new(state.pool.clone(), state.cipher.clone())
+                .list_by_app_env(app.id, &environment)
+                .await
+                .map_err(|e| async_graphql::Error::new(e.to_string()))?;
+
+        Ok(secrets
+            .into_iter()
+            .map(|s| AppSecretEntryGql::new(s, reveal))
+            .collect())
+    }
+
+    /// Secrets of an app environment with `$KEY`/`${KEY}` references to
+    /// other keys of the same app + environment expanded.
+    ///
+    /// Stored values are left untouched. Undefined references and cycles
+    /// are reported as errors. `FILE` secrets are returned verbatim and
+    /// cannot be referenced.
+    ///
+    /// Like `appSecrets`, values are only readable with a role allowed to
+    /// manage the app's secrets.
+    async fn get_resolved_app_secrets(
+        &self,
+        ctx: &Context<'_>,
+        app_id: i64,
+        environment: String,
+    ) -> GqlResult<Vec<ResolvedSecretGql>> {
+        let current = get_current_user(ctx).await?;
+        let state = ctx.data::<AppState>()?;
+
+        let app = AppRepository::new(state.pool.clone())
+            .find_by_id(app_id)
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?
+            .ok_or_else(|| async_graphql::Error::new("App not found"))?;
+
+        if !has_app_role(ctx, &current.user, &app, MANAGE_SECRETS_ROLES)
+            .await?
+        {
+            return Err(forbidden("Not allowed to read secrets of this app"));
+        }
+
+        let (files, strings): (Vec<_>, Vec<_>) =
+            AppSecretRepository::new(state.pool.clone(), state.cipher.clone())
+                .list_by_app_env(app.id, &environment)
+                .await
+                .map_err(|e| async_graphql::Error::new(e.to_string()))?
+                .into_iter()
+                .partition(|s| s.kind == SecretKind::File);
+
+        let raw: BTreeMap<String, String> =
+            strings.into_iter().map(|s| (s.key, s.value)).collect();
+        let resolved = resolve_secrets(&raw)
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?;
+
+        let mut secrets: Vec<ResolvedSecretGql> = resolved
+            .into_iter()
+            .map(|(key, value)| ResolvedSecretGql {
+                key,
+                value,
+                kind: SecretKindGql::String,
+            })
+            .chain(files.into_iter().map(|s| ResolvedSecretGql {
+                key: s.key,
+                value: s.value,
+                kind: SecretKindGql::File,
+            }))
+            .collect();
+        secrets.sort_by(|a, b| a.key.cmp(&b.key));
+
+        Ok(secrets)
+    }
+
+    /// Previous values of a secret, newest first.
+    ///
+    /// Readable by users who may manage the app's secrets; values are only
+    /// returned to organization owners/admins.
+    async fn app_secret_history(
+        &self,
+        ctx: &Context<'_>,
+        app_id: i64,
+        environment: String,
+        key: String,
+    ) -> GqlResult<Vec<AppSecretVersionGql>> {
+        let current = get_current_user(ctx).await?;
+        let state = ctx.data::<AppState>()?;
+
+        let app = AppRepository::new(state.pool.clone())
+            .find_by_id(app_id)
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?
+            .ok_or_else(|| async_graphql::Error::new("App not found"))?;
+
+        if !has_app_role(ctx, &current.user, &app, MANAGE_SECRETS_ROLES)
+            .await?
+        {
+            return Err(async_graphql::Error::new(
+                "Not allowed to read secrets of this app",
+            ));
+        }
+        let reveal = has_org_role(
+            ctx,
+            &current.user,
+            app.organization_id,
+            &[OrgRole::Owner, OrgRole::Admin],
+        )
+        .await?;
+
+        let versions =
+            AppSecretRepository::new(state.pool.clone(), state.cipher.clone())
+                .list_versions(app.id, &environment, &key)
+                .await
+                .map_err(|e| async_graphql::Error::new(e.to_string()))?;
+
+        Ok(versions
+            .into_iter()
+            .map(|v| AppSecretVersionGql::new(v, reveal))
+            .collect())
+    }
+
+    /// What the current user may do on an app, for showing/hiding actions.
+    ///
+    /// All flags are `false` when the user has no access to the app.
+    async fn permissions(
+        &self,
+        ctx: &Context<'_>,
+        app_id: i64,
+    ) -> GqlResult<AppPermissionsGql> {
+        let current = get_current_user(ctx).await?;
+        let state = ctx.data::<AppState>()?;
+
+        let app = AppRepository::new(state.pool.clone())
+            .find_by_id(app_id)
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?
+            .ok_or_else(|| async_graphql::Error::new("App not found"))?;
+
+        let role = effective_app_role(ctx, &current.user, &app).await?;
+        let can = |roles: &[AppRole]| role.is_some_and(|r| roles.contains(&r));
+
+        Ok(AppPermissionsGql {
+            can_deploy: can(DEPLOY_ROLES),
+            can_manage_secrets: can(MANAGE_SECRETS_ROLES),
+            can_manage_members: can(MANAGE_MEMBERS_ROLES),
+            can_delete: can(DELETE_APP_ROLES),
+        })
+    }
+
+    /// Fetch an organization, team or app by its opaque `globalId`.
+    ///
+    /// Returns `null` for malformed ids, unknown objects and objects in
+    /// organizations the current user does not belong to.
+    async fn node(
+        &self,
+        ctx: &Context<'_>,
+        global_id: ID,
+    ) -> GqlResult<Option<NodeGql>> {
+        let current = get_current_user(ctx).await?;
+        let Some((kind, id)) = decode_global_id(&global_id) else {
+            return Ok(None);
+        };
+
+        let state = ctx.data::<AppState>()?;
+        let (organization_id, node) = match kind {
+            NodeKind::Organization => {
+                let org = OrganizationRepository::new(state.pool.clone())
+                    .find_by_id(id)
+                    .await
+                    .map_err(|e| async_graphql::Error::new(e.to_string()))?;
+                match org {
+                    Some(org) => (org.id, NodeGql::Organization(org.into())),
+                    None => return Ok(None),
+                }
+            }
+            NodeKind::Team => {
+                let team = TeamRepository::new(state.pool.clone())
+                    .find_by_id(id)
+                    .await
+                    .map_err(|e| async_graphql::Error::new(e.to_string()))?;
+                match team {
+                    Some(team) => {
+                        (team.organization_id, NodeGql::Team(team.into()))
+                    }
+                    None => return Ok(None),
+                }
+            }
+            NodeKind::App => {
+                let app = AppRepository::new(state.pool.clone())
+                    .find_by_id(id)
+                    .await
+                    .map_err(|e| async_graphql::Error::new(e.to_string()))?;
+                match app {
+                    Some(app) => {
+                        (app.organization_id, NodeGql::App(app.into()))
+                    }
+                    None => return Ok(None),
+                }
+            }
+        };
+
+        if !has_org_role(ctx, &current.user, organization_id, ANY_ORG_ROLE)
+            .await?
+        {
+            return Ok(None);
+        }
+
+        Ok(Some(node))
+    }
+
+    /// A release by id; `null` if it does not exist or the current user
+    /// has no role on its app.
+    async fn release(
+        &self,
+        ctx: &Context<'_>,
+        id: i64,
+    ) -> GqlResult<Option<ReleaseGql>> {
+        let current = get_current_user(ctx).await?;
+        let state = ctx.data::<AppState>()?;
+
+        let Some(release) = ReleaseRepository::new(state.pool.clone())
+            .find_by_id(id)
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?
+        else {
+            return Ok(None);
+        };
+
+        if !can_view_app(ctx, &current.user, release.app_id).await? {
+            return Ok(None);
+        }
+
+        Ok(Some(release.into()))
+    }
+
+    /// A deploy by id; `null` if it does not exist or the current user
+    /// has no role on its app.
+    async fn deploy(
+        &self,
+        ctx: &Context<'_>,
+        id: i64,
+    ) -> GqlResult<Option<DeployGql>> {
+        let current = get_current_user(ctx).await?;
+        let state = ctx.data::<AppState>()?;
+
+        let Some(deploy) = DeployRepository::new(state.pool.clone())
+            .find_by_id(id)
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?
+        else {
+            return Ok(None);
+        };
+
+        if !can_view_app(ctx, &current.user, deploy.app_id).await? {
+            return Ok(None);
+        }
+
+        Ok(Some(deploy.into()))
+    }
+
+    /// Releases of an app, newest first.
+    ///
+    /// `limit` defaults to 20 (capped at 100).
+    async fn releases(
+        &self,
+        ctx: &Context<'_>,
+        app_id: i64,
+        limit: Option<i64>,
+        #[graphql(default = 0)] offset: i64,
+    ) -> GqlResult<Vec<ReleaseGql>> {
+        let current = get_current_user(ctx).await?;
+        let limit = page_limit("limit", limit)?;
+        let offset = page_offset("offset", offset)?;
+        let state = ctx.data::<AppState>()?;
+
+        let app = AppRepository::new(state.pool.clone())
+            .find_by_id(app_id)
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?
+            .ok_or_else(|| async_graphql::Error::new("App not found"))?;
+
+        if !has_app_role(ctx, &current.user, &app, ANY_APP_ROLE).await? {
+            return Err(async_graphql::Error::new(
+                "Not allowed to read releases of this app",
+            ));
+        }
+
+        let releases = ReleaseRepository::new(state.pool.clone())
+            .list_page_by_app(app.id, limit, offset)
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?;
+
+        Ok(releases.into_iter().map(Into::into).collect())
+    }
+
+    /// Most recent builds of an app, newest first.
+    ///
+    /// `limit` defaults to 20 (capped at 100). `since`/`until` restrict
+    /// `createdAt` (from inclusive, to exclusive).
+    async fn builds(
+        &self,
+        ctx: &Context<'_>,
+        app_id: i64,
+        limit: Option<i64>,
+        #[graphql(default = 0)] offset: i64,
+        since: Option<OffsetDateTime>,
+        until: Option<OffsetDateTime>,
+    ) -> GqlResult<Vec<BuildJobGql>> {
+        let current = get_current_user(ctx).await?;
+        let limit = page_limit("limit", limit)?;
+        let offset = page_offset("offset", offset)?;
+        let mut errors = ValidationErrors::new();
+        errors.validate_time_range(since, until);
+        errors.into_result()?;
+        let state = ctx.data::<AppState>()?;
+
+        let app = AppRepository::new(state.pool.clone())
+            .find_by_id(app_id)
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?
+            .ok_or_else(|| async_graphql::Error::new("App not found"))?;
+
+        if !has_app_role(ctx, &current.user, &app, ANY_APP_ROLE).await? {
+            return Err(async_graphql::Error::new(
+                "Not allowed to read builds of this app",
+            ));
+        }
+
+        let jobs = BuildJobRepository::new(state.pool.clone())
+            .list_recent_by_app(app.id, since, until, limit, offset)
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?;
+
+        Ok(jobs.into_iter().map(Into::into).collect())
+    }
+
+    /// Most recent deploys of an app, newest first, optionally only those
+    /// to `environment`.
+    ///
+    /// `limit` defaults to 20 (capped at 100). `since`/`until` restrict
+    /// `createdAt` (from inclusive, to exclusive).
+    #[allow(clippy::too_many_arguments)]
+    async fn deploys(
+        &self,
+        ctx: &Context<'_>,
+        app_id: i64,
+        environment: Option<String>,
+        limit: Option<i64>,
+        #[graphql(default = 0)] offset: i64,
+        since: Option<OffsetDateTime>,
+        until: Option<OffsetDateTime>,
+    ) -> GqlResult<Vec<DeployGql>> {
+        let current = get_current_user(ctx).await?;
+        let limit = page_limit("limit", limit)?;
+        let offset = page_offset("offset", offset)?;
+        let mut errors = ValidationErrors::new();
+        errors.validate_time_range(since, until);
+        errors.into_result()?;
+        let state = ctx.data::<AppState>()?;
+
+        let app = AppRepository::new(state.pool.clone())
+            .find_by_id(app_id)
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?
+            .ok_or_else(|| async_graphql::Error::new("App not found"))?;
+
+        if !has_app_role(ctx, &current.user, &app, ANY_APP_ROLE).await? {
+            return Err(async_graphql::Error::new(
+                "Not allowed to read deploys of this app",
+            ));
+        }
+
+        let deploys = DeployRepository::new(state.pool.clone())
+            .list_recent_by_app(
+                app.id,
+                environment.as_deref(),
+                since,
+                until,
+                limit,
+                offset,
+            )
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?;
+
+        Ok(deploys.into_iter().map(Into::into).collect())
+    }
+
+    /// The most recent build of an app that produced `imageRef`.
+    ///
+    /// Lets the deploy side link an image back to its originating build.
+    async fn build_job_by_image_ref(
+        &self,
+        ctx: &Context<'_>,
+        app_id: i64,
+        image_ref: String,
+    ) -> GqlResult<Option<BuildJobGql>> {
+        let current = get_current_user(ctx).await?;
+        let state = ctx.data::<AppState>()?;
+
+        let Some(app) = AppRepository::new(state.pool.clone())
+            .find_by_id(app_id)
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?
+        else {
+            return Ok(None);
+        };
+
+        if !has_app_role(ctx, &current.user, &app, ANY_APP_ROLE).await? {
+            return Err(async_graphql::Error::new(
+                "Not allowed to read builds of this app",
+            ));
+        }
+
+        let job = BuildJobRepository::new(state.pool.clone())
+            .find_by_image_ref(app.id, &image_ref)
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?;
+
+        Ok(job.map(Into::into))
+    }
+
+    /// Output of a build, in chunk order.
+    ///
+    /// With `tail`, only the last `tail` chunks are returned.
+    async fn build_logs(
+        &self,
+        ctx: &Context<'_>,
+        build_id: i64,
+        tail: Option<i64>,
+    ) -> GqlResult<Vec<BuildLogGql>> {
+        let current = get_current_user(ctx).await?;
+
+        if tail.is_some_and(|n| n < 1) {
+            let mut errors = ValidationErrors::new();
+            errors.add("tail", "must be at least 1");
+            errors.into_result()?;
+        }
+
+        let state = ctx.data::<AppState>()?;
+        let job = BuildJobRepository::new(state.pool.clone())
+            .find_by_id(build_id)
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?
+            .ok_or_else(|| async_graphql::Error::new("Build not found"))?;
+        let app = AppRepository::new(state.pool.clone())
+            .find_by_id(job.app_id)
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?
+            .ok_or_else(|| async_graphql::Error::new("Build not found"))?;
+
+        if !has_app_role(ctx, &current.user, &app, ANY_APP_ROLE).await? {
+            return Err(async_graphql::Error::new(
+                "Not allowed to read builds of this app",
+            ));
+        }
+
+        let repo = BuildLogRepository::new(state.pool.clone());
+        let logs = match tail {
+            Some(n) => repo.tail_by_build(job.id, n).await,
+            None => repo.list_by_build(job.id).await,
+        }
+        .map_err(|e| async_graphql::Error::new(e.to_string()))?;
+
+        Ok(logs.into_iter().map(Into::into).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use sqlx::PgPool;
+
+    use crate::graphql::schema::AppSchema;
+    use crate::graphql::test_support::{
+        add_app_member, add_org_member, authed_request, create_test_app,
+        create_test_org, create_test_team, data, error_code,
+        register_test_user, request, test_schema,
+    };
+
+    fn permissions(app_id: i64) -> String {
+        format!(
+            r#"{{
+                permissions(appId: {app_id}) {{
+                    canDeploy canManageSecrets canManageMembers canDelete
+                }}
+            }}"#
+        )
+    }
+
+    #[sqlx::test]
+    async fn permissions_follow_the_app_role(pool: PgPool) {
+        let schema = test_schema(pool.clone());
+        let acme = create_test_org(&pool, "acme").await;
+        let web = create_test_app(&pool, acme, "web").await;
+        let cases = [
+            ("viewer", [false, false, false, false]),
+            ("deployer", [true, false, false, false]),
+            ("maintainer", [true, true, false, false]),
+            ("owner", [true, true, true, true]),
+        ];
+
+        for (role, [deploy, secrets, members, delete]) in cases {
+            let email = format!("{role}@example.com");
+            let token = register_test_user(&schema, &email).await;
+            add_app_member(&pool, web, &email, role).await;
+
+            let got =
+                data(authed_request(&schema, &permissions(web), &token).await);
+
+            assert_eq!(
+                got["permissions"],
+                json!({
+                    "canDeploy": deploy,
+                    "canManageSecrets": secrets,
+                    "canManageMembers": members,
+                    "canDelete": delete,
+                }),
+                "{role}"
+            );
+        }
+    }
+
+    #[sqlx::test]
+    async fn org_admins_act_as_app_owners(pool: PgPool) {
+        let schema = test_schema(pool.clone());
+        let token = register_test_user(&schema, "ada@example.com").await;
+        let acme = create_test_org(&pool, "acme").await;
+        let web = create_test_app(&pool, acme, "web").await;
+        add_org_member(&pool, acme, "ada@example.com", "admin").await;
+
+        let got =
+            data(authed_request(&schema, &permissions(web), &token).await);
+
+        assert_eq!(got["permissions"]["canDelete"], true);
+    }
+
+    #[sqlx::test]
+    async fn members_without_an_app_role_get_no_permissions(pool: PgPool) {
+        let schema = test_schema(pool.clone());
+        let token = register_test_user(&schema, "ada@example.com").await;
+        let acme = create_test_org(&pool, "acme").await;
+        let web = create_test_app(&pool, acme, "web").await;
+        add_org_member(&pool, acme, "ada@example.com", "member").await;
+
+        let got =
+            data(authed_request(&schema, &permissions(web), &token).await);
+
+        assert_eq!(
+            got["permissions"],
+            json!({
+                "canDeploy": false,
+                "canManageSecrets": false,
+                "canManageMembers": false,
+                "canDelete": false,
+            })
+        );
+    }
+
+    fn set_secret(app_id: i64, value: &str) -> String {
+        format!(
+            r#"mutation {{
+                setAppSecret(input: {{
+                    appId: {app_id}, key: "API_KEY", value: "{value}"
+                }}) {{ key }}
+            }}"#
+        )
+    }
+
+    fn secret_history(app_id: i64) -> String {
+        format!(
+            r#"{{
+                appSecretHistory(
+                    appId: {app_id}, environment: "default", key: "API_KEY"
+                ) {{ version value size }}
+            }}"#
+        )
+    }
+
+    #[sqlx::test]
+    async fn overwritten_secrets_can_be_rolled_back(pool: PgPool) {
+        let schema = test_schema(pool.clone());
+        let token = register_test_user(&schema, "ada@example.com").await;
+        let acme = create_test_org(&pool, "acme").await;
+        let web = create_test_app(&pool, acme, "web").await;
+        add_org_member(&pool, acme, "ada@example.com", "owner").await;
+        for value in ["first", "second"] {
+            data(
+                authed_request(&schema, &set_secret(web, value), &token).await,
+            );
+        }
+
+        let before =
+            data(authed_request(&schema, &secret_history(web), &token).await);
+        data(
+            authed_request(
+                &schema,
+                &format!(
+                    r#"mutation {{
+                        rollbackSecret(appId: {web}, key: "API_KEY", version: 1) {{
+                            key
+                        }}
+                    }}"#
+                ),
+                &token,
+            )
+            .await,
+        );
+        let after =
+            data(authed_request(&schema, &secret_history(web), &token).await);
+        let resolved = data(
+            authed_request(
+                &schema,
+                &format!(
+                    r#"{{
+                        getResolvedAppSecrets(appId: {web}, environment: "default") {{
+                            value
+                        }}
+                    }}"#
+                ),
+                &token,
+            )
+            .await,
+        );
+
+        assert_eq!(
+            before["appSecretHistory"],
+            json!([{ "version": 1, "value": "first", "size": 5 }])
+        );
+        assert_eq!(
+            after["appSecretHistory"],
+            json!([
+                { "version": 2, "value": "second", "size": 6 },
+                { "version": 1, "value": "first", "size": 5 },
+            ])
+        );
+        assert_eq!(resolved["getResolvedAppSecrets"][0]["value"], "first");
+    }
+
+    #[sqlx::test]
+    async fn history_values_are_only_shown_to_org_admins(pool: PgPool) {
+        let schema = test_schema(pool.clone());
+        let admin = register_test_user(&schema, "ada@example.com").await;
+        let maintainer = register_test_user(&schema, "bob@example.com").await;
+        let acme = create_test_org(&pool, "acme").await;
+        let web = create_test_app(&pool, acme, "web").await;
+        add_org_member(&pool, acme, "ada@example.com", "admin").await;
+        add_org_member(&pool, acme, "bob@example.com", "member").await;
+        add_app_member(&pool, web, "bob@example.com", "maintainer").await;
+        for value in ["first", "second"] {
+            data(
+                authed_request(&schema, &set_secret(web, value), &admin).await,
+            );
+        }
+
+        let as_admin =
+            data(authed_request(&schema, &secret_history(web), &admin).await);
+        let as_maintainer = data(
+            authed_request(&schema, &secret_history(web), &maintainer).await,
+        );
+
+        assert_eq!(as_admin["appSecretHistory"][0]["value"], "first");
+        assert_eq!(
+            as_maintainer["appSecretHistory"],
+            json!([{ "version": 1, "value": null, "size": 5 }])
+        );
+    }
+
+    #[sqlx::test]
+    async fn history_needs_secret_management_rights(pool: PgPool) {
+        let schema = test_schema(pool.clone());
+        let token = register_test_user(&schema, "eve@example.com").await;
+        let acme = create_test_org(&pool, "acme").await;
+        let web = create_test_app(&pool, acme, "web").await;
+        add_org_member(&pool, acme, "eve@example.com", "member").await;
+        add_app_member(&pool, web, "eve@example.com", "deployer").await;
+
+        let response =
+            authed_request(&schema, &secret_history(web), &token).await;
+
+        assert!(!response.errors.is_empty());
+        assert_eq!(response.data, async_graphql::Value::Null);
+    }
+
+    fn org_apps(org_id: i64, paging: &str) -> String {
+        format!(
+            "{{ organization(id: {org_id}) {{ apps{paging} {{ slug }} }} }}"
+        )
+    }
+
+    #[sqlx::test]
+    async fn organization_apps_are_paged_by_name(pool: PgPool) {
+        let schema = test_schema(pool.clone());
+        let token = register_test_user(&schema, "ada@example.com").await;
+        let acme = create_test_org(&pool, "acme").await;
+        add_org_member(&pool, acme, "ada@example.com", "member").await;
+        for slug in ["web", "api", "worker", "cron"] {
+            create_test_app(&pool, acme, slug).await;
+        }
+        let globex = create_test_org(&pool, "globex").await;
+        create_test_app(&pool, globex, "shop").await;
+
+        let all =
+            data(authed_request(&schema, &org_apps(acme, ""), &token).await);
+        let page = data(
+            authed_request(
+                &schema,
+                &org_apps(acme, "(limit: 2, offset: 1)"),
+                &token,
+            )
+            .await,
+        );
+
+        assert_eq!(
+            all["organization"]["apps"],
+            json!([
+                { "slug": "api" },
+                { "slug": "cron" },
+                { "slug": "web" },
+                { "slug": "worker" },
+            ])
+        );
+        assert_eq!(
+            page["organization"]["apps"],
+            json!([{ "slug": "cron" }, { "slug": "web" }])
+        );
+    }
+
+    #[sqlx::test]
+    async fn organization_apps_are_for_members_only(pool: PgPool) {
+        let schema = test_schema(pool.clone());
+        let token = register_test_user(&schema, "eve@example.com").await;
+        let acme = create_test_org(&pool, "acme").await;
+        create_test_app(&pool, acme, "web").await;
+
+        let response =
+            data(authed_request(&schema, &org_apps(acme, ""), &token).await);
+
+        assert_eq!(response, json!({ "organization": null }));
+    }
+
+    #[sqlx::test]
+    async fn organization_apps_reject_bad_paging(pool: PgPool) {
+        let schema = test_schema(pool.clone());
+        let token = register_test_user(&schema, "ada@example.com").await;
+        let acme = create_test_org(&pool, "acme").await;
+        add_org_member(&pool, acme, "ada@example.com", "member").await;
+
+        for paging in ["(offset: -1)", "(limit: 0)"] {
+            let response =
+                authed_request(&schema, &org_apps(acme, paging), &token).await;
+
+            assert_eq!(
+                error_code(&response).as_deref(),
+                Some("BAD_USER_INPUT"),
+                "{paging}"
+            );
+        }
+    }
+
+    fn apps(org_id: i64) -> String {
+        format!(
+            "{{ apps(organizationId: {org_id}) {{
+                organizationId teamId name slug repoUrl
+            }} }}"
+        )
+    }
+
+    #[sqlx::test]
+    async fn apps_lists_the_organization_apps(pool: PgPool) {
+        let schema = test_schema(pool.clone());
+        let token = register_test_user(&schema, "ada@example.com").await;
+        let acme = create_test_org(&pool, "acme").await;
+        let empty = create_test_org(&pool, "empty").await;
+        add_org_member(&pool, acme, "ada@example.com", "member").await;
+        add_org_member(&pool, empty, "ada@example.com", "member").await;
+        create_test_app(&pool, acme, "web").await;
+        let globex = create_test_org(&pool, "globex").await;
+        create_test_app(&pool, globex, "shop").await;
+
+        let listed = data(authed_request(&schema, &apps(acme), &token).await);
+        let none = data(authed_request(&schema, &apps(empty), &token).await);
+
+        assert_eq!(
+            listed["apps"],
+            json!([{
+                "organizationId": acme,
+                "teamId": null,
+                "name": "web",
+                "slug": "web",
+                "repoUrl": null,
+            }])
+        );
+        assert_eq!(none["apps"], json!([]));
+    }
+
+    #[sqlx::test]
+    async fn apps_requires_an_authenticated_member(pool: PgPool) {
+        let schema = test_schema(pool.clone());
+        let token = register_test_user(&schema, "eve@example.com").await;
+        let acme = create_test_org(&pool, "acme").await;
+        create_test_app(&pool, acme, "web").await;
+
+        let anonymous = request(&schema, &apps(acme)).await;
+        let outsider = authed_request(&schema, &apps(acme), &token).await;
+
+        assert!(!anonymous.errors.is_empty());
+        assert_eq!(
+            outsider.errors[0].message,
+            "Not a member of this organization"
+        );
+    }
+
+    fn org_and_team(org_id: i64, team_id: i64) -> String {
+        format!(
+            "{{
+                organization(id: {org_id}) {{ slug }}
+                team(id: {team_id}) {{ slug }}
+            }}"
+        )
+    }
+
+    #[sqlx::test]
+    async fn members_see_their_organization_and_teams(pool: PgPool) {
+        let schema = test_schema(pool.clone());
+        let token = register_test_user(&schema, "ada@example.com").await;
+        let acme = create_test_org(&pool, "acme").await;
+        let web = create_test_team(&pool, acme, "web").await;
+        add_org_member(&pool, acme, "ada@example.com", "billing").await;
+
+        let got = data(
+            authed_request(&schema, &org_and_team(acme, web), &token).await,
+        );
+
+        assert_eq!(
+            got,
+            json!({
+                "organization": { "slug": "acme" },
+                "team": { "slug": "web" },
+            })
+        );
+    }
+
+    #[sqlx::test]
+    async fn non_members_get_null(pool: PgPool) {
+        let schema = test_schema(pool.clone());
+        let token = register_test_user(&schema, "eve@example.com").await;
+        let acme = create_test_org(&pool, "acme").await;
+        let web = create_test_team(&pool, acme, "web").await;
+
+        let got = data(
+            authed_request(&schema, &org_and_team(acme, web), &token).await,
+        );
+
+        assert_eq!(got, json!({ "organization": null, "team": null }));
+    }
+
+    #[sqlx::test]
+    async fn platform_admins_see_every_organization(pool: PgPool) {
+        let schema = test_schema(pool.clone());
+        let token = register_test_user(&schema, "root@example.com").await;
+        sqlx::query("UPDATE users SET is_admin = true")
+            .execute(&pool)
+            .await
+            .unwrap();
+        let acme = create_test_org(&pool, "acme").await;
+        let web = create_test_team(&pool, acme, "web").await;
+
+        let got = data(
+            authed_request(&schema, &org_and_team(acme, web), &token).await,
+        );
+
+        assert_eq!(got["organization"]["slug"], "acme");
+        assert_eq!(got["team"]["slug"], "web");
+    }
+
+    #[sqlx::test]
+    async fn organization_and_team_require_authentication(pool: PgPool) {
+        let schema = test_schema(pool.clone());
+        let acme = create_test_org(&pool, "acme").await;
+        let web = create_test_team(&pool, acme, "web").await;
+
+        let response = request(&schema, &org_and_team(acme, web)).await;
+
+        assert_eq!(response.errors.len(), 2);
+    }
+
+    #[sqlx::test]
+    async fn me_returns_the_token_owner(pool: PgPool) {
+        let schema = test_schema(pool);
+        let token = register_test_user(&schema, "ada@example.com").await;
+
+        let me = data(
+            authed_request(&schema, "{ me { name email } }", &token).await,
+        );
+        let anonymous = request(&schema, "{ me { email } }").await;
+
+        assert_eq!(
+            me["me"],
+            json!({ "name": "Test User", "email": "ada@example.com" })
+        );
+        assert!(!anonymous.errors.is_empty());
+    }
+
+    #[sqlx::test]
+    async fn my_organizations_lists_memberships_with_roles(pool: PgPool) {
+        let schema = test_schema(pool.clone());
+        let token = register_test_user(&schema, "ada@example.com").await;
+        let zeta = create_test_org(&pool, "zeta").await;
+        let acme = create_test_org(&pool, "acme").await;
+        let gone = create_test_org(&pool, "gone").await;
+        create_test_org(&pool, "other").await;
+        add_org_member(&pool, zeta, "ada@example.com", "member").await;
+        add_org_member(&pool, acme, "ada@example.com", "owner").await;
+        add_org_member(&pool, gone, "ada@example.com", "admin").await;
+        sqlx::query(
+            "UPDATE organizations SET deleted_at = now() WHERE id = $1",
+        )
+        .bind(gone)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let got = data(
+            authed_request(
+                &schema,
+                "{ myOrganizations { role organization { slug } } }",
+                &token,
+            )
+            .await,
+        );
+
+        assert_eq!(
+            got["myOrganizations"],
+            json!([
+                { "role": "OWNER", "organization": { "slug": "acme" } },
+                { "role": "MEMBER", "organization": { "slug": "zeta" } },
+            ])
+        );
+    }
+
+    #[sqlx::test]
+    async fn deploys_are_newest_first_and_filtered_by_env(pool: PgPool) {
+        let schema = test_schema(pool.clone());
+        let token = register_test_user(&schema, "ada@example.com").await;
+        let outsider = register_test_user(&schema, "eve@example.com").await;
+        let acme = create_test_org(&pool, "acme").await;
+        let web = create_test_app(&pool, acme, "web").await;
+        add_app_member(&pool, web, "ada@example.com", "viewer").await;
+        sqlx::query(
+            r#"
+            WITH r AS (
+                INSERT INTO releases (app_id, version, status)
+                VALUES ($1, 'v1', 'built')
+                RETURNING id
+            )
+            INSERT INTO deploys (app_id, release_id, environment, created_at)
+            SELECT $1, r.id, env, now() - make_interval(mins => age)
+            FROM r, (VALUES ('staging', 3), ('production', 2), ('staging', 1))
+                AS d(env, age)
+            "#,
+        )
+        .bind(web)
+        .execute(&pool)
+        .await
+        .unwrap();
+        let query = |env: &str| {
+            format!(
+                "{{ deploys(appId: {web}, environment: {env}, limit: 5) \
+                 {{ environment }} }}"
+            )
+        };
+
+        let all = data(authed_request(&schema, &query("null"), &token).await);
+        let staging = data(
+            authed_request(&schema, &query(r#""staging""#), &token).await,
+        );
+        let denied = authed_request(&schema, &query("null"), &outsider).await;
+
+        assert_eq!(
+            all["deploys"],
+            json!([
+                { "environment": "staging" },
+                { "environment": "production" },
+                { "environment": "staging" },
+            ])
+        );
+        assert_eq!(staging["deploys"].as_array().unwrap().len(), 2);
+        assert!(!denied.errors.is_empty());
+    }
+
+    fn teams(org_id: i64, include_deleted: bool) -> String {
+        format!(
+            "{{ teams(organizationId: {org_id}, \
+             includeDeleted: {include_deleted}) {{ slug }} }}"
+        )
+    }
+
+    #[sqlx::test]
+    async fn teams_lists_the_organization_teams_by_name(pool: PgPool) {
+        let schema = test_schema(pool.clone());
+        let token = register_test_user(&schema, "ada@example.com").await;
+        let acme = create_test_org(&pool, "acme").await;
+        let empty = create_test_org(&pool, "empty").await;
+        add_org_member(&pool, acme, "ada@example.com", "member").await;
+        add_org_member(&pool, empty, "ada@example.com", "member").await;
+        create_test_team(&pool, acme, "web").await;
+        create_test_team(&pool, acme, "api").await;
+        let old = create_test_team(&pool, acme, "old").await;
+        sqlx::query("UPDATE teams SET deleted_at = now() WHERE id = $1")
+            .bind(old)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let listed =
+            data(authed_request(&schema, &teams(acme, false), &token).await);
+        let none =
+            data(authed_request(&schema, &teams(empty, false), &token).await);
+
+        assert_eq!(
+            listed["teams"],
+            json!([{ "slug": "api" }, { "slug": "web" }])
+        );
+        assert_eq!(none["teams"], json!([]));
+    }
+
+    #[sqlx::test]
+    async fn deleted_teams_are_listed_to_org_admins_only(pool: PgPool) {
+        let schema = test_schema(pool.clone());
+        let admin = register_test_user(&schema, "ada@example.com").await;
+        let member = register_test_user(&schema, "bob@example.com").await;
+        let outsider = register_test_user(&schema, "eve@example.com").await;
+        let acme = create_test_org(&pool, "acme").await;
+        add_org_member(&pool, acme, "ada@example.com", "admin").await;
+        add_org_member(&pool, acme, "bob@example.com", "member").await;
+        let old = create_test_team(&pool, acme, "old").await;
+        sqlx::query("UPDATE teams SET deleted_at = now() WHERE id = $1")
+            .bind(old)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let listed =
+            data(authed_request(&schema, &teams(acme, true), &admin).await);
+        let member_denied =
+            authed_request(&schema, &teams(acme, true), &member).await;
+        let outsider_denied =
+            authed_request(&schema, &teams(acme, false), &outsider).await;
+        let anonymous = request(&schema, &teams(acme, false)).await;
+
+        assert_eq!(listed["teams"], json!([{ "slug": "old" }]));
+        assert!(!member_denied.errors.is_empty());
+        assert!(!outsider_denied.errors.is_empty());
+        assert!(!anonymous.errors.is_empty());
+    }
+
+    #[sqlx::test]
+    async fn release_and_deploy_are_visible_to_app_members_only(pool: PgPool) {
+        let schema = test_schema(pool.clone());
+        let member = register_test_user(&schema, "ada@example.com").await;
+        let outsider = register_test_user(&schema, "eve@example.com").await;
+        let acme = create_test_org(&pool, "acme").await;
+        let web = create_test_app(&pool, acme, "web").await;
+        add_app_member(&pool, web, "ada@example.com", "viewer").await;
+        let (release, deploy): (i64, i64) = sqlx::query_as(
+            r#"
+            WITH r AS (
+                INSERT INTO releases (app_id, version, status)
+                VALUES ($1, 'v1', 'built')
+                RETURNING id
+            )
+            INSERT INTO deploys (app_id, release_id, environment)
+            SELECT $1, r.id, 'staging' FROM r
+            RETURNING release_id, id
+            "#,
+        )
+        .bind(web)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        let query = |release: i64, deploy: i64| {
+            format!(
+                "{{ release(id: {release}) {{ version }} \
+                 deploy(id: {deploy}) {{ environment }} }}"
+            )
+        };
+
+        let found = data(
+            authed_request(&schema, &query(release, deploy), &member).await,
+        );
+        let missing = data(
+            authed_request(&schema, &query(release + 1, deploy + 1), &member)
+                .await,
+        );
+        let hidden = data(
+            authed_request(&schema, &query(release, deploy), &outsider).await,
+        );
+
+        assert_eq!(
+            found,
+            json!({
+                "release": { "version": "v1" },
+                "deploy": { "environment": "staging" },
+            })
+        );
+        assert_eq!(missing, json!({ "release": null, "deploy": null }));
+        assert_eq!(hidden, json!({ "release": null, "deploy": null }));
+    }
+
+    #[sqlx::test]
+    async fn organization_by_slug_is_null_unless_a_member(pool: PgPool) {
+        let schema = test_schema(pool.clone());
+        let member = register_test_user(&schema, "ada@example.com").await;
+        let outsider = register_test_user(&schema, "eve@example.com").await;
+        let acme = create_test_org(&pool, "acme").await;
+        add_org_member(&pool, acme, "ada@example.com", "member").await;
+        let query = |slug: &str| {
+            format!(r#"{{ organizationBySlug(slug: "{slug}") {{ id }} }}"#)
+        };
+
+        let found =
+            data(authed_request(&schema, &query("acme"), &member).await);
+        let missing =
+            data(authed_request(&schema, &query("globex"), &member).await);
+        let hidden =
+            data(authed_request(&schema, &query("acme"), &outsider).await);
+
+        assert_eq!(found["organizationBySlug"], json!({ "id": acme }));
+        assert_eq!(missing["organizationBySlug"], json!(null));
+        assert_eq!(hidden["organizationBySlug"], json!(null));
+    }
+
+    #[sqlx::test]
+    async fn team_by_slug_is_scoped_to_the_organization(pool: PgPool) {
+        let schema = test_schema(pool.clone());
+        let member = register_test_user(&schema, "ada@example.com").await;
+        let outsider = register_test_user(&schema, "eve@example.com").await;
+        let acme = create_test_org(&pool, "acme").await;
+        let globex = create_test_org(&pool, "globex").await;
+        add_org_member(&pool, acme, "ada@example.com", "member").await;
+        add_org_member(&pool, globex, "ada@example.com", "member").await;
+        let web = create_test_team(&pool, acme, "web").await;
+        create_test_team(&pool, globex, "api").await;
+        let query = |org_id: i64, slug: &str| {
+            format!(
+                r#"{{ teamBySlug(organizationId: {org_id}, slug: "{slug}")
+                    {{ id }} }}"#
+            )
+        };
+
+        let found =
+            data(authed_request(&schema, &query(acme, "web"), &member).await);
+        let elsewhere =
+            data(authed_request(&schema, &query(acme, "api"), &member).await);
+        let hidden = data(
+            authed_request(&schema, &query(acme, "web"), &outsider).await,
+        );
+
+        assert_eq!(found["teamBySlug"], json!({ "id": web }));
+        assert_eq!(elsewhere["teamBySlug"], json!(null));
+        assert_eq!(hidden["teamBySlug"], json!(null));
+    }
+
+    #[sqlx::test]
+    async fn deploys_and_builds_are_filtered_by_time_range(pool: PgPool) {
+        let schema = test_schema(pool.clone());
+        let token = register_test_user(&schema, "ada@example.com").await;
+        let acme = create_test_org(&pool, "acme").await;
+        let web = create_test_app(&pool, acme, "web").await;
+        add_app_member(&pool, web, "ada@example.com", "viewer").await;
+        sqlx::query(
+            r#"
+            WITH r AS (
+                INSERT INTO releases (app_id, version, status)
+                VALUES ($1, 'v1', 'built')
+                RETURNING id
+            )
+            INSERT INTO deploys (app_id, release_id, environment, created_at)
+            SELECT $1, r.id, env, at::timestamptz
+            FROM r, (VALUES
+                ('old', '2025-11-15T12:00:00Z'),
+                ('first', '2025-11-16T00:00:00Z'),
+                ('last', '2025-11-16T23:59:59Z'),
+                ('next', '2025-11-17T00:00:00Z')
+            ) AS d(env, at)
+            "#,
+        )
+        .bind(web)
+        .execute(&pool)
+        .await
+        .unwrap();
+        let range = r#"since: "2025-11-16T00:00:00Z",
+                       until: "2025-11-17T00:00:00Z""#;
+
+        let deploys = data(
+            authed_request(
+                &schema,
+                &format!(
+                    "{{ deploys(appId: {web}, {range}) {{ environment }} }}"
+                ),
+                &token,
+            )
+            .await,
+        );
+        let builds = data(
+            authed_request(
+                &schema,
+                &format!("{{ builds(appId: {web}, {range}) {{ id }} }}"),
+                &token,
+            )
+            .await,
+        );
+
+        assert_eq!(
+            deploys["deploys"],
+            json!([{ "environment": "last" }, { "environment": "first" }])
+        );
+        assert_eq!(builds["builds"], json!([]));
+    }
+
+    #[sqlx::test]
+    async fn time_ranges_must_be_ordered(pool: PgPool) {
+        let schema = test_schema(pool.clone());
+        let token = register_test_user(&schema, "ada@example.com").await;
+        let acme = create_test_org(&pool, "acme").await;
+        let web = create_test_app(&pool, acme, "web").await;
+        add_app_member(&pool, web, "ada@example.com", "viewer").await;
+
+        for field in ["deploys", "builds"] {
+            let response = authed_request(
+                &schema,
+                &format!(
+                    r#"{{ {field}(appId: {web},
+                        since: "2025-11-17T00:00:00Z",
+                        until: "2025-11-16T00:00:00Z") {{ id }} }}"#
+                ),
+                &token,
+            )
+            .await;
+
+            assert_eq!(
+                error_code(&response).as_deref(),
+                Some("BAD_USER_INPUT")
+            );
+        }
+    }
+
+    fn app_secrets(app_id: i64) -> String {
+        format!("{{ appSecrets(appId: {app_id}) {{ key value }} }}")
+    }
+
+    #[sqlx::test]
+    async fn app_secret_values_are_masked_for_viewers(pool: PgPool) {
+        let schema = test_schema(pool.clone());
+        let owner = register_test_user(&schema, "ada@example.com").await;
+        let viewer = register_test_user(&schema, "bob@example.com").await;
+        let outsider = register_test_user(&schema, "eve@example.com").await;
+        let acme = create_test_org(&pool, "acme").await;
+        let web = create_test_app(&pool, acme, "web").await;
+        add_org_member(&pool, acme, "ada@example.com", "owner").await;
+        add_app_member(&pool, web, "bob@example.com", "viewer").await;
+        data(
+            authed_request(&schema, &set_secret(web, "s3cr3t"), &owner).await,
+        );
+
+        let as_owner =
+            data(authed_request(&schema, &app_secrets(web), &owner).await);
+        let as_viewer =
+            data(authed_request(&schema, &app_secrets(web), &viewer).await);
+        let as_outsider =
+            authed_request(&schema, &app_secrets(web), &outsider).await;
+
+        assert_eq!(
+            as_owner["appSecrets"],
+            json!([{ "key": "API_KEY", "value": "s3cr3t" }])
+        );
+        assert_eq!(
+            as_viewer["appSecrets"],
+            json!([{ "key": "API_KEY", "value": "***" }])
+        );
+        assert!(!as_outsider.errors.is_empty());
+    }
+
+    #[sqlx::test]
+    async fn deleted_secrets_stay_in_the_history(pool: PgPool) {
+        let schema = test_schema(pool.clone());
+        let owner = register_test_user(&schema, "ada@example.com").await;
+        let viewer = register_test_user(&schema, "bob@example.com").await;
+        let acme = create_test_org(&pool, "acme").await;
+        let web = create_test_app(&pool, acme, "web").await;
+        add_org_member(&pool, acme, "ada@example.com", "owner").await;
+        add_app_member(&pool, web, "bob@example.com", "viewer").await;
+        data(authed_request(&schema, &set_secret(web, "first"), &owner).await);
+        let delete = format!(
+            r#"mutation {{ deleteAppSecret(appId: {web}, key: "API_KEY") }}"#
+        );
+
+        let by_viewer = authed_request(&schema, &delete, &viewer).await;
+        let first = data(authed_request(&schema, &delete, &owner).await);
+        let again = data(authed_request(&schema, &delete, &owner).await);
+        let listed =
+            data(authed_request(&schema, &app_secrets(web), &owner).await);
+        let history =
+            data(authed_request(&schema, &secret_history(web), &owner).await);
+
+        assert_eq!(error_code(&by_viewer).as_deref(), Some("FORBIDDEN"));
+        assert_eq!(first["deleteAppSecret"], true);
+        assert_eq!(again["deleteAppSecret"], false);
+        assert_eq!(listed["appSecrets"], json!([]));
+        assert_eq!(
+            history["appSecretHistory"],
+            json!([{ "version": 1, "value": "first", "size": 5 }])
+        );
+    }
+
+    fn team_by_slug_path(org_slug: &str, team_slug: &str) -> String {
+        format!(
+            r#"{{ teamBySlugPath(organizationSlug: "{org_slug}",
+                teamSlug: "{team_slug}") {{ id organization {{ slug }} }} }}"#
+        )
+    }
+
+    #[sqlx::test]
+    async fn team_by_slug_path_resolves_both_slugs(pool: PgPool) {
+        let schema = test_schema(pool.clone());
+        let token = register_test_user(&schema, "ada@example.com").await;
+        let acme = create_test_org(&pool, "acme").await;
+        add_org_member(&pool, acme, "ada@example.com", "member").await;
+        let web = create_test_team(&pool, acme, "web").await;
+
+        let found = data(
+            authed_request(&schema, &team_by_slug_path("acme", "web"), &token)
+                .await,
+        );
+
+        assert_eq!(
+            found["teamBySlugPath"],
+            json!({ "id": web, "organization": { "slug": "acme" } })
+        );
+    }
+
+    #[sqlx::test]
+    async fn team_by_slug_path_is_null_when_not_found_or_hidden(pool: PgPool) {
+        let schema = test_schema(pool.clone());
+        let member = register_test_user(&schema, "ada@example.com").await;
+        let outsider = register_test_user(&schema, "eve@example.com").await;
+        let acme = create_test_org(&pool, "acme").await;
+        let globex = create_test_org(&pool, "globex").await;
+        add_org_member(&pool, acme, "ada@example.com", "member").await;
+        create_test_team(&pool, acme, "web").await;
+        create_test_team(&pool, globex, "api").await;
+
+        for (org_slug, team_slug, token) in [
+            ("nope", "web", &member),
+            ("acme", "nope", &member),
+            ("acme", "api", &member),
+            ("acme", "web", &outsider),
+        ] {
+            let got = data(
+                authed_request(
+                    &schema,
+                    &team_by_slug_path(org_slug, team_slug),
+                    token,
+                )
+                .await,
+            );
+
+            assert_eq!(
+                got["teamBySlugPath"],
+                json!(null),
+                "{org_slug}/{team_slug}"
+            );
+        }
+    }
+
+    #[sqlx::test]
+    async fn app_by_slug_is_null_unless_a_member(pool: PgPool) {
+        let schema = test_schema(pool.clone());
+        let member = register_test_user(&schema, "ada@example.com").await;
+        let outsider = register_test_user(&schema, "eve@example.com").await;
+        let acme = create_test_org(&pool, "acme").await;
+        let globex = create_test_org(&pool, "globex").await;
+        add_org_member(&pool, acme, "ada@example.com", "member").await;
+        let web = create_test_app(&pool, acme, "web").await;
+        create_test_app(&pool, globex, "api").await;
+        let query = |org_id: i64, slug: &str| {
+            format!(
+                r#"{{ appBySlug(organizationId: {org_id}, slug: "{slug}")
+                    {{ id }} }}"#
+            )
+        };
+
+        let found =
+            data(authed_request(&schema, &query(acme, "web"), &member).await);
+        let elsewhere =
+            data(authed_request(&schema, &query(acme, "api"), &member).await);
+        let hidden = data(
+            authed_request(&schema, &query(acme, "web"), &outsider).await,
+        );
+
+        assert_eq!(found["appBySlug"], json!({ "id": web }));
+        assert_eq!(elsewhere["appBySlug"], json!(null));
+        assert_eq!(hidden["appBySlug"], json!(null));
+    }
+
+    fn resolved_secrets(app_id: i64) -> String {
+        format!(
+            r#"{{
+                getResolvedAppSecrets(appId: {app_id}, environment: "default") {{
+                    key value
+                }}
+            }}"#
+        )
+    }
+
+    #[sqlx::test]
+    async fn resolved_secrets_need_secret_management_rights(pool: PgPool) {
+        let schema = test_schema(pool.clone());
+        let maintainer = register_test_user(&schema, "ada@example.com").await;
+        let deployer = register_test_user(&schema, "bob@example.com").await;
+        let acme = create_test_org(&pool, "acme").await;
+        let web = create_test_app(&pool, acme, "web").await;
+        add_app_member(&pool, web, "ada@example.com", "maintainer").await;
+        add_app_member(&pool, web, "bob@example.com", "deployer").await;
+        data(
+            authed_request(&schema, &set_secret(web, "first"), &maintainer)
+                .await,
+        );
+
+        let as_maintainer = data(
+            authed_request(&schema, &resolved_secrets(web), &maintainer).await,
+        );
+        let as_deployer =
+            authed_request(&schema, &resolved_secrets(web), &deployer).await;
+
+        assert_eq!(
+            as_maintainer["getResolvedAppSecrets"],
+            json!([{ "key": "API_KEY", "value": "first" }])
+        );
+        assert_eq!(error_code(&as_deployer).as_deref(), Some("FORBIDDEN"));
+    }
+
+    #[sqlx::test]
+    async fn deleted_organizations_are_for_platform_admins(pool: PgPool) {
+        let schema = test_schema(pool.clone());
+        let token = register_test_user(&schema, "ada@example.com").await;
+        let acme = create_test_org(&pool, "acme").await;
+        add_org_member(&pool, acme, "ada@example.com", "owner").await;
+        sqlx::query("UPDATE organizations SET deleted_at = NOW()")
+            .execute(&pool)
+            .await
+            .unwrap();
+        let query = "{ deletedOrganizations { slug } }";
+
+        let as_owner = authed_request(&schema, query, &token).await;
+        sqlx::query("UPDATE users SET is_admin = true")
+            .execute(&pool)
+            .await
+            .unwrap();
+        let as_admin = data(authed_request(&schema, query, &token).await);
+
+        assert_eq!(error_code(&as_owner).as_deref(), Some("FORBIDDEN"));
+        assert_eq!(
+            as_admin["deletedOrganizations"],
+            json!([{ "slug": "acme" }])
+        );
+    }
+
+    /// Number of rows `{ <list><paging>) { id } }` returns.
+    async fn list_len(
+        schema: &AppSchema,
+        token: &str,
+        list: &str,
+        paging: &str,
+    ) -> usize {
+        let field = &list[..list.find('(').unwrap()];
+        let query = format!("{{ {list}{paging}) {{ id }} }}");
+        let page = data(authed_request(schema, &query, token).await);
+
+        page[field].as_array().unwrap().len()
+    }
+
+    #[sqlx::test]
+    async fn list_queries_default_to_20_and_cap_at_100(pool: PgPool) {
+        let schema = test_schema(pool.clone());
+        let token = register_test_user(&schema, "ada@example.com").await;
+        let acme = create_test_org(&pool, "acme").await;
+        add_org_member(&pool, acme, "ada@example.com", "owner").await;
+        let web = create_test_app(&pool, acme, "web").await;
+        // 105 of everything, so a full page leaves 5 behind
+        for insert in [
+            "INSERT INTO apps (organization_id, name, slug) \
+             SELECT $1, 'app-' || n, 'app-' || n \
+             FROM generate_series(1, 104) n",
+            "INSERT INTO teams (organization_id, name, slug) \
+             SELECT $1, 'team-' || n, 'team-' || n \
+             FROM generate_series(1, 105) n",
+        ] {
+            sqlx::query(insert).bind(acme).execute(&pool).await.unwrap();
+        }
+        for insert in [
+            "INSERT INTO releases (app_id, version, status) \
+             SELECT $1, 'v' || n, 'built' FROM generate_series(1, 105) n",
+            "INSERT INTO build_jobs (app_id) \
+             SELECT $1 FROM generate_series(1, 105)",
+            "INSERT INTO deploys (app_id, release_id, environment) \
+             SELECT $1, id, 'production' FROM releases",
+        ] {
+            sqlx::query(insert).bind(web).execute(&pool).await.unwrap();
+        }
+
+        for list in [
+            format!("teams(organizationId: {acme}"),
+            format!("apps(organizationId: {acme}"),
+            format!("releases(appId: {web}"),
+            format!("builds(appId: {web}"),
+            format!("deploys(appId: {web}"),
+        ] {
+            let len = |paging| list_len(&schema, &token, &list, paging);
+            let negative = authed_request(
+                &schema,
+                &format!("{{ {list}, offset: -1) {{ id }} }}"),
+                &token,
+            )
+            .await;
+
+            assert_eq!(len("").await, 20, "{list}");
+            assert_eq!(len(", limit: 1000").await, 100, "{list}");
+            assert_eq!(len(", limit: 100, offset: 100").await, 5, "{list}");
+            assert_eq!(
+                error_code(&negative).as_deref(),
+                Some("BAD_USER_INPUT"),
+                "{list}"
+            );
+        }
+    }
+
+    #[sqlx::test]
+    async fn releases_are_listed_newest_first_for_app_members(pool: PgPool) {
+        let schema = test_schema(pool.clone());
+        let viewer = register_test_user(&schema, "ada@example.com").await;
+        let outsider = register_test_user(&schema, "eve@example.com").await;
+        let acme = create_test_org(&pool, "acme").await;
+        let web = create_test_app(&pool, acme, "web").await;
+        add_app_member(&pool, web, "ada@example.com", "viewer").await;
+        for version in ["v1", "v2", "v3"] {
+            sqlx::query(
+                "INSERT INTO releases (app_id, version, status) \
+                 VALUES ($1, $2, 'built')",
+            )
+            .bind(web)
+            .bind(version)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+        let query = format!(
+            "{{ releases(appId: {web}, limit: 2, offset: 1) {{ version }} }}"
+        );
+
+        let page = data(authed_request(&schema, &query, &viewer).await);
+        let refused = authed_request(&schema, &query, &outsider).await;
+
+        assert_eq!(
+            page["releases"],
+            json!([{ "version": "v2" }, { "version": "v1" }])
+        );
+        assert!(!refused.errors.is_empty());
     }
 }
@@ -0,0 +1,143 @@
+use async_graphql::{Context, Error as GqlError, Result as GqlResult};
+use sqlx::PgPool;
+
+use crate::config::Config;
+use crate::domain::models::Organization;
+use crate::graphql::errors::quota_exceeded;
+use crate::graphql::state::AppState;
+use crate::infrastructure::repositories::AppRepository;
+
+/// Effective app limit for an organization, if any.
+///
+/// A per-organization `max_apps` wins over `PAASTEL_MAX_APPS_PER_ORG`.
+fn max_apps_for(org: &Organization, config: &Config) -> Option<usize> {
+    match org.max_apps {
+        Some(max) => Some(max.max(0) as usize),
+        None => config.max_apps_per_org,
+    }
+}
+
+/// Fail with `QUOTA_EXCEEDED` when the organization cannot take another app.
+///
+/// Soft-deleted apps do not count towards the limit.
+pub async fn ensure_app_quota(
+    ctx: &Context<'_>,
+    org: &Organization,
+) -> GqlResult<()> {
+    let state = ctx.data::<AppState>()?;
+    check_app_quota(&state.pool, org, &state.config).await
+}
+
+async fn check_app_quota(
+    pool: &PgPool,
+    org: &Organization,
+    config: &Config,
+) -> GqlResult<()> {
+    let Some(max) = max_apps_for(org, config) else {
+        return Ok(());
+    };
+
+    let apps = AppRepository::new(pool.clone())
+        .list_by_organization(org.id)
+        .await
+        .map_err(|e| GqlError::new(e.to_string()))?;
+
+    if apps.len() >= max {
+        return Err(quota_exceeded(format!(
+            "Organization '{}' has reached its limit of {max} apps",
+            org.slug
+        )));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use async_graphql::Value;
+    use sqlx::query_scalar;
+
+    use super::*;
+    use crate::graphql::test_support::test_config;
+    use crate::infrastructure::repositories::OrganizationRepository;
+
+    /// Organization with a custom limit, so the default is not consulted.
+    async fn org_with_limit(pool: &PgPool, max_apps: i32) -> Organization {
+        let id: i64 = query_scalar(
+            r#"
+            INSERT INTO organizations (name, slug, max_apps)
+            VALUES ('Acme', 'acme', $1)
+            RETURNING id
+            "#,
+        )
+        .bind(max_apps)
+        .fetch_one(pool)
+        .await
+        .unwrap();
+
+        OrganizationRepository::new(pool.clone())
+            .find_by_id(id)
+            .await
+            .unwrap()
+            .unwrap()
+    }
+
+    async fn add_app(pool: &PgPool, org_id: i64, slug: &str) -> i64 {
+        query_scalar(
+            r#"
+            INSERT INTO apps (organization_id, name, slug)
+            VALUES ($1, $2, $2)
+            RETURNING id
+            "#,
+        )
+        .bind(org_id)
+        .bind(slug)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+    }
+
+    fn code(err: &GqlError) -> Option<&Value> {
+        err.extensions.as_ref().and_then(|ext| ext.get("code"))
+    }
+
+    #[sqlx::test]
+    async fn apps_below_the_limit_are_allowed(pool: PgPool) {
+        let org = org_with_limit(&pool, 2).await;
+        add_app(&pool, org.id, "web").await;
+
+        assert!(check_app_quota(&pool, &org, &test_config()).await.is_ok());
+    }
+
+    #[sqlx::test]
+    async fn limit_is_enforced_at_and_beyond_it(pool: PgPool) {
+        let org = org_with_limit(&pool, 1).await;
+        add_app(&pool, org.id, "web").await;
+
+        let err =
+            check_app_quota(&pool, &org, &test_config()).await.unwrap_err();
+        assert_eq!(code(&err), Some(&Value::from("QUOTA_EXCEEDED")));
+        assert_eq!(
+            err.message,
+            "Organization 'acme' has reached its limit of 1 apps"
+        );
+
+        add_app(&pool, org.id, "api").await;
+        assert!(check_app_quota(&pool, &org, &test_config()).await.is_err());
+    }
+
+    #[sqlx::test]
+    async fn deleting_an_app_frees_a_slot(pool: PgPool) {
+        let org = org_with_limit(&pool, 1).await;
+        let web = add_app(&pool, org.id, "web").await;
+        assert!(check_app_quota(&pool, &org, &test_config()).await.is_err());
+
+        sqlx::query("UPDATE apps SET deleted_at = NOW() WHERE id = $1")
+            .bind(web)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        assert!(check_app_quota(&pool, &org, &test_config()).await.is_ok());
+    }
+}
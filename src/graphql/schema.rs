@@ -0,0 +1,24 @@
+use async_graphql::dataloader::DataLoader;
+use async_graphql::{EmptySubscription, Schema};
+
+use crate::graphql::loaders::{OrganizationLoader, UserLoader};
+use crate::graphql::mutation::MutationRoot;
+use crate::graphql::query::QueryRoot;
+use crate::graphql::state::AppState;
+
+pub type AppSchema = Schema<QueryRoot, MutationRoot, EmptySubscription>;
+
+/// Build the executable schema with its data and loaders.
+///
+/// Shared by the server and anything that needs to run GraphQL documents
+/// against a real pool (e.g. a throwaway database).
+pub fn build_schema(state: AppState) -> AppSchema {
+    let organizations = OrganizationLoader::new(state.pool.clone());
+    let users = UserLoader::new(state.pool.clone());
+
+    Schema::build(QueryRoot, MutationRoot, EmptySubscription)
+        .data(state)
+        .data(DataLoader::new(organizations, tokio::spawn))
+        .data(DataLoader::new(users, tokio::spawn))
+        .finish()
+}
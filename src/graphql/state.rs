@@ -1,7 +1,15 @@
+use std::sync::Arc;
+
 use sqlx::PgPool;
 
+use crate::config::Config;
+use crate::infrastructure::secret_cipher::SecretCipher;
+
 /// Shared application state injected into GraphQL schema.
 #[derive(Clone)]
 pub struct AppState {
     pub pool: PgPool,
+    pub config: Arc<Config>,
+    /// Encrypts app secret values at rest
+    pub cipher: SecretCipher,
 }
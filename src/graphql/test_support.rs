@@ -0,0 +1,197 @@
+//! Helpers for running GraphQL documents against a throwaway database.
+//!
+//! Tests take their pool from `#[sqlx::test]`, which creates a fresh
+//! database per test and applies `migrations/`. `DATABASE_URL` must point
+//! at a Postgres the tests may create databases on (e.g. the one from
+//! `docker-compose.yml`).
+
+use std::sync::Arc;
+
+use async_graphql::{Request, Response};
+use axum::http::{HeaderMap, HeaderValue, header::AUTHORIZATION};
+use serde_json::Value;
+use sqlx::PgPool;
+
+use crate::config::Config;
+use crate::graphql::schema::{AppSchema, build_schema};
+use crate::graphql::state::AppState;
+use crate::infrastructure::secret_cipher::SecretCipher;
+
+/// Password of every user created by [`register_test_user`].
+pub const TEST_PASSWORD: &str = "correct horse battery";
+
+/// Key encrypting app secrets in tests; never used outside them.
+const TEST_SECRET_KEY: &str = "AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA=";
+
+/// The default configuration (only the required variables set).
+pub fn test_config() -> Config {
+    Config::from_vars(|name| match name {
+        "DATABASE_URL" => Some("postgres://unused".to_string()),
+        "PAASTEL_SECRET_KEY" => Some(TEST_SECRET_KEY.to_string()),
+        _ => None,
+    })
+    .expect("test configuration")
+}
+
+/// Cipher for [`TEST_SECRET_KEY`].
+pub fn test_cipher() -> SecretCipher {
+    SecretCipher::from_base64_key(TEST_SECRET_KEY).expect("test key")
+}
+
+/// Server state wired to `pool` with the default configuration.
+pub fn test_state(pool: PgPool) -> AppState {
+    AppState { pool, config: Arc::new(test_config()), cipher: test_cipher() }
+}
+
+/// Schema wired to `pool`.
+pub fn test_schema(pool: PgPool) -> AppSchema {
+    build_schema(test_state(pool))
+}
+
+/// Run `query` without credentials.
+pub async fn request(schema: &AppSchema, query: &str) -> Response {
+    schema.execute(Request::new(query).data(HeaderMap::new())).await
+}
+
+/// Run `query` as the owner of `token`.
+pub async fn authed_request(
+    schema: &AppSchema,
+    query: &str,
+    token: &str,
+) -> Response {
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        AUTHORIZATION,
+        HeaderValue::from_str(&format!("Bearer {token}"))
+            .expect("token is a valid header value"),
+    );
+
+    schema.execute(Request::new(query).data(headers)).await
+}
+
+/// `data` of a response that must not contain errors.
+pub fn data(response: Response) -> Value {
+    assert!(response.errors.is_empty(), "errors: {:?}", response.errors);
+    response.data.into_json().expect("response data is JSON")
+}
+
+/// `extensions.code` of the first error of a response that must fail.
+pub fn error_code(response: &Response) -> Option<String> {
+    let error = response.errors.first().expect("response has errors");
+    match error.extensions.as_ref()?.get("code")? {
+        async_graphql::Value::String(code) => Some(code.clone()),
+        _ => None,
+    }
+}
+
+/// Register `email` through `registerUser` and return its access token.
+pub async fn register_test_user(schema: &AppSchema, email: &str) -> String {
+    let query = format!(
+        r#"mutation {{
+            registerUser(input: {{
+                name: "Test User", email: "{email}", password: "{TEST_PASSWORD}"
+            }}) {{
+                token {{ token }}
+            }}
+        }}"#
+    );
+    let data = data(request(schema, &query).await);
+
+    data["registerUser"]["token"]["token"]
+        .as_str()
+        .expect("registerUser returns a token")
+        .to_string()
+}
+
+/// Insert an organization directly; returns its id.
+pub async fn create_test_org(pool: &PgPool, slug: &str) -> i64 {
+    sqlx::query_scalar(
+        "INSERT INTO organizations (name, slug) VALUES ($1, $1) RETURNING id",
+    )
+    .bind(slug)
+    .fetch_one(pool)
+    .await
+    .expect("insert organization")
+}
+
+/// Give the registered user `email` the org role `role` (e.g. "owner").
+pub async fn add_org_member(
+    pool: &PgPool,
+    organization_id: i64,
+    email: &str,
+    role: &str,
+) {
+    sqlx::query(
+        r#"
+        INSERT INTO organization_memberships (organization_id, user_id, role)
+        SELECT $1, id, $3::org_role FROM users WHERE email = $2
+        "#,
+    )
+    .bind(organization_id)
+    .bind(email)
+    .bind(role)
+    .execute(pool)
+    .await
+    .expect("insert membership");
+}
+
+/// Insert a team into an organization; returns its id.
+pub async fn create_test_team(
+    pool: &PgPool,
+    organization_id: i64,
+    slug: &str,
+) -> i64 {
+    sqlx::query_scalar(
+        r#"
+        INSERT INTO teams (organization_id, name, slug)
+        VALUES ($1, $2, $2)
+        RETURNING id
+        "#,
+    )
+    .bind(organization_id)
+    .bind(slug)
+    .fetch_one(pool)
+    .await
+    .expect("insert team")
+}
+
+/// Insert an app into an organization; returns its id.
+pub async fn create_test_app(
+    pool: &PgPool,
+    organization_id: i64,
+    slug: &str,
+) -> i64 {
+    sqlx::query_scalar(
+        r#"
+        INSERT INTO apps (organization_id, name, slug)
+        VALUES ($1, $2, $2)
+        RETURNING id
+        "#,
+    )
+    .bind(organization_id)
+    .bind(slug)
+    .fetch_one(pool)
+    .await
+    .expect("insert app")
+}
+
+/// Give the registered user `email` the app role `role` (e.g. "viewer").
+pub async fn add_app_member(
+    pool: &PgPool,
+    app_id: i64,
+    email: &str,
+    role: &str,
+) {
+    sqlx::query(
+        r#"
+        INSERT INTO app_memberships (app_id, user_id, role)
+        SELECT $1, id, $3::app_role FROM users WHERE email = $2
+        "#,
+    )
+    .bind(app_id)
+    .bind(email)
+    .bind(role)
+    .execute(pool)
+    .await
+    .expect("insert app membership");
+}
@@ -1,7 +1,27 @@
-use async_graphql::{InputObject, SimpleObject};
+use async_graphql::dataloader::DataLoader;
+use async_graphql::{
+    ComplexObject, Context, Enum, ID, InputObject, Interface,
+    Result as GqlResult, SimpleObject, Union,
+};
+use time::{Duration, OffsetDateTime};
 
 use crate::domain::models::{
-    Organization as OrgModel, Team as TeamModel, User,
+    App as AppModel, AppMembership, AppRole, AppSecret, AppSecretSyncSummary,
+    AppSecretVersion, BuildJob as BuildJobModel, BuildLog, BuildStatus,
+    BuildStep, BuildTrigger, Deploy as DeployModel, DeployStatus,
+    DeployStatusEvent, OrgRole, Organization as OrgModel, OrganizationInvite,
+    OrganizationMembership, Release as ReleaseModel, ReleaseStatus,
+    SecretKind, Team as TeamModel, TeamMembership, TeamRole, User,
+};
+use crate::graphql::auth_helpers::{
+    ANY_ORG_ROLE, get_current_user, has_org_role,
+};
+use crate::graphql::loaders::{OrganizationLoader, UserLoader};
+use crate::graphql::node::{NodeKind, encode_global_id};
+use crate::graphql::pagination::{page_limit, page_offset};
+use crate::graphql::state::AppState;
+use crate::infrastructure::repositories::{
+    AppRepository, BuildJobRepository, BuildStepRepository, DeployRepository,
 };
 
 // ------------ User ------------
@@ -20,14 +40,287 @@ impl From<User> for UserGql {
     }
 }
 
+// ------------ Memberships ------------
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Enum)]
+#[graphql(name = "OrgRole")]
+pub enum OrgRoleGql {
+    Owner,
+    Admin,
+    Member,
+    Billing,
+}
+
+impl From<OrgRole> for OrgRoleGql {
+    fn from(role: OrgRole) -> Self {
+        match role {
+            OrgRole::Owner => Self::Owner,
+            OrgRole::Admin => Self::Admin,
+            OrgRole::Member => Self::Member,
+            OrgRole::Billing => Self::Billing,
+        }
+    }
+}
+
+impl From<OrgRoleGql> for OrgRole {
+    fn from(role: OrgRoleGql) -> Self {
+        match role {
+            OrgRoleGql::Owner => Self::Owner,
+            OrgRoleGql::Admin => Self::Admin,
+            OrgRoleGql::Member => Self::Member,
+            OrgRoleGql::Billing => Self::Billing,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Enum)]
+#[graphql(name = "TeamRole")]
+pub enum TeamRoleGql {
+    Member,
+    Maintainer,
+    Lead,
+}
+
+impl From<TeamRole> for TeamRoleGql {
+    fn from(role: TeamRole) -> Self {
+        match role {
+            TeamRole::Member => Self::Member,
+            TeamRole::Maintainer => Self::Maintainer,
+            TeamRole::Lead => Self::Lead,
+        }
+    }
+}
+
+impl From<TeamRoleGql> for TeamRole {
+    fn from(role: TeamRoleGql) -> Self {
+        match role {
+            TeamRoleGql::Member => Self::Member,
+            TeamRoleGql::Maintainer => Self::Maintainer,
+            TeamRoleGql::Lead => Self::Lead,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Enum)]
+#[graphql(name = "AppRole")]
+pub enum AppRoleGql {
+    Owner,
+    Maintainer,
+    Deployer,
+    Viewer,
+}
+
+impl From<AppRole> for AppRoleGql {
+    fn from(role: AppRole) -> Self {
+        match role {
+            AppRole::Owner => Self::Owner,
+            AppRole::Maintainer => Self::Maintainer,
+            AppRole::Deployer => Self::Deployer,
+            AppRole::Viewer => Self::Viewer,
+        }
+    }
+}
+
+/// Resolve a member through the per-request user loader.
+async fn load_member(
+    ctx: &Context<'_>,
+    user_id: i64,
+) -> GqlResult<Option<UserGql>> {
+    let loader = ctx.data::<DataLoader<UserLoader>>()?;
+    let user = loader.load_one(user_id).await?;
+
+    Ok(user.map(Into::into))
+}
+
+/// Resolve the user behind an action; `null` for system actions (no user)
+/// and for deleted users.
+async fn load_actor(
+    ctx: &Context<'_>,
+    user_id: Option<i64>,
+) -> GqlResult<Option<UserGql>> {
+    match user_id {
+        Some(user_id) => load_member(ctx, user_id).await,
+        None => Ok(None),
+    }
+}
+
+#[derive(Debug, Clone, SimpleObject)]
+#[graphql(name = "OrganizationMembership", complex)]
+pub struct OrganizationMembershipGql {
+    pub organization_id: i64,
+    pub user_id: i64,
+    pub role: OrgRoleGql,
+    pub created_at: OffsetDateTime,
+}
+
+impl From<OrganizationMembership> for OrganizationMembershipGql {
+    fn from(m: OrganizationMembership) -> Self {
+        Self {
+            organization_id: m.organization_id,
+            user_id: m.user_id,
+            role: m.role.into(),
+            created_at: m.created_at,
+        }
+    }
+}
+
+#[ComplexObject]
+impl OrganizationMembershipGql {
+    /// The member (`null` if the user was deleted).
+    async fn user(&self, ctx: &Context<'_>) -> GqlResult<Option<UserGql>> {
+        load_member(ctx, self.user_id).await
+    }
+}
+
+/// A pending invitation of the current user to an organization.
+#[derive(Debug, Clone, SimpleObject)]
+#[graphql(name = "OrganizationInvitation", complex)]
+pub struct OrganizationInvitationGql {
+    pub id: i64,
+    pub organization_id: i64,
+    pub role: OrgRoleGql,
+    pub created_at: OffsetDateTime,
+    pub expires_at: OffsetDateTime,
+    #[graphql(skip)]
+    pub invited_by: Option<i64>,
+}
+
+impl From<OrganizationInvite> for OrganizationInvitationGql {
+    fn from(invite: OrganizationInvite) -> Self {
+        Self {
+            id: invite.id,
+            organization_id: invite.organization_id,
+            role: invite.role.into(),
+            created_at: invite.created_at,
+            expires_at: invite.expires_at,
+            invited_by: invite.invited_by,
+        }
+    }
+}
+
+#[ComplexObject]
+impl OrganizationInvitationGql {
+    /// The inviting organization, visible to the invitee before joining.
+    async fn organization(
+        &self,
+        ctx: &Context<'_>,
+    ) -> GqlResult<Option<OrganizationGql>> {
+        let loader = ctx.data::<DataLoader<OrganizationLoader>>()?;
+        let org = loader.load_one(self.organization_id).await?;
+
+        Ok(org.map(Into::into))
+    }
+
+    /// Who sent the invitation (`null` if unknown or deleted).
+    async fn invited_by(
+        &self,
+        ctx: &Context<'_>,
+    ) -> GqlResult<Option<UserGql>> {
+        load_actor(ctx, self.invited_by).await
+    }
+}
+
+/// What `inviteUserToOrganization` did.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Enum)]
+#[graphql(name = "InviteOutcome")]
+pub enum InviteOutcomeGql {
+    /// The email belongs to a user, who is now a member
+    Added,
+    /// No user has that email yet; once registered, they can accept it
+    /// from `myInvitations`
+    Invited,
+}
+
+#[derive(Debug, Clone, SimpleObject)]
+#[graphql(name = "InviteUserPayload")]
+pub struct InviteUserPayload {
+    pub outcome: InviteOutcomeGql,
+    /// The membership, when `outcome` is `ADDED`
+    pub membership: Option<OrganizationMembershipGql>,
+}
+
+#[derive(Debug, Clone, SimpleObject)]
+#[graphql(name = "TeamMembership", complex)]
+pub struct TeamMembershipGql {
+    pub team_id: i64,
+    pub user_id: i64,
+    pub role: TeamRoleGql,
+    pub created_at: OffsetDateTime,
+}
+
+impl From<TeamMembership> for TeamMembershipGql {
+    fn from(m: TeamMembership) -> Self {
+        Self {
+            team_id: m.team_id,
+            user_id: m.user_id,
+            role: m.role.into(),
+            created_at: m.created_at,
+        }
+    }
+}
+
+#[ComplexObject]
+impl TeamMembershipGql {
+    /// The member (`null` if the user was deleted).
+    async fn user(&self, ctx: &Context<'_>) -> GqlResult<Option<UserGql>> {
+        load_member(ctx, self.user_id).await
+    }
+}
+
+#[derive(Debug, Clone, SimpleObject)]
+#[graphql(name = "AppMembership", complex)]
+pub struct AppMembershipGql {
+    pub app_id: i64,
+    pub user_id: i64,
+    pub role: AppRoleGql,
+    pub created_at: OffsetDateTime,
+}
+
+impl From<AppMembership> for AppMembershipGql {
+    fn from(m: AppMembership) -> Self {
+        Self {
+            app_id: m.app_id,
+            user_id: m.user_id,
+            role: m.role.into(),
+            created_at: m.created_at,
+        }
+    }
+}
+
+#[ComplexObject]
+impl AppMembershipGql {
+    /// The member (`null` if the user was deleted).
+    async fn user(&self, ctx: &Context<'_>) -> GqlResult<Option<UserGql>> {
+        load_member(ctx, self.user_id).await
+    }
+}
+
+// ------------ Node ------------
+
+/// Objects addressable through `Query.node(globalId)`.
+///
+/// `globalId` is opaque; the numeric `id` fields are kept for existing
+/// clients.
+#[derive(Debug, Clone, Interface)]
+#[graphql(name = "Node", field(name = "global_id", ty = "ID"))]
+pub enum NodeGql {
+    Organization(OrganizationGql),
+    Team(TeamGql),
+    App(AppGql),
+}
+
 // GraphQL Organization exposed type
 #[derive(Debug, Clone, SimpleObject)]
-#[graphql(name = "Organization")]
+#[graphql(name = "Organization", complex)]
 pub struct OrganizationGql {
     pub id: i64,
     pub name: String,
     pub slug: String,
     pub description: Option<String>,
+    /// User who created the organization
+    pub created_by: Option<i64>,
+    /// Pass back as `expectedUpdatedAt` for optimistic concurrency
+    pub updated_at: OffsetDateTime,
 }
 
 impl From<OrgModel> for OrganizationGql {
@@ -37,19 +330,71 @@ impl From<OrgModel> for OrganizationGql {
             name: org.name,
             slug: org.slug,
             description: org.description,
+            created_by: org.created_by,
+            updated_at: org.updated_at,
+        }
+    }
+}
+
+#[ComplexObject]
+impl OrganizationGql {
+    /// Opaque id for `Query.node`.
+    async fn global_id(&self) -> ID {
+        ID(encode_global_id(NodeKind::Organization, self.id))
+    }
+
+    /// Apps of the organization across all teams, ordered by name.
+    ///
+    /// `limit` defaults to 20 (capped at 100). Members only.
+    async fn apps(
+        &self,
+        ctx: &Context<'_>,
+        limit: Option<i64>,
+        #[graphql(default = 0)] offset: i64,
+    ) -> GqlResult<Vec<AppGql>> {
+        let current = get_current_user(ctx).await?;
+        if !has_org_role(ctx, &current.user, self.id, ANY_ORG_ROLE).await? {
+            return Err(async_graphql::Error::new(
+                "Not a member of this organization",
+            ));
         }
+
+        let limit = page_limit("limit", limit)?;
+        let offset = page_offset("offset", offset)?;
+
+        let state = ctx.data::<AppState>()?;
+        let apps = AppRepository::new(state.pool.clone())
+            .list_page_by_organization(self.id, limit, offset)
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?;
+
+        Ok(apps.into_iter().map(Into::into).collect())
     }
 }
 
+/// An organization the current user belongs to, with their role in it.
+#[derive(Debug, Clone, SimpleObject)]
+#[graphql(name = "MyOrganization")]
+pub struct MyOrganizationGql {
+    pub organization: OrganizationGql,
+    pub role: OrgRoleGql,
+}
+
 // GraphQL Team exposed type
 #[derive(Debug, Clone, SimpleObject)]
-#[graphql(name = "Team")]
+#[graphql(name = "Team", complex)]
 pub struct TeamGql {
     pub id: i64,
     pub organization_id: i64,
     pub name: String,
     pub slug: String,
     pub description: Option<String>,
+    /// User who created the team
+    pub created_by: Option<i64>,
+    /// Pass back as `expectedUpdatedAt` for optimistic concurrency
+    pub updated_at: OffsetDateTime,
+    /// Set when the team was soft-deleted
+    pub deleted_at: Option<OffsetDateTime>,
 }
 
 impl From<TeamModel> for TeamGql {
@@ -60,7 +405,107 @@ impl From<TeamModel> for TeamGql {
             name: team.name,
             slug: team.slug,
             description: team.description,
+            created_by: team.created_by,
+            updated_at: team.updated_at,
+            deleted_at: team.deleted_at,
+        }
+    }
+}
+
+#[ComplexObject]
+impl TeamGql {
+    /// Opaque id for `Query.node`.
+    async fn global_id(&self) -> ID {
+        ID(encode_global_id(NodeKind::Team, self.id))
+    }
+
+    /// Organization that owns this team.
+    async fn organization(
+        &self,
+        ctx: &Context<'_>,
+    ) -> GqlResult<Option<OrganizationGql>> {
+        let loader = ctx.data::<DataLoader<OrganizationLoader>>()?;
+        let org = loader.load_one(self.organization_id).await?;
+
+        Ok(org.map(Into::into))
+    }
+}
+
+// GraphQL App exposed type
+#[derive(Debug, Clone, SimpleObject)]
+#[graphql(name = "App", complex)]
+pub struct AppGql {
+    pub id: i64,
+    pub organization_id: i64,
+    pub team_id: Option<i64>,
+    pub name: String,
+    pub slug: String,
+    pub repo_url: Option<String>,
+    /// Pass back as `expectedUpdatedAt` for optimistic concurrency
+    pub updated_at: OffsetDateTime,
+    #[graphql(skip)]
+    pub created_by: Option<i64>,
+}
+
+impl From<AppModel> for AppGql {
+    fn from(app: AppModel) -> Self {
+        Self {
+            id: app.id,
+            organization_id: app.organization_id,
+            team_id: app.team_id,
+            name: app.name,
+            slug: app.slug,
+            repo_url: app.repo_url,
+            updated_at: app.updated_at,
+            created_by: app.created_by,
+        }
+    }
+}
+
+#[ComplexObject]
+impl AppGql {
+    /// Opaque id for `Query.node`.
+    async fn global_id(&self) -> ID {
+        ID(encode_global_id(NodeKind::App, self.id))
+    }
+
+    /// User who created the app (`null` if unknown or deleted).
+    async fn created_by(
+        &self,
+        ctx: &Context<'_>,
+    ) -> GqlResult<Option<UserGql>> {
+        load_actor(ctx, self.created_by).await
+    }
+
+    /// Total number of builds for this app.
+    async fn build_count(&self, ctx: &Context<'_>) -> GqlResult<i64> {
+        let state = ctx.data::<AppState>()?;
+        let repo = BuildJobRepository::new(state.pool.clone());
+
+        repo.count_by_app(self.id)
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))
+    }
+
+    /// Number of deploys for this app over the last `last_days` days.
+    async fn deploy_count(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(default = 30)] last_days: i64,
+    ) -> GqlResult<i64> {
+        if !(1..=3650).contains(&last_days) {
+            return Err(async_graphql::Error::new(
+                "lastDays must be between 1 and 3650",
+            ));
         }
+
+        let state = ctx.data::<AppState>()?;
+        let repo = DeployRepository::new(state.pool.clone());
+        let since = OffsetDateTime::now_utc() - Duration::days(last_days);
+
+        repo.count_by_app_since(self.id, since)
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))
     }
 }
 
@@ -72,16 +517,62 @@ pub struct AccessTokenGql {
     /// Token string that the CLI must store and send in the Authorization header.
     pub token: String,
     pub description: Option<String>,
+    pub expires_at: Option<OffsetDateTime>,
 }
 
 // -------- Inputs --------
 
+/// A secret with `$KEY` references expanded (read-time only)
+#[derive(Debug, Clone, SimpleObject)]
+#[graphql(name = "ResolvedSecret")]
+pub struct ResolvedSecretGql {
+    pub key: String,
+    pub value: String,
+    /// `FILE` values are used verbatim (no reference expansion)
+    #[graphql(name = "type")]
+    pub kind: SecretKindGql,
+}
+
+/// A hit of `Query.search`
+#[derive(Debug, Clone, Union)]
+#[graphql(name = "SearchResult")]
+pub enum SearchResultGql {
+    Organization(OrganizationGql),
+    Team(TeamGql),
+    App(AppGql),
+}
+
 #[derive(Debug, InputObject)]
 pub struct RegisterUserInput {
     pub name: String,
     pub email: String,
-    /// Plain password for now. You should hash it before storing.
+    /// Plain password; hashed with Argon2 before storing.
     pub password: String,
+    /// Lifetime of the returned token (default and max: 90 days)
+    pub ttl_days: Option<i64>,
+}
+
+#[derive(Debug, InputObject)]
+pub struct ChangePasswordInput {
+    pub current_password: String,
+    pub new_password: String,
+    /// Revoke every other auth token (the current one is kept)
+    #[graphql(default = true)]
+    pub revoke_other_sessions: bool,
+}
+
+#[derive(Debug, SimpleObject)]
+#[graphql(name = "ChangePasswordPayload")]
+pub struct ChangePasswordPayload {
+    /// Number of other sessions (auth tokens) that were revoked
+    pub revoked_sessions: i64,
+}
+
+#[derive(Debug, InputObject)]
+pub struct ResetPasswordInput {
+    /// Token received from `requestPasswordReset`
+    pub token: String,
+    pub new_password: String,
 }
 
 #[derive(Debug, SimpleObject)]
@@ -105,3 +596,743 @@ pub struct CreateTeamInput {
     pub slug: String,
     pub description: Option<String>,
 }
+
+/// Fields left out (or `null`) are kept as they are.
+#[derive(Debug, InputObject)]
+pub struct UpdateOrganizationInput {
+    pub id: i64,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    /// `updatedAt` last seen by the client; the update is rejected with
+    /// `CONFLICT` if the organization was modified since
+    pub expected_updated_at: Option<OffsetDateTime>,
+}
+
+/// Fields left out (or `null`) are kept as they are.
+#[derive(Debug, InputObject)]
+pub struct UpdateTeamInput {
+    pub id: i64,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    /// `updatedAt` last seen by the client; the update is rejected with
+    /// `CONFLICT` if the team was modified since
+    pub expected_updated_at: Option<OffsetDateTime>,
+}
+
+// ------------ Secrets ------------
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Enum)]
+#[graphql(name = "SecretType")]
+pub enum SecretKindGql {
+    /// Environment variable value
+    String,
+    /// File contents, written to disk at deploy time
+    File,
+}
+
+impl From<SecretKind> for SecretKindGql {
+    fn from(kind: SecretKind) -> Self {
+        match kind {
+            SecretKind::String => Self::String,
+            SecretKind::File => Self::File,
+        }
+    }
+}
+
+impl From<SecretKindGql> for SecretKind {
+    fn from(kind: SecretKindGql) -> Self {
+        match kind {
+            SecretKindGql::String => Self::String,
+            SecretKindGql::File => Self::File,
+        }
+    }
+}
+
+/// Secret metadata; the value is never returned here.
+#[derive(Debug, Clone, SimpleObject)]
+#[graphql(name = "AppSecret")]
+pub struct AppSecretGql {
+    pub id: i64,
+    pub app_id: i64,
+    pub environment: String,
+    pub key: String,
+    #[graphql(name = "type")]
+    pub kind: SecretKindGql,
+    /// Size of the value in bytes
+    pub size: i64,
+    pub updated_at: OffsetDateTime,
+}
+
+impl From<AppSecret> for AppSecretGql {
+    fn from(secret: AppSecret) -> Self {
+        Self {
+            id: secret.id,
+            app_id: secret.app_id,
+            environment: secret.environment,
+            key: secret.key,
+            kind: secret.kind.into(),
+            size: secret.value.len() as i64,
+            updated_at: secret.updated_at,
+        }
+    }
+}
+
+/// Shown instead of the value to users who may not manage secrets.
+pub const MASKED_SECRET_VALUE: &str = "***";
+
+/// A secret as listed by `appSecrets`.
+#[derive(Debug, Clone, SimpleObject)]
+#[graphql(name = "AppSecretEntry")]
+pub struct AppSecretEntryGql {
+    pub environment: String,
+    pub key: String,
+    #[graphql(name = "type")]
+    pub kind: SecretKindGql,
+    /// `***` unless the caller may manage the app's secrets
+    pub value: String,
+    pub created_by: Option<i64>,
+    pub updated_at: OffsetDateTime,
+}
+
+impl AppSecretEntryGql {
+    pub fn new(secret: AppSecret, reveal: bool) -> Self {
+        Self {
+            environment: secret.environment,
+            key: secret.key,
+            kind: secret.kind.into(),
+            value: if reveal {
+                secret.value
+            } else {
+                MASKED_SECRET_VALUE.to_string()
+            },
+            created_by: secret.created_by,
+            updated_at: secret.updated_at,
+        }
+    }
+}
+
+/// A previous value of a secret.
+#[derive(Debug, Clone, SimpleObject)]
+#[graphql(name = "AppSecretVersion")]
+pub struct AppSecretVersionGql {
+    pub version: i32,
+    pub environment: String,
+    pub key: String,
+    #[graphql(name = "type")]
+    pub kind: SecretKindGql,
+    /// Only returned to organization owners/admins
+    pub value: Option<String>,
+    /// Size of the value in bytes
+    pub size: i64,
+    pub created_by: Option<i64>,
+    pub replaced_at: OffsetDateTime,
+}
+
+impl AppSecretVersionGql {
+    pub fn new(version: AppSecretVersion, reveal: bool) -> Self {
+        Self {
+            version: version.version,
+            environment: version.environment,
+            key: version.key,
+            kind: version.kind.into(),
+            size: version.value.len() as i64,
+            value: reveal.then_some(version.value),
+            created_by: version.created_by,
+            replaced_at: version.replaced_at,
+        }
+    }
+}
+
+#[derive(Debug, InputObject)]
+pub struct SetAppSecretInput {
+    pub app_id: i64,
+    #[graphql(default_with = "\"default\".to_string()")]
+    pub environment: String,
+    pub key: String,
+    /// May span several lines (PEM, JSON...)
+    pub value: String,
+    #[graphql(name = "type", default_with = "SecretKindGql::String")]
+    pub kind: SecretKindGql,
+}
+
+/// One key of [`SyncAppSecretsInput`].
+#[derive(Debug, InputObject)]
+pub struct SecretEntryInput {
+    pub key: String,
+    pub value: String,
+    #[graphql(name = "type", default_with = "SecretKindGql::String")]
+    pub kind: SecretKindGql,
+}
+
+/// Desired full set of secrets of an app environment.
+#[derive(Debug, InputObject)]
+pub struct SyncAppSecretsInput {
+    pub app_id: i64,
+    #[graphql(default_with = "\"default\".to_string()")]
+    pub environment: String,
+    pub secrets: Vec<SecretEntryInput>,
+    /// Delete keys that are not in `secrets`
+    #[graphql(default = false)]
+    pub prune: bool,
+}
+
+/// Keys changed by `updateAppSecretsBulk`, sorted.
+#[derive(Debug, Clone, SimpleObject)]
+#[graphql(name = "AppSecretsSyncSummary")]
+pub struct AppSecretsSyncSummaryGql {
+    pub added: Vec<String>,
+    pub updated: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+impl From<AppSecretSyncSummary> for AppSecretsSyncSummaryGql {
+    fn from(summary: AppSecretSyncSummary) -> Self {
+        Self {
+            added: summary.added,
+            updated: summary.updated,
+            removed: summary.removed,
+        }
+    }
+}
+
+// ------------ Releases / Deploys ------------
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Enum)]
+#[graphql(name = "ReleaseStatus")]
+pub enum ReleaseStatusGql {
+    Pending,
+    Building,
+    Built,
+    Failed,
+}
+
+impl From<ReleaseStatus> for ReleaseStatusGql {
+    fn from(status: ReleaseStatus) -> Self {
+        match status {
+            ReleaseStatus::Pending => Self::Pending,
+            ReleaseStatus::Building => Self::Building,
+            ReleaseStatus::Built => Self::Built,
+            ReleaseStatus::Failed => Self::Failed,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Enum)]
+#[graphql(name = "DeployStatus")]
+pub enum DeployStatusGql {
+    Pending,
+    Running,
+    Succeeded,
+    Failed,
+    Canceled,
+}
+
+impl From<DeployStatus> for DeployStatusGql {
+    fn from(status: DeployStatus) -> Self {
+        match status {
+            DeployStatus::Pending => Self::Pending,
+            DeployStatus::Running => Self::Running,
+            DeployStatus::Succeeded => Self::Succeeded,
+            DeployStatus::Failed => Self::Failed,
+            DeployStatus::Canceled => Self::Canceled,
+        }
+    }
+}
+
+// GraphQL Release exposed type
+#[derive(Debug, Clone, SimpleObject)]
+#[graphql(name = "Release", complex)]
+pub struct ReleaseGql {
+    pub id: i64,
+    pub app_id: i64,
+    pub version: String,
+    pub commit_sha: Option<String>,
+    pub branch: Option<String>,
+    pub tag: Option<String>,
+    pub image_ref: Option<String>,
+    pub status: ReleaseStatusGql,
+    pub changelog: Option<String>,
+    /// When the release last changed status
+    pub status_changed_at: OffsetDateTime,
+    /// User behind the last status change (`null` for system changes)
+    pub status_changed_by: Option<i64>,
+    #[graphql(skip)]
+    pub created_by: Option<i64>,
+}
+
+impl From<ReleaseModel> for ReleaseGql {
+    fn from(release: ReleaseModel) -> Self {
+        Self {
+            id: release.id,
+            app_id: release.app_id,
+            version: release.version,
+            commit_sha: release.commit_sha,
+            branch: release.branch,
+            tag: release.tag,
+            image_ref: release.image_ref,
+            status: release.status.into(),
+            changelog: release.changelog,
+            status_changed_at: release.status_changed_at,
+            status_changed_by: release.status_changed_by,
+            created_by: release.created_by,
+        }
+    }
+}
+
+#[ComplexObject]
+impl ReleaseGql {
+    /// User who created the release (`null` for system-created releases).
+    async fn created_by(
+        &self,
+        ctx: &Context<'_>,
+    ) -> GqlResult<Option<UserGql>> {
+        load_actor(ctx, self.created_by).await
+    }
+}
+
+// GraphQL Deploy exposed type
+#[derive(Debug, Clone, SimpleObject)]
+#[graphql(name = "Deploy", complex)]
+pub struct DeployGql {
+    pub id: i64,
+    pub app_id: i64,
+    pub release_id: i64,
+    pub environment: String,
+    pub status: DeployStatusGql,
+    pub error_message: Option<String>,
+    /// When the deploy last changed status
+    pub status_changed_at: OffsetDateTime,
+    /// User behind the last status change (`null` for system changes)
+    pub status_changed_by: Option<i64>,
+    #[graphql(skip)]
+    pub triggered_by: Option<i64>,
+}
+
+impl From<DeployModel> for DeployGql {
+    fn from(deploy: DeployModel) -> Self {
+        Self {
+            id: deploy.id,
+            app_id: deploy.app_id,
+            release_id: deploy.release_id,
+            environment: deploy.environment,
+            status: deploy.status.into(),
+            error_message: deploy.error_message,
+            status_changed_at: deploy.status_changed_at,
+            status_changed_by: deploy.status_changed_by,
+            triggered_by: deploy.triggered_by,
+        }
+    }
+}
+
+#[ComplexObject]
+impl DeployGql {
+    /// User who triggered the deploy (`null` for system-triggered ones).
+    async fn triggered_by(
+        &self,
+        ctx: &Context<'_>,
+    ) -> GqlResult<Option<UserGql>> {
+        load_actor(ctx, self.triggered_by).await
+    }
+
+    /// Every status transition of this deploy, oldest first.
+    async fn status_history(
+        &self,
+        ctx: &Context<'_>,
+    ) -> GqlResult<Vec<DeployStatusEventGql>> {
+        let state = ctx.data::<AppState>()?;
+        let repo = DeployRepository::new(state.pool.clone());
+
+        let events = repo
+            .list_status_events(self.id)
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?;
+
+        Ok(events.into_iter().map(Into::into).collect())
+    }
+}
+
+/// A single deploy status transition
+#[derive(Debug, Clone, SimpleObject)]
+#[graphql(name = "DeployStatusEvent")]
+pub struct DeployStatusEventGql {
+    /// `null` for the initial status
+    pub from_status: Option<DeployStatusGql>,
+    pub to_status: DeployStatusGql,
+    pub changed_by: Option<i64>,
+    pub changed_at: OffsetDateTime,
+}
+
+impl From<DeployStatusEvent> for DeployStatusEventGql {
+    fn from(event: DeployStatusEvent) -> Self {
+        Self {
+            from_status: event.from_status.map(Into::into),
+            to_status: event.to_status.into(),
+            changed_by: event.changed_by,
+            changed_at: event.created_at,
+        }
+    }
+}
+
+// ------------ Builds ------------
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Enum)]
+#[graphql(name = "BuildStatus")]
+pub enum BuildStatusGql {
+    Pending,
+    Running,
+    Succeeded,
+    Failed,
+    Canceled,
+}
+
+impl From<BuildStatus> for BuildStatusGql {
+    fn from(status: BuildStatus) -> Self {
+        match status {
+            BuildStatus::Pending => Self::Pending,
+            BuildStatus::Running => Self::Running,
+            BuildStatus::Succeeded => Self::Succeeded,
+            BuildStatus::Failed => Self::Failed,
+            BuildStatus::Canceled => Self::Canceled,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Enum)]
+#[graphql(name = "BuildTrigger")]
+pub enum BuildTriggerGql {
+    Manual,
+    GitPush,
+    Api,
+}
+
+impl From<BuildTrigger> for BuildTriggerGql {
+    fn from(trigger: BuildTrigger) -> Self {
+        match trigger {
+            BuildTrigger::Manual => Self::Manual,
+            BuildTrigger::GitPush => Self::GitPush,
+            BuildTrigger::Api => Self::Api,
+        }
+    }
+}
+
+// GraphQL BuildJob exposed type
+#[derive(Debug, Clone, SimpleObject)]
+#[graphql(name = "BuildJob", complex)]
+pub struct BuildJobGql {
+    pub id: i64,
+    pub app_id: i64,
+    pub release_id: Option<i64>,
+    pub status: BuildStatusGql,
+    pub trigger: BuildTriggerGql,
+    pub triggered_by: Option<i64>,
+    pub commit_sha: Option<String>,
+    pub branch: Option<String>,
+    pub tag: Option<String>,
+    pub image_ref: Option<String>,
+    pub created_at: OffsetDateTime,
+    pub started_at: Option<OffsetDateTime>,
+    pub finished_at: Option<OffsetDateTime>,
+    pub error_message: Option<String>,
+}
+
+impl From<BuildJobModel> for BuildJobGql {
+    fn from(job: BuildJobModel) -> Self {
+        Self {
+            id: job.id,
+            app_id: job.app_id,
+            release_id: job.release_id,
+            status: job.status.into(),
+            trigger: job.trigger.into(),
+            triggered_by: job.triggered_by,
+            commit_sha: job.commit_sha,
+            branch: job.branch,
+            tag: job.tag,
+            image_ref: job.image_ref,
+            created_at: job.created_at,
+            started_at: job.started_at,
+            finished_at: job.finished_at,
+            error_message: job.error_message,
+        }
+    }
+}
+
+#[ComplexObject]
+impl BuildJobGql {
+    /// Steps in position order; steps sharing a `group` run in parallel.
+    async fn steps(&self, ctx: &Context<'_>) -> GqlResult<Vec<BuildStepGql>> {
+        let state = ctx.data::<AppState>()?;
+        let steps = BuildStepRepository::new(state.pool.clone())
+            .list_by_build(self.id)
+            .await
+            .map_err(|e| async_graphql::Error::new(e.to_string()))?;
+
+        Ok(steps.into_iter().map(Into::into).collect())
+    }
+}
+
+/// One step of a build.
+#[derive(Debug, Clone, SimpleObject)]
+#[graphql(name = "BuildStep")]
+pub struct BuildStepGql {
+    pub id: i64,
+    pub build_id: i64,
+    pub position: i32,
+    /// Steps sharing a group run in parallel; `null` runs alone
+    pub group: Option<i32>,
+    pub name: String,
+    pub status: BuildStatusGql,
+    pub started_at: Option<OffsetDateTime>,
+    pub finished_at: Option<OffsetDateTime>,
+    pub logs_url: Option<String>,
+    pub error_message: Option<String>,
+}
+
+impl From<BuildStep> for BuildStepGql {
+    fn from(step: BuildStep) -> Self {
+        Self {
+            id: step.id,
+            build_id: step.build_id,
+            position: step.position,
+            group: step.group,
+            name: step.name,
+            status: step.status.into(),
+            started_at: step.started_at,
+            finished_at: step.finished_at,
+            logs_url: step.logs_url,
+            error_message: step.error_message,
+        }
+    }
+}
+
+/// A chunk of build output.
+#[derive(Debug, Clone, SimpleObject)]
+#[graphql(name = "BuildLog")]
+pub struct BuildLogGql {
+    pub id: i64,
+    pub build_id: i64,
+    pub step_id: Option<i64>,
+    pub chunk_index: i32,
+    pub content: String,
+    pub created_at: OffsetDateTime,
+}
+
+impl From<BuildLog> for BuildLogGql {
+    fn from(log: BuildLog) -> Self {
+        Self {
+            id: log.id,
+            build_id: log.build_id,
+            step_id: log.step_id,
+            chunk_index: log.chunk_index,
+            content: log.content,
+            created_at: log.created_at,
+        }
+    }
+}
+
+/// What the current user may do on an app.
+#[derive(Debug, Clone, SimpleObject)]
+#[graphql(name = "AppPermissions")]
+pub struct AppPermissionsGql {
+    pub can_deploy: bool,
+    pub can_manage_secrets: bool,
+    pub can_manage_members: bool,
+    pub can_delete: bool,
+}
+
+#[derive(Debug, InputObject)]
+pub struct CreateAppInput {
+    /// Organization that owns this app
+    pub organization_id: i64,
+    /// Team to attach the app to, if any
+    pub team_id: Option<i64>,
+    pub name: String,
+    /// Unique within the organization
+    pub slug: String,
+    pub repo_url: Option<String>,
+}
+
+#[derive(Debug, InputObject)]
+pub struct UpdateAppInput {
+    pub id: i64,
+    pub name: Option<String>,
+    pub repo_url: Option<String>,
+    /// `updatedAt` last seen by the client; the update is rejected with
+    /// `CONFLICT` if the app was modified since
+    pub expected_updated_at: Option<OffsetDateTime>,
+    /// Queue a build (trigger `API`) from the new `repoUrl`
+    #[graphql(default = false)]
+    pub rebuild: bool,
+}
+
+#[derive(Debug, InputObject)]
+pub struct PromoteBuildInput {
+    /// Succeeded build job whose image becomes the release artifact
+    pub build_id: i64,
+    /// Release version; defaults to `build-<id>`
+    pub version: Option<String>,
+    pub changelog: Option<String>,
+    /// When set, the build's app must belong to this organization
+    pub organization_id: Option<i64>,
+}
+
+#[derive(Debug, InputObject)]
+pub struct CreateDeployInput {
+    pub app_id: i64,
+    pub release_id: i64,
+    pub environment: String,
+}
+
+#[derive(Debug, InputObject)]
+pub struct CreateDeploysInput {
+    pub app_id: i64,
+    pub release_id: i64,
+    pub environments: Vec<String>,
+    /// Skip environments with a deploy in progress instead of failing
+    #[graphql(default = false)]
+    pub skip_busy: bool,
+}
+
+#[derive(Debug, Clone, SimpleObject)]
+pub struct CreateDeploysPayload {
+    pub deploys: Vec<DeployGql>,
+    /// Environments left out because a deploy was already in progress
+    pub skipped_environments: Vec<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use async_graphql::{EmptyMutation, EmptySubscription, Schema, value};
+    use sqlx::{PgPool, query_scalar};
+
+    use super::*;
+
+    #[derive(SimpleObject)]
+    struct Memberships {
+        org: OrganizationMembershipGql,
+        team: TeamMembershipGql,
+        app: AppMembershipGql,
+    }
+
+    async fn user(pool: &PgPool, email: &str) -> i64 {
+        query_scalar(
+            r#"
+            INSERT INTO users (name, email, password_hash)
+            VALUES ($1, $1, 'x')
+            RETURNING id
+            "#,
+        )
+        .bind(email)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+    }
+
+    #[sqlx::test]
+    async fn memberships_serialize_with_roles_and_users(pool: PgPool) {
+        let ada = user(&pool, "ada@example.com").await;
+        let created_at = OffsetDateTime::now_utc();
+        let root = Memberships {
+            org: OrganizationMembership {
+                organization_id: 1,
+                user_id: ada,
+                role: OrgRole::Billing,
+                created_at,
+            }
+            .into(),
+            team: TeamMembership {
+                team_id: 2,
+                user_id: ada,
+                role: TeamRole::Maintainer,
+                created_at,
+            }
+            .into(),
+            app: AppMembership {
+                app_id: 3,
+                user_id: ada,
+                role: AppRole::Deployer,
+                created_at,
+            }
+            .into(),
+        };
+        let schema = Schema::build(root, EmptyMutation, EmptySubscription)
+            .data(DataLoader::new(UserLoader::new(pool), tokio::spawn))
+            .finish();
+
+        let response = schema
+            .execute(
+                "{
+                    org { organizationId role user { email } }
+                    team { teamId role user { email } }
+                    app { appId role user { email } }
+                }",
+            )
+            .await;
+
+        assert!(response.errors.is_empty(), "{:?}", response.errors);
+        assert_eq!(
+            response.data,
+            value!({
+                "org": {
+                    "organizationId": 1,
+                    "role": "BILLING",
+                    "user": { "email": "ada@example.com" },
+                },
+                "team": {
+                    "teamId": 2,
+                    "role": "MAINTAINER",
+                    "user": { "email": "ada@example.com" },
+                },
+                "app": {
+                    "appId": 3,
+                    "role": "DEPLOYER",
+                    "user": { "email": "ada@example.com" },
+                },
+            })
+        );
+    }
+
+    #[sqlx::test]
+    async fn deleted_members_serialize_as_null_users(pool: PgPool) {
+        let ada = user(&pool, "ada@example.com").await;
+        sqlx::query("UPDATE users SET deleted_at = now() WHERE id = $1")
+            .bind(ada)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let membership: AppMembershipGql = AppMembership {
+            app_id: 3,
+            user_id: ada,
+            role: AppRole::Viewer,
+            created_at: OffsetDateTime::now_utc(),
+        }
+        .into();
+        let root = Memberships {
+            org: OrganizationMembership {
+                organization_id: 1,
+                user_id: ada,
+                role: OrgRole::Owner,
+                created_at: membership.created_at,
+            }
+            .into(),
+            team: TeamMembership {
+                team_id: 2,
+                user_id: ada,
+                role: TeamRole::Lead,
+                created_at: membership.created_at,
+            }
+            .into(),
+            app: membership,
+        };
+        let schema = Schema::build(root, EmptyMutation, EmptySubscription)
+            .data(DataLoader::new(UserLoader::new(pool), tokio::spawn))
+            .finish();
+
+        let response = schema.execute("{ app { role user { email } } }").await;
+
+        assert!(response.errors.is_empty(), "{:?}", response.errors);
+        assert_eq!(
+            response.data,
+            value!({ "app": { "role": "VIEWER", "user": null } })
+        );
+    }
+}
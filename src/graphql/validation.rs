@@ -0,0 +1,355 @@
+use std::collections::BTreeMap;
+
+use async_graphql::{Error as GqlError, ErrorExtensions, Name, Value};
+use time::OffsetDateTime;
+
+const SLUG_MAX_LEN: usize = 63;
+const PASSWORD_MIN_LEN: usize = 8;
+const SECRET_KEY_MAX_LEN: usize = 255;
+
+/// Field-level validation problems collected for a single input.
+///
+/// Validators push into this instead of returning early, so a client gets
+/// every problem at once. The resulting GraphQL error carries a
+/// `{field: message}` map under `extensions.fields`.
+#[derive(Debug, Default)]
+pub struct ValidationErrors {
+    errors: BTreeMap<String, String>,
+}
+
+impl ValidationErrors {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a problem for `field`. The first message per field wins.
+    pub fn add(&mut self, field: &str, message: impl Into<String>) {
+        self.errors.entry(field.to_string()).or_insert_with(|| message.into());
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// `Ok(())` when nothing was recorded, the aggregated error otherwise.
+    pub fn into_result(self) -> Result<(), GqlError> {
+        if self.is_empty() { Ok(()) } else { Err(self.into()) }
+    }
+
+    pub fn validate_slug(&mut self, field: &str, slug: &str) {
+        if slug.is_empty() || slug.len() > SLUG_MAX_LEN {
+            self.add(
+                field,
+                format!("must be between 1 and {SLUG_MAX_LEN} characters"),
+            );
+        } else if !slug
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+            || slug.starts_with('-')
+            || slug.ends_with('-')
+        {
+            self.add(
+                field,
+                "must contain only lowercase letters, digits and inner hyphens",
+            );
+        }
+    }
+
+    /// `since` must come before `until` when both are given.
+    pub fn validate_time_range(
+        &mut self,
+        since: Option<OffsetDateTime>,
+        until: Option<OffsetDateTime>,
+    ) {
+        if let (Some(since), Some(until)) = (since, until)
+            && since >= until
+        {
+            self.add("since", "must be before until");
+        }
+    }
+
+    pub fn validate_name(&mut self, field: &str, name: &str) {
+        if name.trim().is_empty() {
+            self.add(field, "must not be empty");
+        }
+    }
+
+    pub fn validate_email(&mut self, field: &str, email: &str) {
+        let valid = match email.split_once('@') {
+            Some((local, domain)) => {
+                !local.is_empty()
+                    && !domain.contains('@')
+                    && domain.contains('.')
+                    && !domain.starts_with('.')
+                    && !domain.ends_with('.')
+            }
+            None => false,
+        };
+
+        if !valid {
+            self.add(field, "must be a valid email address");
+        }
+    }
+
+    /// Secret keys are environment variable names: `[A-Za-z_][A-Za-z0-9_]*`.
+    pub fn validate_secret_key(&mut self, field: &str, key: &str) {
+        if key.is_empty() || key.len() > SECRET_KEY_MAX_LEN {
+            self.add(
+                field,
+                format!(
+                    "must be between 1 and {SECRET_KEY_MAX_LEN} characters"
+                ),
+            );
+        } else if key.starts_with(|c: char| c.is_ascii_digit())
+            || !key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        {
+            self.add(
+                field,
+                "must contain only letters, digits and underscores, \
+                 not starting with a digit",
+            );
+        }
+    }
+
+    /// Git remotes: `https://host/path`, `ssh://[user@]host[:port]/path`
+    /// or the scp-like `user@host:path`.
+    pub fn validate_repo_url(&mut self, field: &str, url: &str) {
+        let host_and_path = |rest: &str| match rest.split_once('/') {
+            Some((host, path)) => !host.is_empty() && !path.is_empty(),
+            None => false,
+        };
+
+        let valid = !url.chars().any(char::is_whitespace)
+            && if let Some(rest) = url.strip_prefix("https://") {
+                host_and_path(rest)
+            } else if let Some(rest) = url.strip_prefix("ssh://") {
+                host_and_path(rest)
+            } else if url.contains("://") {
+                false
+            } else {
+                match url.split_once(':') {
+                    Some((user_host, path)) => match user_host.split_once('@')
+                    {
+                        Some((user, host)) => {
+                            !user.is_empty()
+                                && !host.is_empty()
+                                && !path.is_empty()
+                        }
+                        None => false,
+                    },
+                    None => false,
+                }
+            };
+
+        if !valid {
+            self.add(
+                field,
+                "must be an https:// or ssh:// git URL (or user@host:path)",
+            );
+        }
+    }
+
+    pub fn validate_password(&mut self, field: &str, password: &str) {
+        if password.chars().count() < PASSWORD_MIN_LEN {
+            self.add(
+                field,
+                format!("must be at least {PASSWORD_MIN_LEN} characters"),
+            );
+        }
+    }
+}
+
+impl From<ValidationErrors> for GqlError {
+    fn from(errors: ValidationErrors) -> Self {
+        let summary = errors
+            .errors
+            .iter()
+            .map(|(field, message)| format!("{field} {message}"))
+            .collect::<Vec<_>>()
+            .join("; ");
+
+        let fields = Value::Object(
+            errors
+                .errors
+                .into_iter()
+                .map(|(field, message)| {
+                    (Name::new(field), Value::String(message))
+                })
+                .collect(),
+        );
+
+        GqlError::new(format!("Invalid input: {summary}")).extend_with(
+            |_, ext| {
+                ext.set("code", "BAD_USER_INPUT");
+                ext.set("fields", fields.clone());
+            },
+        )
+    }
+}
+
+/// Canonical form of an email address: trimmed and lowercased.
+///
+/// Applied before storing or looking up an email, so `Ada@Example.com `
+/// and `ada@example.com` are the same account.
+pub fn normalize_email(email: &str) -> String {
+    email.trim().to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn messages(errors: &ValidationErrors) -> Vec<(&str, &str)> {
+        errors
+            .errors
+            .iter()
+            .map(|(field, message)| (field.as_str(), message.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn nothing_recorded_is_ok() {
+        let errors = ValidationErrors::new();
+
+        assert!(errors.is_empty());
+        assert!(errors.into_result().is_ok());
+    }
+
+    #[test]
+    fn first_message_per_field_wins() {
+        let mut errors = ValidationErrors::new();
+        errors.add("name", "first");
+        errors.add("name", "second");
+        errors.add("slug", "other");
+
+        assert_eq!(messages(&errors), [("name", "first"), ("slug", "other")]);
+    }
+
+    #[test]
+    fn error_carries_code_and_every_field() {
+        let mut errors = ValidationErrors::new();
+        errors.validate_name("name", "  ");
+        errors.validate_password("password", "short");
+
+        let error = errors.into_result().unwrap_err();
+        assert_eq!(
+            error.message,
+            "Invalid input: name must not be empty; \
+             password must be at least 8 characters"
+        );
+
+        let extensions = error.extensions.unwrap();
+        assert_eq!(
+            extensions.get("code"),
+            Some(&Value::String("BAD_USER_INPUT".into()))
+        );
+        let Some(Value::Object(fields)) = extensions.get("fields") else {
+            panic!("fields is not an object");
+        };
+        assert_eq!(
+            fields.keys().map(Name::as_str).collect::<Vec<_>>(),
+            ["name", "password"]
+        );
+    }
+
+    #[test]
+    fn slugs() {
+        for valid in ["a", "acme", "acme-2", &"a".repeat(SLUG_MAX_LEN)] {
+            let mut errors = ValidationErrors::new();
+            errors.validate_slug("slug", valid);
+            assert!(errors.is_empty(), "{valid:?} rejected");
+        }
+        for invalid in [
+            "",
+            "Acme",
+            "-acme",
+            "acme-",
+            "ac me",
+            &"a".repeat(SLUG_MAX_LEN + 1),
+        ] {
+            let mut errors = ValidationErrors::new();
+            errors.validate_slug("slug", invalid);
+            assert!(!errors.is_empty(), "{invalid:?} accepted");
+        }
+    }
+
+    #[test]
+    fn emails() {
+        for valid in ["ada@example.com", "a.b+c@mail.example.org"] {
+            let mut errors = ValidationErrors::new();
+            errors.validate_email("email", valid);
+            assert!(errors.is_empty(), "{valid:?} rejected");
+        }
+        for invalid in
+            ["", "ada", "@example.com", "ada@example", "ada@.com", "a@b@c.com"]
+        {
+            let mut errors = ValidationErrors::new();
+            errors.validate_email("email", invalid);
+            assert!(!errors.is_empty(), "{invalid:?} accepted");
+        }
+    }
+
+    #[test]
+    fn password_length_counts_characters() {
+        let mut errors = ValidationErrors::new();
+        errors.validate_password("password", "ééééééé");
+        assert!(!errors.is_empty());
+
+        let mut errors = ValidationErrors::new();
+        errors.validate_password("password", "éééééééé");
+        assert!(errors.is_empty());
+    }
+
+    #[test]
+    fn secret_keys() {
+        for valid in ["DATABASE_URL", "_private", "a1"] {
+            let mut errors = ValidationErrors::new();
+            errors.validate_secret_key("key", valid);
+            assert!(errors.is_empty(), "{valid:?} rejected");
+        }
+        for invalid in ["", "1KEY", "MY-KEY", "MY KEY", &"K".repeat(256)] {
+            let mut errors = ValidationErrors::new();
+            errors.validate_secret_key("key", invalid);
+            assert!(!errors.is_empty(), "{invalid:?} accepted");
+        }
+    }
+
+    #[test]
+    fn repo_urls() {
+        for valid in [
+            "https://github.com/paastel-io/paastel.git",
+            "https://git.example.com:8443/team/app",
+            "ssh://git@localhost:2222/kovi/devsecops/site-estatico.git",
+            "ssh://git.example.com/app.git",
+            "git@github.com:paastel-io/paastel.git",
+        ] {
+            let mut errors = ValidationErrors::new();
+            errors.validate_repo_url("repoUrl", valid);
+            assert!(errors.is_empty(), "{valid:?} rejected");
+        }
+        for invalid in [
+            "",
+            "github.com/paastel-io/paastel",
+            "http://github.com/paastel-io/paastel.git",
+            "ftp://example.com/app.git",
+            "https://",
+            "https://github.com",
+            "https://github.com/",
+            "ssh:///app.git",
+            "git@github.com:",
+            "github.com:paastel-io/paastel.git",
+            "@github.com:app.git",
+            "https://github.com/paastel io/paastel",
+        ] {
+            let mut errors = ValidationErrors::new();
+            errors.validate_repo_url("repoUrl", invalid);
+            assert!(!errors.is_empty(), "{invalid:?} accepted");
+        }
+    }
+
+    #[test]
+    fn emails_are_trimmed_and_lowercased() {
+        assert_eq!(normalize_email(" Ada@Example.COM\n"), "ada@example.com");
+        assert_eq!(normalize_email("ada@example.com"), "ada@example.com");
+    }
+}
@@ -0,0 +1,538 @@
+//! Git smart HTTP endpoint (`git clone https://host/git/<org>/<path>.git`).
+//!
+//! Requests are authenticated with HTTP Basic auth carrying a PaaStel token
+//! as the password and handed to `git http-backend`, which speaks the
+//! protocol (including gzip-encoded request bodies).
+
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+
+use axum::Router;
+use axum::body::{Body, Bytes};
+use axum::extract::{self, RawQuery, State};
+use axum::http::header::{
+    AUTHORIZATION, CACHE_CONTROL, CONTENT_ENCODING, CONTENT_TYPE,
+    WWW_AUTHENTICATE,
+};
+use axum::http::{HeaderMap, HeaderValue, Method, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::any;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+use crate::domain::models::OrgRole;
+use crate::git::{
+    check_namespace, init_bare_repo, repo_namespace, sanitize_repo_path,
+};
+use crate::graphql::state::AppState;
+use crate::infrastructure::repositories::{
+    AuthTokenRepository, OrganizationMembershipRepository,
+    OrganizationRepository, UserRepository,
+};
+
+/// Mount point of the git endpoint.
+pub const GIT_HTTP_PATH: &str = "/git/{*path}";
+
+/// Largest push (receive-pack request body) accepted.
+const MAX_REQUEST_BYTES: usize = 512 * 1024 * 1024;
+
+/// Org roles allowed to push; any member may fetch.
+const PUSH_ROLES: &[OrgRole] =
+    &[OrgRole::Owner, OrgRole::Admin, OrgRole::Member];
+
+type HttpResult<T> = Result<T, Rejection>;
+
+/// Router serving the git smart HTTP protocol under `/git/`.
+pub fn router(state: AppState) -> Router {
+    Router::new().route(GIT_HTTP_PATH, any(git_http)).with_state(state)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Service {
+    UploadPack,
+    ReceivePack,
+}
+
+impl Service {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "git-upload-pack" => Some(Self::UploadPack),
+            "git-receive-pack" => Some(Self::ReceivePack),
+            _ => None,
+        }
+    }
+}
+
+async fn git_http(
+    State(state): State<AppState>,
+    extract::Path(path): extract::Path<String>,
+    method: Method,
+    RawQuery(query): RawQuery,
+    headers: HeaderMap,
+    body: Body,
+) -> Response {
+    serve(state, &path, method, query, headers, body)
+        .await
+        .unwrap_or_else(IntoResponse::into_response)
+}
+
+async fn serve(
+    state: AppState,
+    path: &str,
+    method: Method,
+    query: Option<String>,
+    headers: HeaderMap,
+    body: Body,
+) -> HttpResult<Response> {
+    let (repo_raw, service) = route(path, &method, query.as_deref())?;
+
+    let repo_rel = sanitize_repo_path(repo_raw)
+        .map_err(|e| plain(StatusCode::BAD_REQUEST, e.to_string()))?;
+    check_namespace(&repo_rel, state.config.git_namespaces.as_deref())
+        .map_err(|e| plain(StatusCode::FORBIDDEN, e.to_string()))?;
+
+    let user = authenticate(&state, &headers).await?;
+    authorize(&state, &user, &repo_rel, service).await?;
+
+    let git_root = &state.config.git_root;
+    let repo_full = git_root.join(&repo_rel);
+    if !repo_full.exists() {
+        if service != Service::ReceivePack {
+            return Err(plain(StatusCode::NOT_FOUND, "Repository not found"));
+        }
+        create_repo(repo_full).await?;
+    }
+
+    let input = axum::body::to_bytes(body, MAX_REQUEST_BYTES)
+        .await
+        .map_err(|_| plain(StatusCode::PAYLOAD_TOO_LARGE, "Push too large"))?;
+
+    http_backend(git_root, &user, path, &method, query, &headers, input).await
+}
+
+/// Split `<repo>/info/refs`, `<repo>/git-upload-pack` and
+/// `<repo>/git-receive-pack` into the repo path and the service.
+fn route<'a>(
+    path: &'a str,
+    method: &Method,
+    query: Option<&str>,
+) -> HttpResult<(&'a str, Service)> {
+    if let Some(repo) = path.strip_suffix("/info/refs") {
+        if method != Method::GET {
+            return Err(plain(StatusCode::METHOD_NOT_ALLOWED, "Use GET"));
+        }
+        let service = query
+            .unwrap_or_default()
+            .split('&')
+            .find_map(|pair| pair.strip_prefix("service="))
+            .and_then(Service::from_name)
+            .ok_or_else(|| {
+                // Dumb HTTP clients are not supported.
+                plain(StatusCode::FORBIDDEN, "Only smart HTTP is supported")
+            })?;
+        return Ok((repo, service));
+    }
+
+    let (repo, name) = path
+        .rsplit_once('/')
+        .ok_or_else(|| plain(StatusCode::NOT_FOUND, "Not found"))?;
+    let service = Service::from_name(name)
+        .ok_or_else(|| plain(StatusCode::NOT_FOUND, "Not found"))?;
+    if method != Method::POST {
+        return Err(plain(StatusCode::METHOD_NOT_ALLOWED, "Use POST"));
+    }
+
+    Ok((repo, service))
+}
+
+struct GitUser {
+    id: i64,
+    email: String,
+    is_admin: bool,
+}
+
+/// Resolve the token from `Authorization: Basic` (password, or the user
+/// name when the password is empty).
+async fn authenticate(
+    state: &AppState,
+    headers: &HeaderMap,
+) -> HttpResult<GitUser> {
+    let token = headers
+        .get(AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Basic "))
+        .and_then(|encoded| BASE64.decode(encoded.trim()).ok())
+        .and_then(|decoded| String::from_utf8(decoded).ok())
+        .and_then(|credentials| {
+            let (user, password) = credentials.split_once(':')?;
+            let token = if password.is_empty() { user } else { password };
+            Some(token.to_string())
+        })
+        .ok_or_else(unauthorized)?;
+
+    let token = AuthTokenRepository::new(state.pool.clone())
+        .find_valid_by_token(&token)
+        .await
+        .map_err(internal)?
+        .ok_or_else(unauthorized)?;
+
+    let user = UserRepository::new(state.pool.clone())
+        .find_by_id(token.user_id)
+        .await
+        .map_err(internal)?
+        .filter(|u| u.is_active)
+        .ok_or_else(unauthorized)?;
+
+    Ok(GitUser { id: user.id, email: user.email, is_admin: user.is_admin })
+}
+
+/// The first path segment is the organization slug: members may fetch,
+/// Owner/Admin/Member may push. Platform admins are always allowed.
+async fn authorize(
+    state: &AppState,
+    user: &GitUser,
+    repo_rel: &Path,
+    service: Service,
+) -> HttpResult<()> {
+    if user.is_admin {
+        return Ok(());
+    }
+
+    let not_found = || plain(StatusCode::NOT_FOUND, "Repository not found");
+    let slug = repo_namespace(repo_rel).ok_or_else(not_found)?;
+    let org = OrganizationRepository::new(state.pool.clone())
+        .find_by_slug(slug)
+        .await
+        .map_err(internal)?
+        .ok_or_else(not_found)?;
+
+    let role = OrganizationMembershipRepository::new(state.pool.clone())
+        .list_by_user(user.id)
+        .await
+        .map_err(internal)?
+        .into_iter()
+        .find(|m| m.organization_id == org.id)
+        .map(|m| m.role)
+        .ok_or_else(not_found)?;
+
+    if service == Service::ReceivePack && !PUSH_ROLES.contains(&role) {
+        return Err(plain(
+            StatusCode::FORBIDDEN,
+            "Your role does not allow pushing to this repository",
+        ));
+    }
+
+    Ok(())
+}
+
+async fn create_repo(repo_full: PathBuf) -> HttpResult<()> {
+    tokio::task::spawn_blocking(move || {
+        if let Some(parent) = repo_full.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        init_bare_repo(&repo_full)
+    })
+    .await
+    .map_err(internal)?
+    .map_err(internal)
+}
+
+/// Run `git http-backend` as a CGI script and translate its output.
+async fn http_backend(
+    git_root: &Path,
+    user: &GitUser,
+    path: &str,
+    method: &Method,
+    query: Option<String>,
+    headers: &HeaderMap,
+    input: Bytes,
+) -> HttpResult<Response> {
+    let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+
+    let mut child = Command::new("git")
+        .arg("http-backend")
+        .env_clear()
+        .env("PATH", std::env::var_os("PATH").unwrap_or_default())
+        .env("GIT_PROJECT_ROOT", git_root)
+        .env("GIT_HTTP_EXPORT_ALL", "1")
+        .env("GIT_HTTP_MAX_REQUEST_BUFFER", MAX_REQUEST_BYTES.to_string())
+        .env("REQUEST_METHOD", method.as_str())
+        .env("PATH_INFO", format!("/{}", path.trim_start_matches('/')))
+        .env("QUERY_STRING", query.unwrap_or_default())
+        .env("CONTENT_TYPE", header(CONTENT_TYPE.as_str()).unwrap_or_default())
+        .env("CONTENT_LENGTH", input.len().to_string())
+        .env(
+            "HTTP_CONTENT_ENCODING",
+            header(CONTENT_ENCODING.as_str()).unwrap_or_default(),
+        )
+        .env("GIT_PROTOCOL", header("git-protocol").unwrap_or_default())
+        .env("REMOTE_USER", &user.email)
+        .env("REMOTE_ADDR", "127.0.0.1")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .spawn()
+        .map_err(internal)?;
+
+    // Feed stdin concurrently so a large response can't deadlock the pipe.
+    let mut stdin = child.stdin.take().ok_or_else(|| internal("no stdin"))?;
+    let writer = tokio::spawn(async move {
+        let _ = stdin.write_all(&input).await;
+    });
+
+    let output = child.wait_with_output().await.map_err(internal)?;
+    let _ = writer.await;
+    if !output.status.success() {
+        return Err(internal(format!(
+            "git http-backend exited with {}",
+            output.status
+        )));
+    }
+
+    cgi_response(output.stdout)
+}
+
+/// Split a CGI response into headers (`Status:` included) and body.
+fn cgi_response(stdout: Vec<u8>) -> HttpResult<Response> {
+    let (head_len, sep_len) = find_header_end(&stdout)
+        .ok_or_else(|| internal("malformed git http-backend output"))?;
+    let head = String::from_utf8_lossy(&stdout[..head_len]).into_owned();
+    let body = stdout[head_len + sep_len..].to_vec();
+
+    let mut response = Response::new(Body::from(body));
+    for line in head.lines().filter(|l| !l.is_empty()) {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        if name.eq_ignore_ascii_case("status") {
+            let code = value.split_whitespace().next().unwrap_or_default();
+            if let Ok(status) = code.parse::<u16>() {
+                *response.status_mut() = StatusCode::from_u16(status)
+                    .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+            }
+        } else if let (Ok(name), Ok(value)) = (
+            axum::http::HeaderName::try_from(name),
+            HeaderValue::try_from(value),
+        ) {
+            response.headers_mut().append(name, value);
+        }
+    }
+    response
+        .headers_mut()
+        .entry(CACHE_CONTROL)
+        .or_insert(HeaderValue::from_static("no-cache"));
+
+    Ok(response)
+}
+
+/// Offset and length of the blank line ending the CGI headers.
+fn find_header_end(out: &[u8]) -> Option<(usize, usize)> {
+    let crlf = out.windows(4).position(|w| w == b"\r\n\r\n").map(|i| (i, 4));
+    let lf = out.windows(2).position(|w| w == b"\n\n").map(|i| (i, 2));
+    match (crlf, lf) {
+        (Some(a), Some(b)) => Some(if a.0 < b.0 { a } else { b }),
+        (a, b) => a.or(b),
+    }
+}
+
+/// Plain-text error answered to the git client.
+enum Rejection {
+    Plain(StatusCode, String),
+    Unauthorized,
+}
+
+impl IntoResponse for Rejection {
+    fn into_response(self) -> Response {
+        match self {
+            Self::Plain(status, message) => (status, message).into_response(),
+            Self::Unauthorized => (
+                StatusCode::UNAUTHORIZED,
+                [(WWW_AUTHENTICATE, "Basic realm=\"paastel\"")],
+                "Authentication required: use a PaaStel token as the password",
+            )
+                .into_response(),
+        }
+    }
+}
+
+fn plain(status: StatusCode, message: impl Into<String>) -> Rejection {
+    Rejection::Plain(status, message.into())
+}
+
+fn unauthorized() -> Rejection {
+    Rejection::Unauthorized
+}
+
+fn internal(error: impl std::fmt::Display) -> Rejection {
+    tracing::error!(%error, "git http request failed");
+    plain(StatusCode::INTERNAL_SERVER_ERROR, "Internal git error")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+    use std::sync::Arc;
+
+    use sqlx::PgPool;
+
+    use super::*;
+    use crate::config::Config;
+    use crate::graphql::test_support::{
+        add_org_member, create_test_org, register_test_user, test_config,
+        test_schema, test_state,
+    };
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir()
+            .join(format!("paastel-git-http-{}-{name}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Default configuration serving the repos under `root`.
+    fn rooted_at(root: &Path) -> Config {
+        Config { git_root: root.to_path_buf(), ..test_config() }
+    }
+
+    /// Serve the git endpoint with `config`; returns its address.
+    async fn serve_git(pool: PgPool, config: Config) -> SocketAddr {
+        let mut state = test_state(pool);
+        state.config = Arc::new(config);
+        let app = router(state);
+        let listener =
+            tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        addr
+    }
+
+    async fn git(dir: &Path, args: &[&str]) -> std::process::Output {
+        Command::new("git")
+            .args(["-c", "user.name=Ada", "-c", "user.email=ada@example.com"])
+            .args(args)
+            .current_dir(dir)
+            .env("GIT_TERMINAL_PROMPT", "0")
+            .output()
+            .await
+            .unwrap()
+    }
+
+    /// A work tree with one commit on `main`.
+    async fn work_tree(name: &str) -> PathBuf {
+        let dir = scratch_dir(name);
+        std::fs::write(dir.join("README"), "hello\n").unwrap();
+        for args in [
+            &["init", "--quiet", "--initial-branch", "main"][..],
+            &["add", "README"],
+            &["commit", "--quiet", "-m", "initial"],
+        ] {
+            assert!(git(&dir, args).await.status.success());
+        }
+        dir
+    }
+
+    /// Register `email` as an org `role` of `acme`; returns its token.
+    async fn acme_member(pool: &PgPool, email: &str, role: &str) -> String {
+        let token =
+            register_test_user(&test_schema(pool.clone()), email).await;
+        let acme = match OrganizationRepository::new(pool.clone())
+            .find_by_slug("acme")
+            .await
+            .unwrap()
+        {
+            Some(org) => org.id,
+            None => create_test_org(pool, "acme").await,
+        };
+        add_org_member(pool, acme, email, role).await;
+        token
+    }
+
+    #[sqlx::test]
+    async fn members_push_over_http(pool: PgPool) {
+        let root = scratch_dir("push-root");
+        let token = acme_member(&pool, "ada@example.com", "member").await;
+        let addr = serve_git(pool, rooted_at(&root)).await;
+        let work = work_tree("push-work").await;
+
+        let url = format!("http://x:{token}@{addr}/git/acme/web.git");
+        let push = git(&work, &["push", "--quiet", &url, "main"]).await;
+
+        assert!(push.status.success(), "{push:?}");
+        let pushed = git(
+            &root.join("acme/web.git"),
+            &["rev-parse", "--verify", "refs/heads/main"],
+        )
+        .await;
+        let local = git(&work, &["rev-parse", "HEAD"]).await;
+        assert_eq!(pushed.stdout, local.stdout);
+    }
+
+    #[sqlx::test]
+    async fn pushes_need_a_push_role_and_membership(pool: PgPool) {
+        let root = scratch_dir("denied-root");
+        let billing = acme_member(&pool, "bob@example.com", "billing").await;
+        let outsider =
+            register_test_user(&test_schema(pool.clone()), "eve@example.com")
+                .await;
+        let addr = serve_git(pool, rooted_at(&root)).await;
+        let work = work_tree("denied-work").await;
+
+        let push = |token: String| {
+            let url = format!("http://x:{token}@{addr}/git/acme/web.git");
+            let work = work.clone();
+            async move { git(&work, &["push", "--quiet", &url, "main"]).await }
+        };
+        let as_billing = push(billing).await;
+        let as_outsider = push(outsider).await;
+
+        assert!(!as_billing.status.success());
+        assert!(String::from_utf8_lossy(&as_billing.stderr).contains("403"));
+        assert!(!as_outsider.status.success());
+        assert!(
+            String::from_utf8_lossy(&as_outsider.stderr).contains("not found")
+        );
+        assert!(!root.join("acme/web.git").exists());
+    }
+
+    #[sqlx::test]
+    async fn anonymous_requests_are_challenged(pool: PgPool) {
+        let addr =
+            serve_git(pool, rooted_at(&scratch_dir("anonymous-root"))).await;
+
+        let res = reqwest::get(format!(
+            "http://{addr}/git/acme/web.git/info/refs?service=git-upload-pack"
+        ))
+        .await
+        .unwrap();
+
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(res.headers()[WWW_AUTHENTICATE], "Basic realm=\"paastel\"");
+    }
+
+    #[sqlx::test]
+    async fn namespaces_outside_the_allow_list_are_refused(pool: PgPool) {
+        let root = scratch_dir("namespaces-root");
+        let config = Config {
+            git_namespaces: Some(vec!["acme".to_string()]),
+            ..rooted_at(&root)
+        };
+        let addr = serve_git(pool, config).await;
+
+        let res = reqwest::get(format!(
+            "http://{addr}/git/globex/web.git/info/refs?service=git-upload-pack"
+        ))
+        .await
+        .unwrap();
+
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+        assert_eq!(
+            res.text().await.unwrap(),
+            "Namespace 'globex' is not allowed on this server"
+        );
+    }
+}
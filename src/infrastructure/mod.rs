@@ -1 +1,5 @@
+pub mod git_http;
+pub mod password;
+pub mod rate_limit;
 pub mod repositories;
+pub mod secret_cipher;
@@ -0,0 +1,53 @@
+use anyhow::{Result, anyhow};
+use argon2::Argon2;
+use argon2::password_hash::rand_core::OsRng;
+use argon2::password_hash::{
+    PasswordHash, PasswordHasher, PasswordVerifier, SaltString,
+};
+use sha2::{Digest, Sha256};
+
+/// Hash a plain password with Argon2id (PHC string format).
+pub fn hash_password(password: &str) -> Result<String> {
+    let salt = SaltString::generate(&mut OsRng);
+    let hash = Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map_err(|e| anyhow!("failed to hash password: {e}"))?;
+
+    Ok(hash.to_string())
+}
+
+/// Check a plain password against a stored PHC hash.
+///
+/// Returns `false` for a mismatch or a malformed stored hash.
+pub fn verify_password(password: &str, password_hash: &str) -> bool {
+    let Ok(parsed) = PasswordHash::new(password_hash) else {
+        return false;
+    };
+
+    Argon2::default().verify_password(password.as_bytes(), &parsed).is_ok()
+}
+
+/// SHA-256 hex digest for one-time tokens stored at rest.
+///
+/// Tokens are high-entropy random strings, so a fast hash is enough.
+pub fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verify_accepts_only_the_hashed_password() {
+        let hash = hash_password("correct horse").unwrap();
+
+        assert!(verify_password("correct horse", &hash));
+        assert!(!verify_password("wrong horse", &hash));
+    }
+
+    #[test]
+    fn verify_rejects_a_malformed_hash() {
+        assert!(!verify_password("correct horse", "not-a-phc-string"));
+    }
+}
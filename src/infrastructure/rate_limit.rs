@@ -0,0 +1,185 @@
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use axum::extract::{ConnectInfo, Request, State};
+use axum::http::{HeaderMap, HeaderValue, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+
+/// Windows tracked before expired ones are pruned.
+const PRUNE_THRESHOLD: usize = 10_000;
+
+/// Fixed-window request limiter keyed by client IP.
+pub struct RateLimiter {
+    limit: u32,
+    window: Duration,
+    windows: Mutex<HashMap<IpAddr, Window>>,
+}
+
+struct Window {
+    started: Instant,
+    count: u32,
+}
+
+/// Outcome of [`RateLimiter::check`].
+#[derive(Debug, Clone, Copy)]
+pub struct Decision {
+    pub allowed: bool,
+    pub limit: u32,
+    pub remaining: u32,
+    /// Time until the current window ends
+    pub reset_after: Duration,
+}
+
+impl RateLimiter {
+    /// Allow `limit` requests per client per `window`.
+    pub fn new(limit: u32, window: Duration) -> Self {
+        Self { limit, window, windows: Mutex::new(HashMap::new()) }
+    }
+
+    /// Count one request from `client`.
+    pub fn check(&self, client: IpAddr) -> Decision {
+        let now = Instant::now();
+        let mut windows =
+            self.windows.lock().unwrap_or_else(|e| e.into_inner());
+
+        if windows.len() >= PRUNE_THRESHOLD {
+            windows.retain(|_, w| now.duration_since(w.started) < self.window);
+        }
+
+        let window =
+            windows.entry(client).or_insert(Window { started: now, count: 0 });
+        if now.duration_since(window.started) >= self.window {
+            *window = Window { started: now, count: 0 };
+        }
+
+        let allowed = window.count < self.limit;
+        if allowed {
+            window.count += 1;
+        }
+
+        Decision {
+            allowed,
+            limit: self.limit,
+            remaining: self.limit - window.count,
+            reset_after: self.window - now.duration_since(window.started),
+        }
+    }
+}
+
+impl Decision {
+    /// `X-RateLimit-*` headers, plus `Retry-After` when throttled.
+    ///
+    /// Reset and retry values are whole seconds from now, rounded up.
+    fn apply(&self, headers: &mut HeaderMap) {
+        let reset = self.reset_after.as_secs()
+            + u64::from(self.reset_after.subsec_nanos() > 0);
+
+        headers.insert("x-ratelimit-limit", HeaderValue::from(self.limit));
+        headers.insert(
+            "x-ratelimit-remaining",
+            HeaderValue::from(self.remaining),
+        );
+        headers.insert("x-ratelimit-reset", HeaderValue::from(reset));
+        if !self.allowed {
+            headers.insert("retry-after", HeaderValue::from(reset.max(1)));
+        }
+    }
+}
+
+/// Axum middleware: reject clients over the limit with `429 Too Many
+/// Requests` and a GraphQL-shaped error body.
+///
+/// Needs the server to be run with `ConnectInfo<SocketAddr>`.
+pub async fn rate_limit(
+    State(limiter): State<Arc<RateLimiter>>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let decision = limiter.check(addr.ip());
+
+    let mut response = if decision.allowed {
+        next.run(request).await
+    } else {
+        let body = serde_json::json!({
+            "errors": [{
+                "message": "Too many requests, retry later",
+                "extensions": { "code": "RATE_LIMITED" },
+            }],
+        });
+        (StatusCode::TOO_MANY_REQUESTS, axum::Json(body)).into_response()
+    };
+
+    decision.apply(response.headers_mut());
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::Router;
+    use axum::middleware::from_fn_with_state;
+    use axum::routing::get;
+
+    use super::*;
+
+    /// Serve a rate-limited `GET /` on a free local port.
+    async fn serve(limiter: RateLimiter) -> String {
+        let app = Router::new()
+            .route("/", get(|| async { "ok" }))
+            .layer(from_fn_with_state(Arc::new(limiter), rate_limit));
+        let listener =
+            tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(
+                listener,
+                app.into_make_service_with_connect_info::<SocketAddr>(),
+            )
+            .await
+            .unwrap();
+        });
+
+        format!("http://{addr}/")
+    }
+
+    fn header<'a>(res: &'a reqwest::Response, name: &str) -> Option<&'a str> {
+        res.headers().get(name).map(|v| v.to_str().unwrap())
+    }
+
+    #[tokio::test]
+    async fn throttled_responses_carry_backoff_headers() {
+        let url = serve(RateLimiter::new(1, Duration::from_secs(60))).await;
+
+        let allowed = reqwest::get(&url).await.unwrap();
+        let throttled = reqwest::get(&url).await.unwrap();
+
+        assert_eq!(allowed.status(), StatusCode::OK);
+        assert_eq!(header(&allowed, "x-ratelimit-remaining"), Some("0"));
+        assert_eq!(header(&allowed, "retry-after"), None);
+        assert_eq!(throttled.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(header(&throttled, "x-ratelimit-limit"), Some("1"));
+        assert_eq!(header(&throttled, "x-ratelimit-remaining"), Some("0"));
+        assert_eq!(header(&throttled, "x-ratelimit-reset"), Some("60"));
+        assert_eq!(header(&throttled, "retry-after"), Some("60"));
+        let body: serde_json::Value = throttled.json().await.unwrap();
+        assert_eq!(body["errors"][0]["extensions"]["code"], "RATE_LIMITED");
+    }
+
+    #[test]
+    fn windows_are_per_client_and_reset() {
+        let limiter = RateLimiter::new(2, Duration::from_millis(50));
+        let a: IpAddr = "10.0.0.1".parse().unwrap();
+        let b: IpAddr = "10.0.0.2".parse().unwrap();
+
+        assert!(limiter.check(a).allowed);
+        assert_eq!(limiter.check(a).remaining, 0);
+        assert!(!limiter.check(a).allowed);
+        assert!(limiter.check(b).allowed);
+
+        std::thread::sleep(Duration::from_millis(60));
+        assert!(limiter.check(a).allowed);
+    }
+}
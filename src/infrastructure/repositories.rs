@@ -1,7 +1,20 @@
+use std::collections::HashMap;
+
 use anyhow::Result;
-use sqlx::{PgPool, query_as};
+use sqlx::types::time::OffsetDateTime;
+use sqlx::{PgConnection, PgExecutor, PgPool, query_as, query_scalar};
+use time::Duration;
 
+use crate::domain::build_steps::rollup_status;
 use crate::domain::models::*;
+use crate::infrastructure::secret_cipher::SecretCipher;
+
+/// Whether `err` was caused by a unique constraint violation.
+pub fn is_unique_violation(err: &anyhow::Error) -> bool {
+    err.downcast_ref::<sqlx::Error>()
+        .and_then(|e| e.as_database_error())
+        .is_some_and(|e| e.is_unique_violation())
+}
 
 #[derive(Clone)]
 pub struct AuthTokenRepository {
@@ -16,14 +29,15 @@ impl AuthTokenRepository {
     pub async fn create(&self, new_token: NewAuthToken) -> Result<AuthToken> {
         let token = query_as::<_, AuthToken>(
             r#"
-            INSERT INTO auth_tokens (user_id, token, description)
-            VALUES ($1, $2, $3)
+            INSERT INTO auth_tokens (user_id, token, description, expires_at)
+            VALUES ($1, $2, $3, $4)
             RETURNING *
             "#,
         )
         .bind(new_token.user_id)
         .bind(new_token.token)
         .bind(new_token.description)
+        .bind(new_token.expires_at)
         .fetch_one(&self.pool)
         .await?;
 
@@ -40,6 +54,7 @@ impl AuthTokenRepository {
             FROM auth_tokens
             WHERE token = $1
               AND revoked_at IS NULL
+              AND (expires_at IS NULL OR expires_at > NOW())
             "#,
         )
         .bind(token)
@@ -48,6 +63,48 @@ impl AuthTokenRepository {
 
         Ok(row)
     }
+
+    /// Revoke every active token of a user, optionally sparing one.
+    ///
+    /// Returns how many tokens were revoked.
+    pub async fn revoke_all_for_user(
+        &self,
+        user_id: i64,
+        except_token_id: Option<i64>,
+    ) -> Result<u64> {
+        let result = sqlx::query(
+            r#"
+            UPDATE auth_tokens
+            SET revoked_at = NOW()
+            WHERE user_id = $1
+              AND revoked_at IS NULL
+              AND ($2::BIGINT IS NULL OR id <> $2)
+            "#,
+        )
+        .bind(user_id)
+        .bind(except_token_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Mark one token revoked. Returns `false` if it already was.
+    pub async fn revoke(&self, id: i64) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            UPDATE auth_tokens
+            SET revoked_at = NOW()
+            WHERE id = $1
+              AND revoked_at IS NULL
+            "#,
+        )
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
 }
 
 // ---------- OrganizationRepository ----------
@@ -73,6 +130,49 @@ impl OrganizationRepository {
         Ok(org)
     }
 
+    /// Non-deleted organizations with the given ids (in no particular order).
+    pub async fn find_by_ids(&self, ids: &[i64]) -> Result<Vec<Organization>> {
+        let orgs = query_as::<_, Organization>(
+            "SELECT * FROM organizations WHERE id = ANY($1) AND deleted_at IS NULL",
+        )
+        .bind(ids)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(orgs)
+    }
+
+    /// Non-deleted organizations `user_id` belongs to, with their role in
+    /// each, ordered by name.
+    pub async fn list_for_user(
+        &self,
+        user_id: i64,
+    ) -> Result<Vec<(Organization, OrgRole)>> {
+        #[derive(sqlx::FromRow)]
+        struct Row {
+            #[sqlx(flatten)]
+            organization: Organization,
+            role: OrgRole,
+        }
+
+        let rows = query_as::<_, Row>(
+            r#"
+            SELECT o.*, m.role
+            FROM organizations o
+            JOIN organization_memberships m
+              ON m.organization_id = o.id
+             AND m.user_id = $1
+            WHERE o.deleted_at IS NULL
+            ORDER BY o.name, o.id
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(|r| (r.organization, r.role)).collect())
+    }
+
     pub async fn find_by_slug(
         &self,
         slug: &str,
@@ -91,21 +191,217 @@ impl OrganizationRepository {
         &self,
         new_org: NewOrganization,
     ) -> Result<Organization> {
+        insert_organization(&self.pool, new_org).await
+    }
+
+    /// [`Self::create`] on a caller-owned connection, usually a transaction.
+    ///
+    /// The row is only visible to others once the caller commits, and is
+    /// discarded if the transaction is rolled back (or dropped), together
+    /// with whatever else was written in it.
+    pub async fn create_tx(
+        conn: &mut PgConnection,
+        new_org: NewOrganization,
+    ) -> Result<Organization> {
+        insert_organization(conn, new_org).await
+    }
+
+    /// Change the name and/or description; `None` keeps the current value.
+    ///
+    /// Returns `None` if the organization does not exist or is deleted, or
+    /// if it was modified since `expected_updated_at` (when given).
+    pub async fn update(
+        &self,
+        id: i64,
+        name: Option<&str>,
+        description: Option<&str>,
+        expected_updated_at: Option<OffsetDateTime>,
+    ) -> Result<Option<Organization>> {
         let org = query_as::<_, Organization>(
             r#"
-            INSERT INTO organizations (name, slug, description)
-            VALUES ($1, $2, $3)
+            UPDATE organizations
+            SET name = COALESCE($2, name),
+                description = COALESCE($3, description)
+            WHERE id = $1
+              AND deleted_at IS NULL
+              AND ($4::TIMESTAMPTZ IS NULL OR updated_at = $4)
             RETURNING *
             "#,
         )
-        .bind(new_org.name)
-        .bind(new_org.slug)
-        .bind(new_org.description)
-        .fetch_one(&self.pool)
+        .bind(id)
+        .bind(name)
+        .bind(description)
+        .bind(expected_updated_at)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(org)
+    }
+
+    /// Mark an organization as deleted. Teams and apps are left untouched.
+    ///
+    /// Returns `None` if the organization does not exist or is already
+    /// deleted.
+    pub async fn soft_delete(&self, id: i64) -> Result<Option<Organization>> {
+        let org = query_as::<_, Organization>(
+            r#"
+            UPDATE organizations
+            SET deleted_at = NOW()
+            WHERE id = $1 AND deleted_at IS NULL
+            RETURNING *
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(org)
+    }
+
+    pub async fn list_deleted(&self) -> Result<Vec<Organization>> {
+        let orgs = query_as::<_, Organization>(
+            r#"
+            SELECT * FROM organizations
+            WHERE deleted_at IS NOT NULL
+            ORDER BY deleted_at DESC
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(orgs)
+    }
+
+    pub async fn find_deleted_by_id(
+        &self,
+        id: i64,
+    ) -> Result<Option<Organization>> {
+        let org = query_as::<_, Organization>(
+            "SELECT * FROM organizations WHERE id = $1 AND deleted_at IS NOT NULL",
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
         .await?;
 
         Ok(org)
     }
+
+    /// Clear `deleted_at` on a soft-deleted organization.
+    ///
+    /// With `restore_children`, teams and apps that were soft-deleted at or
+    /// after the organization are restored in the same transaction.
+    /// Returns `None` if the organization is not currently deleted.
+    pub async fn restore(
+        &self,
+        id: i64,
+        restore_children: bool,
+    ) -> Result<Option<Organization>> {
+        let mut tx = self.pool.begin().await?;
+
+        let deleted_at = query_scalar::<_, Option<OffsetDateTime>>(
+            r#"
+            SELECT deleted_at FROM organizations
+            WHERE id = $1 AND deleted_at IS NOT NULL
+            FOR UPDATE
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .flatten();
+
+        let Some(deleted_at) = deleted_at else {
+            return Ok(None);
+        };
+
+        if restore_children {
+            sqlx::query(
+                r#"
+                UPDATE teams SET deleted_at = NULL
+                WHERE organization_id = $1 AND deleted_at >= $2
+                "#,
+            )
+            .bind(id)
+            .bind(deleted_at)
+            .execute(&mut *tx)
+            .await?;
+
+            sqlx::query(
+                r#"
+                UPDATE apps SET deleted_at = NULL
+                WHERE organization_id = $1 AND deleted_at >= $2
+                "#,
+            )
+            .bind(id)
+            .bind(deleted_at)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        let org = query_as::<_, Organization>(
+            r#"
+            UPDATE organizations SET deleted_at = NULL
+            WHERE id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(Some(org))
+    }
+
+    /// Case-insensitive match on name/slug, limited to the user's orgs.
+    pub async fn search_for_user(
+        &self,
+        user_id: i64,
+        term: &str,
+        limit: i64,
+    ) -> Result<Vec<Organization>> {
+        let rows = query_as::<_, Organization>(
+            r#"
+            SELECT o.* FROM organizations o
+            JOIN organization_memberships m
+              ON m.organization_id = o.id
+             AND m.user_id = $1
+            WHERE o.deleted_at IS NULL
+              AND (o.name ILIKE $2 OR o.slug ILIKE $2)
+            ORDER BY o.name
+            LIMIT $3
+            "#,
+        )
+        .bind(user_id)
+        .bind(like_pattern(term))
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows)
+    }
+}
+
+async fn insert_organization<'e, E: PgExecutor<'e>>(
+    executor: E,
+    new_org: NewOrganization,
+) -> Result<Organization> {
+    let org = query_as::<_, Organization>(
+        r#"
+        INSERT INTO organizations (name, slug, description, created_by)
+        VALUES ($1, $2, $3, $4)
+        RETURNING *
+        "#,
+    )
+    .bind(new_org.name)
+    .bind(new_org.slug)
+    .bind(new_org.description)
+    .bind(new_org.created_by)
+    .fetch_one(executor)
+    .await?;
+
+    Ok(org)
 }
 
 // ---------- UserRepository ----------
@@ -131,9 +427,28 @@ impl UserRepository {
         Ok(user)
     }
 
+    /// Non-deleted users with the given ids (in no particular order).
+    pub async fn find_by_ids(&self, ids: &[i64]) -> Result<Vec<User>> {
+        let users = query_as::<_, User>(
+            "SELECT * FROM users WHERE id = ANY($1) AND deleted_at IS NULL",
+        )
+        .bind(ids)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(users)
+    }
+
+    /// Case-insensitive, so accounts stored before emails were normalized
+    /// still match.
     pub async fn find_by_email(&self, email: &str) -> Result<Option<User>> {
         let user = query_as::<_, User>(
-            "SELECT * FROM users WHERE email = $1 AND deleted_at IS NULL",
+            r#"
+            SELECT * FROM users
+            WHERE lower(email) = lower($1) AND deleted_at IS NULL
+            ORDER BY id
+            LIMIT 1
+            "#,
         )
         .bind(email)
         .fetch_optional(&self.pool)
@@ -158,6 +473,142 @@ impl UserRepository {
 
         Ok(user)
     }
+
+    pub async fn update_password_hash(
+        &self,
+        user_id: i64,
+        password_hash: &str,
+    ) -> Result<User> {
+        let user = query_as::<_, User>(
+            r#"
+            UPDATE users
+            SET password_hash = $2,
+                updated_at = NOW()
+            WHERE id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(user_id)
+        .bind(password_hash)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(user)
+    }
+
+    pub async fn record_login(&self, user_id: i64) -> Result<()> {
+        sqlx::query("UPDATE users SET last_login_at = NOW() WHERE id = $1")
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+}
+
+// ---------- PasswordResetRepository ----------
+
+#[derive(Clone)]
+pub struct PasswordResetRepository {
+    pool: PgPool,
+}
+
+impl PasswordResetRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn create(
+        &self,
+        new_reset: NewPasswordReset,
+    ) -> Result<PasswordReset> {
+        let reset = query_as::<_, PasswordReset>(
+            r#"
+            INSERT INTO password_resets (user_id, token_hash, expires_at)
+            VALUES ($1, $2, $3)
+            RETURNING *
+            "#,
+        )
+        .bind(new_reset.user_id)
+        .bind(new_reset.token_hash)
+        .bind(new_reset.expires_at)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(reset)
+    }
+
+    /// Consume a reset token and set the user's new password hash.
+    ///
+    /// In a single transaction: marks the token as used (only if unused and
+    /// not expired), updates the password and revokes every auth token of
+    /// the user. Returns `None` when the token is invalid.
+    pub async fn reset_password(
+        &self,
+        token_hash: &str,
+        password_hash: &str,
+    ) -> Result<Option<User>> {
+        let mut tx = self.pool.begin().await?;
+
+        let reset = query_as::<_, PasswordReset>(
+            r#"
+            UPDATE password_resets
+            SET used_at = NOW()
+            WHERE token_hash = $1
+              AND used_at IS NULL
+              AND expires_at > NOW()
+            RETURNING *
+            "#,
+        )
+        .bind(token_hash)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let Some(reset) = reset else {
+            return Ok(None);
+        };
+
+        let user = query_as::<_, User>(
+            r#"
+            UPDATE users
+            SET password_hash = $2,
+                updated_at = NOW()
+            WHERE id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(reset.user_id)
+        .bind(password_hash)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        // Invalidate any other pending reset links
+        sqlx::query(
+            r#"
+            UPDATE password_resets
+            SET used_at = NOW()
+            WHERE user_id = $1 AND used_at IS NULL
+            "#,
+        )
+        .bind(reset.user_id)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            r#"
+            UPDATE auth_tokens
+            SET revoked_at = NOW()
+            WHERE user_id = $1 AND revoked_at IS NULL
+            "#,
+        )
+        .bind(reset.user_id)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(Some(user))
+    }
 }
 
 // ---------- OrganizationMembershipRepository ----------
@@ -180,6 +631,7 @@ impl OrganizationMembershipRepository {
             r#"
             SELECT * FROM organization_memberships
             WHERE organization_id = $1
+            ORDER BY created_at, user_id
             "#,
         )
         .bind(organization_id)
@@ -212,22 +664,22 @@ impl OrganizationMembershipRepository {
         user_id: i64,
         role: OrgRole,
     ) -> Result<OrganizationMembership> {
-        let row = query_as::<_, OrganizationMembership>(
-            r#"
-            INSERT INTO organization_memberships (organization_id, user_id, role)
-            VALUES ($1, $2, $3)
-            ON CONFLICT (organization_id, user_id)
-            DO UPDATE SET role = EXCLUDED.role
-            RETURNING *
-            "#,
-        )
-        .bind(organization_id)
-        .bind(user_id)
-        .bind(role)
-        .fetch_one(&self.pool)
-        .await?;
+        upsert_org_membership(&self.pool, organization_id, user_id, role).await
+    }
 
-        Ok(row)
+    /// [`Self::upsert_membership`] on a caller-owned connection, usually a
+    /// transaction.
+    ///
+    /// Statements run in call order on that connection, so the organization
+    /// may be inserted earlier in the same transaction; nothing is visible
+    /// to others before the caller commits.
+    pub async fn upsert_membership_tx(
+        conn: &mut PgConnection,
+        organization_id: i64,
+        user_id: i64,
+        role: OrgRole,
+    ) -> Result<OrganizationMembership> {
+        upsert_org_membership(conn, organization_id, user_id, role).await
     }
 
     pub async fn delete_membership(
@@ -250,52 +702,340 @@ impl OrganizationMembershipRepository {
     }
 }
 
-// ---------- TeamRepository ----------
+async fn upsert_org_membership<'e, E: PgExecutor<'e>>(
+    executor: E,
+    organization_id: i64,
+    user_id: i64,
+    role: OrgRole,
+) -> Result<OrganizationMembership> {
+    let row = query_as::<_, OrganizationMembership>(
+        r#"
+        INSERT INTO organization_memberships (organization_id, user_id, role)
+        VALUES ($1, $2, $3)
+        ON CONFLICT (organization_id, user_id)
+        DO UPDATE SET role = EXCLUDED.role
+        RETURNING *
+        "#,
+    )
+    .bind(organization_id)
+    .bind(user_id)
+    .bind(role)
+    .fetch_one(executor)
+    .await?;
+
+    Ok(row)
+}
+
+// ---------- OrganizationInviteRepository ----------
 
 #[derive(Clone)]
-pub struct TeamRepository {
+pub struct OrganizationInviteRepository {
     pool: PgPool,
 }
 
-impl TeamRepository {
+impl OrganizationInviteRepository {
     pub fn new(pool: PgPool) -> Self {
         Self { pool }
     }
 
-    pub async fn find_by_id(&self, id: i64) -> Result<Option<Team>> {
-        let team = query_as::<_, Team>(
-            "SELECT * FROM teams WHERE id = $1 AND deleted_at IS NULL",
+    /// Create an invite, or renew the one for the same email (new role,
+    /// inviter and expiry).
+    pub async fn upsert(
+        &self,
+        new_invite: NewOrganizationInvite,
+    ) -> Result<OrganizationInvite> {
+        let row = query_as::<_, OrganizationInvite>(
+            r#"
+            INSERT INTO organization_invites
+                (organization_id, email, role, invited_by, expires_at)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (organization_id, email)
+            DO UPDATE SET role = EXCLUDED.role,
+                          invited_by = EXCLUDED.invited_by,
+                          expires_at = EXCLUDED.expires_at
+            RETURNING *
+            "#,
         )
-        .bind(id)
-        .fetch_optional(&self.pool)
+        .bind(new_invite.organization_id)
+        .bind(new_invite.email)
+        .bind(new_invite.role)
+        .bind(new_invite.invited_by)
+        .bind(new_invite.expires_at)
+        .fetch_one(&self.pool)
         .await?;
 
-        Ok(team)
+        Ok(row)
     }
 
-    pub async fn list_by_organization(
+    /// Unexpired invites for `email` to non-deleted organizations, newest
+    /// first.
+    pub async fn list_pending_for_email(
         &self,
-        organization_id: i64,
-    ) -> Result<Vec<Team>> {
-        let teams = query_as::<_, Team>(
+        email: &str,
+    ) -> Result<Vec<OrganizationInvite>> {
+        let rows = query_as::<_, OrganizationInvite>(
             r#"
-            SELECT * FROM teams
-            WHERE organization_id = $1 AND deleted_at IS NULL
-            ORDER BY name
+            SELECT i.* FROM organization_invites i
+            JOIN organizations o
+              ON o.id = i.organization_id
+             AND o.deleted_at IS NULL
+            WHERE i.email = $1 AND i.expires_at > NOW()
+            ORDER BY i.created_at DESC, i.id DESC
             "#,
         )
-        .bind(organization_id)
+        .bind(email)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows)
+    }
+
+    /// Accept the unexpired invite `id` addressed to `email`: make
+    /// `user_id` a member with the invited role and delete the invite, in
+    /// one transaction. An existing membership keeps its role.
+    ///
+    /// Returns `None` when there is no such invite (or it expired).
+    pub async fn accept(
+        &self,
+        id: i64,
+        email: &str,
+        user_id: i64,
+    ) -> Result<Option<OrganizationMembership>> {
+        let mut tx = self.pool.begin().await?;
+
+        let invite = query_as::<_, OrganizationInvite>(
+            r#"
+            DELETE FROM organization_invites
+            WHERE id = $1 AND email = $2 AND expires_at > NOW()
+            RETURNING *
+            "#,
+        )
+        .bind(id)
+        .bind(email)
+        .fetch_optional(&mut *tx)
+        .await?;
+        let Some(invite) = invite else {
+            return Ok(None);
+        };
+
+        sqlx::query(
+            r#"
+            INSERT INTO organization_memberships (organization_id, user_id, role)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (organization_id, user_id) DO NOTHING
+            "#,
+        )
+        .bind(invite.organization_id)
+        .bind(user_id)
+        .bind(invite.role)
+        .execute(&mut *tx)
+        .await?;
+
+        let membership = query_as::<_, OrganizationMembership>(
+            r#"
+            SELECT * FROM organization_memberships
+            WHERE organization_id = $1 AND user_id = $2
+            "#,
+        )
+        .bind(invite.organization_id)
+        .bind(user_id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(Some(membership))
+    }
+
+    /// Delete the invite `id` addressed to `email`, expired or not.
+    ///
+    /// Returns `false` if there was none.
+    pub async fn decline(&self, id: i64, email: &str) -> Result<bool> {
+        let result = sqlx::query(
+            "DELETE FROM organization_invites WHERE id = $1 AND email = $2",
+        )
+        .bind(id)
+        .bind(email)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Delete invites that expired. Returns how many were removed.
+    pub async fn delete_expired(&self) -> Result<u64> {
+        let result = sqlx::query(
+            "DELETE FROM organization_invites WHERE expires_at <= NOW()",
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+}
+
+// ---------- TeamRepository ----------
+
+#[derive(Clone)]
+pub struct TeamRepository {
+    pool: PgPool,
+}
+
+impl TeamRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn find_by_id(&self, id: i64) -> Result<Option<Team>> {
+        let team = query_as::<_, Team>(
+            "SELECT * FROM teams WHERE id = $1 AND deleted_at IS NULL",
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(team)
+    }
+
+    pub async fn find_by_slug(
+        &self,
+        organization_id: i64,
+        slug: &str,
+    ) -> Result<Option<Team>> {
+        let team = query_as::<_, Team>(
+            r#"
+            SELECT * FROM teams
+            WHERE organization_id = $1 AND slug = $2 AND deleted_at IS NULL
+            "#,
+        )
+        .bind(organization_id)
+        .bind(slug)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(team)
+    }
+
+    pub async fn list_by_organization(
+        &self,
+        organization_id: i64,
+    ) -> Result<Vec<Team>> {
+        let teams = query_as::<_, Team>(
+            r#"
+            SELECT * FROM teams
+            WHERE organization_id = $1 AND deleted_at IS NULL
+            ORDER BY name
+            "#,
+        )
+        .bind(organization_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(teams)
+    }
+
+    /// Like [`Self::list_by_organization`], but soft-deleted teams too.
+    pub async fn list_by_organization_with_deleted(
+        &self,
+        organization_id: i64,
+    ) -> Result<Vec<Team>> {
+        let teams = query_as::<_, Team>(
+            r#"
+            SELECT * FROM teams
+            WHERE organization_id = $1
+            ORDER BY name
+            "#,
+        )
+        .bind(organization_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(teams)
+    }
+
+    /// One page of [`Self::list_by_organization`], or of
+    /// [`Self::list_by_organization_with_deleted`] with `include_deleted`.
+    pub async fn list_page_by_organization(
+        &self,
+        organization_id: i64,
+        include_deleted: bool,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<Team>> {
+        let teams = query_as::<_, Team>(
+            r#"
+            SELECT * FROM teams
+            WHERE organization_id = $1
+              AND ($2 OR deleted_at IS NULL)
+            ORDER BY name, id
+            LIMIT $3 OFFSET $4
+            "#,
+        )
+        .bind(organization_id)
+        .bind(include_deleted)
+        .bind(limit)
+        .bind(offset)
         .fetch_all(&self.pool)
         .await?;
 
         Ok(teams)
     }
 
+    /// Change the name and/or description; `None` keeps the current value.
+    ///
+    /// Returns `None` if the team does not exist or is deleted, or if it was
+    /// modified since `expected_updated_at` (when given).
+    pub async fn update(
+        &self,
+        id: i64,
+        name: Option<&str>,
+        description: Option<&str>,
+        expected_updated_at: Option<OffsetDateTime>,
+    ) -> Result<Option<Team>> {
+        let team = query_as::<_, Team>(
+            r#"
+            UPDATE teams
+            SET name = COALESCE($2, name),
+                description = COALESCE($3, description)
+            WHERE id = $1
+              AND deleted_at IS NULL
+              AND ($4::TIMESTAMPTZ IS NULL OR updated_at = $4)
+            RETURNING *
+            "#,
+        )
+        .bind(id)
+        .bind(name)
+        .bind(description)
+        .bind(expected_updated_at)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(team)
+    }
+
+    /// Mark a team as deleted. Its apps are kept (still attached to it).
+    ///
+    /// Returns `None` if the team does not exist or is already deleted.
+    pub async fn soft_delete(&self, id: i64) -> Result<Option<Team>> {
+        let team = query_as::<_, Team>(
+            r#"
+            UPDATE teams
+            SET deleted_at = NOW()
+            WHERE id = $1 AND deleted_at IS NULL
+            RETURNING *
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(team)
+    }
+
     pub async fn create(&self, new_team: NewTeam) -> Result<Team> {
         let team = query_as::<_, Team>(
             r#"
-            INSERT INTO teams (organization_id, name, slug, description)
-            VALUES ($1, $2, $3, $4)
+            INSERT INTO teams (organization_id, name, slug, description, created_by)
+            VALUES ($1, $2, $3, $4, $5)
             RETURNING *
             "#,
         )
@@ -303,11 +1043,140 @@ impl TeamRepository {
         .bind(new_team.name)
         .bind(new_team.slug)
         .bind(new_team.description)
+        .bind(new_team.created_by)
         .fetch_one(&self.pool)
         .await?;
 
         Ok(team)
     }
+
+    /// Move a team, and every app attached to it, to another organization.
+    ///
+    /// Runs in a single transaction. Fails without changes when the team or
+    /// any of its app slugs already exists in the target organization.
+    pub async fn transfer(
+        &self,
+        team_id: i64,
+        target_organization_id: i64,
+    ) -> Result<Team> {
+        let mut tx = self.pool.begin().await?;
+
+        let team = query_as::<_, Team>(
+            r#"
+            SELECT * FROM teams
+            WHERE id = $1 AND deleted_at IS NULL
+            FOR UPDATE
+            "#,
+        )
+        .bind(team_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("team {team_id} not found"))?;
+
+        if team.organization_id == target_organization_id {
+            anyhow::bail!("team is already in the target organization");
+        }
+
+        let team_slug_taken = query_scalar::<_, bool>(
+            r#"
+            SELECT EXISTS (
+                SELECT 1 FROM teams
+                WHERE organization_id = $1 AND slug = $2
+            )
+            "#,
+        )
+        .bind(target_organization_id)
+        .bind(&team.slug)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        if team_slug_taken {
+            anyhow::bail!(
+                "team slug '{}' already exists in the target organization",
+                team.slug
+            );
+        }
+
+        let colliding_apps = query_scalar::<_, String>(
+            r#"
+            SELECT a.slug FROM apps a
+            WHERE a.team_id = $1
+              AND EXISTS (
+                SELECT 1 FROM apps b
+                WHERE b.organization_id = $2 AND b.slug = a.slug
+              )
+            ORDER BY a.slug
+            "#,
+        )
+        .bind(team_id)
+        .bind(target_organization_id)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        if !colliding_apps.is_empty() {
+            anyhow::bail!(
+                "app slug(s) already exist in the target organization: {}",
+                colliding_apps.join(", ")
+            );
+        }
+
+        let team = query_as::<_, Team>(
+            r#"
+            UPDATE teams
+            SET organization_id = $2
+            WHERE id = $1
+            RETURNING *
+            "#,
+        )
+        .bind(team_id)
+        .bind(target_organization_id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            r#"
+            UPDATE apps
+            SET organization_id = $2
+            WHERE team_id = $1
+            "#,
+        )
+        .bind(team_id)
+        .bind(target_organization_id)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(team)
+    }
+
+    /// Case-insensitive match on name/slug, limited to the user's orgs.
+    pub async fn search_for_user(
+        &self,
+        user_id: i64,
+        term: &str,
+        limit: i64,
+    ) -> Result<Vec<Team>> {
+        let rows = query_as::<_, Team>(
+            r#"
+            SELECT t.* FROM teams t
+            JOIN organization_memberships m
+              ON m.organization_id = t.organization_id
+             AND m.user_id = $1
+            WHERE t.deleted_at IS NULL
+              AND (t.name ILIKE $2 OR t.slug ILIKE $2)
+            ORDER BY t.name
+            LIMIT $3
+            "#,
+        )
+        .bind(user_id)
+        .bind(like_pattern(term))
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows)
+    }
 }
 
 // ---------- TeamMembershipRepository ----------
@@ -330,6 +1199,7 @@ impl TeamMembershipRepository {
             r#"
             SELECT * FROM team_memberships
             WHERE team_id = $1
+            ORDER BY created_at, user_id
             "#,
         )
         .bind(team_id)
@@ -463,6 +1333,31 @@ impl AppRepository {
         Ok(apps)
     }
 
+    /// One page of [`Self::list_by_organization`].
+    pub async fn list_page_by_organization(
+        &self,
+        organization_id: i64,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<App>> {
+        let apps = query_as::<_, App>(
+            r#"
+            SELECT * FROM apps
+            WHERE organization_id = $1
+              AND deleted_at IS NULL
+            ORDER BY name, id
+            LIMIT $2 OFFSET $3
+            "#,
+        )
+        .bind(organization_id)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(apps)
+    }
+
     pub async fn list_by_team(&self, team_id: i64) -> Result<Vec<App>> {
         let apps = query_as::<_, App>(
             r#"
@@ -479,11 +1374,22 @@ impl AppRepository {
         Ok(apps)
     }
 
+    /// Create an app. A `team_id` must point to a live team of the same
+    /// organization; deleted teams cannot receive new apps.
     pub async fn create(&self, new_app: NewApp) -> Result<App> {
+        let team_id = new_app.team_id;
+
         let app = query_as::<_, App>(
             r#"
             INSERT INTO apps (organization_id, team_id, name, slug, repo_url, created_by)
-            VALUES ($1, $2, $3, $4, $5, $6)
+            SELECT $1, $2, $3, $4, $5, $6
+            WHERE $2::BIGINT IS NULL
+               OR EXISTS (
+                   SELECT 1 FROM teams
+                   WHERE id = $2
+                     AND organization_id = $1
+                     AND deleted_at IS NULL
+               )
             RETURNING *
             "#,
         )
@@ -493,18 +1399,84 @@ impl AppRepository {
         .bind(new_app.slug)
         .bind(new_app.repo_url)
         .bind(new_app.created_by)
-        .fetch_one(&self.pool)
+        .fetch_optional(&self.pool)
         .await?;
 
-        Ok(app)
+        match app {
+            Some(app) => Ok(app),
+            None => anyhow::bail!(
+                "team {} does not exist in this organization or was deleted",
+                team_id.unwrap_or_default()
+            ),
+        }
     }
-}
-
-// ---------- AppMembershipRepository ----------
 
-#[derive(Clone)]
-pub struct AppMembershipRepository {
-    pool: PgPool,
+    /// Update name and/or repo URL; `None` leaves a field unchanged.
+    ///
+    /// With `expected_updated_at`, the row is only updated if it was not
+    /// modified since; `Ok(None)` then means "not found or stale".
+    pub async fn update(
+        &self,
+        id: i64,
+        name: Option<&str>,
+        repo_url: Option<&str>,
+        expected_updated_at: Option<OffsetDateTime>,
+    ) -> Result<Option<App>> {
+        let app = query_as::<_, App>(
+            r#"
+            UPDATE apps
+            SET name = COALESCE($2, name),
+                repo_url = COALESCE($3, repo_url)
+            WHERE id = $1
+              AND deleted_at IS NULL
+              AND ($4::TIMESTAMPTZ IS NULL OR updated_at = $4)
+            RETURNING *
+            "#,
+        )
+        .bind(id)
+        .bind(name)
+        .bind(repo_url)
+        .bind(expected_updated_at)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(app)
+    }
+
+    /// Case-insensitive match on name/slug, limited to the user's orgs.
+    pub async fn search_for_user(
+        &self,
+        user_id: i64,
+        term: &str,
+        limit: i64,
+    ) -> Result<Vec<App>> {
+        let rows = query_as::<_, App>(
+            r#"
+            SELECT a.* FROM apps a
+            JOIN organization_memberships m
+              ON m.organization_id = a.organization_id
+             AND m.user_id = $1
+            WHERE a.deleted_at IS NULL
+              AND (a.name ILIKE $2 OR a.slug ILIKE $2)
+            ORDER BY a.name
+            LIMIT $3
+            "#,
+        )
+        .bind(user_id)
+        .bind(like_pattern(term))
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows)
+    }
+}
+
+// ---------- AppMembershipRepository ----------
+
+#[derive(Clone)]
+pub struct AppMembershipRepository {
+    pool: PgPool,
 }
 
 impl AppMembershipRepository {
@@ -590,16 +1562,88 @@ impl AppMembershipRepository {
     }
 }
 
+// ---------- AppDeployTokenRepository ----------
+
+#[derive(Clone)]
+pub struct AppDeployTokenRepository {
+    pool: PgPool,
+}
+
+impl AppDeployTokenRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Revoke the app's active token (if any) and store a new one, in one
+    /// transaction.
+    pub async fn regenerate(
+        &self,
+        new_token: NewAppDeployToken,
+    ) -> Result<AppDeployToken> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query(
+            r#"
+            UPDATE app_deploy_tokens
+            SET revoked_at = NOW()
+            WHERE app_id = $1 AND revoked_at IS NULL
+            "#,
+        )
+        .bind(new_token.app_id)
+        .execute(&mut *tx)
+        .await?;
+
+        let token = query_as::<_, AppDeployToken>(
+            r#"
+            INSERT INTO app_deploy_tokens (app_id, token_hash, created_by)
+            VALUES ($1, $2, $3)
+            RETURNING *
+            "#,
+        )
+        .bind(new_token.app_id)
+        .bind(new_token.token_hash)
+        .bind(new_token.created_by)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(token)
+    }
+
+    /// The active token with this hash, marking it as used.
+    pub async fn find_valid_by_hash(
+        &self,
+        token_hash: &str,
+    ) -> Result<Option<AppDeployToken>> {
+        let token = query_as::<_, AppDeployToken>(
+            r#"
+            UPDATE app_deploy_tokens
+            SET last_used_at = NOW()
+            WHERE token_hash = $1 AND revoked_at IS NULL
+            RETURNING *
+            "#,
+        )
+        .bind(token_hash)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(token)
+    }
+}
+
 // ---------- AppSecretRepository ----------
 
+/// Values are encrypted with `cipher` on write and decrypted on read, so
+/// callers only ever see plaintext.
 #[derive(Clone)]
 pub struct AppSecretRepository {
     pool: PgPool,
+    cipher: SecretCipher,
 }
 
 impl AppSecretRepository {
-    pub fn new(pool: PgPool) -> Self {
-        Self { pool }
+    pub fn new(pool: PgPool, cipher: SecretCipher) -> Self {
+        Self { pool, cipher }
     }
 
     pub async fn list_by_app_env(
@@ -620,21 +1664,46 @@ impl AppSecretRepository {
         .fetch_all(&self.pool)
         .await?;
 
-        Ok(rows)
+        rows.into_iter().map(|s| self.decrypted(s)).collect()
     }
 
+    /// Insert or overwrite a secret.
+    ///
+    /// An overwritten value is appended to `app_secret_versions` first, in
+    /// the same transaction.
     pub async fn upsert_secret(
         &self,
         new_secret: NewAppSecret,
     ) -> Result<AppSecret> {
+        let mut tx = self.pool.begin().await?;
+
+        let previous = query_as::<_, AppSecret>(
+            r#"
+            SELECT * FROM app_secrets
+            WHERE app_id = $1
+              AND environment = $2
+              AND key = $3
+            FOR UPDATE
+            "#,
+        )
+        .bind(new_secret.app_id)
+        .bind(&new_secret.environment)
+        .bind(&new_secret.key)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        if let Some(previous) = previous {
+            record_secret_version(&mut tx, &previous).await?;
+        }
+
         let row = query_as::<_, AppSecret>(
             r#"
-            INSERT INTO app_secrets (app_id, environment, key, value, created_by)
-            VALUES ($1, $2, $3, $4, $5)
+            INSERT INTO app_secrets (app_id, environment, key, value, kind, created_by)
+            VALUES ($1, $2, $3, $4, $5, $6)
             ON CONFLICT (app_id, environment, key)
             DO UPDATE SET
                 value = EXCLUDED.value,
-                updated_at = NOW(),
+                kind = EXCLUDED.kind,
                 created_by = EXCLUDED.created_by
             RETURNING *
             "#,
@@ -642,38 +1711,296 @@ impl AppSecretRepository {
         .bind(new_secret.app_id)
         .bind(new_secret.environment)
         .bind(new_secret.key)
-        .bind(new_secret.value)
+        .bind(self.cipher.encrypt(&new_secret.value)?)
+        .bind(new_secret.kind)
         .bind(new_secret.created_by)
-        .fetch_one(&self.pool)
+        .fetch_one(&mut *tx)
         .await?;
 
-        Ok(row)
+        tx.commit().await?;
+
+        self.decrypted(row)
+    }
+
+    /// Make the secrets of an app environment match `desired`.
+    ///
+    /// Missing keys are inserted and changed ones updated; with `prune`,
+    /// keys absent from `desired` are deleted. Replaced and deleted values
+    /// go to `app_secret_versions`. All in one transaction.
+    pub async fn sync_secrets(
+        &self,
+        app_id: i64,
+        environment: &str,
+        desired: Vec<NewAppSecret>,
+        prune: bool,
+    ) -> Result<AppSecretSyncSummary> {
+        let mut tx = self.pool.begin().await?;
+
+        let mut current: HashMap<String, AppSecret> =
+            query_as::<_, AppSecret>(
+                r#"
+                SELECT * FROM app_secrets
+                WHERE app_id = $1
+                  AND environment = $2
+                FOR UPDATE
+                "#,
+            )
+            .bind(app_id)
+            .bind(environment)
+            .fetch_all(&mut *tx)
+            .await?
+            .into_iter()
+            .map(|s| (s.key.clone(), s))
+            .collect();
+
+        let mut summary = AppSecretSyncSummary::default();
+        for secret in desired {
+            match current.remove(&secret.key) {
+                Some(existing)
+                    if existing.kind == secret.kind
+                        && self.cipher.decrypt(&existing.value)?
+                            == secret.value =>
+                {
+                    continue;
+                }
+                Some(existing) => {
+                    record_secret_version(&mut tx, &existing).await?;
+                    summary.updated.push(secret.key.clone());
+                }
+                None => summary.added.push(secret.key.clone()),
+            }
+
+            sqlx::query(
+                r#"
+                INSERT INTO app_secrets (app_id, environment, key, value, kind, created_by)
+                VALUES ($1, $2, $3, $4, $5, $6)
+                ON CONFLICT (app_id, environment, key)
+                DO UPDATE SET
+                    value = EXCLUDED.value,
+                    kind = EXCLUDED.kind,
+                    created_by = EXCLUDED.created_by
+                "#,
+            )
+            .bind(app_id)
+            .bind(environment)
+            .bind(secret.key)
+            .bind(self.cipher.encrypt(&secret.value)?)
+            .bind(secret.kind)
+            .bind(secret.created_by)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        if prune {
+            for stale in current.into_values() {
+                record_secret_version(&mut tx, &stale).await?;
+                sqlx::query("DELETE FROM app_secrets WHERE id = $1")
+                    .bind(stale.id)
+                    .execute(&mut *tx)
+                    .await?;
+                summary.removed.push(stale.key);
+            }
+        }
+
+        tx.commit().await?;
+
+        summary.added.sort();
+        summary.updated.sort();
+        summary.removed.sort();
+        Ok(summary)
+    }
+
+    /// Previous values of a secret, newest first.
+    pub async fn list_versions(
+        &self,
+        app_id: i64,
+        environment: &str,
+        key: &str,
+    ) -> Result<Vec<AppSecretVersion>> {
+        let rows = query_as::<_, AppSecretVersion>(
+            r#"
+            SELECT * FROM app_secret_versions
+            WHERE app_id = $1
+              AND environment = $2
+              AND key = $3
+            ORDER BY version DESC
+            "#,
+        )
+        .bind(app_id)
+        .bind(environment)
+        .bind(key)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter().map(|v| self.decrypted_version(v)).collect()
+    }
+
+    pub async fn find_version(
+        &self,
+        app_id: i64,
+        environment: &str,
+        key: &str,
+        version: i32,
+    ) -> Result<Option<AppSecretVersion>> {
+        let row = query_as::<_, AppSecretVersion>(
+            r#"
+            SELECT * FROM app_secret_versions
+            WHERE app_id = $1
+              AND environment = $2
+              AND key = $3
+              AND version = $4
+            "#,
+        )
+        .bind(app_id)
+        .bind(environment)
+        .bind(key)
+        .bind(version)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.map(|v| self.decrypted_version(v)).transpose()
     }
 
+    /// Delete a secret, keeping its last value in `app_secret_versions`.
+    ///
+    /// Returns `false` if there was no such secret.
     pub async fn delete_secret(
         &self,
         app_id: i64,
         environment: &str,
         key: &str,
-    ) -> Result<()> {
-        sqlx::query(
+    ) -> Result<bool> {
+        let mut tx = self.pool.begin().await?;
+
+        let deleted = query_as::<_, AppSecret>(
             r#"
             DELETE FROM app_secrets
             WHERE app_id = $1
               AND environment = $2
               AND key = $3
+            RETURNING *
             "#,
         )
         .bind(app_id)
         .bind(environment)
         .bind(key)
-        .execute(&self.pool)
+        .fetch_optional(&mut *tx)
         .await?;
+        let Some(deleted) = deleted else {
+            return Ok(false);
+        };
+        record_secret_version(&mut tx, &deleted).await?;
 
-        Ok(())
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    /// Encrypt values stored before encryption at rest was enabled, in the
+    /// live secrets and their history. Returns how many were encrypted.
+    pub async fn encrypt_plaintext_values(&self) -> Result<u64> {
+        let mut tx = self.pool.begin().await?;
+        let mut encrypted = 0;
+
+        for table in ["app_secrets", "app_secret_versions"] {
+            let rows: Vec<(i64, String)> = sqlx::query_as(&format!(
+                "SELECT id, value FROM {table} FOR UPDATE"
+            ))
+            .fetch_all(&mut *tx)
+            .await?;
+
+            for (id, value) in rows {
+                if SecretCipher::is_encrypted(&value) {
+                    continue;
+                }
+                sqlx::query(&format!(
+                    "UPDATE {table} SET value = $2 WHERE id = $1"
+                ))
+                .bind(id)
+                .bind(self.cipher.encrypt(&value)?)
+                .execute(&mut *tx)
+                .await?;
+                encrypted += 1;
+            }
+        }
+
+        tx.commit().await?;
+        Ok(encrypted)
+    }
+
+    /// `secret` with its stored value decrypted.
+    fn decrypted(&self, mut secret: AppSecret) -> Result<AppSecret> {
+        secret.value = self.cipher.decrypt(&secret.value)?;
+        Ok(secret)
+    }
+
+    fn decrypted_version(
+        &self,
+        mut version: AppSecretVersion,
+    ) -> Result<AppSecretVersion> {
+        version.value = self.cipher.decrypt(&version.value)?;
+        Ok(version)
     }
 }
 
+/// Append `previous` to the history of its key as the next version.
+async fn record_secret_version(
+    conn: &mut PgConnection,
+    previous: &AppSecret,
+) -> Result<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO app_secret_versions
+            (app_id, environment, key, version, value, kind, created_by)
+        SELECT $1, $2, $3, COALESCE(MAX(version), 0) + 1, $4, $5, $6
+        FROM app_secret_versions
+        WHERE app_id = $1
+          AND environment = $2
+          AND key = $3
+        "#,
+    )
+    .bind(previous.app_id)
+    .bind(&previous.environment)
+    .bind(&previous.key)
+    .bind(&previous.value)
+    .bind(previous.kind)
+    .bind(previous.created_by)
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+async fn insert_deploy<'e, E: PgExecutor<'e>>(
+    executor: E,
+    new_deploy: NewDeploy,
+) -> Result<Deploy> {
+    let row = query_as::<_, Deploy>(
+        r#"
+        INSERT INTO deploys (
+            app_id, release_id, environment, status,
+            triggered_by, target_cluster, target_region,
+            pipeline_url, logs_url, error_message, status_changed_by
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $5)
+        RETURNING *
+        "#,
+    )
+    .bind(new_deploy.app_id)
+    .bind(new_deploy.release_id)
+    .bind(new_deploy.environment)
+    .bind(new_deploy.status)
+    .bind(new_deploy.triggered_by)
+    .bind(new_deploy.target_cluster)
+    .bind(new_deploy.target_region)
+    .bind(new_deploy.pipeline_url)
+    .bind(new_deploy.logs_url)
+    .bind(new_deploy.error_message)
+    .fetch_one(executor)
+    .await?;
+
+    Ok(row)
+}
+
 // ---------- ReleaseRepository ----------
 
 #[derive(Clone)]
@@ -730,29 +2057,182 @@ impl ReleaseRepository {
         Ok(rows)
     }
 
+    /// One page of [`Self::list_by_app`].
+    pub async fn list_page_by_app(
+        &self,
+        app_id: i64,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<Release>> {
+        let rows = query_as::<_, Release>(
+            r#"
+            SELECT * FROM releases
+            WHERE app_id = $1
+            ORDER BY created_at DESC, id DESC
+            LIMIT $2 OFFSET $3
+            "#,
+        )
+        .bind(app_id)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows)
+    }
+
     pub async fn create(&self, new_release: NewRelease) -> Result<Release> {
+        insert_release(&self.pool, new_release).await
+    }
+
+    /// [`Self::create`] on a caller-owned connection, usually a transaction.
+    pub async fn create_tx(
+        conn: &mut PgConnection,
+        new_release: NewRelease,
+    ) -> Result<Release> {
+        insert_release(conn, new_release).await
+    }
+
+    /// Mark a pending release as `building`.
+    pub async fn mark_building(
+        &self,
+        release_id: i64,
+        changed_by: Option<i64>,
+    ) -> Result<Release> {
         let row = query_as::<_, Release>(
             r#"
-            INSERT INTO releases (
-                app_id, version, commit_sha, branch, tag, image_ref,
-                status, created_by, changelog
-            )
-            VALUES ($1, $2, $3, $4, $5, $6, 'pending', $7, $8)
+            UPDATE releases
+            SET status = 'building',
+                status_changed_at = NOW(),
+                status_changed_by = $2,
+                updated_at = NOW()
+            WHERE id = $1 AND status = 'pending'
             RETURNING *
             "#,
         )
-        .bind(new_release.app_id)
-        .bind(new_release.version)
-        .bind(new_release.commit_sha)
-        .bind(new_release.branch)
-        .bind(new_release.tag)
-        .bind(new_release.image_ref)
-        .bind(new_release.created_by)
-        .bind(new_release.changelog)
-        .fetch_one(&self.pool)
+        .bind(release_id)
+        .bind(changed_by)
+        .fetch_optional(&self.pool)
         .await?;
 
-        Ok(row)
+        match row {
+            Some(release) => Ok(release),
+            None => match self.find_by_id(release_id).await? {
+                Some(release) => anyhow::bail!(
+                    "release {} cannot start building ({:?})",
+                    release.id,
+                    release.status
+                ),
+                None => anyhow::bail!("release {release_id} not found"),
+            },
+        }
+    }
+
+    /// Attach the built image to a pending or building release and finalize
+    /// its status.
+    ///
+    /// The update only applies while the release is not finalized yet, so a
+    /// release that is already `built` or `failed` is rejected.
+    pub async fn update_image_ref(
+        &self,
+        release_id: i64,
+        image_ref: &str,
+        status: ReleaseStatus,
+        changed_by: Option<i64>,
+    ) -> Result<Release> {
+        let mut conn = self.pool.acquire().await?;
+        finalize_release(&mut conn, release_id, image_ref, status, changed_by)
+            .await
+    }
+
+    /// [`Self::update_image_ref`] on a caller-owned connection, usually the
+    /// transaction that created the release.
+    pub async fn update_image_ref_tx(
+        conn: &mut PgConnection,
+        release_id: i64,
+        image_ref: &str,
+        status: ReleaseStatus,
+        changed_by: Option<i64>,
+    ) -> Result<Release> {
+        finalize_release(conn, release_id, image_ref, status, changed_by).await
+    }
+}
+
+async fn insert_release<'e, E: PgExecutor<'e>>(
+    executor: E,
+    new_release: NewRelease,
+) -> Result<Release> {
+    let row = query_as::<_, Release>(
+        r#"
+        INSERT INTO releases (
+            app_id, version, commit_sha, branch, tag, image_ref,
+            status, created_by, changelog, status_changed_by
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, 'pending', $7, $8, $7)
+        RETURNING *
+        "#,
+    )
+    .bind(new_release.app_id)
+    .bind(new_release.version)
+    .bind(new_release.commit_sha)
+    .bind(new_release.branch)
+    .bind(new_release.tag)
+    .bind(new_release.image_ref)
+    .bind(new_release.created_by)
+    .bind(new_release.changelog)
+    .fetch_one(executor)
+    .await?;
+
+    Ok(row)
+}
+
+async fn finalize_release(
+    conn: &mut PgConnection,
+    release_id: i64,
+    image_ref: &str,
+    status: ReleaseStatus,
+    changed_by: Option<i64>,
+) -> Result<Release> {
+    if !ReleaseStatus::Building.can_transition_to(status) {
+        anyhow::bail!("release can only be finalized as built or failed");
+    }
+
+    let row = query_as::<_, Release>(
+        r#"
+        UPDATE releases
+        SET image_ref = $2,
+            status = $3,
+            status_changed_at = NOW(),
+            status_changed_by = $4,
+            updated_at = NOW()
+        WHERE id = $1 AND status IN ('pending', 'building')
+        RETURNING *
+        "#,
+    )
+    .bind(release_id)
+    .bind(image_ref)
+    .bind(status)
+    .bind(changed_by)
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    if let Some(release) = row {
+        return Ok(release);
+    }
+
+    let current =
+        query_as::<_, Release>("SELECT * FROM releases WHERE id = $1")
+            .bind(release_id)
+            .fetch_optional(&mut *conn)
+            .await?;
+
+    match current {
+        Some(release) => anyhow::bail!(
+            "release {} is already finalized ({:?})",
+            release.id,
+            release.status
+        ),
+        None => anyhow::bail!("release {release_id} not found"),
     }
 }
 
@@ -797,6 +2277,40 @@ impl DeployRepository {
         Ok(rows)
     }
 
+    /// Latest deploys of an app, optionally only those to `environment`.
+    /// Newest first; `since` is inclusive and `until` exclusive.
+    pub async fn list_recent_by_app(
+        &self,
+        app_id: i64,
+        environment: Option<&str>,
+        since: Option<OffsetDateTime>,
+        until: Option<OffsetDateTime>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<Deploy>> {
+        let rows = query_as::<_, Deploy>(
+            r#"
+            SELECT * FROM deploys
+            WHERE app_id = $1
+              AND ($2::text IS NULL OR environment = $2)
+              AND ($3::timestamptz IS NULL OR created_at >= $3)
+              AND ($4::timestamptz IS NULL OR created_at < $4)
+            ORDER BY created_at DESC, id DESC
+            LIMIT $5 OFFSET $6
+            "#,
+        )
+        .bind(app_id)
+        .bind(environment)
+        .bind(since)
+        .bind(until)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows)
+    }
+
     pub async fn list_by_release(
         &self,
         release_id: i64,
@@ -816,71 +2330,197 @@ impl DeployRepository {
     }
 
     pub async fn create(&self, new_deploy: NewDeploy) -> Result<Deploy> {
-        let row = query_as::<_, Deploy>(
+        insert_deploy(&self.pool, new_deploy).await
+    }
+
+    /// Create one deploy of `template` per environment, atomically.
+    ///
+    /// Environments with a pending or running deploy of the app are busy:
+    /// with `skip_busy` they are left out, otherwise nothing is created
+    /// and only `busy` is filled in.
+    pub async fn create_for_environments(
+        &self,
+        template: NewDeploy,
+        environments: &[String],
+        skip_busy: bool,
+    ) -> Result<DeployBatch> {
+        let mut tx = self.pool.begin().await?;
+
+        // Serialize batch deploys of the same app.
+        sqlx::query("SELECT pg_advisory_xact_lock($1)")
+            .bind(template.app_id)
+            .execute(&mut *tx)
+            .await?;
+
+        let busy = query_scalar::<_, String>(
             r#"
-            INSERT INTO deploys (
-                app_id, release_id, environment, status,
-                triggered_by, target_cluster, target_region,
-                pipeline_url, logs_url, error_message
-            )
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
-            RETURNING *
+            SELECT DISTINCT environment FROM deploys
+            WHERE app_id = $1
+              AND environment = ANY($2)
+              AND status IN ('pending', 'running')
+            ORDER BY environment
             "#,
         )
-        .bind(new_deploy.app_id)
-        .bind(new_deploy.release_id)
-        .bind(new_deploy.environment)
-        .bind(new_deploy.status)
-        .bind(new_deploy.triggered_by)
-        .bind(new_deploy.target_cluster)
-        .bind(new_deploy.target_region)
-        .bind(new_deploy.pipeline_url)
-        .bind(new_deploy.logs_url)
-        .bind(new_deploy.error_message)
-        .fetch_one(&self.pool)
+        .bind(template.app_id)
+        .bind(environments)
+        .fetch_all(&mut *tx)
         .await?;
 
-        Ok(row)
-    }
-}
+        if !busy.is_empty() && !skip_busy {
+            return Ok(DeployBatch { created: Vec::new(), busy });
+        }
 
-// ---------- BuildJobRepository ----------
+        let mut created = Vec::new();
+        for environment in environments.iter().filter(|e| !busy.contains(e)) {
+            let new_deploy = NewDeploy {
+                environment: environment.clone(),
+                ..template.clone()
+            };
+            created.push(insert_deploy(&mut *tx, new_deploy).await?);
+        }
 
-#[derive(Clone)]
-pub struct BuildJobRepository {
-    pool: PgPool,
-}
+        tx.commit().await?;
 
-impl BuildJobRepository {
-    pub fn new(pool: PgPool) -> Self {
-        Self { pool }
+        Ok(DeployBatch { created, busy })
     }
 
-    pub async fn find_by_id(&self, id: i64) -> Result<Option<BuildJob>> {
-        let row =
-            query_as::<_, BuildJob>("SELECT * FROM build_jobs WHERE id = $1")
-                .bind(id)
-                .fetch_optional(&self.pool)
-                .await?;
+    /// Status transitions of a deploy, oldest first.
+    pub async fn list_status_events(
+        &self,
+        deploy_id: i64,
+    ) -> Result<Vec<DeployStatusEvent>> {
+        let rows = query_as::<_, DeployStatusEvent>(
+            r#"
+            SELECT * FROM deploy_status_events
+            WHERE deploy_id = $1
+            ORDER BY id
+            "#,
+        )
+        .bind(deploy_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows)
+    }
+
+    /// Number of deploys of an app created at or after `since`.
+    pub async fn count_by_app_since(
+        &self,
+        app_id: i64,
+        since: OffsetDateTime,
+    ) -> Result<i64> {
+        let count = query_scalar::<_, i64>(
+            r#"
+            SELECT COUNT(*) FROM deploys
+            WHERE app_id = $1 AND created_at >= $2
+            "#,
+        )
+        .bind(app_id)
+        .bind(since)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(count)
+    }
+
+    /// Fail `running` deploys started more than `older_than` ago.
+    ///
+    /// Housekeeping for deploys left behind by a dead worker. Returns how
+    /// many deploys were marked as failed.
+    pub async fn mark_stale_as_failed(
+        &self,
+        older_than: Duration,
+    ) -> Result<u64> {
+        let cutoff = OffsetDateTime::now_utc() - older_than;
+
+        let result = sqlx::query(
+            r#"
+            UPDATE deploys
+            SET status = 'failed',
+                finished_at = NOW(),
+                error_message = 'deploy timed out',
+                status_changed_at = NOW(),
+                status_changed_by = NULL
+            WHERE status = 'running'
+              AND started_at < $1
+            "#,
+        )
+        .bind(cutoff)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+}
+
+// ---------- BuildJobRepository ----------
+
+#[derive(Clone)]
+pub struct BuildJobRepository {
+    pool: PgPool,
+}
+
+impl BuildJobRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn find_by_id(&self, id: i64) -> Result<Option<BuildJob>> {
+        let row =
+            query_as::<_, BuildJob>("SELECT * FROM build_jobs WHERE id = $1")
+                .bind(id)
+                .fetch_optional(&self.pool)
+                .await?;
 
         Ok(row)
     }
 
+    /// Most recent build of an app that produced `image_ref`.
+    pub async fn find_by_image_ref(
+        &self,
+        app_id: i64,
+        image_ref: &str,
+    ) -> Result<Option<BuildJob>> {
+        let row = query_as::<_, BuildJob>(
+            r#"
+            SELECT * FROM build_jobs
+            WHERE app_id = $1 AND image_ref = $2
+            ORDER BY created_at DESC, id DESC
+            LIMIT 1
+            "#,
+        )
+        .bind(app_id)
+        .bind(image_ref)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row)
+    }
+
+    /// Newest first; `since` is inclusive and `until` exclusive.
     pub async fn list_recent_by_app(
         &self,
         app_id: i64,
+        since: Option<OffsetDateTime>,
+        until: Option<OffsetDateTime>,
         limit: i64,
+        offset: i64,
     ) -> Result<Vec<BuildJob>> {
         let rows = query_as::<_, BuildJob>(
             r#"
             SELECT * FROM build_jobs
             WHERE app_id = $1
-            ORDER BY created_at DESC
-            LIMIT $2
+              AND ($2::timestamptz IS NULL OR created_at >= $2)
+              AND ($3::timestamptz IS NULL OR created_at < $3)
+            ORDER BY created_at DESC, id DESC
+            LIMIT $4 OFFSET $5
             "#,
         )
         .bind(app_id)
+        .bind(since)
+        .bind(until)
         .bind(limit)
+        .bind(offset)
         .fetch_all(&self.pool)
         .await?;
 
@@ -921,6 +2561,135 @@ impl BuildJobRepository {
 
         Ok(row)
     }
+
+    pub async fn count_by_app(&self, app_id: i64) -> Result<i64> {
+        let count = query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM build_jobs WHERE app_id = $1",
+        )
+        .bind(app_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(count)
+    }
+
+    /// Fail `running` builds started more than `older_than` ago.
+    ///
+    /// Their pending/running steps are failed too, so a build never ends up
+    /// `failed` with steps still in progress; finished steps are kept.
+    /// Returns how many builds were marked as failed.
+    pub async fn mark_stale_as_failed(
+        &self,
+        older_than: Duration,
+    ) -> Result<u64> {
+        let cutoff = OffsetDateTime::now_utc() - older_than;
+        let mut tx = self.pool.begin().await?;
+
+        let build_ids = query_scalar::<_, i64>(
+            r#"
+            UPDATE build_jobs
+            SET status = 'failed',
+                finished_at = NOW(),
+                error_message = 'build timed out'
+            WHERE status = 'running'
+              AND started_at < $1
+            RETURNING id
+            "#,
+        )
+        .bind(cutoff)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        if !build_ids.is_empty() {
+            sqlx::query(
+                r#"
+                UPDATE build_steps
+                SET status = 'failed',
+                    finished_at = NOW(),
+                    error_message = 'build timed out'
+                WHERE build_id = ANY($1)
+                  AND status IN ('pending', 'running')
+                "#,
+            )
+            .bind(&build_ids)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(build_ids.len() as u64)
+    }
+
+    /// Fail `running` builds whose steps roll up to failed (see
+    /// [`rollup_status`]): a failed step fails its whole parallel group,
+    /// so the build fails without waiting for the rest of the group.
+    ///
+    /// Their pending/running steps are failed too. Returns how many
+    /// builds were marked as failed.
+    pub async fn fail_builds_with_failed_steps(&self) -> Result<u64> {
+        let mut tx = self.pool.begin().await?;
+
+        let steps = query_as::<_, BuildStep>(
+            r#"
+            SELECT s.* FROM build_steps s
+            JOIN build_jobs b ON b.id = s.build_id
+            WHERE b.status = 'running'
+              AND EXISTS (
+                  SELECT 1 FROM build_steps f
+                  WHERE f.build_id = b.id AND f.status = 'failed'
+              )
+            FOR UPDATE OF b
+            "#,
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+
+        let mut by_build: HashMap<i64, Vec<BuildStep>> = HashMap::new();
+        for step in steps {
+            by_build.entry(step.build_id).or_default().push(step);
+        }
+        let build_ids: Vec<i64> = by_build
+            .into_iter()
+            .filter(|(_, steps)| {
+                rollup_status(steps) == Some(BuildStatus::Failed)
+            })
+            .map(|(build_id, _)| build_id)
+            .collect();
+
+        if !build_ids.is_empty() {
+            sqlx::query(
+                r#"
+                UPDATE build_jobs
+                SET status = 'failed',
+                    finished_at = NOW(),
+                    error_message = 'build step failed'
+                WHERE id = ANY($1)
+                "#,
+            )
+            .bind(&build_ids)
+            .execute(&mut *tx)
+            .await?;
+
+            sqlx::query(
+                r#"
+                UPDATE build_steps
+                SET status = 'failed',
+                    finished_at = NOW(),
+                    error_message = 'another build step failed'
+                WHERE build_id = ANY($1)
+                  AND status IN ('pending', 'running')
+                "#,
+            )
+            .bind(&build_ids)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(build_ids.len() as u64)
+    }
 }
 
 // ---------- BuildStepRepository ----------
@@ -943,7 +2712,7 @@ impl BuildStepRepository {
             r#"
             SELECT * FROM build_steps
             WHERE build_id = $1
-            ORDER BY position
+            ORDER BY position, id
             "#,
         )
         .bind(build_id)
@@ -957,14 +2726,16 @@ impl BuildStepRepository {
         let row = query_as::<_, BuildStep>(
             r#"
             INSERT INTO build_steps (
-                build_id, position, name, status, logs_url, error_message
+                build_id, position, step_group, name, status, logs_url,
+                error_message
             )
-            VALUES ($1, $2, $3, $4, $5, $6)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
             RETURNING *
             "#,
         )
         .bind(new_step.build_id)
         .bind(new_step.position)
+        .bind(new_step.group)
         .bind(new_step.name)
         .bind(new_step.status)
         .bind(new_step.logs_url)
@@ -988,22 +2759,57 @@ impl BuildLogRepository {
         Self { pool }
     }
 
+    /// All chunks of a build in the order they were written.
+    ///
+    /// `chunk_index` only counts within one step, so insertion order (`id`)
+    /// is what interleaves the steps correctly.
     pub async fn list_by_build(&self, build_id: i64) -> Result<Vec<BuildLog>> {
         let rows = query_as::<_, BuildLog>(
             r#"
             SELECT * FROM build_logs
             WHERE build_id = $1
-            ORDER BY chunk_index
+            ORDER BY id
+            "#,
+        )
+        .bind(build_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows)
+    }
+
+    /// The last `max_chunks` chunks written for a build, oldest first.
+    pub async fn tail_by_build(
+        &self,
+        build_id: i64,
+        max_chunks: i64,
+    ) -> Result<Vec<BuildLog>> {
+        let mut rows = query_as::<_, BuildLog>(
+            r#"
+            SELECT * FROM build_logs
+            WHERE build_id = $1
+            ORDER BY id DESC
+            LIMIT $2
             "#,
         )
         .bind(build_id)
+        .bind(max_chunks)
         .fetch_all(&self.pool)
         .await?;
 
+        rows.reverse();
         Ok(rows)
     }
 
+    /// Insert a single chunk; use [`Self::append`] for arbitrary output.
     pub async fn create(&self, new_log: NewBuildLog) -> Result<BuildLog> {
+        if new_log.content.len() > BUILD_LOG_CHUNK_BYTES {
+            anyhow::bail!(
+                "build log chunk is {} bytes (max {BUILD_LOG_CHUNK_BYTES})",
+                new_log.content.len()
+            );
+        }
+
         let row = query_as::<_, BuildLog>(
             r#"
             INSERT INTO build_logs (
@@ -1022,4 +2828,1507 @@ impl BuildLogRepository {
 
         Ok(row)
     }
+
+    /// Append output to a build (or one of its steps).
+    ///
+    /// `content` is split into chunks of at most [`BUILD_LOG_CHUNK_BYTES`]
+    /// stored with consecutive `chunk_index`es after the existing ones, all
+    /// in one transaction. Payloads above [`BUILD_LOG_MAX_APPEND_BYTES`]
+    /// are rejected.
+    pub async fn append(
+        &self,
+        build_id: i64,
+        step_id: Option<i64>,
+        content: &str,
+    ) -> Result<Vec<BuildLog>> {
+        if content.len() > BUILD_LOG_MAX_APPEND_BYTES {
+            anyhow::bail!(
+                "build log payload is {} bytes (max {BUILD_LOG_MAX_APPEND_BYTES})",
+                content.len()
+            );
+        }
+
+        let mut tx = self.pool.begin().await?;
+
+        // Serialize concurrent appends to the same build
+        sqlx::query("SELECT id FROM build_jobs WHERE id = $1 FOR UPDATE")
+            .bind(build_id)
+            .fetch_optional(&mut *tx)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("build {build_id} not found"))?;
+
+        let next_index = query_scalar::<_, i32>(
+            r#"
+            SELECT COALESCE(MAX(chunk_index) + 1, 0)
+            FROM build_logs
+            WHERE build_id = $1 AND step_id IS NOT DISTINCT FROM $2
+            "#,
+        )
+        .bind(build_id)
+        .bind(step_id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        let mut rows = Vec::new();
+        for (offset, chunk) in split_chunks(content, BUILD_LOG_CHUNK_BYTES)
+            .into_iter()
+            .enumerate()
+        {
+            let row = query_as::<_, BuildLog>(
+                r#"
+                INSERT INTO build_logs (
+                    build_id, step_id, chunk_index, content
+                )
+                VALUES ($1, $2, $3, $4)
+                RETURNING *
+                "#,
+            )
+            .bind(build_id)
+            .bind(step_id)
+            .bind(next_index + offset as i32)
+            .bind(chunk)
+            .fetch_one(&mut *tx)
+            .await?;
+
+            rows.push(row);
+        }
+
+        tx.commit().await?;
+
+        Ok(rows)
+    }
+}
+
+/// Max size of a single `build_logs.content` row.
+pub const BUILD_LOG_CHUNK_BYTES: usize = 16 * 1024;
+
+/// Max payload accepted by one [`BuildLogRepository::append`] call.
+pub const BUILD_LOG_MAX_APPEND_BYTES: usize = 4 * 1024 * 1024;
+
+/// Split `s` into pieces of at most `max` bytes on UTF-8 boundaries.
+fn split_chunks(s: &str, max: usize) -> Vec<&str> {
+    let mut chunks = Vec::new();
+    let mut rest = s;
+
+    while rest.len() > max {
+        let mut end = max;
+        while !rest.is_char_boundary(end) {
+            end -= 1;
+        }
+        let (chunk, tail) = rest.split_at(end);
+        chunks.push(chunk);
+        rest = tail;
+    }
+    if !rest.is_empty() {
+        chunks.push(rest);
+    }
+
+    chunks
+}
+
+/// `%term%` for ILIKE, with the LIKE wildcards in `term` escaped.
+fn like_pattern(term: &str) -> String {
+    let escaped =
+        term.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+    format!("%{escaped}%")
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::{PgPool, query_scalar};
+
+    use super::*;
+
+    // ---------- fixtures ----------
+
+    async fn org(pool: &PgPool, slug: &str) -> i64 {
+        query_scalar(
+            "INSERT INTO organizations (name, slug) VALUES ($1, $1) RETURNING id",
+        )
+        .bind(slug)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+    }
+
+    async fn app(pool: &PgPool, organization_id: i64, slug: &str) -> i64 {
+        query_scalar(
+            r#"
+            INSERT INTO apps (organization_id, name, slug)
+            VALUES ($1, $2, $2)
+            RETURNING id
+            "#,
+        )
+        .bind(organization_id)
+        .bind(slug)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+    }
+
+    async fn release(pool: &PgPool, app_id: i64, version: &str) -> Release {
+        ReleaseRepository::new(pool.clone())
+            .create(NewRelease {
+                app_id,
+                version: version.to_string(),
+                commit_sha: None,
+                branch: None,
+                tag: None,
+                image_ref: None,
+                created_by: None,
+                changelog: None,
+            })
+            .await
+            .unwrap()
+    }
+
+    async fn soft_delete(pool: &PgPool, table: &str, id: i64) {
+        sqlx::query(&format!(
+            "UPDATE {table} SET deleted_at = NOW() WHERE id = $1"
+        ))
+        .bind(id)
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    async fn team(pool: &PgPool, organization_id: i64, slug: &str) -> i64 {
+        query_scalar(
+            r#"
+            INSERT INTO teams (organization_id, name, slug)
+            VALUES ($1, $2, $2)
+            RETURNING id
+            "#,
+        )
+        .bind(organization_id)
+        .bind(slug)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+    }
+
+    async fn team_app(pool: &PgPool, team_id: i64, slug: &str) -> i64 {
+        query_scalar(
+            r#"
+            INSERT INTO apps (organization_id, team_id, name, slug)
+            SELECT organization_id, id, $2, $2 FROM teams WHERE id = $1
+            RETURNING id
+            "#,
+        )
+        .bind(team_id)
+        .bind(slug)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+    }
+
+    async fn user(pool: &PgPool, email: &str) -> i64 {
+        query_scalar(
+            r#"
+            INSERT INTO users (name, email, password_hash)
+            VALUES ($1, $1, 'x')
+            RETURNING id
+            "#,
+        )
+        .bind(email)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+    }
+
+    async fn auth_token(pool: &PgPool, user_id: i64, token: &str) -> i64 {
+        AuthTokenRepository::new(pool.clone())
+            .create(NewAuthToken {
+                user_id,
+                token: token.to_string(),
+                description: None,
+                expires_at: None,
+            })
+            .await
+            .unwrap()
+            .id
+    }
+
+    /// Reset link for `token_hash` that expires `expires_in` from now.
+    async fn password_reset(
+        pool: &PgPool,
+        user_id: i64,
+        token_hash: &str,
+        expires_in: time::Duration,
+    ) {
+        PasswordResetRepository::new(pool.clone())
+            .create(NewPasswordReset {
+                user_id,
+                token_hash: token_hash.to_string(),
+                expires_at: OffsetDateTime::now_utc() + expires_in,
+            })
+            .await
+            .unwrap();
+    }
+
+    async fn member(pool: &PgPool, organization_id: i64, user_id: i64) {
+        sqlx::query(
+            r#"
+            INSERT INTO organization_memberships (organization_id, user_id)
+            VALUES ($1, $2)
+            "#,
+        )
+        .bind(organization_id)
+        .bind(user_id)
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    /// Deploy of `release` in `status`, started `started_ago` ago.
+    async fn deploy(
+        pool: &PgPool,
+        release: &Release,
+        status: &str,
+        started_ago: &str,
+    ) -> i64 {
+        query_scalar(
+            r#"
+            INSERT INTO deploys
+                (app_id, release_id, environment, status, started_at)
+            VALUES
+                ($1, $2, 'production', $3::deploy_status, NOW() - $4::interval)
+            RETURNING id
+            "#,
+        )
+        .bind(release.app_id)
+        .bind(release.id)
+        .bind(status)
+        .bind(started_ago)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+    }
+
+    // ---------- releases ----------
+
+    #[sqlx::test]
+    async fn update_image_ref_finalizes_a_pending_release(pool: PgPool) {
+        let org_id = org(&pool, "acme").await;
+        let app_id = app(&pool, org_id, "web").await;
+        let pending = release(&pool, app_id, "v1").await;
+
+        let built = ReleaseRepository::new(pool)
+            .update_image_ref(
+                pending.id,
+                "registry/web:v1",
+                ReleaseStatus::Built,
+                None,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(built.status, ReleaseStatus::Built);
+        assert_eq!(built.image_ref.as_deref(), Some("registry/web:v1"));
+    }
+
+    #[sqlx::test]
+    async fn update_image_ref_rejects_a_finalized_release(pool: PgPool) {
+        let org_id = org(&pool, "acme").await;
+        let app_id = app(&pool, org_id, "web").await;
+        let pending = release(&pool, app_id, "v1").await;
+        let repo = ReleaseRepository::new(pool);
+
+        repo.update_image_ref(
+            pending.id,
+            "registry/web:v1",
+            ReleaseStatus::Built,
+            None,
+        )
+        .await
+        .unwrap();
+        let err = repo
+            .update_image_ref(
+                pending.id,
+                "registry/web:v2",
+                ReleaseStatus::Failed,
+                None,
+            )
+            .await
+            .unwrap_err();
+
+        assert!(err.to_string().contains("already finalized"), "{err}");
+        let stored = repo.find_by_id(pending.id).await.unwrap().unwrap();
+        assert_eq!(stored.image_ref.as_deref(), Some("registry/web:v1"));
+    }
+
+    #[sqlx::test]
+    async fn update_image_ref_never_goes_back_to_pending(pool: PgPool) {
+        let org_id = org(&pool, "acme").await;
+        let app_id = app(&pool, org_id, "web").await;
+        let pending = release(&pool, app_id, "v1").await;
+
+        let err = ReleaseRepository::new(pool)
+            .update_image_ref(
+                pending.id,
+                "registry/web:v1",
+                ReleaseStatus::Pending,
+                None,
+            )
+            .await;
+
+        assert!(err.is_err());
+    }
+
+    #[test]
+    fn release_status_transitions_only_move_forward() {
+        use ReleaseStatus::*;
+
+        assert!(Pending.can_transition_to(Building));
+        assert!(Pending.can_transition_to(Built));
+        assert!(Building.can_transition_to(Built));
+        assert!(Building.can_transition_to(Failed));
+        assert!(!Building.can_transition_to(Pending));
+        assert!(!Built.can_transition_to(Building));
+        assert!(!Failed.can_transition_to(Built));
+        assert!(!Pending.can_transition_to(Pending));
+    }
+
+    #[sqlx::test]
+    async fn building_releases_can_be_finalized(pool: PgPool) {
+        let org_id = org(&pool, "acme").await;
+        let app_id = app(&pool, org_id, "web").await;
+        let pending = release(&pool, app_id, "v1").await;
+        let repo = ReleaseRepository::new(pool);
+
+        let building = repo.mark_building(pending.id, None).await.unwrap();
+        let failed = repo
+            .update_image_ref(
+                pending.id,
+                "registry/web:v1",
+                ReleaseStatus::Failed,
+                None,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(building.status, ReleaseStatus::Building);
+        assert_eq!(failed.status, ReleaseStatus::Failed);
+    }
+
+    #[sqlx::test]
+    async fn only_pending_releases_start_building(pool: PgPool) {
+        let org_id = org(&pool, "acme").await;
+        let app_id = app(&pool, org_id, "web").await;
+        let pending = release(&pool, app_id, "v1").await;
+        let repo = ReleaseRepository::new(pool);
+
+        repo.mark_building(pending.id, None).await.unwrap();
+        let again = repo.mark_building(pending.id, None).await.unwrap_err();
+        let missing = repo.mark_building(-1, None).await.unwrap_err();
+
+        assert!(
+            again.to_string().contains("cannot start building"),
+            "{again}"
+        );
+        assert!(missing.to_string().contains("not found"), "{missing}");
+    }
+
+    // ---------- organizations ----------
+
+    #[sqlx::test]
+    async fn deleted_organizations_are_listed_and_can_be_restored(
+        pool: PgPool,
+    ) {
+        let kept = org(&pool, "kept").await;
+        let deleted = org(&pool, "deleted").await;
+        soft_delete(&pool, "organizations", deleted).await;
+        let repo = OrganizationRepository::new(pool);
+
+        let listed: Vec<i64> =
+            repo.list_deleted().await.unwrap().iter().map(|o| o.id).collect();
+        assert_eq!(listed, [deleted]);
+        assert!(repo.find_by_id(deleted).await.unwrap().is_none());
+
+        let restored = repo.restore(deleted, false).await.unwrap().unwrap();
+        assert_eq!(restored.deleted_at, None);
+        assert!(repo.find_by_id(deleted).await.unwrap().is_some());
+        assert!(repo.find_by_slug("deleted").await.unwrap().is_some());
+        assert!(repo.list_deleted().await.unwrap().is_empty());
+        assert!(repo.find_by_id(kept).await.unwrap().is_some());
+    }
+
+    #[sqlx::test]
+    async fn restore_ignores_live_organizations(pool: PgPool) {
+        let id = org(&pool, "acme").await;
+
+        let restored =
+            OrganizationRepository::new(pool).restore(id, true).await.unwrap();
+
+        assert!(restored.is_none());
+    }
+
+    #[sqlx::test]
+    async fn restore_with_children_only_brings_back_later_deletions(
+        pool: PgPool,
+    ) {
+        let org_id = org(&pool, "acme").await;
+        let old_app = app(&pool, org_id, "old").await;
+        sqlx::query(
+            "UPDATE apps SET deleted_at = NOW() - INTERVAL '1 day' WHERE id = $1",
+        )
+        .bind(old_app)
+        .execute(&pool)
+        .await
+        .unwrap();
+        let web = app(&pool, org_id, "web").await;
+        soft_delete(&pool, "organizations", org_id).await;
+        soft_delete(&pool, "apps", web).await;
+
+        OrganizationRepository::new(pool.clone())
+            .restore(org_id, true)
+            .await
+            .unwrap()
+            .unwrap();
+
+        let apps = AppRepository::new(pool);
+        assert!(apps.find_by_id(web).await.unwrap().is_some());
+        assert!(apps.find_by_id(old_app).await.unwrap().is_none());
+    }
+
+    #[sqlx::test]
+    async fn list_for_user_returns_each_organization_with_its_role(
+        pool: PgPool,
+    ) {
+        let ada = user(&pool, "ada@example.com").await;
+        let zeta = org(&pool, "zeta").await;
+        let acme = org(&pool, "acme").await;
+        let gone = org(&pool, "gone").await;
+        org(&pool, "other").await;
+        for org_id in [zeta, acme, gone] {
+            member(&pool, org_id, ada).await;
+        }
+        sqlx::query(
+            r#"
+            UPDATE organization_memberships SET role = 'owner'
+            WHERE organization_id = $1
+            "#,
+        )
+        .bind(zeta)
+        .execute(&pool)
+        .await
+        .unwrap();
+        soft_delete(&pool, "organizations", gone).await;
+
+        let listed: Vec<(String, OrgRole)> = OrganizationRepository::new(pool)
+            .list_for_user(ada)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|(o, role)| (o.slug, role))
+            .collect();
+
+        assert_eq!(
+            listed,
+            [
+                ("acme".into(), OrgRole::Member),
+                ("zeta".into(), OrgRole::Owner)
+            ]
+        );
+    }
+
+    // ---------- teams ----------
+
+    #[sqlx::test]
+    async fn transfer_moves_the_team_and_its_apps(pool: PgPool) {
+        let from = org(&pool, "from").await;
+        let to = org(&pool, "to").await;
+        let team_id = team(&pool, from, "platform").await;
+        let api = team_app(&pool, team_id, "api").await;
+        let other = app(&pool, from, "other").await;
+
+        let team = TeamRepository::new(pool.clone())
+            .transfer(team_id, to)
+            .await
+            .unwrap();
+
+        assert_eq!(team.organization_id, to);
+        let apps = AppRepository::new(pool);
+        let api = apps.find_by_id(api).await.unwrap().unwrap();
+        assert_eq!(api.organization_id, to);
+        let other = apps.find_by_id(other).await.unwrap().unwrap();
+        assert_eq!(other.organization_id, from);
+    }
+
+    #[sqlx::test]
+    async fn transfer_rolls_back_on_an_app_slug_collision(pool: PgPool) {
+        let from = org(&pool, "from").await;
+        let to = org(&pool, "to").await;
+        let team_id = team(&pool, from, "platform").await;
+        let api = team_app(&pool, team_id, "api").await;
+        let web = team_app(&pool, team_id, "web").await;
+        app(&pool, to, "web").await;
+
+        let err = TeamRepository::new(pool.clone())
+            .transfer(team_id, to)
+            .await
+            .unwrap_err();
+
+        assert!(err.to_string().contains("web"), "{err}");
+        let team = TeamRepository::new(pool.clone())
+            .find_by_id(team_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(team.organization_id, from);
+        let apps = AppRepository::new(pool);
+        for id in [api, web] {
+            let app = apps.find_by_id(id).await.unwrap().unwrap();
+            assert_eq!(app.organization_id, from);
+        }
+    }
+
+    #[sqlx::test]
+    async fn transfer_rejects_a_taken_team_slug(pool: PgPool) {
+        let from = org(&pool, "from").await;
+        let to = org(&pool, "to").await;
+        let team_id = team(&pool, from, "platform").await;
+        team(&pool, to, "platform").await;
+
+        let err = TeamRepository::new(pool).transfer(team_id, to).await;
+
+        assert!(err.is_err());
+    }
+
+    #[sqlx::test]
+    async fn deleted_teams_are_only_listed_on_request(pool: PgPool) {
+        let org_id = org(&pool, "acme").await;
+        let core = team(&pool, org_id, "core").await;
+        team(&pool, org_id, "web").await;
+        let teams = TeamRepository::new(pool);
+
+        let deleted = teams.soft_delete(core).await.unwrap().unwrap();
+        let again = teams.soft_delete(core).await.unwrap();
+
+        assert!(deleted.deleted_at.is_some());
+        assert!(again.is_none());
+        let live = teams.list_by_organization(org_id).await.unwrap();
+        assert_eq!(live.iter().map(|t| &t.slug).collect::<Vec<_>>(), ["web"]);
+        let all =
+            teams.list_by_organization_with_deleted(org_id).await.unwrap();
+        assert_eq!(
+            all.iter().map(|t| &t.slug).collect::<Vec<_>>(),
+            ["core", "web"]
+        );
+    }
+
+    #[sqlx::test]
+    async fn deleted_teams_cannot_receive_apps(pool: PgPool) {
+        let org_id = org(&pool, "acme").await;
+        let core = team(&pool, org_id, "core").await;
+        let apps = AppRepository::new(pool.clone());
+        let new_app = |slug: &str| NewApp {
+            organization_id: org_id,
+            team_id: Some(core),
+            name: slug.to_string(),
+            slug: slug.to_string(),
+            repo_url: None,
+            created_by: None,
+        };
+        apps.create(new_app("web")).await.unwrap();
+
+        TeamRepository::new(pool).soft_delete(core).await.unwrap();
+        let err = apps.create(new_app("api")).await.unwrap_err();
+
+        assert!(err.to_string().contains("was deleted"), "{err}");
+    }
+
+    #[sqlx::test]
+    async fn team_slugs_are_looked_up_within_the_organization(pool: PgPool) {
+        let acme = org(&pool, "acme").await;
+        let globex = org(&pool, "globex").await;
+        let web = team(&pool, acme, "web").await;
+        team(&pool, globex, "web").await;
+        let old = team(&pool, acme, "old").await;
+        soft_delete(&pool, "teams", old).await;
+        let teams = TeamRepository::new(pool);
+
+        let found = teams.find_by_slug(acme, "web").await.unwrap().unwrap();
+
+        assert_eq!(found.id, web);
+        assert!(teams.find_by_slug(acme, "api").await.unwrap().is_none());
+        assert!(teams.find_by_slug(acme, "old").await.unwrap().is_none());
+    }
+
+    // ---------- updated_at trigger ----------
+
+    #[sqlx::test]
+    async fn raw_updates_bump_updated_at(pool: PgPool) {
+        let org_id = org(&pool, "acme").await;
+        let app_id = app(&pool, org_id, "api").await;
+        // Backdate the row with the trigger off, since it would otherwise
+        // overwrite the value being set.
+        for sql in [
+            "ALTER TABLE apps DISABLE TRIGGER trg_apps_updated_at",
+            "UPDATE apps SET updated_at = NOW() - INTERVAL '1 day'",
+            "ALTER TABLE apps ENABLE TRIGGER trg_apps_updated_at",
+        ] {
+            sqlx::query(sql).execute(&pool).await.unwrap();
+        }
+
+        sqlx::query("UPDATE apps SET name = 'API' WHERE id = $1")
+            .bind(app_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let recent: bool = query_scalar(
+            "SELECT updated_at > NOW() - INTERVAL '1 hour' FROM apps WHERE id = $1",
+        )
+        .bind(app_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert!(recent);
+    }
+
+    // ---------- created_by ----------
+
+    #[sqlx::test]
+    async fn creators_are_recorded_on_organizations_and_teams(pool: PgPool) {
+        let creator = user(&pool, "ada@example.com").await;
+
+        let org = OrganizationRepository::new(pool.clone())
+            .create(NewOrganization {
+                name: "Acme".into(),
+                slug: "acme".into(),
+                description: None,
+                created_by: Some(creator),
+            })
+            .await
+            .unwrap();
+        let team = TeamRepository::new(pool)
+            .create(NewTeam {
+                organization_id: org.id,
+                name: "Platform".into(),
+                slug: "platform".into(),
+                description: None,
+                created_by: Some(creator),
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(org.created_by, Some(creator));
+        assert_eq!(team.created_by, Some(creator));
+    }
+
+    // ---------- activity counts ----------
+
+    #[sqlx::test]
+    async fn activity_counts_are_per_app_and_window(pool: PgPool) {
+        let org_id = org(&pool, "acme").await;
+        let web = app(&pool, org_id, "web").await;
+        let api = app(&pool, org_id, "api").await;
+        let v1 = release(&pool, web, "v1").await;
+        for app_id in [web, web, api] {
+            sqlx::query("INSERT INTO build_jobs (app_id) VALUES ($1)")
+                .bind(app_id)
+                .execute(&pool)
+                .await
+                .unwrap();
+        }
+        for age in ["1 hour", "10 days"] {
+            sqlx::query(
+                r#"
+                INSERT INTO deploys (app_id, release_id, environment, created_at)
+                VALUES ($1, $2, 'production', NOW() - $3::interval)
+                "#,
+            )
+            .bind(web)
+            .bind(v1.id)
+            .bind(age)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+
+        let since = OffsetDateTime::now_utc() - time::Duration::days(7);
+        let deploys = DeployRepository::new(pool.clone())
+            .count_by_app_since(web, since)
+            .await
+            .unwrap();
+        let builds =
+            BuildJobRepository::new(pool).count_by_app(web).await.unwrap();
+
+        assert_eq!(deploys, 1);
+        assert_eq!(builds, 2);
+    }
+
+    // ---------- password resets ----------
+
+    #[sqlx::test]
+    async fn reset_password_sets_the_hash_and_revokes_tokens(pool: PgPool) {
+        let user_id = user(&pool, "ada@example.com").await;
+        auth_token(&pool, user_id, "cli").await;
+        auth_token(&pool, user_id, "laptop").await;
+        password_reset(&pool, user_id, "h1", time::Duration::minutes(30))
+            .await;
+
+        let user = PasswordResetRepository::new(pool.clone())
+            .reset_password("h1", "new-hash")
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(user.password_hash, "new-hash");
+        let tokens = AuthTokenRepository::new(pool);
+        for token in ["cli", "laptop"] {
+            assert!(
+                tokens.find_valid_by_token(token).await.unwrap().is_none()
+            );
+        }
+    }
+
+    #[sqlx::test]
+    async fn expired_reset_links_are_rejected(pool: PgPool) {
+        let user_id = user(&pool, "ada@example.com").await;
+        auth_token(&pool, user_id, "cli").await;
+        password_reset(&pool, user_id, "h1", time::Duration::minutes(-1))
+            .await;
+
+        let reset = PasswordResetRepository::new(pool.clone())
+            .reset_password("h1", "new-hash")
+            .await
+            .unwrap();
+
+        assert!(reset.is_none());
+        let tokens = AuthTokenRepository::new(pool);
+        assert!(tokens.find_valid_by_token("cli").await.unwrap().is_some());
+    }
+
+    #[sqlx::test]
+    async fn reset_links_are_single_use(pool: PgPool) {
+        let user_id = user(&pool, "ada@example.com").await;
+        password_reset(&pool, user_id, "h1", time::Duration::minutes(30))
+            .await;
+        password_reset(&pool, user_id, "h2", time::Duration::minutes(30))
+            .await;
+        let resets = PasswordResetRepository::new(pool);
+
+        assert!(resets.reset_password("h1", "a").await.unwrap().is_some());
+
+        assert!(resets.reset_password("h1", "b").await.unwrap().is_none());
+        assert!(resets.reset_password("h2", "c").await.unwrap().is_none());
+    }
+
+    // ---------- auth tokens and users ----------
+
+    #[sqlx::test]
+    async fn revoking_all_tokens_can_spare_the_current_one(pool: PgPool) {
+        let user_id = user(&pool, "ada@example.com").await;
+        let current = auth_token(&pool, user_id, "current").await;
+        auth_token(&pool, user_id, "laptop").await;
+        auth_token(&pool, user_id, "ci").await;
+        let tokens = AuthTokenRepository::new(pool);
+
+        let revoked =
+            tokens.revoke_all_for_user(user_id, Some(current)).await.unwrap();
+
+        assert_eq!(revoked, 2);
+        assert!(
+            tokens.find_valid_by_token("current").await.unwrap().is_some()
+        );
+        assert!(tokens.find_valid_by_token("laptop").await.unwrap().is_none());
+        assert_eq!(
+            tokens.revoke_all_for_user(user_id, None).await.unwrap(),
+            1
+        );
+    }
+
+    #[sqlx::test]
+    async fn password_hash_is_replaced(pool: PgPool) {
+        let user_id = user(&pool, "ada@example.com").await;
+
+        let user = UserRepository::new(pool)
+            .update_password_hash(user_id, "new-hash")
+            .await
+            .unwrap();
+
+        assert_eq!(user.password_hash, "new-hash");
+    }
+
+    #[sqlx::test]
+    async fn find_by_ids_skips_deleted_users(pool: PgPool) {
+        let ada = user(&pool, "ada@example.com").await;
+        let bob = user(&pool, "bob@example.com").await;
+        let eve = user(&pool, "eve@example.com").await;
+        sqlx::query("UPDATE users SET deleted_at = now() WHERE id = $1")
+            .bind(eve)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let mut ids: Vec<i64> = UserRepository::new(pool)
+            .find_by_ids(&[ada, bob, eve, 0])
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|u| u.id)
+            .collect();
+        ids.sort();
+
+        assert_eq!(ids, [ada, bob]);
+    }
+
+    #[sqlx::test]
+    async fn expired_tokens_are_not_valid(pool: PgPool) {
+        let user_id = user(&pool, "ada@example.com").await;
+        let tokens = AuthTokenRepository::new(pool);
+        for (token, expires_in) in [("fresh", 1), ("stale", -1)] {
+            tokens
+                .create(NewAuthToken {
+                    user_id,
+                    token: token.to_string(),
+                    description: None,
+                    expires_at: Some(
+                        OffsetDateTime::now_utc()
+                            + Duration::hours(expires_in),
+                    ),
+                })
+                .await
+                .unwrap();
+        }
+
+        assert!(tokens.find_valid_by_token("fresh").await.unwrap().is_some());
+        assert!(tokens.find_valid_by_token("stale").await.unwrap().is_none());
+    }
+
+    #[sqlx::test]
+    async fn revoke_reports_whether_the_token_was_live(pool: PgPool) {
+        let user_id = user(&pool, "ada@example.com").await;
+        let id = auth_token(&pool, user_id, "laptop").await;
+        let tokens = AuthTokenRepository::new(pool);
+
+        assert!(tokens.revoke(id).await.unwrap());
+        assert!(!tokens.revoke(id).await.unwrap());
+        assert!(tokens.find_valid_by_token("laptop").await.unwrap().is_none());
+    }
+
+    // ---------- search ----------
+
+    #[sqlx::test]
+    async fn search_matches_every_type_within_memberships(pool: PgPool) {
+        let user_id = user(&pool, "ada@example.com").await;
+        let mine = org(&pool, "payroll").await;
+        member(&pool, mine, user_id).await;
+        team(&pool, mine, "payments").await;
+        app(&pool, mine, "paypal-bridge").await;
+        app(&pool, mine, "web").await;
+        let other = org(&pool, "paymaster").await;
+        app(&pool, other, "pay-api").await;
+
+        let orgs = OrganizationRepository::new(pool.clone())
+            .search_for_user(user_id, "PAY", 10)
+            .await
+            .unwrap();
+        let teams = TeamRepository::new(pool.clone())
+            .search_for_user(user_id, "PAY", 10)
+            .await
+            .unwrap();
+        let apps = AppRepository::new(pool)
+            .search_for_user(user_id, "PAY", 10)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            orgs.iter().map(|o| &o.slug).collect::<Vec<_>>(),
+            ["payroll"]
+        );
+        assert_eq!(
+            teams.iter().map(|t| &t.slug).collect::<Vec<_>>(),
+            ["payments"]
+        );
+        assert_eq!(
+            apps.iter().map(|a| &a.slug).collect::<Vec<_>>(),
+            ["paypal-bridge"]
+        );
+    }
+
+    #[test]
+    fn like_pattern_escapes_wildcards() {
+        assert_eq!(like_pattern("web"), "%web%");
+        assert_eq!(like_pattern("50%_off\\"), "%50\\%\\_off\\\\%");
+    }
+
+    // ---------- housekeeping ----------
+
+    #[sqlx::test]
+    async fn only_stale_running_deploys_are_failed(pool: PgPool) {
+        let org_id = org(&pool, "acme").await;
+        let app_id = app(&pool, org_id, "web").await;
+        let v1 = release(&pool, app_id, "v1").await;
+        let stale = deploy(&pool, &v1, "running", "2 hours").await;
+        let fresh = deploy(&pool, &v1, "running", "10 minutes").await;
+        let done = deploy(&pool, &v1, "succeeded", "3 hours").await;
+        let deploys = DeployRepository::new(pool);
+
+        let failed = deploys.mark_stale_as_failed(Duration::hours(1)).await;
+
+        assert_eq!(failed.unwrap(), 1);
+        for (id, expected) in [
+            (stale, DeployStatus::Failed),
+            (fresh, DeployStatus::Running),
+            (done, DeployStatus::Succeeded),
+        ] {
+            let deploy = deploys.find_by_id(id).await.unwrap().unwrap();
+            assert_eq!(deploy.status, expected);
+        }
+    }
+
+    #[sqlx::test]
+    async fn stale_builds_fail_with_their_unfinished_steps(pool: PgPool) {
+        let org_id = org(&pool, "acme").await;
+        let app_id = app(&pool, org_id, "web").await;
+        let mut builds = Vec::new();
+        for started_ago in ["2 hours", "10 minutes"] {
+            let build_id: i64 = query_scalar(
+                r#"
+                INSERT INTO build_jobs (app_id, status, started_at)
+                VALUES ($1, 'running', NOW() - $2::interval)
+                RETURNING id
+                "#,
+            )
+            .bind(app_id)
+            .bind(started_ago)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+            sqlx::query(
+                r#"
+                INSERT INTO build_steps (build_id, position, name, status)
+                VALUES ($1, 1, 'fetch', 'succeeded'),
+                       ($1, 2, 'build', 'running'),
+                       ($1, 3, 'push', 'pending')
+                "#,
+            )
+            .bind(build_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+            builds.push(build_id);
+        }
+
+        let failed = BuildJobRepository::new(pool.clone())
+            .mark_stale_as_failed(Duration::hours(1))
+            .await
+            .unwrap();
+
+        assert_eq!(failed, 1);
+        let statuses = |build_id| {
+            query_scalar::<_, String>(
+                r#"
+                SELECT s.status::text
+                FROM (
+                    SELECT 0 AS position, status FROM build_jobs WHERE id = $1
+                    UNION ALL
+                    SELECT position, status FROM build_steps WHERE build_id = $1
+                ) s
+                ORDER BY s.position
+                "#,
+            )
+            .bind(build_id)
+            .fetch_all(&pool)
+        };
+        // Build status first, then its steps in order.
+        assert_eq!(
+            statuses(builds[0]).await.unwrap(),
+            ["failed", "succeeded", "failed", "failed"]
+        );
+        assert_eq!(
+            statuses(builds[1]).await.unwrap(),
+            ["running", "succeeded", "running", "pending"]
+        );
+    }
+
+    #[sqlx::test]
+    async fn only_expired_invites_are_deleted(pool: PgPool) {
+        let org_id = org(&pool, "acme").await;
+        sqlx::query(
+            r#"
+            INSERT INTO organization_invites
+                (organization_id, email, role, expires_at)
+            VALUES ($1, 'old@example.com', 'member', NOW() - INTERVAL '1 day'),
+                   ($1, 'new@example.com', 'member', NOW() + INTERVAL '1 day')
+            "#,
+        )
+        .bind(org_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+        let repo = OrganizationInviteRepository::new(pool);
+
+        let deleted = repo.delete_expired().await.unwrap();
+
+        assert_eq!(deleted, 1);
+        assert!(
+            repo.list_pending_for_email("old@example.com")
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        assert_eq!(
+            repo.list_pending_for_email("new@example.com")
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+
+    #[sqlx::test]
+    async fn a_failed_step_fails_its_running_build(pool: PgPool) {
+        let org_id = org(&pool, "acme").await;
+        let app_id = app(&pool, org_id, "web").await;
+        let mut builds = Vec::new();
+        for failed_step in ["failed", "running"] {
+            let build_id: i64 = query_scalar(
+                r#"
+                INSERT INTO build_jobs (app_id, status, started_at)
+                VALUES ($1, 'running', NOW())
+                RETURNING id
+                "#,
+            )
+            .bind(app_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+            // `test` and `lint` run in parallel; `push` waits for both.
+            sqlx::query(
+                r#"
+                INSERT INTO build_steps
+                    (build_id, position, step_group, name, status)
+                VALUES ($1, 1, 1, 'test', $2::build_status),
+                       ($1, 2, 1, 'lint', 'running'),
+                       ($1, 3, NULL, 'push', 'pending')
+                "#,
+            )
+            .bind(build_id)
+            .bind(failed_step)
+            .execute(&pool)
+            .await
+            .unwrap();
+            builds.push(build_id);
+        }
+
+        let failed = BuildJobRepository::new(pool.clone())
+            .fail_builds_with_failed_steps()
+            .await
+            .unwrap();
+
+        assert_eq!(failed, 1);
+        let steps = BuildStepRepository::new(pool.clone());
+        let mut statuses = Vec::new();
+        for build_id in builds {
+            let build_steps = steps.list_by_build(build_id).await.unwrap();
+            statuses.push(
+                build_steps.into_iter().map(|s| s.status).collect::<Vec<_>>(),
+            );
+        }
+        assert_eq!(statuses[0], [BuildStatus::Failed; 3]);
+        assert_eq!(
+            statuses[1],
+            [BuildStatus::Running, BuildStatus::Running, BuildStatus::Pending]
+        );
+    }
+
+    // ---------- apps ----------
+
+    #[sqlx::test]
+    async fn app_updates_are_rejected_when_stale(pool: PgPool) {
+        let org_id = org(&pool, "acme").await;
+        let app_id = app(&pool, org_id, "web").await;
+        let apps = AppRepository::new(pool);
+        let seen = apps.find_by_id(app_id).await.unwrap().unwrap();
+
+        let renamed = apps
+            .update(app_id, Some("Web"), None, Some(seen.updated_at))
+            .await
+            .unwrap()
+            .unwrap();
+        let stale = apps
+            .update(app_id, Some("Website"), None, Some(seen.updated_at))
+            .await
+            .unwrap();
+
+        assert_eq!(renamed.name, "Web");
+        assert!(renamed.updated_at > seen.updated_at);
+        assert!(stale.is_none());
+        let forced = apps
+            .update(app_id, None, Some("https://git/web.git"), None)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(forced.name, "Web");
+        assert_eq!(forced.repo_url.as_deref(), Some("https://git/web.git"));
+    }
+
+    #[sqlx::test]
+    async fn organization_and_team_updates_are_rejected_when_stale(
+        pool: PgPool,
+    ) {
+        let org_id = org(&pool, "acme").await;
+        let team_id = team(&pool, org_id, "web").await;
+        let orgs = OrganizationRepository::new(pool.clone());
+        let teams = TeamRepository::new(pool);
+        let seen_org = orgs.find_by_id(org_id).await.unwrap().unwrap();
+        let seen_team = teams.find_by_id(team_id).await.unwrap().unwrap();
+
+        let org = orgs
+            .update(org_id, Some("Acme"), None, Some(seen_org.updated_at))
+            .await
+            .unwrap()
+            .unwrap();
+        let stale_org = orgs
+            .update(org_id, Some("ACME"), None, Some(seen_org.updated_at))
+            .await
+            .unwrap();
+        let team = teams
+            .update(
+                team_id,
+                None,
+                Some("Frontend"),
+                Some(seen_team.updated_at),
+            )
+            .await
+            .unwrap()
+            .unwrap();
+        let stale_team = teams
+            .update(team_id, None, Some("UI"), Some(seen_team.updated_at))
+            .await
+            .unwrap();
+
+        assert_eq!(org.name, "Acme");
+        assert!(stale_org.is_none());
+        assert_eq!(team.description.as_deref(), Some("Frontend"));
+        assert!(stale_team.is_none());
+        let forced = orgs.update(org_id, Some("ACME"), None, None).await;
+        assert_eq!(forced.unwrap().unwrap().name, "ACME");
+    }
+
+    // ---------- build logs ----------
+
+    #[test]
+    fn split_chunks_keeps_small_content_whole() {
+        assert_eq!(split_chunks("hello", 16), ["hello"]);
+        assert_eq!(split_chunks("exactly16bytes!!", 16), ["exactly16bytes!!"]);
+        assert!(split_chunks("", 16).is_empty());
+    }
+
+    #[test]
+    fn split_chunks_splits_a_large_blob_in_order() {
+        let blob: String =
+            (0..10_000).map(|i| format!("line {i}\n")).collect();
+
+        let chunks = split_chunks(&blob, BUILD_LOG_CHUNK_BYTES);
+
+        assert!(chunks.len() > 1);
+        assert!(chunks.iter().all(|c| c.len() <= BUILD_LOG_CHUNK_BYTES));
+        assert!(
+            chunks[..chunks.len() - 1]
+                .iter()
+                .all(|c| c.len() == BUILD_LOG_CHUNK_BYTES)
+        );
+        assert_eq!(chunks.concat(), blob);
+    }
+
+    #[test]
+    fn split_chunks_never_cuts_a_character() {
+        // 2-, 3- and 4-byte characters straddling every chunk edge
+        let text = "aé€😀".repeat(100);
+
+        for max in 4..=12 {
+            let chunks = split_chunks(&text, max);
+
+            assert!(chunks.iter().all(|c| !c.is_empty() && c.len() <= max));
+            assert_eq!(chunks.concat(), text, "max = {max}");
+        }
+    }
+
+    #[test]
+    fn split_chunks_backs_off_to_the_previous_boundary() {
+        // "€" is 3 bytes: the first chunk stops before it instead of
+        // taking 1 of its bytes.
+        assert_eq!(split_chunks("ab€cd", 3), ["ab", "€", "cd"]);
+    }
+
+    #[sqlx::test]
+    async fn append_stores_consecutive_chunks(pool: PgPool) {
+        let org_id = org(&pool, "acme").await;
+        let app_id = app(&pool, org_id, "web").await;
+        let build_id: i64 = query_scalar(
+            "INSERT INTO build_jobs (app_id) VALUES ($1) RETURNING id",
+        )
+        .bind(app_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        let logs = BuildLogRepository::new(pool);
+        let output = "x".repeat(BUILD_LOG_CHUNK_BYTES * 2 + 10);
+
+        let first = logs.append(build_id, None, &output).await.unwrap();
+        let second = logs.append(build_id, None, "done\n").await.unwrap();
+
+        let indexes = first
+            .iter()
+            .chain(&second)
+            .map(|log| log.chunk_index)
+            .collect::<Vec<_>>();
+        assert_eq!(indexes, [0, 1, 2, 3]);
+        assert_eq!(first[2].content.len(), 10);
+        let too_big = "x".repeat(BUILD_LOG_MAX_APPEND_BYTES + 1);
+        assert!(logs.append(build_id, None, &too_big).await.is_err());
+    }
+
+    #[sqlx::test]
+    async fn tail_returns_the_newest_chunks_oldest_first(pool: PgPool) {
+        let org_id = org(&pool, "acme").await;
+        let app_id = app(&pool, org_id, "web").await;
+        let build_id: i64 = query_scalar(
+            "INSERT INTO build_jobs (app_id) VALUES ($1) RETURNING id",
+        )
+        .bind(app_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        let logs = BuildLogRepository::new(pool);
+        for line in ["one\n", "two\n", "three\n", "four\n"] {
+            logs.append(build_id, None, line).await.unwrap();
+        }
+
+        let tail = logs.tail_by_build(build_id, 2).await.unwrap();
+        let everything = logs.tail_by_build(build_id, 10).await.unwrap();
+
+        let contents = |rows: &[BuildLog]| {
+            rows.iter().map(|r| r.content.clone()).collect::<String>()
+        };
+        assert_eq!(contents(&tail), "three\nfour\n");
+        assert_eq!(contents(&everything), "one\ntwo\nthree\nfour\n");
+    }
+
+    #[sqlx::test]
+    async fn step_logs_come_back_in_write_order(pool: PgPool) {
+        let org_id = org(&pool, "acme").await;
+        let app_id = app(&pool, org_id, "web").await;
+        let build_id: i64 = query_scalar(
+            "INSERT INTO build_jobs (app_id) VALUES ($1) RETURNING id",
+        )
+        .bind(app_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        let mut steps = Vec::new();
+        for (position, name) in [(1, "fetch"), (2, "build")] {
+            let step_id: i64 = query_scalar(
+                r#"
+                INSERT INTO build_steps (build_id, position, name)
+                VALUES ($1, $2, $3)
+                RETURNING id
+                "#,
+            )
+            .bind(build_id)
+            .bind(position)
+            .bind(name)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+            steps.push(step_id);
+        }
+        let logs = BuildLogRepository::new(pool);
+        // each step starts its own chunk_index at 0
+        for (step, line) in [
+            (steps[0], "fetch 1\n"),
+            (steps[0], "fetch 2\n"),
+            (steps[1], "build 1\n"),
+            (steps[1], "build 2\n"),
+        ] {
+            logs.append(build_id, Some(step), line).await.unwrap();
+        }
+
+        let all = logs.list_by_build(build_id).await.unwrap();
+        let tail = logs.tail_by_build(build_id, 3).await.unwrap();
+
+        let contents = |rows: &[BuildLog]| {
+            rows.iter().map(|r| r.content.clone()).collect::<String>()
+        };
+        assert_eq!(contents(&all), "fetch 1\nfetch 2\nbuild 1\nbuild 2\n");
+        assert_eq!(contents(&tail), "fetch 2\nbuild 1\nbuild 2\n");
+    }
+
+    // ---------- status audit ----------
+
+    #[sqlx::test]
+    async fn release_status_changes_record_who_and_when(pool: PgPool) {
+        let user_id = user(&pool, "ada@example.com").await;
+        let org_id = org(&pool, "acme").await;
+        let app_id = app(&pool, org_id, "web").await;
+        let pending = release(&pool, app_id, "v1").await;
+
+        let built = ReleaseRepository::new(pool)
+            .update_image_ref(
+                pending.id,
+                "registry/web:v1",
+                ReleaseStatus::Built,
+                Some(user_id),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(built.status_changed_by, Some(user_id));
+        assert!(built.status_changed_at >= pending.status_changed_at);
+    }
+
+    #[sqlx::test]
+    async fn each_deploy_transition_is_recorded(pool: PgPool) {
+        let user_id = user(&pool, "ada@example.com").await;
+        let org_id = org(&pool, "acme").await;
+        let app_id = app(&pool, org_id, "web").await;
+        let v1 = release(&pool, app_id, "v1").await;
+        let deploy_id = deploy(&pool, &v1, "pending", "0 seconds").await;
+        for status in ["running", "succeeded"] {
+            sqlx::query(
+                r#"
+                UPDATE deploys
+                SET status = $2::deploy_status, status_changed_by = $3
+                WHERE id = $1
+                "#,
+            )
+            .bind(deploy_id)
+            .bind(status)
+            .bind(user_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+
+        let events = DeployRepository::new(pool)
+            .list_status_events(deploy_id)
+            .await
+            .unwrap();
+
+        let transitions = events
+            .iter()
+            .map(|e| (e.from_status, e.to_status))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            transitions,
+            [
+                (None, DeployStatus::Pending),
+                (Some(DeployStatus::Pending), DeployStatus::Running),
+                (Some(DeployStatus::Running), DeployStatus::Succeeded),
+            ]
+        );
+        assert_eq!(events[2].changed_by, Some(user_id));
+    }
+
+    // ---------- builds ----------
+
+    #[sqlx::test]
+    async fn image_ref_lookup_returns_the_newest_build(pool: PgPool) {
+        let org_id = org(&pool, "acme").await;
+        let web = app(&pool, org_id, "web").await;
+        let api = app(&pool, org_id, "api").await;
+        let mut ids = Vec::new();
+        for (app_id, age) in
+            [(web, "2 hours"), (web, "1 hour"), (api, "0 hours")]
+        {
+            let id: i64 = query_scalar(
+                r#"
+                INSERT INTO build_jobs (app_id, image_ref, created_at)
+                VALUES ($1, 'registry/web:v1', NOW() - $2::interval)
+                RETURNING id
+                "#,
+            )
+            .bind(app_id)
+            .bind(age)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+            ids.push(id);
+        }
+        let builds = BuildJobRepository::new(pool);
+
+        let found = builds.find_by_image_ref(web, "registry/web:v1").await;
+
+        assert_eq!(found.unwrap().unwrap().id, ids[1]);
+        let missing = builds.find_by_image_ref(web, "registry/web:v2").await;
+        assert!(missing.unwrap().is_none());
+    }
+
+    // ---------- secret encryption ----------
+
+    fn secrets(pool: &PgPool) -> AppSecretRepository {
+        let key = "AQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQE=";
+        AppSecretRepository::new(
+            pool.clone(),
+            SecretCipher::from_base64_key(key).unwrap(),
+        )
+    }
+
+    fn new_secret(app_id: i64, value: &str) -> NewAppSecret {
+        NewAppSecret {
+            app_id,
+            environment: "default".to_string(),
+            key: "API_KEY".to_string(),
+            value: value.to_string(),
+            kind: SecretKind::String,
+            created_by: None,
+        }
+    }
+
+    #[sqlx::test]
+    async fn secret_values_are_stored_encrypted(pool: PgPool) {
+        let org_id = org(&pool, "acme").await;
+        let app_id = app(&pool, org_id, "web").await;
+        let repo = secrets(&pool);
+
+        repo.upsert_secret(new_secret(app_id, "first")).await.unwrap();
+        let secret = repo.upsert_secret(new_secret(app_id, "second")).await;
+
+        assert_eq!(secret.unwrap().value, "second");
+        let stored: Vec<String> = sqlx::query_scalar(
+            r#"
+            SELECT value FROM app_secrets
+            UNION ALL
+            SELECT value FROM app_secret_versions
+            "#,
+        )
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+        assert_eq!(stored.len(), 2);
+        for value in stored {
+            assert!(SecretCipher::is_encrypted(&value), "{value}");
+        }
+        let listed = repo.list_by_app_env(app_id, "default").await.unwrap();
+        assert_eq!(listed[0].value, "second");
+    }
+
+    #[sqlx::test]
+    async fn plaintext_values_are_encrypted_once(pool: PgPool) {
+        let org_id = org(&pool, "acme").await;
+        let app_id = app(&pool, org_id, "web").await;
+        sqlx::query(
+            r#"
+            INSERT INTO app_secrets (app_id, environment, key, value)
+            VALUES ($1, 'default', 'API_KEY', 'legacy')
+            "#,
+        )
+        .bind(app_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+        let repo = secrets(&pool);
+
+        let first = repo.encrypt_plaintext_values().await.unwrap();
+        let second = repo.encrypt_plaintext_values().await.unwrap();
+
+        assert_eq!((first, second), (1, 0));
+        let listed = repo.list_by_app_env(app_id, "default").await.unwrap();
+        assert_eq!(listed[0].value, "legacy");
+    }
 }
@@ -0,0 +1,140 @@
+//! Encryption of app secret values at rest (AES-256-GCM).
+
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Nonce};
+use anyhow::{Result, anyhow, bail};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+
+/// Marks an encrypted value: `enc:v1:<base64 nonce>:<base64 ciphertext>`.
+const ENCRYPTED_PREFIX: &str = "enc:v1:";
+/// AES-256 key length.
+const KEY_BYTES: usize = 32;
+/// AES-GCM nonce length; a fresh random one per value.
+const NONCE_BYTES: usize = 12;
+
+/// Encrypts/decrypts secret values with the server key
+/// (`PAASTEL_SECRET_KEY`).
+#[derive(Clone)]
+pub struct SecretCipher {
+    cipher: Aes256Gcm,
+}
+
+impl SecretCipher {
+    /// Build from a base64-encoded 32-byte key.
+    pub fn from_base64_key(encoded: &str) -> Result<Self> {
+        let key = BASE64
+            .decode(encoded.trim())
+            .map_err(|_| anyhow!("secret key is not valid base64"))?;
+        if key.len() != KEY_BYTES {
+            bail!("secret key must be {KEY_BYTES} bytes, got {}", key.len());
+        }
+        let cipher = Aes256Gcm::new_from_slice(&key)
+            .map_err(|_| anyhow!("invalid secret key"))?;
+
+        Ok(Self { cipher })
+    }
+
+    /// Encrypt `plaintext` with a fresh nonce, stored next to the
+    /// ciphertext.
+    pub fn encrypt(&self, plaintext: &str) -> Result<String> {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce, plaintext.as_bytes())
+            .map_err(|_| anyhow!("failed to encrypt secret value"))?;
+
+        Ok(format!(
+            "{ENCRYPTED_PREFIX}{}:{}",
+            BASE64.encode(nonce),
+            BASE64.encode(ciphertext)
+        ))
+    }
+
+    /// Decrypt a value produced by [`encrypt`](Self::encrypt).
+    ///
+    /// Fails for plaintext, a different key or tampered data.
+    pub fn decrypt(&self, stored: &str) -> Result<String> {
+        let (nonce, ciphertext) = stored
+            .strip_prefix(ENCRYPTED_PREFIX)
+            .and_then(|rest| rest.split_once(':'))
+            .ok_or_else(|| anyhow!("secret value is not encrypted"))?;
+        let nonce = BASE64
+            .decode(nonce)
+            .ok()
+            .filter(|n| n.len() == NONCE_BYTES)
+            .ok_or_else(|| anyhow!("malformed secret value nonce"))?;
+        let ciphertext = BASE64
+            .decode(ciphertext)
+            .map_err(|_| anyhow!("malformed secret value"))?;
+
+        let plaintext = self
+            .cipher
+            .decrypt(Nonce::from_slice(&nonce), ciphertext.as_slice())
+            .map_err(|_| {
+                anyhow!("failed to decrypt secret value (wrong key?)")
+            })?;
+
+        String::from_utf8(plaintext)
+            .map_err(|_| anyhow!("decrypted secret value is not UTF-8"))
+    }
+
+    /// Whether `stored` looks like an [`encrypt`](Self::encrypt) output
+    /// rather than a value written before encryption was enabled.
+    pub fn is_encrypted(stored: &str) -> bool {
+        stored.starts_with(ENCRYPTED_PREFIX)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cipher(byte: u8) -> SecretCipher {
+        SecretCipher::from_base64_key(&BASE64.encode([byte; KEY_BYTES]))
+            .unwrap()
+    }
+
+    #[test]
+    fn values_round_trip_with_a_fresh_nonce_each_time() {
+        let cipher = cipher(1);
+
+        let first = cipher.encrypt("s3cr3t").unwrap();
+        let second = cipher.encrypt("s3cr3t").unwrap();
+
+        assert!(SecretCipher::is_encrypted(&first));
+        assert!(!first.contains("s3cr3t"));
+        assert_ne!(first, second);
+        assert_eq!(cipher.decrypt(&first).unwrap(), "s3cr3t");
+        assert_eq!(cipher.decrypt(&second).unwrap(), "s3cr3t");
+    }
+
+    #[test]
+    fn decrypting_fails_for_another_key_or_tampered_data() {
+        let stored = cipher(1).encrypt("s3cr3t").unwrap();
+        let (head, ciphertext) = stored.rsplit_once(':').unwrap();
+        let mut bytes = BASE64.decode(ciphertext).unwrap();
+        bytes[0] ^= 1;
+        let tampered = format!("{head}:{}", BASE64.encode(bytes));
+
+        assert!(cipher(2).decrypt(&stored).is_err());
+        assert!(cipher(1).decrypt(&tampered).is_err());
+        assert!(cipher(1).decrypt("s3cr3t").is_err());
+        assert!(!SecretCipher::is_encrypted("s3cr3t"));
+    }
+
+    #[test]
+    fn keys_must_be_base64_of_32_bytes() {
+        assert!(SecretCipher::from_base64_key("not base64!").is_err());
+        assert!(
+            SecretCipher::from_base64_key(&BASE64.encode([0u8; 16])).is_err()
+        );
+        assert!(
+            SecretCipher::from_base64_key(&format!(
+                " {} ",
+                BASE64.encode([0u8; KEY_BYTES])
+            ))
+            .is_ok()
+        );
+    }
+}
@@ -1,3 +1,5 @@
-// pub mod domain;
-// pub mod graphql;
-// pub mod infrastructure;
+pub mod config;
+pub mod domain;
+pub mod git;
+pub mod graphql;
+pub mod infrastructure;
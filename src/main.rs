@@ -1,58 +1,712 @@
-use anyhow::Result;
-use async_graphql::{EmptySubscription, Schema};
+use std::net::SocketAddr;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use async_graphql::parser::parse_query;
+use async_graphql::parser::types::DocumentOperations;
+use async_graphql::{Pos, ServerError};
 use async_graphql_axum::{GraphQLRequest, GraphQLResponse};
-use axum::{Router, extract::State, http::HeaderMap, routing::post};
+use axum::body::Body;
+use axum::http::HeaderValue;
+use axum::http::header::{CONTENT_LENGTH, CONTENT_TYPE};
+use axum::response::{IntoResponse, Response};
+use axum::{
+    Extension, Router, extract::State, http::HeaderMap, routing::post,
+};
+use rand::RngCore;
 use sqlx::PgPool;
+use sqlx::postgres::PgConnectOptions;
+use time::Duration;
+use tracing::Instrument;
 use tracing_subscriber::EnvFilter;
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::util::SubscriberInitExt;
 
-use paastel::graphql::mutation::MutationRoot;
-use paastel::graphql::query::QueryRoot;
+use paastel::config::{Config, LogFormat};
+use paastel::graphql::errors::bad_request;
+use paastel::graphql::schema::{AppSchema, build_schema};
 use paastel::graphql::state::AppState;
+use paastel::infrastructure::git_http;
+use paastel::infrastructure::rate_limit::{RateLimiter, rate_limit};
+use paastel::infrastructure::repositories::{
+    AppSecretRepository, BuildJobRepository, DeployRepository,
+    OrganizationInviteRepository,
+};
+use paastel::infrastructure::secret_cipher::SecretCipher;
 
-type AppSchema = Schema<QueryRoot, MutationRoot, EmptySubscription>;
+const BIND_ADDR: &str = "0.0.0.0:8080";
+const GRAPHQL_PATH: &str = "/graphql";
 
 #[tokio::main]
 async fn main() -> Result<()> {
     dotenvy::dotenv()?;
+
+    // All settings are validated up front, so a typo fails the startup
+    // instead of the first request that needs it.
+    let config = Arc::new(Config::from_env()?);
+
     // Initialize tracing
-    tracing_subscriber::fmt()
-        .with_env_filter(EnvFilter::from_default_env())
-        .init();
+    log_subscriber(
+        config.log_format,
+        EnvFilter::from_default_env(),
+        std::io::stdout,
+    )
+    .init();
+
+    // No plaintext fallback: secrets are never stored unencrypted.
+    let cipher = SecretCipher::from_base64_key(&config.secret_key).context(
+        "Invalid PAASTEL_SECRET_KEY (expected a base64 32-byte key)",
+    )?;
+
+    let connect_options = pg_connect_options(&config.database_url)?;
+    tracing::info!(
+        ssl_mode = ?connect_options.get_ssl_mode(),
+        "connecting to Postgres"
+    );
+
+    let attempts = config.db_connect_attempts;
+    let interval = config.db_connect_interval_secs;
+    let pool =
+        retry(attempts, std::time::Duration::from_secs(interval), |attempt| {
+            let options = connect_options.clone();
+            async move {
+                let result = PgPool::connect_with(options).await;
+                if let Err(e) = &result {
+                    tracing::warn!(
+                        attempt,
+                        max_attempts = attempts,
+                        error = %e,
+                        "Postgres not reachable yet"
+                    );
+                }
+                result
+            }
+        })
+        .await
+        .context("Could not connect to Postgres")?;
+    let state = AppState { pool, config: config.clone(), cipher };
 
-    let database_url = std::env::var("DATABASE_URL")
-        .expect("DATABASE_URL environment variable must be set");
+    // Values written before encryption at rest was enabled.
+    let encrypted =
+        AppSecretRepository::new(state.pool.clone(), state.cipher.clone())
+            .encrypt_plaintext_values()
+            .await
+            .context("Could not encrypt stored app secrets")?;
+    if encrypted > 0 {
+        tracing::info!(count = encrypted, "encrypted plaintext app secrets");
+    }
 
-    let pool = PgPool::connect(&database_url).await?;
-    let state = AppState { pool };
+    tokio::spawn(housekeeping(state.clone()));
 
-    let schema = Schema::build(QueryRoot, MutationRoot, EmptySubscription)
-        .data(state.clone())
-        .finish();
+    let schema = build_schema(state.clone());
+    let require_op_name = config.require_op_name;
 
-    let app = Router::new()
-        .route("/graphql", post(graphql_handler).get(graphiql))
-        .with_state(schema);
+    let mut app = Router::new()
+        .route(GRAPHQL_PATH, post(graphql_handler).get(graphiql))
+        .with_state(schema)
+        .layer(Extension(RequireOperationName(require_op_name)))
+        .layer(axum::middleware::map_response(json_error_body))
+        .merge(git_http::router(state.clone()));
 
-    let listener = tokio::net::TcpListener::bind("0.0.0.0:8080").await?;
-    tracing::info!("listening on http://{}", listener.local_addr().unwrap());
-    axum::serve(listener, app).await?;
+    let rate_limit_per_window = config.rate_limit;
+    let rate_limit_window = config.rate_limit_window_secs;
+    if rate_limit_per_window > 0 {
+        let limiter = Arc::new(RateLimiter::new(
+            rate_limit_per_window,
+            std::time::Duration::from_secs(rate_limit_window),
+        ));
+        app = app
+            .layer(axum::middleware::from_fn_with_state(limiter, rate_limit));
+    }
+
+    let listener = tokio::net::TcpListener::bind(BIND_ADDR).await?;
+
+    // Effective configuration in one event; credentials are never logged.
+    tracing::info!(
+        version = env!("CARGO_PKG_VERSION"),
+        listen_addr = %listener.local_addr()?,
+        graphql_path = GRAPHQL_PATH,
+        db_host = connect_options.get_host(),
+        db_port = connect_options.get_port(),
+        db_name = connect_options.get_database().unwrap_or_default(),
+        db_user = connect_options.get_username(),
+        db_ssl_mode = ?connect_options.get_ssl_mode(),
+        pool_max_connections = state.pool.options().get_max_connections(),
+        introspection = true,
+        playground = true,
+        registration_open = true,
+        require_operation_name = require_op_name,
+        rate_limit_per_window,
+        rate_limit_window_secs = rate_limit_window,
+        "paastel server started"
+    );
+
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await?;
 
     Ok(())
 }
 
+/// Periodically fail deploys and builds left `running` by a dead worker,
+/// and builds whose steps already failed.
+async fn housekeeping(state: AppState) {
+    let cfg = state.config.clone();
+    let deploys = DeployRepository::new(state.pool.clone());
+    let builds = BuildJobRepository::new(state.pool.clone());
+    let invites = OrganizationInviteRepository::new(state.pool.clone());
+    let mut ticker = tokio::time::interval(std::time::Duration::from_secs(
+        cfg.reaper_interval_secs,
+    ));
+
+    loop {
+        ticker.tick().await;
+
+        match deploys
+            .mark_stale_as_failed(Duration::seconds(
+                cfg.deploy_timeout_secs as i64,
+            ))
+            .await
+        {
+            Ok(0) => {}
+            Ok(n) => {
+                tracing::warn!(count = n, "marked stale deploys as failed")
+            }
+            Err(e) => tracing::error!(error = %e, "deploy reaper failed"),
+        }
+
+        match builds
+            .mark_stale_as_failed(Duration::seconds(
+                cfg.build_timeout_secs as i64,
+            ))
+            .await
+        {
+            Ok(0) => {}
+            Ok(n) => {
+                tracing::warn!(count = n, "marked stale builds as failed")
+            }
+            Err(e) => tracing::error!(error = %e, "build reaper failed"),
+        }
+
+        match builds.fail_builds_with_failed_steps().await {
+            Ok(0) => {}
+            Ok(n) => {
+                tracing::warn!(count = n, "failed builds with failed steps")
+            }
+            Err(e) => tracing::error!(error = %e, "build step rollup failed"),
+        }
+
+        match invites.delete_expired().await {
+            Ok(0) => {}
+            Ok(n) => tracing::info!(count = n, "deleted expired invites"),
+            Err(e) => tracing::error!(error = %e, "invite cleanup failed"),
+        }
+    }
+}
+
+/// Log subscriber for `format`, writing what `filter` lets through to
+/// `writer`.
+fn log_subscriber<W>(
+    format: LogFormat,
+    filter: EnvFilter,
+    writer: W,
+) -> Box<dyn tracing::Subscriber + Send + Sync>
+where
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    let logs =
+        tracing_subscriber::fmt().with_env_filter(filter).with_writer(writer);
+    match format {
+        LogFormat::Pretty => Box::new(logs.finish()),
+        // Event fields at the top level; the request span (request_id,
+        // operation) under `span`.
+        LogFormat::Json => Box::new(
+            logs.json()
+                .flatten_event(true)
+                .with_current_span(true)
+                .with_span_list(false)
+                .finish(),
+        ),
+    }
+}
+
+/// Run `op` up to `attempts` times, sleeping `interval` between failures.
+///
+/// `op` gets the 1-based attempt number; the last error is returned.
+async fn retry<T, E, F, Fut>(
+    attempts: u64,
+    interval: std::time::Duration,
+    mut op: F,
+) -> std::result::Result<T, E>
+where
+    F: FnMut(u64) -> Fut,
+    Fut: std::future::Future<Output = std::result::Result<T, E>>,
+{
+    let mut attempt = 1;
+    loop {
+        match op(attempt).await {
+            Ok(value) => return Ok(value),
+            Err(e) if attempt >= attempts => return Err(e),
+            Err(_) => {
+                tokio::time::sleep(interval).await;
+                attempt += 1;
+            }
+        }
+    }
+}
+
+/// Build Postgres connect options from `DATABASE_URL`.
+///
+/// `sslmode`/`sslrootcert` are read from the URL query string, falling back
+/// to `PGSSLMODE`/`PGSSLROOTCERT`. A configured CA certificate must be
+/// readable, so a bad path fails at startup instead of on first connect.
+fn pg_connect_options(database_url: &str) -> Result<PgConnectOptions> {
+    let options = PgConnectOptions::from_str(database_url)
+        .context("DATABASE_URL is not a valid Postgres connection string")?;
+
+    let root_cert = url_query_param(database_url, "sslrootcert")
+        .or_else(|| std::env::var("PGSSLROOTCERT").ok());
+
+    if let Some(path) = root_cert {
+        std::fs::read(&path).with_context(|| {
+            format!("Failed to load Postgres CA certificate from {path}")
+        })?;
+    }
+
+    Ok(options)
+}
+
+/// Value of `key` in the query string of a connection URL, if present.
+fn url_query_param(url: &str, key: &str) -> Option<String> {
+    let (_, query) = url.split_once('?')?;
+    query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(k, _)| *k == key)
+        .map(|(_, v)| v.to_string())
+}
+
+/// Policy from `PAASTEL_REQUIRE_OP_NAME`.
+#[derive(Clone, Copy)]
+struct RequireOperationName(bool);
+
 async fn graphql_handler(
     State(schema): State<AppSchema>,
+    Extension(RequireOperationName(require_op_name)): Extension<
+        RequireOperationName,
+    >,
     headers: HeaderMap,
     req: GraphQLRequest,
-) -> GraphQLResponse {
+) -> Response {
     let mut request = req.into_inner();
-    request = request.data(headers);
-    schema.execute(request).await.into()
+
+    let request_id = request_id(&headers);
+    let operation = operation_name(&request);
+    // Everything logged while executing carries both fields.
+    let span = tracing::info_span!(
+        "graphql",
+        request_id = %request_id,
+        operation = operation.as_deref().unwrap_or("<anonymous>"),
+    );
+
+    let response: GraphQLResponse = async {
+        tracing::info!("graphql request");
+        if let Err(error) =
+            check_operation_name(operation.as_deref(), require_op_name)
+        {
+            return async_graphql::Response::from_errors(vec![error]).into();
+        }
+
+        request = request.data(headers);
+        schema.execute(request).await.into()
+    }
+    .instrument(span)
+    .await;
+
+    let mut response = response.into_response();
+    if let Ok(value) = HeaderValue::from_str(&request_id) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+    response
+}
+
+/// Correlates a response with the server logs; a valid incoming value is
+/// kept so callers can pass their own.
+const REQUEST_ID_HEADER: &str = "x-request-id";
+/// Longest incoming request id that is reused as is.
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+/// The caller's `X-Request-Id` when it is short printable ASCII, or a new
+/// random one.
+fn request_id(headers: &HeaderMap) -> String {
+    headers
+        .get(REQUEST_ID_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(str::trim)
+        .filter(|id| {
+            !id.is_empty()
+                && id.len() <= MAX_REQUEST_ID_LEN
+                && id.bytes().all(|b| b.is_ascii_graphic())
+        })
+        .map(str::to_string)
+        .unwrap_or_else(|| {
+            let mut bytes = [0u8; 8];
+            rand::rng().fill_bytes(&mut bytes);
+            hex::encode(bytes)
+        })
+}
+
+/// Enforce the `PAASTEL_REQUIRE_OP_NAME` policy for one request.
+fn check_operation_name(
+    operation: Option<&str>,
+    require_op_name: bool,
+) -> Result<(), ServerError> {
+    if require_op_name && operation.is_none() {
+        return Err(bad_request(
+            "An operation name is required: name the operation \
+             (e.g. `query Me { ... }`) or set `operationName`",
+        )
+        .into_server_error(Pos::default()));
+    }
+
+    Ok(())
+}
+
+/// Largest plain-text error body kept as the JSON error message.
+const MAX_ERROR_BODY_BYTES: usize = 4096;
+
+/// Give non-JSON error responses (unknown route, wrong method, body too
+/// large...) the GraphQL `{"errors": [...]}` shape, keeping the status.
+async fn json_error_body(response: Response) -> Response {
+    let status = response.status();
+    let is_json = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("application/json"));
+    if !(status.is_client_error() || status.is_server_error()) || is_json {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let reason = status.canonical_reason().unwrap_or("Error");
+    let message = axum::body::to_bytes(body, MAX_ERROR_BODY_BYTES)
+        .await
+        .ok()
+        .and_then(|bytes| String::from_utf8(bytes.to_vec()).ok())
+        .map(|text| text.trim().to_string())
+        .filter(|text| !text.is_empty())
+        .unwrap_or_else(|| reason.to_string());
+    let code = reason.to_ascii_uppercase().replace([' ', '-'], "_");
+
+    let body = serde_json::json!({
+        "errors": [{ "message": message, "extensions": { "code": code } }],
+    });
+    parts.headers.remove(CONTENT_LENGTH);
+    parts
+        .headers
+        .insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+
+    (parts, Body::from(body.to_string())).into_response()
+}
+
+/// `operationName` of the request, or the name of the only operation in
+/// the document. `None` for anonymous (or unparsable) operations.
+fn operation_name(request: &async_graphql::Request) -> Option<String> {
+    if let Some(name) = &request.operation_name {
+        return Some(name.clone());
+    }
+
+    match parse_query(&request.query).ok()?.operations {
+        DocumentOperations::Multiple(ops) if ops.len() == 1 => {
+            ops.into_keys().next().map(|name| name.to_string())
+        }
+        _ => None,
+    }
 }
 
 /// Simple GraphiQL-like playground using async-graphql built-in HTML.
 async fn graphiql() -> axum::response::Html<String> {
     use async_graphql::http::GraphiQLSource;
 
-    axum::response::Html(GraphiQLSource::build().endpoint("/graphql").finish())
+    axum::response::Html(
+        GraphiQLSource::build().endpoint(GRAPHQL_PATH).finish(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::postgres::PgSslMode;
+
+    use super::*;
+
+    #[test]
+    fn sslmode_is_read_from_the_url() {
+        let options = pg_connect_options(
+            "postgres://u:p@db.example.com/app?sslmode=require",
+        )
+        .unwrap();
+
+        assert!(matches!(options.get_ssl_mode(), PgSslMode::Require));
+    }
+
+    #[test]
+    fn readable_root_cert_is_accepted() {
+        let path = std::env::temp_dir()
+            .join(format!("paastel-ca-{}.pem", std::process::id()));
+        std::fs::write(&path, "-----BEGIN CERTIFICATE-----\n").unwrap();
+
+        let url = format!(
+            "postgres://db.example.com/app?sslmode=verify-full&sslrootcert={}",
+            path.display()
+        );
+        let options = pg_connect_options(&url);
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(matches!(
+            options.unwrap().get_ssl_mode(),
+            PgSslMode::VerifyFull
+        ));
+    }
+
+    #[test]
+    fn missing_root_cert_fails_clearly() {
+        let err = pg_connect_options(
+            "postgres://db.example.com/app?sslrootcert=/nonexistent/ca.pem",
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "Failed to load Postgres CA certificate from /nonexistent/ca.pem"
+        );
+    }
+
+    #[test]
+    fn invalid_url_is_rejected() {
+        assert!(pg_connect_options("postgres://db:notaport/app").is_err());
+    }
+
+    #[test]
+    fn query_params_are_looked_up_by_key() {
+        let url = "postgres://db/app?application_name=x&sslmode=disable";
+
+        assert_eq!(
+            url_query_param(url, "sslmode").as_deref(),
+            Some("disable")
+        );
+        assert_eq!(url_query_param(url, "sslrootcert"), None);
+        assert_eq!(url_query_param("postgres://db/app", "sslmode"), None);
+    }
+
+    fn op_name(query: &str) -> Option<String> {
+        operation_name(&async_graphql::Request::new(query))
+    }
+
+    #[test]
+    fn operation_name_comes_from_the_request_or_the_document() {
+        let explicit =
+            async_graphql::Request::new("query A { x } query B { x }")
+                .operation_name("B");
+
+        assert_eq!(operation_name(&explicit).as_deref(), Some("B"));
+        assert_eq!(op_name("query Me { me { id } }").as_deref(), Some("Me"));
+        assert_eq!(op_name("mutation Login { x }").as_deref(), Some("Login"));
+    }
+
+    #[test]
+    fn anonymous_or_ambiguous_operations_have_no_name() {
+        assert_eq!(op_name("{ me { id } }"), None);
+        assert_eq!(op_name("query { me { id } }"), None);
+        assert_eq!(op_name("query A { x } query B { x }"), None);
+        assert_eq!(op_name("not graphql"), None);
+    }
+
+    #[test]
+    fn strict_mode_rejects_only_anonymous_operations() {
+        let error = check_operation_name(None, true).unwrap_err();
+
+        assert_eq!(
+            error.extensions.unwrap().get("code"),
+            Some(&async_graphql::Value::from("BAD_REQUEST"))
+        );
+        assert!(check_operation_name(Some("Me"), true).is_ok());
+        assert!(check_operation_name(None, false).is_ok());
+    }
+
+    /// Serve `POST /graphql` behind the JSON error layer on a free port.
+    async fn serve_json_errors() -> String {
+        let app = Router::new()
+            .route(GRAPHQL_PATH, post(|| async { "ok" }))
+            .layer(axum::middleware::map_response(json_error_body));
+        let listener =
+            tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        format!("http://{addr}")
+    }
+
+    #[tokio::test]
+    async fn unknown_routes_get_a_json_404() {
+        let base = serve_json_errors().await;
+
+        let res = reqwest::get(format!("{base}/nope")).await.unwrap();
+
+        assert_eq!(res.status(), reqwest::StatusCode::NOT_FOUND);
+        assert_eq!(res.headers()[CONTENT_TYPE], "application/json");
+        let body: serde_json::Value = res.json().await.unwrap();
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "errors": [{
+                    "message": "Not Found",
+                    "extensions": { "code": "NOT_FOUND" },
+                }],
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn wrong_methods_get_a_json_405() {
+        let base = serve_json_errors().await;
+
+        let res = reqwest::get(format!("{base}{GRAPHQL_PATH}")).await.unwrap();
+
+        assert_eq!(res.status(), reqwest::StatusCode::METHOD_NOT_ALLOWED);
+        let body: serde_json::Value = res.json().await.unwrap();
+        assert_eq!(
+            body["errors"][0]["extensions"]["code"],
+            "METHOD_NOT_ALLOWED"
+        );
+    }
+
+    #[tokio::test]
+    async fn text_error_bodies_become_the_message() {
+        let response = (
+            axum::http::StatusCode::PAYLOAD_TOO_LARGE,
+            "length limit exceeded\n",
+        )
+            .into_response();
+
+        let response = json_error_body(response).await;
+
+        assert_eq!(response.status(), 413);
+        let bytes =
+            axum::body::to_bytes(response.into_body(), usize::MAX).await;
+        let body: serde_json::Value =
+            serde_json::from_slice(&bytes.unwrap()).unwrap();
+        assert_eq!(body["errors"][0]["message"], "length limit exceeded");
+        assert_eq!(
+            body["errors"][0]["extensions"]["code"],
+            "PAYLOAD_TOO_LARGE"
+        );
+    }
+
+    #[tokio::test]
+    async fn json_and_successful_responses_pass_through() {
+        let ok = json_error_body("fine".into_response()).await;
+        let json = json_error_body(
+            (
+                axum::http::StatusCode::TOO_MANY_REQUESTS,
+                [(CONTENT_TYPE, "application/json")],
+                r#"{"errors":[]}"#,
+            )
+                .into_response(),
+        )
+        .await;
+
+        let ok = axum::body::to_bytes(ok.into_body(), usize::MAX).await;
+        let json = axum::body::to_bytes(json.into_body(), usize::MAX).await;
+        assert_eq!(ok.unwrap(), "fine");
+        assert_eq!(json.unwrap(), r#"{"errors":[]}"#);
+    }
+
+    #[tokio::test]
+    async fn retry_returns_the_first_success() {
+        let mut seen = Vec::new();
+
+        let result = retry(5, std::time::Duration::ZERO, |attempt| {
+            seen.push(attempt);
+            async move {
+                if attempt < 3 {
+                    Err("connection refused")
+                } else {
+                    Ok("pool")
+                }
+            }
+        })
+        .await;
+
+        assert_eq!(result, Ok("pool"));
+        assert_eq!(seen, [1, 2, 3]);
+    }
+
+    #[tokio::test]
+    async fn retry_gives_up_with_the_last_error() {
+        let mut calls = 0;
+
+        let result: std::result::Result<(), String> =
+            retry(3, std::time::Duration::ZERO, |attempt| {
+                calls += 1;
+                async move { Err(format!("attempt {attempt} failed")) }
+            })
+            .await;
+
+        assert_eq!(result, Err("attempt 3 failed".to_string()));
+        assert_eq!(calls, 3);
+    }
+
+    /// Log lines written by [`log_subscriber`], shared with the test.
+    #[derive(Clone, Default)]
+    struct Captured(Arc<std::sync::Mutex<Vec<u8>>>);
+
+    impl std::io::Write for Captured {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn json_logs_are_one_object_per_line() {
+        let captured = Captured::default();
+        let writer = captured.clone();
+        let subscriber = log_subscriber(
+            LogFormat::Json,
+            EnvFilter::new("info"),
+            move || writer.clone(),
+        );
+
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!(
+                "graphql",
+                request_id = "abc123",
+                operation = "Me",
+            );
+            span.in_scope(|| tracing::info!(attempt = 2, "first"));
+            tracing::info!("second");
+        });
+
+        let output =
+            String::from_utf8(captured.0.lock().unwrap().clone()).unwrap();
+        let lines: Vec<serde_json::Value> = output
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2, "{output}");
+        assert_eq!(lines[0]["message"], "first");
+        assert_eq!(lines[0]["attempt"], 2);
+        assert_eq!(lines[0]["span"]["request_id"], "abc123");
+        assert_eq!(lines[0]["span"]["operation"], "Me");
+        assert_eq!(lines[1]["message"], "second");
+        assert!(lines[1].get("span").is_none());
+    }
 }